@@ -0,0 +1,101 @@
+//! One-shot `cogni chat`
+
+use crate::session::{response_json, run_turn, TurnOptions};
+use crate::{setup, ChatArgs};
+use cogni::{Error, Message};
+use std::io::{IsTerminal, Read};
+
+/// Combine the prompt argument with piped input
+pub(crate) fn build_prompt(prompt: Option<String>, stdin: Option<String>) -> Result<String, Error> {
+    let stdin = stdin.filter(|text| !text.trim().is_empty());
+    match (prompt, stdin) {
+        (Some(prompt), Some(input)) if prompt == "-" => Ok(input),
+        (Some(prompt), Some(input)) => Ok(format!("{}\n\n{}", prompt, input.trim_end())),
+        (Some(prompt), None) if prompt != "-" => Ok(prompt),
+        (None, Some(input)) => Ok(input),
+        _ => Err(Error::Validation(
+            "No prompt given; pass one as an argument or pipe it on stdin".into(),
+        )),
+    }
+}
+
+pub(crate) async fn run(args: ChatArgs) -> Result<(), Error> {
+    let stdin = if std::io::stdin().is_terminal() {
+        None
+    } else {
+        let mut input = String::new();
+        std::io::stdin()
+            .read_to_string(&mut input)
+            .map_err(|e| Error::Validation(format!("Failed to read stdin: {}", e)))?;
+        Some(input)
+    };
+    let prompt = build_prompt(args.prompt, stdin)?;
+
+    let (client, _) = setup::client(&args.common)?;
+    let response_format = match &args.common.schema {
+        Some(path) => Some(setup::load_schema(path)?),
+        None => None,
+    };
+    let tools = if args.common.tools {
+        Some(setup::builtin_tools(args.common.confirm_tools).await?)
+    } else {
+        None
+    };
+
+    let mut messages = Vec::new();
+    if let Some(system) = &args.common.system {
+        messages.push(Message::system(system));
+    }
+    messages.push(Message::user(prompt));
+
+    let structured = response_format.is_some();
+    let options = TurnOptions {
+        model: None,
+        stream: !args.json && !structured && !args.common.no_stream,
+        print: !args.json && !structured,
+        tools,
+        response_format,
+    };
+    let turn = run_turn(&client, &messages, &options).await?;
+
+    if args.json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&response_json(&turn.response, structured)).map_err(
+                |e| Error::Serialization {
+                    message: e.to_string(),
+                    source: Some(Box::new(e)),
+                }
+            )?
+        );
+    } else if structured {
+        let value = turn.response.parse_json()?;
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&value).unwrap_or_else(|_| value.to_string())
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_prompt() {
+        assert_eq!(build_prompt(Some("hi".into()), None).unwrap(), "hi");
+        assert_eq!(
+            build_prompt(Some("Summarize".into()), Some("text\n".into())).unwrap(),
+            "Summarize\n\ntext"
+        );
+        assert_eq!(build_prompt(None, Some("piped".into())).unwrap(), "piped");
+        assert_eq!(
+            build_prompt(Some("-".into()), Some("piped".into())).unwrap(),
+            "piped"
+        );
+        assert!(build_prompt(None, Some("  \n".into())).is_err());
+        assert!(build_prompt(Some("-".into()), None).is_err());
+    }
+}
@@ -0,0 +1,131 @@
+//! `cogni` command-line interface
+//!
+//! ```text
+//! cogni chat -m gpt-4o "Explain lifetimes in one paragraph"
+//! git diff | cogni chat -m claude-3-5-sonnet-latest "Write a commit message"
+//! cogni chat --json --schema person.json "Invent a person"
+//! cogni repl --config cogni.toml --tools
+//! ```
+
+mod chat;
+mod repl;
+mod session;
+mod setup;
+
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+/// Chat with LLMs from the command line
+#[derive(Debug, Parser)]
+#[command(name = "cogni", version, about)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Send a single prompt and print the answer
+    Chat(ChatArgs),
+    /// Start an interactive chat session
+    Repl(ReplArgs),
+}
+
+/// Options shared by all commands
+#[derive(Debug, Clone, Args)]
+struct CommonArgs {
+    /// Stack configuration file (TOML or YAML)
+    #[arg(short, long, env = "COGNI_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Provider name from the config file, or a provider type such as
+    /// `openai`, `anthropic`, `ollama` or `groq`
+    #[arg(short, long)]
+    provider: Option<String>,
+
+    /// Model to use
+    #[arg(short, long)]
+    model: Option<String>,
+
+    /// Base URL for the provider
+    #[arg(long)]
+    base_url: Option<String>,
+
+    /// System prompt
+    #[arg(short, long)]
+    system: Option<String>,
+
+    /// Sampling temperature
+    #[arg(short, long)]
+    temperature: Option<f32>,
+
+    /// Maximum tokens to generate
+    #[arg(long)]
+    max_tokens: Option<u32>,
+
+    /// JSON Schema file the response must conform to
+    #[arg(long)]
+    schema: Option<PathBuf>,
+
+    /// Enable the built-in tools
+    #[arg(long)]
+    tools: bool,
+
+    /// Ask before running each tool call
+    #[arg(long, requires = "tools")]
+    confirm_tools: bool,
+
+    /// Wait for the complete response instead of streaming it
+    #[arg(long)]
+    no_stream: bool,
+}
+
+#[derive(Debug, Args)]
+struct ChatArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+
+    /// Print the response as a JSON object
+    #[arg(long)]
+    json: bool,
+
+    /// Prompt text; piped stdin is appended to it
+    prompt: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct ReplArgs {
+    #[command(flatten)]
+    common: CommonArgs,
+
+    /// Directory for saved conversations
+    #[arg(long, env = "COGNI_STORE")]
+    store: Option<PathBuf>,
+
+    /// Resume a saved conversation by ID or ID prefix
+    #[arg(long)]
+    resume: Option<String>,
+
+    /// Only save conversations with /save
+    #[arg(long)]
+    no_autosave: bool,
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Command::Chat(args) => chat::run(args).await,
+        Command::Repl(args) => repl::run(args).await,
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! Interactive `cogni repl`
+
+use crate::session::{run_turn, TurnOptions};
+use crate::{setup, ReplArgs};
+use cogni::client::StatefulClient;
+use cogni::config::ConfiguredProvider;
+use cogni::state::{FileStore, StateStore};
+use cogni::{Content, Error, Message, Role};
+use std::io::{BufRead, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+use uuid::Uuid;
+
+const HELP: &str = "\
+Commands:
+  /model [NAME]      show or change the model
+  /system [TEXT]     show or set the system prompt
+  /save [TITLE]      save the conversation, optionally titling it
+  /load ID           load a saved conversation by ID or ID prefix
+  /list              list saved conversations
+  /new               start a new conversation
+  /tools [on|off]    toggle the built-in tools
+  /schema [FILE|off] require responses to match a JSON Schema file
+  /help              show this help
+  /quit              exit";
+
+/// A line of REPL input
+#[derive(Debug, PartialEq)]
+pub(crate) enum Input {
+    Empty,
+    Message(String),
+    Model(Option<String>),
+    System(Option<String>),
+    Save(Option<String>),
+    Load(String),
+    List,
+    New,
+    Tools(Option<bool>),
+    Schema(Option<String>),
+    Help,
+    Quit,
+    Invalid(String),
+}
+
+pub(crate) fn parse_input(line: &str) -> Input {
+    let line = line.trim();
+    if line.is_empty() {
+        return Input::Empty;
+    }
+    let Some(command) = line.strip_prefix('/') else {
+        return Input::Message(line.to_string());
+    };
+
+    let (name, arg) = match command.split_once(char::is_whitespace) {
+        Some((name, arg)) => (name, Some(arg.trim().to_string()).filter(|a| !a.is_empty())),
+        None => (command, None),
+    };
+
+    match (name, arg) {
+        ("model", arg) => Input::Model(arg),
+        ("system", arg) => Input::System(arg),
+        ("save", arg) => Input::Save(arg),
+        ("load", Some(id)) => Input::Load(id),
+        ("load", None) => Input::Invalid("usage: /load ID".into()),
+        ("list", _) => Input::List,
+        ("new", _) => Input::New,
+        ("tools", None) => Input::Tools(None),
+        ("tools", Some(arg)) => match arg.as_str() {
+            "on" => Input::Tools(Some(true)),
+            "off" => Input::Tools(Some(false)),
+            _ => Input::Invalid("usage: /tools [on|off]".into()),
+        },
+        ("schema", arg) => Input::Schema(arg),
+        ("help", _) | ("?", _) => Input::Help,
+        ("quit", _) | ("exit", _) | ("q", _) => Input::Quit,
+        (name, _) => Input::Invalid(format!("unknown command /{}; try /help", name)),
+    }
+}
+
+/// Default directory for saved conversations
+fn default_store_dir() -> PathBuf {
+    match std::env::var_os("HOME") {
+        Some(home) => PathBuf::from(home).join(".cogni").join("conversations"),
+        None => PathBuf::from(".cogni").join("conversations"),
+    }
+}
+
+fn system_message(messages: &[Message]) -> Option<String> {
+    match messages.first() {
+        Some(Message {
+            role: Role::System,
+            content: Content::Text(text),
+            ..
+        }) => Some(text.clone()),
+        _ => None,
+    }
+}
+
+struct Repl {
+    client: StatefulClient<ConfiguredProvider>,
+    options: TurnOptions,
+    default_model: Option<String>,
+    system: Option<String>,
+    autosave: bool,
+    confirm_tools: bool,
+}
+
+impl Repl {
+    async fn ensure_conversation(&mut self) -> Result<(), Error> {
+        if self.client.current_state().is_none() {
+            self.client.new_conversation().await?;
+            let system = self.system.clone();
+            if let Some(state) = self.client.current_state_mut() {
+                state.set_system_message(system.as_deref());
+            }
+        }
+        Ok(())
+    }
+
+    async fn send(&mut self, text: String) -> Result<(), Error> {
+        self.ensure_conversation().await?;
+        let history = {
+            let state = self
+                .client
+                .current_state_mut()
+                .expect("conversation was just ensured");
+            state.add_message(Message::user(text));
+            state.messages().to_vec()
+        };
+
+        let turn = match run_turn(self.client.client(), &history, &self.options).await {
+            Ok(turn) => turn,
+            Err(e) => {
+                // Drop the unanswered message so the user can retry it
+                if let Some(state) = self.client.current_state_mut() {
+                    state.pop_message();
+                }
+                return Err(e);
+            }
+        };
+
+        if self.options.response_format.is_some() {
+            println!("{}", turn.response.content);
+        }
+        let state = self
+            .client
+            .current_state_mut()
+            .expect("conversation was just ensured");
+        state.add_messages(turn.messages);
+        if let Some(usage) = &turn.response.metadata.usage {
+            let count = state.metadata.token_count.unwrap_or(0);
+            state.update_token_count(count + usage.total_tokens);
+        }
+
+        if self.autosave {
+            self.client.save().await?;
+        }
+        Ok(())
+    }
+
+    async fn resolve_id(&self, prefix: &str) -> Result<Uuid, Error> {
+        if let Ok(id) = Uuid::parse_str(prefix) {
+            return Ok(id);
+        }
+        let matches: Vec<Uuid> = self
+            .client
+            .list_summaries()
+            .await?
+            .into_iter()
+            .map(|summary| summary.id)
+            .filter(|id| id.to_string().starts_with(prefix))
+            .collect();
+        match matches.as_slice() {
+            [id] => Ok(*id),
+            [] => Err(Error::Validation(format!(
+                "No conversation matches {}",
+                prefix
+            ))),
+            _ => Err(Error::Validation(format!(
+                "{} matches {} conversations",
+                prefix,
+                matches.len()
+            ))),
+        }
+    }
+
+    async fn load(&mut self, prefix: &str) -> Result<(), Error> {
+        let id = self.resolve_id(prefix).await?;
+        self.client.load_conversation(id).await?;
+        let state = self
+            .client
+            .current_state()
+            .expect("conversation was loaded");
+        self.system = system_message(state.messages());
+        println!(
+            "Loaded {} ({} messages)",
+            state.metadata.title.as_deref().unwrap_or(&id.to_string()),
+            state.messages().len()
+        );
+        for message in state.messages().iter().filter(|m| m.role != Role::System) {
+            if let Some(text) = message.content.as_text() {
+                let who = match message.role {
+                    Role::User => "you",
+                    _ => "assistant",
+                };
+                println!("{}> {}", who, text);
+            }
+        }
+        Ok(())
+    }
+
+    /// Handle one line; returns false to exit
+    async fn handle(&mut self, input: Input) -> Result<bool, Error> {
+        match input {
+            Input::Empty => {}
+            Input::Message(text) => self.send(text).await?,
+            Input::Model(None) => println!(
+                "{}",
+                self.options
+                    .model
+                    .as_deref()
+                    .or(self.default_model.as_deref())
+                    .unwrap_or("(provider default)")
+            ),
+            Input::Model(Some(model)) => {
+                println!("Model set to {}", model);
+                self.options.model = Some(model);
+            }
+            Input::System(None) => {
+                println!("{}", self.system.as_deref().unwrap_or("(no system prompt)"))
+            }
+            Input::System(Some(text)) => {
+                let system = (text != "off").then_some(text);
+                if let Some(state) = self.client.current_state_mut() {
+                    state.set_system_message(system.as_deref());
+                }
+                self.system = system;
+                println!("System prompt updated");
+            }
+            Input::Save(title) => {
+                self.ensure_conversation().await?;
+                if let (Some(title), Some(state)) = (title, self.client.current_state_mut()) {
+                    state.set_title(title);
+                }
+                self.client.save().await?;
+                if let Some(id) = self.client.current_conversation_id() {
+                    println!("Saved {}", id);
+                }
+            }
+            Input::Load(prefix) => self.load(&prefix).await?,
+            Input::List => {
+                let mut conversations = self.client.list_summaries().await?;
+                conversations.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+                for summary in conversations {
+                    println!(
+                        "{}  {}  {:>3} messages  {}",
+                        &summary.id.to_string()[..8],
+                        summary.updated_at.format("%Y-%m-%d %H:%M"),
+                        summary.message_count,
+                        summary.metadata.title.as_deref().unwrap_or("")
+                    );
+                }
+            }
+            Input::New => {
+                self.client.clear_current();
+                println!("Started a new conversation");
+            }
+            Input::Tools(enable) => {
+                let enable = enable.unwrap_or(self.options.tools.is_none());
+                self.options.tools = if enable {
+                    Some(setup::builtin_tools(self.confirm_tools).await?)
+                } else {
+                    None
+                };
+                println!("Tools {}", if enable { "enabled" } else { "disabled" });
+            }
+            Input::Schema(None) => match &self.options.response_format {
+                Some(cogni::ResponseFormat::JsonSchema { schema, .. }) => println!("{}", schema),
+                _ => println!("(no schema)"),
+            },
+            Input::Schema(Some(arg)) if arg == "off" => {
+                self.options.response_format = None;
+                self.options.stream = true;
+                println!("Schema cleared");
+            }
+            Input::Schema(Some(path)) => {
+                self.options.response_format = Some(setup::load_schema(path.as_ref())?);
+                self.options.stream = false;
+                println!("Responses must match {}", path);
+            }
+            Input::Help => println!("{}", HELP),
+            Input::Quit => return Ok(false),
+            Input::Invalid(message) => println!("{}", message),
+        }
+        Ok(true)
+    }
+}
+
+pub(crate) async fn run(args: ReplArgs) -> Result<(), Error> {
+    let (client, default_model) = setup::client(&args.common)?;
+    let store_dir = args.store.unwrap_or_else(default_store_dir);
+    let store: Arc<dyn StateStore> = Arc::new(
+        FileStore::new(&store_dir)
+            .map_err(|e| Error::Storage(format!("{}: {}", store_dir.display(), e)))?,
+    );
+
+    let response_format = match &args.common.schema {
+        Some(path) => Some(setup::load_schema(path)?),
+        None => None,
+    };
+    let tools = if args.common.tools {
+        Some(setup::builtin_tools(args.common.confirm_tools).await?)
+    } else {
+        None
+    };
+
+    let mut repl = Repl {
+        client: client.with_state(store).with_auto_save(!args.no_autosave),
+        options: TurnOptions {
+            model: None,
+            stream: response_format.is_none() && !args.common.no_stream,
+            print: response_format.is_none(),
+            tools,
+            response_format,
+        },
+        default_model,
+        system: args.common.system.clone(),
+        autosave: !args.no_autosave,
+        confirm_tools: args.common.confirm_tools,
+    };
+    if let Some(prefix) = &args.resume {
+        repl.load(prefix).await?;
+    }
+
+    eprintln!(
+        "cogni {} — model {}. Type /help for commands.",
+        env!("CARGO_PKG_VERSION"),
+        repl.default_model
+            .as_deref()
+            .unwrap_or("(provider default)")
+    );
+
+    let stdin = std::io::stdin();
+    let mut line = String::new();
+    loop {
+        print!("> ");
+        let _ = std::io::stdout().flush();
+
+        line.clear();
+        let read = stdin
+            .lock()
+            .read_line(&mut line)
+            .map_err(|e| Error::Validation(format!("Failed to read input: {}", e)))?;
+        if read == 0 {
+            println!();
+            break;
+        }
+
+        match repl.handle(parse_input(&line)).await {
+            Ok(true) => {}
+            Ok(false) => break,
+            Err(e) => eprintln!("error: {}", e),
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cogni::state::ConversationState;
+
+    #[test]
+    fn test_parse_input() {
+        assert_eq!(parse_input("  "), Input::Empty);
+        assert_eq!(
+            parse_input("hello /there"),
+            Input::Message("hello /there".into())
+        );
+        assert_eq!(parse_input("/model"), Input::Model(None));
+        assert_eq!(
+            parse_input("/model gpt-4o-mini"),
+            Input::Model(Some("gpt-4o-mini".into()))
+        );
+        assert_eq!(
+            parse_input("/system  Be terse. "),
+            Input::System(Some("Be terse.".into()))
+        );
+        assert_eq!(parse_input("/load 1a2b"), Input::Load("1a2b".into()));
+        assert!(matches!(parse_input("/load"), Input::Invalid(_)));
+        assert_eq!(parse_input("/tools off"), Input::Tools(Some(false)));
+        assert!(matches!(parse_input("/tools maybe"), Input::Invalid(_)));
+        assert_eq!(parse_input("/exit"), Input::Quit);
+        assert!(matches!(parse_input("/frobnicate"), Input::Invalid(_)));
+    }
+
+    #[test]
+    fn test_system_message() {
+        let mut state = ConversationState::new();
+        state.add_message(Message::user("hi"));
+        state.set_system_message(Some("one"));
+        state.set_system_message(Some("two"));
+        assert_eq!(state.messages().len(), 2);
+        assert_eq!(system_message(state.messages()).as_deref(), Some("two"));
+
+        state.set_system_message(None);
+        assert_eq!(system_message(state.messages()), None);
+    }
+}
@@ -0,0 +1,242 @@
+//! Running a single conversation turn, including tool calls
+
+use cogni::client::Client;
+use cogni::config::ConfiguredProvider;
+use cogni::tools::ToolRegistry;
+use cogni::{
+    Error, Message, Provider, Response, ResponseFormat, ResponseMetadata, StreamAccumulator,
+    StreamEvent, ToolCall,
+};
+use futures::StreamExt;
+use std::io::Write;
+use std::sync::Arc;
+
+/// Upper bound on model/tool round trips per turn
+const MAX_TOOL_ROUNDS: usize = 8;
+
+/// How a turn is sent and displayed
+#[derive(Clone, Default)]
+pub(crate) struct TurnOptions {
+    /// Model override for this turn
+    pub model: Option<String>,
+    /// Stream output as it arrives
+    pub stream: bool,
+    /// Print the response to stdout
+    pub print: bool,
+    /// Tools the model may call
+    pub tools: Option<Arc<ToolRegistry>>,
+    /// Structured output format
+    pub response_format: Option<ResponseFormat>,
+}
+
+/// Result of a turn
+pub(crate) struct Turn {
+    /// Messages to append to the conversation
+    pub messages: Vec<Message>,
+    /// The final response
+    pub response: Response,
+}
+
+/// Send `history` and run tool calls until the model answers
+pub(crate) async fn run_turn(
+    client: &Client<ConfiguredProvider>,
+    history: &[Message],
+    options: &TurnOptions,
+) -> Result<Turn, Error> {
+    let tools = match &options.tools {
+        Some(registry) => registry.list_tools().await,
+        None => Vec::new(),
+    };
+
+    let mut messages = history.to_vec();
+    let mut added = Vec::new();
+
+    for _ in 0..MAX_TOOL_ROUNDS {
+        let mut builder = client
+            .request()
+            .messages(messages.clone())
+            .tools(tools.clone());
+        if let Some(model) = &options.model {
+            builder = builder.model(model.as_str());
+        }
+        if let Some(format) = &options.response_format {
+            builder = builder.response_format(format.clone());
+        }
+        let request = builder.build();
+
+        let response = if options.stream {
+            stream_response(client, request, options.print).await?
+        } else {
+            let response = client.execute(request).await?;
+            if options.print {
+                println!("{}", response.content);
+            }
+            response
+        };
+
+        let registry = match &options.tools {
+            Some(registry) if response.has_tool_calls() => registry,
+            _ => {
+                if !response.content.is_empty() {
+                    added.push(Message::assistant(&response.content));
+                }
+                return Ok(Turn {
+                    messages: added,
+                    response,
+                });
+            }
+        };
+
+        let mut results = Vec::new();
+        for call in &response.tool_calls {
+            eprintln!("[tool] {}({})", call.name, call.arguments);
+            let output = match registry.execute(call).await {
+                Ok(result) if result.success => result.content,
+                Ok(result) => format!("error: {}", result.content),
+                Err(e) => format!("error: {}", e),
+            };
+            results.push((call.clone(), output));
+        }
+
+        let round = tool_round_messages(&response, &results);
+        messages.extend(round.iter().cloned());
+        added.extend(round);
+    }
+
+    Err(Error::Validation(format!(
+        "Stopped after {} rounds of tool calls",
+        MAX_TOOL_ROUNDS
+    )))
+}
+
+async fn stream_response(
+    client: &Client<ConfiguredProvider>,
+    request: cogni::Request,
+    print: bool,
+) -> Result<Response, Error> {
+    let mut stream = client.provider().stream(request).await?;
+    let mut accumulator = StreamAccumulator::new();
+    let mut metadata = ResponseMetadata::default();
+    let mut stdout = std::io::stdout();
+
+    while let Some(event) = stream.next().await {
+        let event = event?;
+        match &event {
+            StreamEvent::Content(delta) if print => {
+                print!("{}", delta.text);
+                let _ = stdout.flush();
+            }
+            StreamEvent::Metadata(delta) => {
+                if delta.model.is_some() {
+                    metadata.model = delta.model.clone();
+                }
+                if delta.id.is_some() {
+                    metadata.id = delta.id.clone();
+                }
+            }
+            _ => {}
+        }
+        accumulator.process_event(event)?;
+    }
+    if print {
+        println!();
+    }
+
+    Ok(Response {
+        content: accumulator.content().to_string(),
+        tool_calls: accumulator.tool_calls(),
+        metadata,
+    })
+}
+
+/// The assistant turn that called tools, followed by one tool message per result
+pub(crate) fn tool_round_messages(
+    response: &Response,
+    results: &[(ToolCall, String)],
+) -> Vec<Message> {
+    std::iter::once(response.to_message())
+        .chain(
+            results
+                .iter()
+                .map(|(call, output)| Message::tool(output, &call.id)),
+        )
+        .collect()
+}
+
+/// Render a response as a JSON object
+pub(crate) fn response_json(response: &Response, structured: bool) -> serde_json::Value {
+    let mut output = serde_json::json!({
+        "content": response.content,
+        "model": response.metadata.model,
+        "id": response.metadata.id,
+        "finish_reason": response.metadata.finish_reason.as_ref().map(|r| format!("{:?}", r)),
+        "usage": response.metadata.usage.as_ref().map(|u| serde_json::json!({
+            "prompt_tokens": u.prompt_tokens,
+            "completion_tokens": u.completion_tokens,
+            "total_tokens": u.total_tokens,
+        })),
+        "tool_calls": response.tool_calls.iter().map(|c| serde_json::json!({
+            "id": c.id,
+            "name": c.name,
+            "arguments": c.arguments,
+        })).collect::<Vec<_>>(),
+    });
+    if structured {
+        output["output"] = response.parse_json().unwrap_or(serde_json::Value::Null);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cogni::{Content, Role, Usage};
+
+    fn call(id: &str, name: &str) -> ToolCall {
+        ToolCall {
+            id: id.into(),
+            name: name.into(),
+            arguments: "{}".into(),
+        }
+    }
+
+    #[test]
+    fn test_tool_round_messages() {
+        let mut response = Response::text("");
+        response.tool_calls = vec![call("1", "calculator"), call("2", "missing")];
+
+        let messages = tool_round_messages(
+            &response,
+            &[
+                (call("1", "calculator"), r#"{"result":4.0}"#.into()),
+                (call("2", "missing"), "error: Tool not found".into()),
+            ],
+        );
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[0].role, Role::Assistant);
+        assert_eq!(messages[0].tool_calls, response.tool_calls);
+        assert_eq!(messages[1].role, Role::Tool);
+        assert_eq!(messages[1].metadata.tool_call_id.as_deref(), Some("1"));
+        assert_eq!(
+            messages[2].content,
+            Content::Text("error: Tool not found".into())
+        );
+    }
+
+    #[test]
+    fn test_response_json() {
+        let mut response = Response::text(r#"{"name":"Ada"}"#);
+        response.metadata.model = Some("m".into());
+        response.metadata.usage = Some(Usage {
+            prompt_tokens: 1,
+            completion_tokens: 2,
+            total_tokens: 3,
+        });
+
+        let json = response_json(&response, true);
+        assert_eq!(json["model"], "m");
+        assert_eq!(json["usage"]["total_tokens"], 3);
+        assert_eq!(json["output"]["name"], "Ada");
+        assert!(response_json(&response, false).get("output").is_none());
+    }
+}
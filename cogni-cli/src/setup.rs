@@ -0,0 +1,197 @@
+//! Building a client from command-line options
+
+use crate::CommonArgs;
+use cogni::client::Client;
+use cogni::config::{Config, ConfiguredProvider, ProviderConfig, ProviderKind};
+use cogni::tools::builtin::create_builtin_registry;
+use cogni::tools::{ApprovalPolicy, CliApprovalHandler, ToolRegistry};
+use cogni::{Error, ResponseFormat};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Environment variable holding the API key for a provider type
+fn api_key_var(kind: ProviderKind) -> Option<&'static str> {
+    match kind {
+        ProviderKind::OpenAI | ProviderKind::OpenAICompatible => Some("OPENAI_API_KEY"),
+        ProviderKind::Anthropic => Some("ANTHROPIC_API_KEY"),
+        ProviderKind::Azure => Some("AZURE_OPENAI_API_KEY"),
+        ProviderKind::Groq => Some("GROQ_API_KEY"),
+        ProviderKind::Together => Some("TOGETHER_API_KEY"),
+        ProviderKind::Mistral => Some("MISTRAL_API_KEY"),
+        ProviderKind::OpenRouter => Some("OPENROUTER_API_KEY"),
+        ProviderKind::Ollama | ProviderKind::Vllm | ProviderKind::LlamaCpp => None,
+    }
+}
+
+/// Guess the provider type from a model name
+pub(crate) fn infer_kind(model: Option<&str>) -> ProviderKind {
+    let Some(model) = model else {
+        return ProviderKind::OpenAI;
+    };
+    if model.starts_with("claude") {
+        ProviderKind::Anthropic
+    } else if model.starts_with("gpt-")
+        || model.starts_with("o1")
+        || model.starts_with("o3")
+        || model.starts_with("o4")
+        || model.starts_with("chatgpt")
+    {
+        ProviderKind::OpenAI
+    } else {
+        ProviderKind::Ollama
+    }
+}
+
+/// Load the config file, or describe a single provider from the options
+pub(crate) fn load_config(
+    args: &CommonArgs,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> Result<(Config, String), Error> {
+    let mut config = match &args.config {
+        Some(path) => Config::from_file(path)?,
+        None => Config::default(),
+    };
+
+    let name = match (&args.provider, config.providers.is_empty()) {
+        (Some(name), false) if config.providers.contains_key(name) => name.clone(),
+        (None, false) => config.default_provider_name()?.to_string(),
+        (provider, _) => {
+            let kind = match provider {
+                Some(name) => name.parse()?,
+                None => infer_kind(args.model.as_deref()),
+            };
+            let mut provider = ProviderConfig::new(kind);
+            if let Some(var) = api_key_var(kind) {
+                provider.api_key = lookup(var);
+                if provider.api_key.is_none() && kind.requires_api_key() {
+                    return Err(Error::Configuration(format!(
+                        "{} is not set; export it or pass --config",
+                        var
+                    )));
+                }
+            }
+            let name = kind.name().to_string();
+            config.providers.insert(name.clone(), provider);
+            name
+        }
+    };
+
+    let provider = config
+        .providers
+        .get_mut(&name)
+        .expect("provider was resolved above");
+    if let Some(model) = &args.model {
+        provider.model = Some(model.clone());
+    }
+    if let Some(base_url) = &args.base_url {
+        provider.base_url = Some(base_url.clone());
+    }
+    if let Some(temperature) = args.temperature {
+        config.parameters.temperature = Some(temperature);
+    }
+    if let Some(max_tokens) = args.max_tokens {
+        config.parameters.max_tokens = Some(max_tokens);
+    }
+
+    config.validate()?;
+    Ok((config, name))
+}
+
+/// Build the client described by the options, with its default model
+pub(crate) fn client(
+    args: &CommonArgs,
+) -> Result<(Client<ConfiguredProvider>, Option<String>), Error> {
+    let (config, name) = load_config(args, |var| std::env::var(var).ok())?;
+    let client = config.client_for(&name)?;
+    Ok((client, config.providers[&name].model.clone()))
+}
+
+/// Load a JSON Schema file as a response format
+pub(crate) fn load_schema(path: &Path) -> Result<ResponseFormat, Error> {
+    let text = std::fs::read_to_string(path).map_err(|e| {
+        Error::Configuration(format!("Failed to read schema {}: {}", path.display(), e))
+    })?;
+    let schema = serde_json::from_str(&text).map_err(|e| {
+        Error::Configuration(format!("Invalid JSON in schema {}: {}", path.display(), e))
+    })?;
+    Ok(ResponseFormat::JsonSchema {
+        schema,
+        strict: true,
+    })
+}
+
+/// Create the built-in tool registry, asking on the terminal before each call
+/// if `confirm` is set
+pub(crate) async fn builtin_tools(confirm: bool) -> Result<Arc<ToolRegistry>, Error> {
+    let registry = create_builtin_registry()
+        .await
+        .map_err(|e| Error::Configuration(format!("Failed to load built-in tools: {}", e)))?;
+    Ok(Arc::new(if confirm {
+        registry.with_approval(ApprovalPolicy::new(CliApprovalHandler::new()))
+    } else {
+        registry
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        common: CommonArgs,
+    }
+
+    fn args(argv: &[&str]) -> CommonArgs {
+        TestCli::parse_from(std::iter::once("cogni").chain(argv.iter().copied())).common
+    }
+
+    fn env(var: &str) -> Option<String> {
+        (var == "ANTHROPIC_API_KEY").then(|| "sk-ant".to_string())
+    }
+
+    #[test]
+    fn test_infer_kind() {
+        assert_eq!(
+            infer_kind(Some("claude-3-5-haiku-latest")),
+            ProviderKind::Anthropic
+        );
+        assert_eq!(infer_kind(Some("gpt-4o-mini")), ProviderKind::OpenAI);
+        assert_eq!(infer_kind(Some("o3-mini")), ProviderKind::OpenAI);
+        assert_eq!(infer_kind(Some("llama3.2")), ProviderKind::Ollama);
+        assert_eq!(infer_kind(None), ProviderKind::OpenAI);
+    }
+
+    #[test]
+    fn test_provider_from_flags() {
+        let (config, name) =
+            load_config(&args(&["-m", "claude-3-5-haiku-latest", "-t", "0.5"]), env).unwrap();
+        assert_eq!(name, "anthropic");
+        assert_eq!(config.providers[&name].api_key.as_deref(), Some("sk-ant"));
+        assert_eq!(
+            config.providers[&name].model.as_deref(),
+            Some("claude-3-5-haiku-latest")
+        );
+        assert_eq!(config.parameters.temperature, Some(0.5));
+
+        let (config, name) = load_config(
+            &args(&["-p", "vllm", "--base-url", "http://gpu:8000/v1"]),
+            env,
+        )
+        .unwrap();
+        assert_eq!(name, "vllm");
+        assert_eq!(
+            config.providers[&name].base_url.as_deref(),
+            Some("http://gpu:8000/v1")
+        );
+    }
+
+    #[test]
+    fn test_missing_api_key() {
+        let err = load_config(&args(&["-p", "openai"]), env).unwrap_err();
+        assert!(err.to_string().contains("OPENAI_API_KEY is not set"));
+        assert!(load_config(&args(&["-p", "bard"]), env).is_err());
+    }
+}
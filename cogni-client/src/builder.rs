@@ -55,6 +55,7 @@ impl RequestBuilder {
         self.messages.push(Message {
             role,
             content: content.into(),
+            tool_calls: Vec::new(),
             metadata: Metadata::default(),
         });
         self
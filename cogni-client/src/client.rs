@@ -218,7 +218,7 @@ impl<P: Provider> Client<P> {
     /// ```
     pub async fn chat_structured<T, M>(&self, messages: M) -> Result<T, Error>
     where
-        T: StructuredOutput + for<'de> Deserialize<'de>,
+        T: StructuredOutput + for<'de> Deserialize<'de> + 'static,
         M: Into<MessageInput>,
     {
         let response = self
@@ -278,6 +278,7 @@ impl MessageInput {
             MessageInput::Single(text) => vec![Message {
                 role: Role::User,
                 content: Content::Text(text),
+                tool_calls: Vec::new(),
                 metadata: Metadata::default(),
             }],
             MessageInput::Multiple(messages) => messages,
@@ -13,7 +13,7 @@ pub use builder::RequestBuilder;
 pub use client::Client;
 pub use middleware::MiddlewareProvider;
 pub use parallel::{parallel_chat, parallel_requests, ExecutionStrategy, ParallelClient};
-pub use stateful::StatefulClient;
+pub use stateful::{StatefulClient, StatefulRequestBuilder};
 
 /// Prelude module for convenient imports
 pub mod prelude {
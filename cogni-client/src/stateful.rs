@@ -1,16 +1,31 @@
 //! Stateful client for managing conversation history
 
-use crate::Client;
-use cogni_core::{Error, Message, Provider, Request, Response};
-use cogni_state::{ConversationState, StateStore};
+use crate::{Client, RequestBuilder};
+use cogni_core::{
+    Error, Message, Model, Parameters, Provider, Request, Response, ResponseFormat,
+    StreamAccumulator, StreamEvent, StructuredOutput, Tool, ToolCall, Usage,
+};
+use cogni_state::{ConversationState, ConversationSummary, QueryPage, StateQuery, StateStore};
+use cogni_tools::ToolRegistry;
+use futures::{Stream, StreamExt};
+use serde::Deserialize;
+use std::pin::Pin;
 use std::sync::Arc;
 use tracing::{debug, trace};
 use uuid::Uuid;
 
+/// Default upper bound on model/tool round trips per turn
+const DEFAULT_MAX_TOOL_ROUNDS: usize = 8;
+
 /// A client that maintains conversation state across interactions
 ///
 /// This wrapper around the base `Client` automatically manages conversation
 /// history, saving and loading state through a `StateStore`.
+///
+/// Tools, a response format and a [`ToolRegistry`] can be attached to every
+/// turn. When a registry is set, tool calls the model makes are executed and
+/// answered automatically; the assistant's tool calls and their results are
+/// recorded in the conversation alongside the final reply.
 pub struct StatefulClient<P: Provider> {
     /// The underlying client
     client: Client<P>,
@@ -20,6 +35,14 @@ pub struct StatefulClient<P: Provider> {
     current_state: Option<ConversationState>,
     /// Whether to auto-save after each interaction
     auto_save: bool,
+    /// Tools offered to the model on every turn
+    tools: Vec<Tool>,
+    /// Registry used to execute tool calls automatically
+    registry: Option<Arc<ToolRegistry>>,
+    /// Response format used on every turn
+    response_format: Option<ResponseFormat>,
+    /// Maximum rounds of automatic tool execution per turn
+    max_tool_rounds: usize,
 }
 
 impl<P: Provider> StatefulClient<P> {
@@ -30,6 +53,10 @@ impl<P: Provider> StatefulClient<P> {
             store,
             current_state: None,
             auto_save: true,
+            tools: Vec::new(),
+            registry: None,
+            response_format: None,
+            max_tool_rounds: DEFAULT_MAX_TOOL_ROUNDS,
         }
     }
 
@@ -39,6 +66,47 @@ impl<P: Provider> StatefulClient<P> {
         self
     }
 
+    /// Offer tools to the model on every turn
+    ///
+    /// Without a registry, tool calls are returned to the caller, who answers
+    /// them with [`Message::tool`] messages on the next turn.
+    pub fn with_tools(mut self, tools: impl IntoIterator<Item = Tool>) -> Self {
+        self.tools.extend(tools);
+        self
+    }
+
+    /// Execute tool calls automatically with a registry
+    ///
+    /// Every tool in the registry is offered to the model. Failed calls are
+    /// reported back to the model as `error: ...` results.
+    pub fn with_tool_registry(mut self, registry: Arc<ToolRegistry>) -> Self {
+        self.registry = Some(registry);
+        self
+    }
+
+    /// Set the maximum rounds of automatic tool execution per turn
+    ///
+    /// Once the limit is reached, the last response is returned and its tool
+    /// calls are answered with an error instead of being executed.
+    pub fn with_max_tool_rounds(mut self, rounds: usize) -> Self {
+        self.max_tool_rounds = rounds;
+        self
+    }
+
+    /// Set the response format used on every turn
+    pub fn with_response_format(mut self, format: ResponseFormat) -> Self {
+        self.response_format = Some(format);
+        self
+    }
+
+    /// Request structured output of a specific type on every turn
+    pub fn with_structured_output<T: StructuredOutput>(self) -> Self {
+        self.with_response_format(ResponseFormat::JsonSchema {
+            schema: T::schema(),
+            strict: true,
+        })
+    }
+
     /// Create a new conversation
     pub async fn new_conversation(&mut self) -> Result<Uuid, Error> {
         let state = ConversationState::new();
@@ -65,18 +133,34 @@ impl<P: Provider> StatefulClient<P> {
     }
 
     /// Save the current conversation
-    pub async fn save(&self) -> Result<(), Error> {
-        if let Some(ref state) = self.current_state {
+    ///
+    /// The current state takes the version the store assigned it.
+    pub async fn save(&mut self) -> Result<(), Error> {
+        if let Some(ref mut state) = self.current_state {
             trace!("Saving conversation: {}", state.id);
             self.store
                 .save(state)
                 .await
                 .map_err(|e| Error::Storage(format!("Failed to save conversation: {}", e)))?;
-            debug!("Saved conversation: {}", state.id);
+            state.version = self
+                .store
+                .load(&state.id)
+                .await
+                .map_err(|e| Error::Storage(format!("Failed to load conversation: {}", e)))?
+                .version;
+            debug!(
+                "Saved conversation: {} at version {}",
+                state.id, state.version
+            );
         }
         Ok(())
     }
 
+    /// Get the underlying client
+    pub fn client(&self) -> &Client<P> {
+        &self.client
+    }
+
     /// Get the current conversation ID
     pub fn current_conversation_id(&self) -> Option<Uuid> {
         self.current_state.as_ref().map(|s| s.id)
@@ -97,86 +181,91 @@ impl<P: Provider> StatefulClient<P> {
         self.current_state = None;
     }
 
+    /// Start a turn with per-call settings
+    ///
+    /// The builder starts from the client's model, parameters, tools and
+    /// response format; anything set on it applies to this turn only.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// # use cogni_client::Client;
+    /// # use cogni_providers::OpenAI;
+    /// # use cogni_state::MemoryStore;
+    /// # use std::sync::Arc;
+    /// # async fn example() -> Result<(), Box<dyn std::error::Error>> {
+    /// # let provider = OpenAI::with_api_key("key".to_string())?;
+    /// let mut stateful = Client::new(provider).with_state(Arc::new(MemoryStore::new()));
+    ///
+    /// let response = stateful
+    ///     .request()
+    ///     .user("Summarize our conversation so far")
+    ///     .temperature(0.2)
+    ///     .max_tokens(200)
+    ///     .send()
+    ///     .await?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn request(&mut self) -> StatefulRequestBuilder<'_, P> {
+        let mut builder = RequestBuilder::new()
+            .model(self.client.default_model.clone())
+            .parameters(self.client.default_parameters.clone())
+            .tools(self.tools.clone());
+        if let Some(format) = &self.response_format {
+            builder = builder.response_format(format.clone());
+        }
+        StatefulRequestBuilder {
+            client: self,
+            builder,
+            messages: Vec::new(),
+        }
+    }
+
     /// Send a chat message and update the conversation state
     pub async fn chat(&mut self, message: &str) -> Result<Response, Error> {
-        // Ensure we have a conversation
-        if self.current_state.is_none() {
-            self.new_conversation().await?;
-        }
+        self.request().user(message).send().await
+    }
 
+    /// Send a chat message and parse the reply as structured output
+    ///
+    /// The reply is recorded in the conversation before it is parsed.
+    pub async fn chat_structured<T>(&mut self, message: &str) -> Result<T, Error>
+    where
+        T: StructuredOutput + for<'de> Deserialize<'de> + 'static,
+    {
+        self.request()
+            .user(message)
+            .with_structured_output::<T>()
+            .send()
+            .await?
+            .parse_structured()
+    }
+
+    /// Regenerate the reply to the last user message
+    ///
+    /// The previous reply stays in the conversation as an alternative; see
+    /// [`ConversationState::siblings`] and [`ConversationState::switch_branch`].
+    pub async fn regenerate(&mut self) -> Result<Response, Error> {
         let state = self
             .current_state
             .as_mut()
-            .expect("current_state should exist after new_conversation");
-
-        // Add user message to state
-        let user_message = Message::user(message);
-        state.add_message(user_message.clone());
-
-        // Build request with full conversation history
-        let request = Request {
-            messages: state.messages.clone(),
-            model: self.client.default_model.clone(),
-            parameters: self.client.default_parameters.clone(),
-            tools: vec![],
-            response_format: None,
-        };
-
-        // Send request
-        let response = self.client.provider.request(request).await?;
-
-        // Add assistant response to state
-        if !response.content.is_empty() {
-            state.add_message(Message::assistant(&response.content));
-        }
-
-        // Update token count if available
-        if let Some(usage) = &response.metadata.usage {
-            let current_count = state.metadata.token_count.unwrap_or(0);
-            state.update_token_count(current_count + usage.total_tokens);
-        }
-
-        // Auto-save if enabled
-        if self.auto_save {
-            self.save().await?;
-        }
-
-        Ok(response)
+            .ok_or_else(|| Error::Validation("No conversation to regenerate".to_string()))?;
+        state
+            .rewind_last_turn()
+            .map_err(|e| Error::Validation(e.to_string()))?;
+        self.request().send().await
     }
 
     /// Stream a chat message and update the conversation state
+    ///
+    /// The assistant's reply is recorded when the stream completes; see
+    /// [`StatefulRequestBuilder::stream`].
     pub async fn stream_chat(
         &mut self,
         message: &str,
-    ) -> Result<impl futures::Stream<Item = Result<cogni_core::StreamEvent, Error>>, Error> {
-        // Ensure we have a conversation
-        if self.current_state.is_none() {
-            self.new_conversation().await?;
-        }
-
-        let state = self
-            .current_state
-            .as_mut()
-            .expect("current_state should exist after new_conversation");
-
-        // Add user message to state
-        let user_message = Message::user(message);
-        state.add_message(user_message.clone());
-
-        // Build request with full conversation history
-        let request = Request {
-            messages: state.messages.clone(),
-            model: self.client.default_model.clone(),
-            parameters: self.client.default_parameters.clone(),
-            tools: vec![],
-            response_format: None,
-        };
-
-        // Note: We'll need to accumulate the response and save it after streaming completes
-        // This is a limitation of the streaming API - we can't update state during streaming
-        let stream = self.client.provider.stream(request).await?;
-
-        Ok(stream)
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent, Error>> + Send + '_>>, Error> {
+        self.request().user(message).stream().await
     }
 
     /// List all conversations in the store
@@ -202,6 +291,22 @@ impl<P: Provider> StatefulClient<P> {
         Ok(())
     }
 
+    /// List every conversation's metadata without loading its messages
+    pub async fn list_summaries(&self) -> Result<Vec<ConversationSummary>, Error> {
+        self.store
+            .list_summaries()
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to list conversations: {}", e)))
+    }
+
+    /// Find conversations matching a query, one page at a time
+    pub async fn query_conversations(&self, query: &StateQuery) -> Result<QueryPage, Error> {
+        self.store
+            .query(query)
+            .await
+            .map_err(|e| Error::Storage(format!("Failed to query conversations: {}", e)))
+    }
+
     /// Find conversations by tags
     pub async fn find_by_tags(&self, tags: &[String]) -> Result<Vec<ConversationState>, Error> {
         self.store
@@ -209,60 +314,344 @@ impl<P: Provider> StatefulClient<P> {
             .await
             .map_err(|e| Error::Storage(format!("Failed to find conversations: {}", e)))
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use cogni_core::{ResponseMetadata, Usage};
-    use cogni_state::MemoryStore;
-    use futures::stream;
-    use std::pin::Pin;
+    /// Add a turn's new messages and build its first request
+    async fn begin_turn(
+        &mut self,
+        builder: RequestBuilder,
+        messages: Vec<Message>,
+    ) -> Result<Request, Error> {
+        // Ensure we have a conversation
+        if self.current_state.is_none() {
+            self.new_conversation().await?;
+        }
+        let state = self.state_mut();
+        state.add_messages(messages);
+
+        let mut request = builder
+            .messages(state.messages().to_vec())
+            .try_build()
+            .map_err(|e| Error::Validation(e.to_string()))?;
+        if let Some(registry) = &self.registry {
+            for tool in registry.list_tools().await {
+                if !request.tools.iter().any(|t| t.name == tool.name) {
+                    request.tools.push(tool);
+                }
+            }
+        }
+        Ok(request)
+    }
 
-    // Mock provider for testing
-    struct MockProvider {
-        responses: std::sync::Mutex<Vec<Response>>,
-        current_index: std::sync::Mutex<usize>,
+    /// Record an assistant reply and its token usage
+    fn record_reply(&mut self, message: Message, usage: Option<&Usage>) {
+        let state = self.state_mut();
+        if message.content.as_text() != Some("") || message.has_tool_calls() {
+            state.add_message(message);
+        }
+        if let Some(usage) = usage {
+            let current_count = state.metadata.token_count.unwrap_or(0);
+            state.update_token_count(current_count + usage.total_tokens);
+        }
+    }
+
+    /// Answer tool calls left over when the tool round limit is reached
+    ///
+    /// Providers reject histories with unanswered tool calls, so each gets an
+    /// error result rather than poisoning the conversation.
+    fn decline_tool_calls(&mut self, calls: &[ToolCall]) {
+        if self.registry.is_none() || calls.is_empty() {
+            return;
+        }
+        let messages = calls
+            .iter()
+            .map(|call| Message::tool("error: tool round limit reached", &call.id));
+        self.state_mut().add_messages(messages);
     }
 
-    impl MockProvider {
-        fn new() -> Self {
-            Self {
-                responses: std::sync::Mutex::new(vec![]),
-                current_index: std::sync::Mutex::new(0),
+    /// Whether tool calls from round `round` should be executed
+    fn should_execute(&self, calls: &[ToolCall], round: usize) -> bool {
+        self.registry.is_some() && !calls.is_empty() && round < self.max_tool_rounds
+    }
+
+    /// Execute tool calls with the registry and record their results
+    async fn execute_tool_calls(&mut self, calls: &[ToolCall]) {
+        let Some(registry) = self.registry.clone() else {
+            return;
+        };
+        let results = registry.execute_many(calls).await;
+        let messages = calls.iter().zip(results).map(|(call, result)| {
+            let output = match result {
+                Ok(result) if result.success => result.content,
+                Ok(result) => format!("error: {}", result.content),
+                Err(e) => format!("error: {}", e),
+            };
+            let mut message = Message::tool(output, &call.id);
+            // Keep approvals, edits and denials in the transcript
+            if let Some(record) = registry.take_approval(&call.id) {
+                message.metadata.custom.extend(record.metadata());
             }
+            message
+        });
+        self.state_mut().add_messages(messages);
+    }
+
+    /// Finish a turn, saving the conversation if auto-save is enabled
+    async fn end_turn(&mut self) -> Result<(), Error> {
+        if self.auto_save {
+            self.save().await?;
         }
+        Ok(())
+    }
+
+    fn state_mut(&mut self) -> &mut ConversationState {
+        self.current_state
+            .as_mut()
+            .expect("current_state should exist after begin_turn")
+    }
+}
+
+/// Builder for a single turn of a [`StatefulClient`] conversation
+pub struct StatefulRequestBuilder<'a, P: Provider> {
+    client: &'a mut StatefulClient<P>,
+    builder: RequestBuilder,
+    messages: Vec<Message>,
+}
+
+impl<'a, P: Provider> StatefulRequestBuilder<'a, P> {
+    /// Add a user message
+    pub fn user(self, content: impl Into<String>) -> Self {
+        self.message(Message::user(content))
+    }
+
+    /// Add a message, such as a tool result answering an earlier call
+    pub fn message(mut self, message: Message) -> Self {
+        self.messages.push(message);
+        self
+    }
+
+    /// Add multiple messages
+    pub fn messages(mut self, messages: impl IntoIterator<Item = Message>) -> Self {
+        self.messages.extend(messages);
+        self
+    }
+
+    /// Set the model
+    pub fn model(mut self, model: impl Into<Model>) -> Self {
+        self.builder = self.builder.model(model);
+        self
+    }
+
+    /// Set the temperature
+    pub fn temperature(mut self, temperature: f32) -> Self {
+        self.builder = self.builder.temperature(temperature);
+        self
+    }
+
+    /// Set max tokens
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.builder = self.builder.max_tokens(max_tokens);
+        self
+    }
+
+    /// Set parameters
+    pub fn parameters(mut self, parameters: Parameters) -> Self {
+        self.builder = self.builder.parameters(parameters);
+        self
+    }
 
-        fn with_response(self, response: Response) -> Self {
-            self.responses.lock().unwrap().push(response);
-            self
+    /// Add one or more tools
+    pub fn tools(mut self, tools: impl IntoIterator<Item = Tool>) -> Self {
+        self.builder = self.builder.tools(tools);
+        self
+    }
+
+    /// Set the response format
+    pub fn response_format(mut self, format: ResponseFormat) -> Self {
+        self.builder = self.builder.response_format(format);
+        self
+    }
+
+    /// Request structured output of a specific type
+    pub fn with_structured_output<T: StructuredOutput>(mut self) -> Self {
+        self.builder = self.builder.with_structured_output::<T>();
+        self
+    }
+
+    /// Request JSON object output
+    pub fn json_mode(mut self) -> Self {
+        self.builder = self.builder.json_mode();
+        self
+    }
+
+    /// Send the turn and record it in the conversation
+    ///
+    /// With a tool registry, tool calls are executed and sent back to the
+    /// model until it replies without calling tools. The final response is
+    /// returned.
+    pub async fn send(self) -> Result<Response, Error> {
+        let client = self.client;
+        let mut request = client.begin_turn(self.builder, self.messages).await?;
+
+        let mut round = 0;
+        loop {
+            let response = client.client.provider.request(request.clone()).await?;
+            client.record_reply(response.to_message(), response.metadata.usage.as_ref());
+
+            if !client.should_execute(&response.tool_calls, round) {
+                client.decline_tool_calls(&response.tool_calls);
+                client.end_turn().await?;
+                return Ok(response);
+            }
+            client.execute_tool_calls(&response.tool_calls).await;
+            request.messages = client.state_mut().messages().to_vec();
+            round += 1;
         }
     }
 
-    #[async_trait::async_trait]
-    impl Provider for MockProvider {
-        type Stream =
-            Pin<Box<dyn futures::Stream<Item = Result<cogni_core::StreamEvent, Error>> + Send>>;
-
-        async fn request(&self, _request: Request) -> Result<Response, Error> {
-            let mut index = self.current_index.lock().unwrap();
-            let responses = self.responses.lock().unwrap();
-
-            if *index < responses.len() {
-                let response = responses[*index].clone();
-                *index += 1;
-                Ok(response)
-            } else {
-                Ok(Response {
-                    content: "Default response".to_string(),
-                    tool_calls: vec![],
-                    metadata: ResponseMetadata::default(),
-                })
+    /// Send the turn and stream the response
+    ///
+    /// Events are accumulated and the assistant's reply is recorded when the
+    /// provider sends [`StreamEvent::Done`]; a stream that ends without it
+    /// yields an error and the partial reply is dropped. With a tool registry, tool calls are executed when a
+    /// round ends and the next round's events follow in the same stream; a
+    /// single [`StreamEvent::Done`] marks the end of the turn.
+    pub async fn stream(
+        self,
+    ) -> Result<Pin<Box<dyn Stream<Item = Result<StreamEvent, Error>> + Send + 'a>>, Error> {
+        let client = self.client;
+        let request = client.begin_turn(self.builder, self.messages).await?;
+        let stream = client.client.provider.stream(request.clone()).await?;
+
+        let turn = StreamTurn {
+            client,
+            request,
+            stream: Some(stream),
+            accumulator: StreamAccumulator::new(),
+            round: 0,
+            finished: false,
+        };
+        Ok(Box::pin(futures::stream::unfold(
+            turn,
+            |mut turn| async move { turn.next().await.map(|event| (event, turn)) },
+        )))
+    }
+}
+
+/// State of a streamed turn between events
+struct StreamTurn<'a, P: Provider> {
+    client: &'a mut StatefulClient<P>,
+    request: Request,
+    stream: Option<P::Stream>,
+    accumulator: StreamAccumulator,
+    round: usize,
+    finished: bool,
+}
+
+impl<P: Provider> StreamTurn<'_, P> {
+    async fn next(&mut self) -> Option<Result<StreamEvent, Error>> {
+        loop {
+            if self.finished {
+                return None;
+            }
+
+            let stream = match &mut self.stream {
+                Some(stream) => stream,
+                None => match self
+                    .client
+                    .client
+                    .provider
+                    .stream(self.request.clone())
+                    .await
+                {
+                    Ok(stream) => self.stream.insert(stream),
+                    Err(e) => return Some(self.fail(e)),
+                },
+            };
+
+            match stream.next().await {
+                Some(Ok(StreamEvent::Done)) => match self.finish_round().await {
+                    Ok(true) => continue,
+                    Ok(false) => {
+                        self.finished = true;
+                        return Some(Ok(StreamEvent::Done));
+                    }
+                    Err(e) => return Some(self.fail(e)),
+                },
+                Some(Ok(event)) => {
+                    if let Err(e) = self.accumulator.process_event(event.clone()) {
+                        return Some(self.fail(e));
+                    }
+                    return Some(Ok(event));
+                }
+                Some(Err(e)) => return Some(self.fail(e)),
+                // A reply cut off mid-stream is not recorded
+                None => {
+                    return Some(self.fail(Error::ResponseError {
+                        message: "Stream ended before the response was complete".to_string(),
+                    }))
+                }
             }
         }
+    }
+
+    /// Record the round's reply; returns whether another round follows
+    async fn finish_round(&mut self) -> Result<bool, Error> {
+        self.stream = None;
+        let accumulator = std::mem::take(&mut self.accumulator);
+        let tool_calls = accumulator.tool_calls();
+        self.client.record_reply(
+            Message::assistant_with_tools(accumulator.content(), tool_calls.clone()),
+            accumulator.usage().as_ref(),
+        );
+
+        if !self.client.should_execute(&tool_calls, self.round) {
+            self.client.decline_tool_calls(&tool_calls);
+            self.client.end_turn().await?;
+            return Ok(false);
+        }
+        self.client.execute_tool_calls(&tool_calls).await;
+        self.request.messages = self.client.state_mut().messages().to_vec();
+        self.round += 1;
+        Ok(true)
+    }
+
+    fn fail(&mut self, error: Error) -> Result<StreamEvent, Error> {
+        self.finished = true;
+        Err(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cogni_core::testing::MockProvider;
+    use cogni_core::{ContentDelta, MetadataDelta, ResponseMetadata, Role, ToolCallDelta};
+    use cogni_state::MemoryStore;
+    use std::collections::HashMap;
+
+    async fn calculator_registry() -> Arc<ToolRegistry> {
+        let tool = cogni_tools::FunctionExecutorBuilder::new("add")
+            .description("Add two numbers")
+            .parameters(serde_json::json!({
+                "type": "object",
+                "properties": {
+                    "a": { "type": "number" },
+                    "b": { "type": "number" }
+                },
+                "required": ["a", "b"]
+            }))
+            .build_sync(|args| {
+                let sum = args["a"].as_f64().unwrap_or(0.0) + args["b"].as_f64().unwrap_or(0.0);
+                Ok(serde_json::json!({ "sum": sum }))
+            });
+        Arc::new(ToolRegistry::from_executors([tool]).await.unwrap())
+    }
 
-        async fn stream(&self, _request: Request) -> Result<Self::Stream, Error> {
-            Ok(Box::pin(stream::empty()))
+    fn add_call() -> ToolCall {
+        ToolCall {
+            id: "call_1".to_string(),
+            name: "add".to_string(),
+            arguments: r#"{"a":2,"b":3}"#.to_string(),
         }
     }
 
@@ -295,17 +684,17 @@ mod tests {
 
         // Check state was updated
         let state = stateful.current_state().unwrap();
-        assert_eq!(state.messages.len(), 2);
-        assert_eq!(state.messages[0].content.as_text(), Some("Hello!"));
+        assert_eq!(state.messages().len(), 2);
+        assert_eq!(state.messages()[0].content.as_text(), Some("Hello!"));
         assert_eq!(
-            state.messages[1].content.as_text(),
+            state.messages()[1].content.as_text(),
             Some("Hello! How can I help you?")
         );
         assert_eq!(state.metadata.token_count, Some(18));
 
         // Verify it was saved
         let loaded = store.load(&id).await.unwrap();
-        assert_eq!(loaded.messages.len(), 2);
+        assert_eq!(loaded.messages().len(), 2);
     }
 
     #[tokio::test]
@@ -350,4 +739,269 @@ mod tests {
         let remaining = stateful.list_conversations().await.unwrap();
         assert_eq!(remaining.len(), 1);
     }
+
+    #[tokio::test]
+    async fn test_tool_registry_executes_calls() {
+        let provider = MockProvider::new()
+            .with_response(Response {
+                content: String::new(),
+                tool_calls: vec![add_call()],
+                metadata: ResponseMetadata::default(),
+            })
+            .with_response(Response::text("2 + 3 = 5"));
+
+        let store = Arc::new(MemoryStore::new());
+        let mut stateful = StatefulClient::new(Client::new(provider.clone()), store)
+            .with_tool_registry(calculator_registry().await);
+
+        let response = stateful
+            .request()
+            .user("What is 2 + 3?")
+            .temperature(0.0)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.content, "2 + 3 = 5");
+
+        let messages = stateful.current_state().unwrap().messages();
+        let roles: Vec<Role> = messages.iter().map(|m| m.role).collect();
+        assert_eq!(
+            roles,
+            [Role::User, Role::Assistant, Role::Tool, Role::Assistant]
+        );
+        assert_eq!(messages[1].tool_calls, vec![add_call()]);
+        assert_eq!(messages[2].metadata.tool_call_id.as_deref(), Some("call_1"));
+        assert_eq!(messages[2].content.as_text(), Some(r#"{"sum":5.0}"#));
+
+        let requests = provider.requests();
+        assert_eq!(requests.len(), 2);
+        assert_eq!(requests[0].tools[0].name, "add");
+        assert_eq!(requests[0].parameters.temperature, Some(0.0));
+        assert_eq!(requests[1].messages.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_tool_approvals_are_recorded() {
+        use cogni_tools::{ApprovalDecision, ApprovalHandler, ApprovalPolicy};
+
+        struct Doubler;
+
+        #[async_trait::async_trait]
+        impl ApprovalHandler for Doubler {
+            async fn review(&self, call: &ToolCall) -> ApprovalDecision {
+                match call.id.as_str() {
+                    "call_1" => ApprovalDecision::Edit {
+                        arguments: serde_json::json!({ "a": 20, "b": 30 }),
+                    },
+                    _ => ApprovalDecision::deny("too big"),
+                }
+            }
+        }
+
+        let denied = ToolCall {
+            id: "call_2".to_string(),
+            ..add_call()
+        };
+        let provider = MockProvider::new()
+            .with_response(Response {
+                content: String::new(),
+                tool_calls: vec![add_call(), denied],
+                metadata: ResponseMetadata::default(),
+            })
+            .with_response(Response::text("Done"));
+        let tool = cogni_tools::FunctionExecutorBuilder::new("add").build_sync(|args| {
+            Ok(serde_json::json!(
+                args["a"].as_f64().unwrap_or(0.0) + args["b"].as_f64().unwrap_or(0.0)
+            ))
+        });
+        let registry = ToolRegistry::from_executors([tool])
+            .await
+            .unwrap()
+            .with_approval(ApprovalPolicy::new(Doubler));
+
+        let mut stateful = StatefulClient::new(Client::new(provider), Arc::new(MemoryStore::new()))
+            .with_tool_registry(Arc::new(registry));
+        stateful.chat("Add 2 and 3").await.unwrap();
+
+        let messages = stateful.current_state().unwrap().messages();
+        let edited = &messages[2].metadata.custom;
+        assert_eq!(messages[2].content.as_text(), Some("50.0"));
+        assert_eq!(edited["approval"], "edited");
+        assert_eq!(edited["approval_arguments"], r#"{"a":20,"b":30}"#);
+        let denied = &messages[3].metadata.custom;
+        assert_eq!(denied["approval"], "denied");
+        assert_eq!(denied["approval_reason"], "too big");
+    }
+
+    #[tokio::test]
+    async fn test_stream_chat_records_reply() {
+        let provider = MockProvider::new().with_stream(vec![
+            StreamEvent::Content(ContentDelta {
+                text: "Hel".to_string(),
+            }),
+            StreamEvent::Content(ContentDelta {
+                text: "lo".to_string(),
+            }),
+            StreamEvent::Metadata(MetadataDelta {
+                custom: HashMap::from([
+                    ("input_tokens".to_string(), "12".to_string()),
+                    ("output_tokens".to_string(), "3".to_string()),
+                ]),
+                ..Default::default()
+            }),
+            StreamEvent::Done,
+        ]);
+        let store = Arc::new(MemoryStore::new());
+        let mut stateful = StatefulClient::new(Client::new(provider), store.clone());
+
+        let events: Vec<_> = stateful.stream_chat("Hi").await.unwrap().collect().await;
+        assert_eq!(events.len(), 4);
+
+        let state = stateful.current_state().unwrap();
+        assert_eq!(state.messages().len(), 2);
+        assert_eq!(state.messages()[1].content.as_text(), Some("Hello"));
+        assert_eq!(state.metadata.token_count, Some(15));
+
+        let loaded = store.load(&state.id).await.unwrap();
+        assert_eq!(loaded.messages().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_stream_without_done_is_not_recorded() {
+        let provider = MockProvider::new().with_stream(vec![StreamEvent::Content(ContentDelta {
+            text: "Hel".to_string(),
+        })]);
+        let store = Arc::new(MemoryStore::new());
+        let mut stateful = StatefulClient::new(Client::new(provider), store.clone());
+
+        let events: Vec<_> = stateful.stream_chat("Hi").await.unwrap().collect().await;
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[1], Err(Error::ResponseError { .. })));
+
+        let state = stateful.current_state().unwrap();
+        assert_eq!(state.messages().len(), 1);
+        let loaded = store.load(&state.id).await.unwrap();
+        assert!(loaded.messages().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_save_tracks_stored_version() {
+        let store = Arc::new(MemoryStore::new());
+        let mut stateful = StatefulClient::new(Client::new(MockProvider::new()), store.clone());
+
+        let id = stateful.new_conversation().await.unwrap();
+        assert_eq!(stateful.current_state().unwrap().version, 1);
+        stateful.chat("Hi").await.unwrap();
+        assert_eq!(stateful.current_state().unwrap().version, 2);
+        stateful.save().await.unwrap();
+        assert_eq!(stateful.current_state().unwrap().version, 3);
+
+        assert_eq!(store.load(&id).await.unwrap().version, 3);
+    }
+
+    #[tokio::test]
+    async fn test_stream_executes_tool_calls() {
+        let provider = MockProvider::new()
+            .with_stream(vec![
+                StreamEvent::ToolCall(ToolCallDelta {
+                    index: 0,
+                    id: Some("call_1".to_string()),
+                    name: Some("add".to_string()),
+                    arguments: Some(r#"{"a":2,"b":3}"#.to_string()),
+                }),
+                StreamEvent::Done,
+            ])
+            .with_stream(vec![
+                StreamEvent::Content(ContentDelta {
+                    text: "5".to_string(),
+                }),
+                StreamEvent::Done,
+            ]);
+        let store = Arc::new(MemoryStore::new());
+        let mut stateful = StatefulClient::new(Client::new(provider), store)
+            .with_tool_registry(calculator_registry().await);
+
+        let events: Vec<_> = stateful
+            .stream_chat("2 + 3?")
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        let done = events
+            .iter()
+            .filter(|e| matches!(e, Ok(StreamEvent::Done)))
+            .count();
+        assert_eq!(done, 1);
+
+        let messages = stateful.current_state().unwrap().messages();
+        assert_eq!(messages.len(), 4);
+        assert_eq!(messages[1].tool_calls, vec![add_call()]);
+        assert_eq!(messages[3].content.as_text(), Some("5"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_round_limit_answers_calls() {
+        let tool_response = || Response {
+            content: String::new(),
+            tool_calls: vec![add_call()],
+            metadata: ResponseMetadata::default(),
+        };
+        let provider = MockProvider::new()
+            .with_response(tool_response())
+            .with_response(tool_response())
+            .with_response(Response::text("Fine"));
+        let mut stateful =
+            StatefulClient::new(Client::new(provider.clone()), Arc::new(MemoryStore::new()))
+                .with_tool_registry(calculator_registry().await)
+                .with_max_tool_rounds(1);
+
+        let response = stateful.chat("Add forever").await.unwrap();
+        assert_eq!(response.tool_calls, vec![add_call()]);
+
+        // Every call in the history is answered, so the next turn is valid
+        let messages = stateful.current_state().unwrap().messages();
+        let roles: Vec<Role> = messages.iter().map(|m| m.role).collect();
+        assert_eq!(
+            roles,
+            [
+                Role::User,
+                Role::Assistant,
+                Role::Tool,
+                Role::Assistant,
+                Role::Tool
+            ]
+        );
+        assert_eq!(
+            messages[4].content.as_text(),
+            Some("error: tool round limit reached")
+        );
+        assert_eq!(messages[4].metadata.tool_call_id.as_deref(), Some("call_1"));
+
+        stateful.chat("Stop").await.unwrap();
+        let requests = provider.requests();
+        assert_eq!(requests[2].messages[4].role, Role::Tool);
+    }
+
+    #[tokio::test]
+    async fn test_regenerate_keeps_previous_reply() {
+        let provider = MockProvider::new()
+            .with_response(Response::text("First"))
+            .with_response(Response::text("Second"));
+        let store = Arc::new(MemoryStore::new());
+        let mut stateful = StatefulClient::new(Client::new(provider), store);
+
+        stateful.chat("Hi").await.unwrap();
+        let response = stateful.regenerate().await.unwrap();
+        assert_eq!(response.content, "Second");
+
+        let state = stateful.current_state().unwrap();
+        assert_eq!(state.messages().len(), 2);
+        let replies: Vec<_> = state
+            .siblings(1)
+            .unwrap()
+            .iter()
+            .map(|n| n.message.content.as_text().unwrap())
+            .collect();
+        assert_eq!(replies, ["First", "Second"]);
+    }
 }
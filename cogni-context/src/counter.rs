@@ -1,11 +1,25 @@
 use async_trait::async_trait;
 use cogni_core::Message;
+#[cfg(feature = "huggingface")]
+use std::path::PathBuf;
 use std::sync::Arc;
 use tiktoken_rs::CoreBPE;
 
 use crate::error::ContextError;
 use crate::types::ModelLimits;
 
+mod approximate;
+#[cfg(feature = "huggingface")]
+mod huggingface;
+#[cfg(feature = "ollama")]
+mod ollama;
+
+pub use self::approximate::ApproximateCounter;
+#[cfg(feature = "huggingface")]
+pub use self::huggingface::{HuggingFaceTokenizerCounter, TOKENIZER_FILE};
+#[cfg(feature = "ollama")]
+pub use self::ollama::{OllamaTokenCounter, OLLAMA_DEFAULT_URL};
+
 #[async_trait]
 pub trait TokenCounter: Send + Sync {
     fn count_text(&self, text: &str) -> usize;
@@ -74,6 +88,85 @@ impl TokenCounter for TiktokenCounter {
     }
 }
 
+/// Picks the most accurate token counter available for a model
+///
+/// In order of preference: tiktoken for OpenAI models, a `tokenizer.json`
+/// found under the tokenizer directory, the calibrated estimate for Claude
+/// models, a plain estimate for models with known [`ModelLimits`], and
+/// finally the Ollama server. Only models nothing else knows cost a request
+/// to Ollama.
+#[derive(Debug, Clone, Default)]
+pub struct CounterSelector {
+    #[cfg(feature = "huggingface")]
+    tokenizer_dir: Option<PathBuf>,
+    #[cfg(feature = "ollama")]
+    ollama_url: Option<String>,
+}
+
+impl CounterSelector {
+    /// Selector that also asks Ollama at its default URL, when enabled
+    pub fn new() -> Self {
+        Self {
+            #[cfg(feature = "huggingface")]
+            tokenizer_dir: None,
+            #[cfg(feature = "ollama")]
+            ollama_url: Some(OLLAMA_DEFAULT_URL.to_string()),
+        }
+    }
+
+    /// Look for tokenizers at `<dir>/<model>/tokenizer.json`
+    #[cfg(feature = "huggingface")]
+    pub fn with_tokenizer_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.tokenizer_dir = Some(dir.into());
+        self
+    }
+
+    /// Ask the Ollama server at this URL, or not at all with `None`
+    #[cfg(feature = "ollama")]
+    pub fn with_ollama_url(mut self, url: Option<String>) -> Self {
+        self.ollama_url = url;
+        self
+    }
+
+    pub async fn select(&self, model: &str) -> Result<Arc<dyn TokenCounter>, ContextError> {
+        if let Ok(counter) = TiktokenCounter::for_model(model) {
+            return Ok(Arc::new(counter));
+        }
+
+        #[cfg(feature = "huggingface")]
+        if let Some(dir) = &self.tokenizer_dir {
+            let dir = dir.join(model);
+            if dir.join(TOKENIZER_FILE).is_file() {
+                return Ok(Arc::new(HuggingFaceTokenizerCounter::from_dir(dir, model)?));
+            }
+        }
+
+        if approximate::is_anthropic_model(model) {
+            return Ok(Arc::new(ApproximateCounter::anthropic(model)?));
+        }
+
+        if let Some(limits) = ModelLimits::for_model(model) {
+            return Ok(Arc::new(ApproximateCounter::new(limits)));
+        }
+
+        #[cfg(feature = "ollama")]
+        if let Some(url) = &self.ollama_url {
+            match OllamaTokenCounter::connect_to(url, model).await {
+                Ok(counter) => return Ok(Arc::new(counter)),
+                Err(e) => tracing::debug!("Ollama counter unavailable for {}: {}", model, e),
+            }
+        }
+
+        Err(ContextError::UnsupportedModel(model.to_string()))
+    }
+}
+
+/// Pick the most accurate token counter available for a model with the
+/// default [`CounterSelector`]
+pub async fn counter_for_model(model: &str) -> Result<Arc<dyn TokenCounter>, ContextError> {
+    CounterSelector::new().select(model).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -111,4 +204,45 @@ mod tests {
         let total_count = counter.count_messages(&messages);
         assert!(total_count > count);
     }
+
+    #[tokio::test]
+    async fn test_counter_for_model() {
+        let selector = CounterSelector::new();
+        #[cfg(feature = "ollama")]
+        let selector = selector.with_ollama_url(None);
+
+        let counter = selector.select("gpt-4").await.unwrap();
+        assert_eq!(counter.model_context_window(), 8192);
+
+        let counter = selector.select("claude-3-5-haiku-20241022").await.unwrap();
+        assert_eq!(counter.model_context_window(), 200000);
+        assert_eq!(counter.count_text("abcdefg"), 2);
+
+        let counter = selector.select("mistral-small-latest").await.unwrap();
+        assert_eq!(counter.model_context_window(), 32768);
+
+        assert!(matches!(
+            selector.select("unknown-model").await,
+            Err(ContextError::UnsupportedModel(_))
+        ));
+    }
+
+    #[cfg(feature = "ollama")]
+    #[tokio::test]
+    async fn test_known_models_skip_ollama() {
+        // A server that accepts connections and never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let selector = CounterSelector::new().with_ollama_url(Some(url));
+
+        let counter = tokio::time::timeout(
+            std::time::Duration::from_secs(1),
+            selector.select("mistral-small-latest"),
+        )
+        .await
+        .expect("known models should not wait for Ollama")
+        .unwrap();
+        assert_eq!(counter.model_context_window(), 32768);
+        drop(listener);
+    }
 }
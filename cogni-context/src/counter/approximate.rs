@@ -0,0 +1,149 @@
+//! Token estimates from text length
+
+use super::TokenCounter;
+use crate::error::ContextError;
+use crate::types::ModelLimits;
+
+/// Counts tokens by estimating them from the length of the text
+///
+/// ASCII text is divided by a characters-per-token ratio calibrated for the
+/// model's tokenizer. Every other character counts as a token of its own,
+/// which is close for CJK scripts and errs high for accented Latin text.
+#[derive(Debug, Clone)]
+pub struct ApproximateCounter {
+    chars_per_token: f64,
+    model_limits: ModelLimits,
+}
+
+impl ApproximateCounter {
+    /// Ratio used when nothing is known about the tokenizer
+    pub const DEFAULT_CHARS_PER_TOKEN: f64 = 4.0;
+
+    /// Ratio Anthropic documents for Claude models ("a token approximately
+    /// represents 3.5 English characters"); use
+    /// [`recalibrate`](Self::recalibrate) with counts from the Messages
+    /// `count_tokens` endpoint where the text differs much from English prose
+    pub const ANTHROPIC_CHARS_PER_TOKEN: f64 = 3.5;
+
+    pub fn new(model_limits: ModelLimits) -> Self {
+        Self {
+            chars_per_token: Self::DEFAULT_CHARS_PER_TOKEN,
+            model_limits,
+        }
+    }
+
+    /// Counter for Claude models, using [`Self::ANTHROPIC_CHARS_PER_TOKEN`]
+    pub fn anthropic(model: &str) -> Result<Self, ContextError> {
+        if !is_anthropic_model(model) {
+            return Err(ContextError::UnsupportedModel(format!(
+                "{}: not a Claude model",
+                model
+            )));
+        }
+        let model_limits = ModelLimits::for_model(model).unwrap_or_else(|| ModelLimits {
+            context_window: 200000,
+            max_output_tokens: 4096,
+            model_name: model.to_string(),
+        });
+        Ok(Self::new(model_limits).with_chars_per_token(Self::ANTHROPIC_CHARS_PER_TOKEN))
+    }
+
+    /// Counter calibrated from a sample whose exact token count is known
+    pub fn calibrated(sample: &str, tokens: usize, model_limits: ModelLimits) -> Self {
+        Self::new(model_limits).recalibrate(sample, tokens)
+    }
+
+    /// Replace the ratio with one measured on a sample whose exact token count
+    /// is known, such as one counted by the provider
+    ///
+    /// Samples that can't give a ratio, such as ones without ASCII text, leave
+    /// the current ratio in place.
+    pub fn recalibrate(self, sample: &str, tokens: usize) -> Self {
+        let (ascii, other) = split_chars(sample);
+        match tokens.checked_sub(other) {
+            Some(ascii_tokens) if ascii_tokens > 0 && ascii > 0 => {
+                self.with_chars_per_token(ascii as f64 / ascii_tokens as f64)
+            }
+            _ => self,
+        }
+    }
+
+    pub fn with_chars_per_token(mut self, chars_per_token: f64) -> Self {
+        if chars_per_token.is_finite() && chars_per_token > 0.0 {
+            self.chars_per_token = chars_per_token;
+        }
+        self
+    }
+
+    pub fn chars_per_token(&self) -> f64 {
+        self.chars_per_token
+    }
+
+    pub fn model_limits(&self) -> &ModelLimits {
+        &self.model_limits
+    }
+}
+
+impl TokenCounter for ApproximateCounter {
+    fn count_text(&self, text: &str) -> usize {
+        let (ascii, other) = split_chars(text);
+        (ascii as f64 / self.chars_per_token).ceil() as usize + other
+    }
+
+    fn model_context_window(&self) -> usize {
+        self.model_limits.context_window
+    }
+}
+
+/// Whether the model name refers to a Claude model, with or without a
+/// provider prefix such as `anthropic/`
+pub(super) fn is_anthropic_model(model: &str) -> bool {
+    model
+        .rsplit('/')
+        .next()
+        .is_some_and(|name| name.to_ascii_lowercase().starts_with("claude"))
+}
+
+/// Count ASCII and non-ASCII characters
+fn split_chars(text: &str) -> (usize, usize) {
+    let ascii = text.bytes().filter(u8::is_ascii).count();
+    (ascii, text.chars().count() - ascii)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approximate_counts() {
+        let counter = ApproximateCounter::anthropic("claude-3-5-sonnet-20241022").unwrap();
+        assert_eq!(counter.model_context_window(), 200000);
+        assert_eq!(counter.count_text(""), 0);
+        assert_eq!(counter.count_text("abcdefg"), 2);
+        assert_eq!(counter.count_text("日本語"), 3);
+
+        assert!(ApproximateCounter::anthropic("gpt-4").is_err());
+        assert!(ApproximateCounter::anthropic("anthropic/claude-3-haiku").is_ok());
+    }
+
+    #[test]
+    fn test_calibration() {
+        let sample = "The quick brown fox jumps over the lazy dog";
+        let counter = ApproximateCounter::calibrated(sample, 9, ModelLimits::default());
+        assert!((counter.chars_per_token() - 43.0 / 9.0).abs() < 1e-9);
+        assert_eq!(counter.count_text(sample), 9);
+
+        let counter = ApproximateCounter::anthropic("claude-3-haiku")
+            .unwrap()
+            .recalibrate("abcdefgh", 4);
+        assert_eq!(counter.chars_per_token(), 2.0);
+        assert_eq!(counter.model_context_window(), 200000);
+
+        // Nonsensical samples leave the default ratio in place
+        let counter = ApproximateCounter::calibrated("日本", 2, ModelLimits::default());
+        assert_eq!(
+            counter.chars_per_token(),
+            ApproximateCounter::DEFAULT_CHARS_PER_TOKEN
+        );
+    }
+}
@@ -0,0 +1,164 @@
+//! Counting with Hugging Face `tokenizer.json` files
+
+use super::TokenCounter;
+use crate::error::ContextError;
+use crate::types::ModelLimits;
+use std::path::Path;
+use std::sync::Arc;
+use tokenizers::Tokenizer;
+
+/// File name of a serialized Hugging Face tokenizer
+pub const TOKENIZER_FILE: &str = "tokenizer.json";
+
+/// `tokenizer_config.json` values above this are placeholders for "no limit"
+const MAX_PLAUSIBLE_CONTEXT: u64 = 100_000_000;
+
+/// Counts tokens exactly with a tokenizer loaded from a `tokenizer.json`
+/// file, as published with Llama, Mistral, Gemma and most open models
+#[derive(Clone)]
+pub struct HuggingFaceTokenizerCounter {
+    tokenizer: Arc<Tokenizer>,
+    model_limits: ModelLimits,
+}
+
+impl HuggingFaceTokenizerCounter {
+    /// Load a tokenizer file for a model with the given limits
+    pub fn from_file(
+        path: impl AsRef<Path>,
+        model_limits: ModelLimits,
+    ) -> Result<Self, ContextError> {
+        let path = path.as_ref();
+        let tokenizer = Tokenizer::from_file(path).map_err(|e| {
+            ContextError::TokenCountingError(format!(
+                "failed to load tokenizer {}: {}",
+                path.display(),
+                e
+            ))
+        })?;
+        Ok(Self::with_tokenizer(tokenizer, model_limits))
+    }
+
+    /// Load `tokenizer.json` from a model directory, such as a Hugging Face
+    /// snapshot
+    ///
+    /// The context window comes from [`ModelLimits::for_model`], or else from
+    /// `model_max_length` in the directory's `tokenizer_config.json`.
+    pub fn from_dir(dir: impl AsRef<Path>, model: &str) -> Result<Self, ContextError> {
+        let dir = dir.as_ref();
+        let model_limits = match ModelLimits::for_model(model) {
+            Some(limits) => limits,
+            None => limits_from_config(dir, model)?,
+        };
+        Self::from_file(dir.join(TOKENIZER_FILE), model_limits)
+    }
+
+    pub fn with_tokenizer(tokenizer: Tokenizer, model_limits: ModelLimits) -> Self {
+        Self {
+            tokenizer: Arc::new(tokenizer),
+            model_limits,
+        }
+    }
+
+    pub fn tokenizer(&self) -> &Tokenizer {
+        &self.tokenizer
+    }
+}
+
+impl std::fmt::Debug for HuggingFaceTokenizerCounter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("HuggingFaceTokenizerCounter")
+            .field("vocab_size", &self.tokenizer.get_vocab_size(true))
+            .field("model_limits", &self.model_limits)
+            .finish()
+    }
+}
+
+impl TokenCounter for HuggingFaceTokenizerCounter {
+    fn count_text(&self, text: &str) -> usize {
+        match self.tokenizer.encode(text, false) {
+            Ok(encoding) => encoding.len(),
+            Err(e) => {
+                tracing::warn!("Tokenizer failed, estimating token count: {}", e);
+                text.len().div_ceil(4)
+            }
+        }
+    }
+
+    fn model_context_window(&self) -> usize {
+        self.model_limits.context_window
+    }
+}
+
+fn limits_from_config(dir: &Path, model: &str) -> Result<ModelLimits, ContextError> {
+    let unsupported =
+        || ContextError::UnsupportedModel(format!("{}: unknown context window", model));
+    let config =
+        std::fs::read_to_string(dir.join("tokenizer_config.json")).map_err(|_| unsupported())?;
+    let config: serde_json::Value = serde_json::from_str(&config).map_err(|e| {
+        ContextError::InvalidConfiguration(format!("invalid tokenizer_config.json: {}", e))
+    })?;
+    let context_window = config["model_max_length"]
+        .as_f64()
+        .filter(|&n| n >= 1.0 && n <= MAX_PLAUSIBLE_CONTEXT as f64)
+        .ok_or_else(unsupported)? as usize;
+
+    Ok(ModelLimits {
+        context_window,
+        max_output_tokens: ModelLimits::default().max_output_tokens.min(context_window),
+        model_name: model.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_tokenizer(dir: &Path) {
+        let tokenizer = json!({
+            "version": "1.0",
+            "truncation": null,
+            "padding": null,
+            "added_tokens": [],
+            "normalizer": null,
+            "pre_tokenizer": { "type": "Whitespace" },
+            "post_processor": null,
+            "decoder": null,
+            "model": {
+                "type": "WordLevel",
+                "vocab": { "hello": 0, "world": 1, "[UNK]": 2 },
+                "unk_token": "[UNK]"
+            }
+        });
+        std::fs::write(dir.join(TOKENIZER_FILE), tokenizer.to_string()).unwrap();
+    }
+
+    #[test]
+    fn test_huggingface_counter() {
+        let dir = tempfile::tempdir().unwrap();
+        write_tokenizer(dir.path());
+
+        let counter = HuggingFaceTokenizerCounter::from_dir(dir.path(), "llama3.1:8b").unwrap();
+        assert_eq!(counter.count_text("hello, world"), 3);
+        assert_eq!(counter.model_context_window(), 131072);
+
+        // Unknown models need a context window from tokenizer_config.json
+        assert!(matches!(
+            HuggingFaceTokenizerCounter::from_dir(dir.path(), "my-model"),
+            Err(ContextError::UnsupportedModel(_))
+        ));
+        std::fs::write(
+            dir.path().join("tokenizer_config.json"),
+            r#"{"model_max_length": 2048}"#,
+        )
+        .unwrap();
+        let counter = HuggingFaceTokenizerCounter::from_dir(dir.path(), "my-model").unwrap();
+        assert_eq!(counter.model_context_window(), 2048);
+
+        assert!(HuggingFaceTokenizerCounter::from_file(
+            dir.path().join("missing.json"),
+            ModelLimits::default()
+        )
+        .is_err());
+    }
+}
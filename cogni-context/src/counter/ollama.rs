@@ -0,0 +1,273 @@
+//! Counting with a local Ollama server
+
+use super::{ApproximateCounter, TokenCounter};
+use crate::error::ContextError;
+use crate::types::ModelLimits;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+/// Base URL of a default local Ollama install
+pub const OLLAMA_DEFAULT_URL: &str = "http://localhost:11434";
+
+/// How long to wait for a connection, kept short so probing a machine
+/// without Ollama fails fast
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Limit on a whole request, which may include loading the model
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Text tokenized once on connect to calibrate estimates for the model
+const CALIBRATION_SAMPLE: &str = "\
+The context manager keeps a conversation within the model's context window. \
+When the messages grow too long, older turns are pruned or summarized so the \
+most recent exchange always fits.\n\
+fn main() {\n    let total: usize = (1..=10).map(|n| n * n).sum();\n    println!(\"{}\", total);\n}\n";
+
+/// Counts tokens for models served by Ollama
+///
+/// Connecting reads the model's context window from `/api/show` and, on
+/// servers that expose `/api/tokenize`, calibrates the synchronous
+/// [`TokenCounter`] estimates against the model's own tokenizer. Use
+/// [`OllamaTokenCounter::count_text_exact`] where an exact count is worth a
+/// round trip.
+#[derive(Debug, Clone)]
+pub struct OllamaTokenCounter {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    estimate: ApproximateCounter,
+}
+
+impl OllamaTokenCounter {
+    /// Connect to Ollama at [`OLLAMA_DEFAULT_URL`]
+    pub async fn connect(model: &str) -> Result<Self, ContextError> {
+        Self::connect_to(OLLAMA_DEFAULT_URL, model).await
+    }
+
+    pub async fn connect_to(base_url: &str, model: &str) -> Result<Self, ContextError> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(CONNECT_TIMEOUT)
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| {
+                ContextError::TokenCountingError(format!("failed to build Ollama client: {}", e))
+            })?;
+        let mut counter = Self {
+            client,
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            estimate: ApproximateCounter::new(ModelLimits::default()),
+        };
+
+        let info = counter.post("show", json!({ "model": model })).await?;
+        let model_limits = limits_from_show(model, &info)?;
+
+        counter.estimate = match counter.tokenize(CALIBRATION_SAMPLE).await {
+            Ok(tokens) => {
+                ApproximateCounter::calibrated(CALIBRATION_SAMPLE, tokens.len(), model_limits)
+            }
+            Err(e) => {
+                tracing::debug!("Ollama tokenization unavailable, using estimates: {}", e);
+                ApproximateCounter::new(model_limits)
+            }
+        };
+        Ok(counter)
+    }
+
+    pub fn model(&self) -> &str {
+        &self.model
+    }
+
+    pub fn model_limits(&self) -> &ModelLimits {
+        self.estimate.model_limits()
+    }
+
+    /// Tokenize text with the model's tokenizer
+    pub async fn tokenize(&self, text: &str) -> Result<Vec<u32>, ContextError> {
+        let response = self
+            .post("tokenize", json!({ "model": self.model, "content": text }))
+            .await?;
+        serde_json::from_value(response["tokens"].clone()).map_err(|e| {
+            ContextError::TokenCountingError(format!("invalid Ollama tokenize response: {}", e))
+        })
+    }
+
+    /// Count tokens with the model's tokenizer rather than an estimate
+    pub async fn count_text_exact(&self, text: &str) -> Result<usize, ContextError> {
+        Ok(self.tokenize(text).await?.len())
+    }
+
+    async fn post(&self, endpoint: &str, body: Value) -> Result<Value, ContextError> {
+        let url = format!("{}/api/{}", self.base_url, endpoint);
+        let error = |e: reqwest::Error| {
+            ContextError::TokenCountingError(format!("Ollama request to {} failed: {}", url, e))
+        };
+        let response = self
+            .client
+            .post(&url)
+            .json(&body)
+            .send()
+            .await
+            .map_err(error)?;
+
+        match response.status() {
+            reqwest::StatusCode::NOT_FOUND if endpoint == "show" => Err(
+                ContextError::UnsupportedModel(format!("{}: not available in Ollama", self.model)),
+            ),
+            _ => response
+                .error_for_status()
+                .map_err(error)?
+                .json()
+                .await
+                .map_err(error),
+        }
+    }
+}
+
+impl TokenCounter for OllamaTokenCounter {
+    fn count_text(&self, text: &str) -> usize {
+        self.estimate.count_text(text)
+    }
+
+    fn model_context_window(&self) -> usize {
+        self.estimate.model_context_window()
+    }
+}
+
+/// Read the context window from an `/api/show` response, preferring the
+/// `num_ctx` the model is configured to run with over what it was trained on
+fn limits_from_show(model: &str, info: &Value) -> Result<ModelLimits, ContextError> {
+    let num_ctx = info["parameters"].as_str().and_then(|parameters| {
+        parameters.lines().find_map(|line| {
+            let mut parts = line.split_whitespace();
+            match (parts.next(), parts.next()) {
+                (Some("num_ctx"), Some(value)) => value.parse().ok(),
+                _ => None,
+            }
+        })
+    });
+    let trained = info["model_info"].as_object().and_then(|model_info| {
+        model_info
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+            .map(|n| n as usize)
+    });
+    let known = ModelLimits::for_model(model);
+
+    let context_window = num_ctx
+        .or(trained)
+        .or(known.as_ref().map(|limits| limits.context_window))
+        .ok_or_else(|| {
+            ContextError::UnsupportedModel(format!("{}: unknown context window", model))
+        })?;
+    let max_output_tokens = known
+        .map(|limits| limits.max_output_tokens)
+        .unwrap_or(ModelLimits::default().max_output_tokens)
+        .min(context_window);
+
+    Ok(ModelLimits {
+        context_window,
+        max_output_tokens,
+        model_name: model.to_string(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// Serve canned `/api/show` and `/api/tokenize` responses, tokenizing on
+    /// whitespace
+    async fn spawn_ollama(tokenize: bool) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let mut request = Vec::new();
+                    let mut buf = [0; 4096];
+                    let (head, body) = loop {
+                        let n = stream.read(&mut buf).await.unwrap();
+                        request.extend_from_slice(&buf[..n]);
+                        let text = String::from_utf8_lossy(&request).to_string();
+                        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+                            let length = head
+                                .lines()
+                                .find_map(|l| {
+                                    l.to_ascii_lowercase()
+                                        .strip_prefix("content-length:")
+                                        .map(|v| v.trim().parse::<usize>().unwrap())
+                                })
+                                .unwrap_or(0);
+                            if body.len() >= length || n == 0 {
+                                break (head.to_string(), body.to_string());
+                            }
+                        }
+                    };
+                    let body: Value = serde_json::from_str(&body).unwrap();
+                    let path = head.split_whitespace().nth(1).unwrap_or_default();
+
+                    let (status, response) = match path {
+                        "/api/show" if body["model"] == "llama3.1:8b" => (
+                            "200 OK",
+                            json!({
+                                "parameters": "stop \"<|eot_id|>\"\nnum_ctx 16384",
+                                "model_info": { "llama.context_length": 131072 },
+                            }),
+                        ),
+                        "/api/show" => ("404 Not Found", json!({ "error": "model not found" })),
+                        "/api/tokenize" if tokenize => {
+                            let text = body["content"].as_str().unwrap();
+                            let tokens: Vec<u32> =
+                                (0..text.split_whitespace().count() as u32).collect();
+                            ("200 OK", json!({ "tokens": tokens }))
+                        }
+                        _ => ("404 Not Found", json!({ "error": "not found" })),
+                    };
+                    let response = response.to_string();
+                    let reply = format!(
+                        "HTTP/1.1 {}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                        status,
+                        response.len(),
+                        response
+                    );
+                    let _ = stream.write_all(reply.as_bytes()).await;
+                });
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_ollama_counter() {
+        let url = spawn_ollama(true).await;
+        let counter = OllamaTokenCounter::connect_to(&url, "llama3.1:8b")
+            .await
+            .unwrap();
+        assert_eq!(counter.model_context_window(), 16384);
+        assert_eq!(counter.count_text_exact("one two three").await.unwrap(), 3);
+
+        // Estimates are calibrated against the server's tokenizer
+        let words = CALIBRATION_SAMPLE.split_whitespace().count();
+        let estimate = counter.count_text(CALIBRATION_SAMPLE);
+        assert!((words..=words + 1).contains(&estimate));
+
+        assert!(matches!(
+            OllamaTokenCounter::connect_to(&url, "missing").await,
+            Err(ContextError::UnsupportedModel(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_ollama_counter_without_tokenize() {
+        let url = spawn_ollama(false).await;
+        let counter = OllamaTokenCounter::connect_to(&url, "llama3.1:8b")
+            .await
+            .unwrap();
+        assert!(counter.count_text("hello world") > 0);
+        assert!(counter.count_text_exact("hello").await.is_err());
+    }
+}
@@ -4,7 +4,13 @@ pub mod manager;
 pub mod strategies;
 pub mod types;
 
-pub use counter::{TiktokenCounter, TokenCounter};
+pub use counter::{
+    counter_for_model, ApproximateCounter, CounterSelector, TiktokenCounter, TokenCounter,
+};
+#[cfg(feature = "huggingface")]
+pub use counter::{HuggingFaceTokenizerCounter, TOKENIZER_FILE};
+#[cfg(feature = "ollama")]
+pub use counter::{OllamaTokenCounter, OLLAMA_DEFAULT_URL};
 pub use error::ContextError;
 pub use manager::ContextManager;
 pub use strategies::{ImportanceBasedStrategy, PruningStrategy, SlidingWindowStrategy};
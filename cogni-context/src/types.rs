@@ -89,12 +89,61 @@ impl ModelLimits {
                 model_name: model.to_string(),
             },
 
-            _ => return None,
+            _ => return Self::for_family(model),
         };
 
         Some(limits)
     }
 
+    /// Limits for dated or tagged variants of known model families, such as
+    /// `claude-3-5-sonnet-20241022` or `llama3.1:8b`
+    fn for_family(model: &str) -> Option<Self> {
+        let name = model.to_ascii_lowercase();
+        let name = name.rsplit('/').next().unwrap_or(&name);
+        let (context_window, max_output_tokens) =
+            if name.starts_with("claude-3-5") || name.starts_with("claude-3.5") {
+                (200000, 8192)
+            } else if name.starts_with("claude-3-7")
+                || name.starts_with("claude-sonnet-4")
+                || name.starts_with("claude-opus-4")
+            {
+                (200000, 32000)
+            } else if name.starts_with("claude-3") {
+                (200000, 4096)
+            } else if name.starts_with("llama3.1")
+                || name.starts_with("llama3.2")
+                || name.starts_with("llama3.3")
+                || name.starts_with("llama-3.1")
+                || name.starts_with("llama-3.2")
+                || name.starts_with("llama-3.3")
+            {
+                (131072, 4096)
+            } else if name.starts_with("llama3") || name.starts_with("llama-3") {
+                (8192, 4096)
+            } else if name.starts_with("llama2") || name.starts_with("llama-2") {
+                (4096, 2048)
+            } else if name.starts_with("mistral-large")
+                || name.starts_with("mistral-nemo")
+                || name.starts_with("open-mistral-nemo")
+            {
+                (131072, 4096)
+            } else if name.starts_with("mixtral") || name.starts_with("mistral") {
+                (32768, 4096)
+            } else if name.starts_with("gemini-1.5") || name.starts_with("gemini-2") {
+                (1048576, 8192)
+            } else if name.starts_with("gemini") {
+                (32768, 8192)
+            } else {
+                return None;
+            };
+
+        Some(Self {
+            context_window,
+            max_output_tokens,
+            model_name: model.to_string(),
+        })
+    }
+
     pub fn available_tokens(&self, reserve_output: usize) -> usize {
         self.context_window
             .saturating_sub(reserve_output.min(self.max_output_tokens))
@@ -117,6 +166,26 @@ mod tests {
         assert!(ModelLimits::for_model("unknown-model").is_none());
     }
 
+    #[test]
+    fn test_model_family_limits() {
+        let limits = ModelLimits::for_model("claude-3-5-sonnet-20241022").unwrap();
+        assert_eq!(limits.context_window, 200000);
+        assert_eq!(limits.max_output_tokens, 8192);
+        assert_eq!(limits.model_name, "claude-3-5-sonnet-20241022");
+
+        let limits = ModelLimits::for_model("llama3.1:8b").unwrap();
+        assert_eq!(limits.context_window, 131072);
+
+        let limits = ModelLimits::for_model("meta-llama/Llama-3-8B-Instruct").unwrap();
+        assert_eq!(limits.context_window, 8192);
+
+        let limits = ModelLimits::for_model("mistral-large-latest").unwrap();
+        assert_eq!(limits.context_window, 131072);
+
+        let limits = ModelLimits::for_model("gemini-1.5-pro").unwrap();
+        assert_eq!(limits.context_window, 1048576);
+    }
+
     #[test]
     fn test_available_tokens() {
         let limits = ModelLimits::for_model("gpt-4").unwrap();
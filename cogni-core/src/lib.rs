@@ -8,7 +8,10 @@
 
 pub mod error;
 pub mod provider;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
 pub mod types;
+pub mod validation;
 
 // Re-export commonly used items
 pub use error::{Error, Result};
@@ -21,6 +24,7 @@ pub use types::{
     structured::{ResponseFormat, StructuredOutput},
     tool::{Function, Tool, ToolCall, ToolChoice, ToolResult},
 };
+pub use validation::{CompiledSchema, ValidationError};
 
 // Re-export derive macro when feature is enabled
 #[cfg(feature = "derive")]
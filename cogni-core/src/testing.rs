@@ -0,0 +1,89 @@
+//! Test doubles for code built on [`Provider`]
+//!
+//! Enabled by the `test-util` feature.
+
+use crate::error::Result;
+use crate::provider::Provider;
+use crate::types::request::Request;
+use crate::types::response::Response;
+use crate::types::stream::StreamEvent;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+
+/// A provider that replays scripted replies and records every request
+///
+/// Replies are consumed in order. Once they run out, requests are answered
+/// with "ok" and streams end immediately. Clones share the same script and
+/// request log, so keep a clone to inspect requests after handing the
+/// provider to a client.
+#[derive(Debug, Clone, Default)]
+pub struct MockProvider {
+    responses: Arc<Mutex<VecDeque<Result<Response>>>>,
+    streams: Arc<Mutex<VecDeque<Vec<StreamEvent>>>>,
+    requests: Arc<Mutex<Vec<Request>>>,
+}
+
+impl MockProvider {
+    /// Create a provider with no scripted replies
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a reply for [`Provider::request`]
+    pub fn with_response(self, response: Response) -> Self {
+        self.responses.lock().unwrap().push_back(Ok(response));
+        self
+    }
+
+    /// Queue a failure for [`Provider::request`]
+    pub fn with_error(self, error: crate::Error) -> Self {
+        self.responses.lock().unwrap().push_back(Err(error));
+        self
+    }
+
+    /// Queue the events of one [`Provider::stream`] call
+    pub fn with_stream(self, events: Vec<StreamEvent>) -> Self {
+        self.streams.lock().unwrap().push_back(events);
+        self
+    }
+
+    /// Requests received so far, oldest first
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Provider for MockProvider {
+    type Stream = MockStream;
+
+    async fn request(&self, request: Request) -> Result<Response> {
+        self.requests.lock().unwrap().push(request);
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Ok(Response::text("ok")))
+    }
+
+    async fn stream(&self, request: Request) -> Result<Self::Stream> {
+        self.requests.lock().unwrap().push(request);
+        let events = self.streams.lock().unwrap().pop_front().unwrap_or_default();
+        Ok(MockStream(events.into()))
+    }
+}
+
+/// Stream of scripted events returned by [`MockProvider`]
+#[derive(Debug)]
+pub struct MockStream(VecDeque<StreamEvent>);
+
+impl futures_core::Stream for MockStream {
+    type Item = Result<StreamEvent>;
+
+    fn poll_next(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        Poll::Ready(self.0.pop_front().map(Ok))
+    }
+}
@@ -1,5 +1,6 @@
 //! Message types for conversations
 
+use crate::types::tool::ToolCall;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -68,6 +69,12 @@ pub struct Message {
     pub role: Role,
     /// The content of the message
     pub content: Content,
+    /// Tools the assistant asked to call in this turn
+    ///
+    /// Each call is answered by a [`Role::Tool`] message whose
+    /// `metadata.tool_call_id` matches the call's ID.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
     /// Additional metadata
     pub metadata: Metadata,
 }
@@ -78,6 +85,7 @@ impl Message {
         Self {
             role,
             content: Content::Text(text.into()),
+            tool_calls: Vec::new(),
             metadata: Metadata::default(),
         }
     }
@@ -97,12 +105,29 @@ impl Message {
         Self::text(Role::Assistant, text)
     }
 
+    /// Create an assistant message that requests tool calls
+    ///
+    /// `text` may be empty when the model only called tools.
+    pub fn assistant_with_tools(
+        text: impl Into<String>,
+        tool_calls: impl IntoIterator<Item = ToolCall>,
+    ) -> Self {
+        let mut msg = Self::assistant(text);
+        msg.tool_calls = tool_calls.into_iter().collect();
+        msg
+    }
+
     /// Create a tool message
     pub fn tool(text: impl Into<String>, tool_call_id: impl Into<String>) -> Self {
         let mut msg = Self::text(Role::Tool, text);
         msg.metadata.tool_call_id = Some(tool_call_id.into());
         msg
     }
+
+    /// Check if the message requests tool calls
+    pub fn has_tool_calls(&self) -> bool {
+        !self.tool_calls.is_empty()
+    }
 }
 
 impl Content {
@@ -3,10 +3,11 @@
 use crate::types::message::Message;
 use crate::types::structured::ResponseFormat;
 use crate::types::tool::Tool;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// A model identifier
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Model(pub String);
 
 impl Model {
@@ -41,7 +42,7 @@ impl std::fmt::Display for Model {
 }
 
 /// Parameters for controlling LLM generation
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct Parameters {
     /// Maximum tokens to generate
     pub max_tokens: Option<u32>,
@@ -106,7 +107,7 @@ impl ParametersBuilder {
 }
 
 /// A request to an LLM
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Request {
     /// The conversation messages
     pub messages: Vec<Message>,
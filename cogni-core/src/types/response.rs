@@ -1,14 +1,18 @@
 //! Response types for LLM interactions
 
+use crate::types::message::Message;
 use crate::types::structured::StructuredOutput;
 use crate::types::tool::ToolCall;
+use crate::validation::CompiledSchema;
 use crate::Error;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::any::TypeId;
 use std::collections::HashMap;
 use std::fmt;
+use std::sync::{Arc, Mutex, OnceLock};
 
 /// Metadata about a response
-#[derive(Debug, Clone, Default, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct ResponseMetadata {
     /// Model used for generation
     pub model: Option<String>,
@@ -23,7 +27,7 @@ pub struct ResponseMetadata {
 }
 
 /// Token usage statistics
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Usage {
     /// Tokens in the prompt
     pub prompt_tokens: u32,
@@ -34,7 +38,7 @@ pub struct Usage {
 }
 
 /// Why the model stopped generating
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum FinishReason {
     /// Natural end of message
     Stop,
@@ -49,7 +53,7 @@ pub enum FinishReason {
 }
 
 /// A complete response from an LLM
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Response {
     /// The generated content
     pub content: String,
@@ -74,19 +78,38 @@ impl Response {
         !self.tool_calls.is_empty()
     }
 
+    /// The assistant message recording this response in a conversation,
+    /// including any tool calls
+    pub fn to_message(&self) -> Message {
+        Message::assistant_with_tools(&self.content, self.tool_calls.iter().cloned())
+    }
+
     /// Parse the response content as structured output
+    ///
+    /// The content is checked against `T::schema()` first, so a response that
+    /// deserializes but breaks a constraint such as `minimum` or `pattern` is
+    /// rejected with [`Error::Validation`] naming the offending fields. The
+    /// schema is compiled on first use for each `T` and kept.
     pub fn parse_structured<T>(&self) -> Result<T, Error>
     where
-        T: StructuredOutput + for<'de> Deserialize<'de>,
+        T: StructuredOutput + for<'de> Deserialize<'de> + 'static,
     {
-        let value: T = serde_json::from_str(&self.content).map_err(|e| Error::ResponseError {
+        let value = serde_json::from_str(&self.content).map_err(|e| Error::ResponseError {
             message: format!("Failed to parse structured response: {}", e),
         })?;
 
-        // TODO: Optional schema validation could be added here
-        // validate_against_schema(&value, &T::schema())?;
+        let schema = structured_schema::<T>()?;
+        if let Err(errors) = schema.validate(&value) {
+            let errors: Vec<String> = errors.iter().map(ToString::to_string).collect();
+            return Err(Error::Validation(format!(
+                "Structured response does not match schema: {}",
+                errors.join("; ")
+            )));
+        }
 
-        Ok(value)
+        serde_json::from_value(value).map_err(|e| Error::ResponseError {
+            message: format!("Failed to parse structured response: {}", e),
+        })
     }
 
     /// Try to parse the response content as JSON Value
@@ -97,6 +120,21 @@ impl Response {
     }
 }
 
+/// The compiled schema of `T`, compiled once per type
+fn structured_schema<T: StructuredOutput + 'static>() -> Result<Arc<CompiledSchema>, Error> {
+    static SCHEMAS: OnceLock<Mutex<HashMap<TypeId, Arc<CompiledSchema>>>> = OnceLock::new();
+    let schemas = SCHEMAS.get_or_init(Default::default);
+    if let Some(schema) = schemas.lock().unwrap().get(&TypeId::of::<T>()) {
+        return Ok(schema.clone());
+    }
+    let schema = Arc::new(CompiledSchema::compile(&T::schema())?);
+    schemas
+        .lock()
+        .unwrap()
+        .insert(TypeId::of::<T>(), schema.clone());
+    Ok(schema)
+}
+
 impl fmt::Display for Response {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.content)?;
@@ -1,5 +1,6 @@
 //! Streaming types for incremental responses
 
+use crate::types::response::Usage;
 use crate::types::tool::ToolCall;
 use std::collections::HashMap;
 
@@ -104,6 +105,30 @@ impl StreamAccumulator {
         &self.content
     }
 
+    /// Token usage reported in the stream's metadata, if any
+    ///
+    /// Reads `prompt_tokens`/`completion_tokens`/`total_tokens` or Anthropic's
+    /// `input_tokens`/`output_tokens`.
+    pub fn usage(&self) -> Option<Usage> {
+        let count = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| self.metadata.get(*key)?.parse::<u32>().ok())
+        };
+        let prompt_tokens = count(&["prompt_tokens", "input_tokens"]);
+        let completion_tokens = count(&["completion_tokens", "output_tokens"]);
+        let total_tokens = count(&["total_tokens"]);
+        if prompt_tokens.is_none() && completion_tokens.is_none() && total_tokens.is_none() {
+            return None;
+        }
+        let prompt_tokens = prompt_tokens.unwrap_or(0);
+        let completion_tokens = completion_tokens.unwrap_or(0);
+        Some(Usage {
+            prompt_tokens,
+            completion_tokens,
+            total_tokens: total_tokens.unwrap_or(prompt_tokens + completion_tokens),
+        })
+    }
+
     /// Convert accumulated tool calls to complete ones
     pub fn tool_calls(&self) -> Vec<ToolCall> {
         self.tool_calls
@@ -38,6 +38,190 @@ pub enum ResponseFormat {
     JsonObject,
 }
 
+/// Runtime support for schemas generated by `#[derive(StructuredOutput)]`
+///
+/// Nested types are stored once under `$defs` at the root of the schema and
+/// referenced with `$ref`. A type that refers to itself, directly or through
+/// other types, points back to the root with `"#"`.
+#[doc(hidden)]
+pub mod __derive {
+    use super::StructuredOutput;
+    use serde_json::{Map, Value};
+    use std::cell::RefCell;
+
+    thread_local! {
+        /// Types whose schema is currently being generated
+        static IN_PROGRESS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Name of a type under `$defs`, e.g. `Page_Item` for `a::Page<b::Item>`
+    pub fn def_name<T: ?Sized>() -> String {
+        let mut name = String::new();
+        let mut segment = String::new();
+        for c in std::any::type_name::<T>().chars() {
+            match c {
+                ':' => segment.clear(),
+                c if c.is_alphanumeric() || c == '_' => segment.push(c),
+                _ => {
+                    push_segment(&mut name, &mut segment);
+                }
+            }
+        }
+        push_segment(&mut name, &mut segment);
+        name
+    }
+
+    fn push_segment(name: &mut String, segment: &mut String) {
+        if !segment.is_empty() {
+            if !name.is_empty() {
+                name.push('_');
+            }
+            name.push_str(segment);
+            segment.clear();
+        }
+    }
+
+    /// Generate the schema of `T`, attaching the definitions it collected
+    pub fn root<T: ?Sized>(body: impl FnOnce(&mut Map<String, Value>) -> Value) -> Value {
+        let name = def_name::<T>();
+        IN_PROGRESS.with(|stack| stack.borrow_mut().push(name.clone()));
+        let mut defs = Map::new();
+        let schema = body(&mut defs);
+        IN_PROGRESS.with(|stack| stack.borrow_mut().pop());
+
+        let mut schema = with_defs(schema, defs);
+        rewrite_refs(&mut schema, &def_ref(&name), "#");
+        schema
+    }
+
+    /// Attach collected definitions to a schema
+    pub fn with_defs(mut schema: Value, defs: Map<String, Value>) -> Value {
+        if !defs.is_empty() {
+            if let Value::Object(object) = &mut schema {
+                object.insert("$defs".to_string(), Value::Object(defs));
+            }
+        }
+        schema
+    }
+
+    /// Reference `T`, adding its schema to `defs` the first time
+    pub fn reference<T: StructuredOutput>(defs: &mut Map<String, Value>) -> Value {
+        let name = def_name::<T>();
+        let in_progress = IN_PROGRESS.with(|stack| stack.borrow().contains(&name));
+        if !in_progress && !defs.contains_key(&name) {
+            let schema = hoist::<T>(&name, defs);
+            defs.insert(name.clone(), schema);
+        }
+        serde_json::json!({ "$ref": def_ref(&name) })
+    }
+
+    /// Schema of `T` written out in place, with its definitions moved to `defs`
+    pub fn inline<T: StructuredOutput>(defs: &mut Map<String, Value>) -> Value {
+        let name = def_name::<T>();
+        let schema = hoist::<T>(&name, defs);
+        if refers_to(&schema, &def_ref(&name)) && !defs.contains_key(&name) {
+            defs.insert(name, schema.clone());
+        }
+        schema
+    }
+
+    /// Allow `null` in addition to what a schema accepts
+    ///
+    /// Schemas with a `type` get `"null"` added to it, and to their `enum`;
+    /// others are wrapped in `anyOf`.
+    pub fn nullable(mut schema: Value) -> Value {
+        let null = Value::from("null");
+        match schema.get_mut("type") {
+            Some(Value::String(ty)) => {
+                let ty = Value::from(std::mem::take(ty));
+                schema["type"] = Value::Array(vec![ty, null]);
+            }
+            Some(Value::Array(types)) if !types.contains(&null) => types.push(null),
+            Some(Value::Array(_)) => return schema,
+            _ => return serde_json::json!({ "anyOf": [schema, { "type": "null" }] }),
+        }
+        if let Some(Value::Array(values)) = schema.get_mut("enum") {
+            if !values.contains(&Value::Null) {
+                values.push(Value::Null);
+            }
+        }
+        schema
+    }
+
+    /// Merge the properties of a flattened `T` into a containing object
+    pub fn flatten<T: StructuredOutput>(
+        properties: &mut Map<String, Value>,
+        required: &mut Vec<Value>,
+        optional: bool,
+        defs: &mut Map<String, Value>,
+    ) {
+        let mut schema = inline::<T>(defs);
+        // Newtypes around another type resolve to a reference
+        while let Some(target) = schema["$ref"].as_str() {
+            match target
+                .strip_prefix("#/$defs/")
+                .and_then(|name| defs.get(name))
+            {
+                Some(resolved) => schema = resolved.clone(),
+                None => break,
+            }
+        }
+        if let Some(Value::Object(nested)) = schema.get("properties") {
+            properties.extend(nested.clone());
+        }
+        if !optional {
+            if let Some(Value::Array(nested)) = schema.get("required") {
+                required.extend(nested.iter().cloned());
+            }
+        }
+    }
+
+    fn hoist<T: StructuredOutput>(name: &str, defs: &mut Map<String, Value>) -> Value {
+        let mut schema = T::schema();
+        rewrite_refs(&mut schema, "#", &def_ref(name));
+        if let Some(Value::Object(nested)) = schema.as_object_mut().and_then(|o| o.remove("$defs"))
+        {
+            for (key, value) in nested {
+                defs.entry(key).or_insert(value);
+            }
+        }
+        schema
+    }
+
+    fn def_ref(name: &str) -> String {
+        format!("#/$defs/{}", name)
+    }
+
+    fn rewrite_refs(value: &mut Value, from: &str, to: &str) {
+        match value {
+            Value::Object(object) => {
+                for (key, value) in object.iter_mut() {
+                    match value {
+                        Value::String(target) if key == "$ref" && target == from => {
+                            *target = to.to_string();
+                        }
+                        _ => rewrite_refs(value, from, to),
+                    }
+                }
+            }
+            Value::Array(items) => items
+                .iter_mut()
+                .for_each(|item| rewrite_refs(item, from, to)),
+            _ => {}
+        }
+    }
+
+    fn refers_to(value: &Value, target: &str) -> bool {
+        match value {
+            Value::Object(object) => object
+                .iter()
+                .any(|(key, value)| (key == "$ref" && value == target) || refers_to(value, target)),
+            Value::Array(items) => items.iter().any(|item| refers_to(item, target)),
+            _ => false,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -172,6 +356,65 @@ mod tests {
         assert!(examples.is_empty());
     }
 
+    #[test]
+    fn test_def_name() {
+        assert_eq!(__derive::def_name::<TestStruct>(), "TestStruct");
+        assert_eq!(
+            __derive::def_name::<std::collections::HashMap<String, Vec<TestStruct>>>(),
+            "HashMap_String_Vec_TestStruct"
+        );
+    }
+
+    #[test]
+    fn test_parse_structured_validates_schema() {
+        use crate::{Error, Response};
+
+        let parsed: TestStruct = Response::text(r#"{"name":"Alice","age":30,"active":true}"#)
+            .parse_structured()
+            .unwrap();
+        assert_eq!(parsed.name, "Alice");
+
+        let err = Response::text(r#"{"name":"Alice","age":30.5}"#)
+            .parse_structured::<TestStruct>()
+            .unwrap_err();
+        match err {
+            Error::Validation(message) => assert_eq!(
+                message,
+                "Structured response does not match schema: \
+                 (root): missing required property 'active'; /age: expected integer, got number"
+            ),
+            other => panic!("unexpected error: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_structured_compiles_schema_once() {
+        use crate::Response;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        static SCHEMA_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        #[derive(Serialize, Deserialize)]
+        struct Counted {
+            value: u32,
+        }
+
+        impl StructuredOutput for Counted {
+            fn schema() -> Value {
+                SCHEMA_CALLS.fetch_add(1, Ordering::SeqCst);
+                json!({ "type": "object", "properties": { "value": { "type": "integer" } } })
+            }
+        }
+
+        for value in 0..3 {
+            let parsed: Counted = Response::text(format!(r#"{{"value":{}}}"#, value))
+                .parse_structured()
+                .unwrap();
+            assert_eq!(parsed.value, value);
+        }
+        assert_eq!(SCHEMA_CALLS.load(Ordering::SeqCst), 1);
+    }
+
     #[test]
     fn test_response_format_clone() {
         let format = ResponseFormat::JsonSchema {
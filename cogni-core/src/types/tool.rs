@@ -1,7 +1,9 @@
 //! Tool/function calling types
 
+use serde::{Deserialize, Serialize};
+
 /// A tool that can be called by the model
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Tool {
     /// The name of the tool
     pub name: String,
@@ -12,7 +14,7 @@ pub struct Tool {
 }
 
 /// Function definition for a tool
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Function {
     /// JSON Schema for the parameters
     pub parameters: serde_json::Value,
@@ -21,9 +23,10 @@ pub struct Function {
 }
 
 /// How the model should use tools
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Default)]
 pub enum ToolChoice {
     /// Let the model decide
+    #[default]
     Auto,
     /// Never call tools
     None,
@@ -33,14 +36,8 @@ pub enum ToolChoice {
     Specific(String),
 }
 
-impl Default for ToolChoice {
-    fn default() -> Self {
-        Self::Auto
-    }
-}
-
 /// A tool call requested by the model
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct ToolCall {
     /// Unique ID for this call
     pub id: String,
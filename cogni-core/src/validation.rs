@@ -0,0 +1,1298 @@
+//! JSON Schema validation
+//!
+//! [`CompiledSchema`] checks values against the assertions of JSON Schema
+//! draft 2020-12. It is used for tool arguments and structured output, so it
+//! covers what models are asked to produce: types, `enum`/`const`, numeric and
+//! string bounds, `pattern`, `format`, arrays (`items`, `prefixItems`,
+//! `contains`, `uniqueItems`), objects (`properties`, `patternProperties`,
+//! `additionalProperties`, `required`, dependencies), the `allOf`/`anyOf`/
+//! `oneOf`/`not`/`if` applicators, and `$ref` within the same document.
+//! Schemas using `unevaluatedProperties` or `unevaluatedItems` are rejected
+//! when compiled rather than silently accepting everything.
+//!
+//! `pattern` and `patternProperties` are compiled with the [`regex`] crate
+//! rather than an ECMA-262 engine. Most patterns behave the same, but
+//! lookaround (`(?=…)`, `(?!…)`, `(?<=…)`, `(?<!…)`) and backreferences
+//! (`\1`) are not supported, and schemas using them fail to compile.
+//!
+//! Errors carry JSON pointers to the offending value and to the schema keyword
+//! that rejected it.
+
+use crate::error::{Error, Result};
+use regex::Regex;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::OnceLock;
+
+/// Limit on nested `$ref`s, which guards against reference cycles
+const MAX_DEPTH: usize = 256;
+
+/// Keywords that depend on annotations collected from other keywords, which
+/// the validator doesn't track
+const UNSUPPORTED_KEYWORDS: &[&str] = &["unevaluatedProperties", "unevaluatedItems"];
+
+/// A value that doesn't match a schema
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// JSON pointer to the offending value, empty for the root
+    pub instance_path: String,
+    /// JSON pointer to the schema keyword that failed
+    pub schema_path: String,
+    /// What went wrong
+    pub message: String,
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.instance_path.is_empty() {
+            write!(f, "(root): {}", self.message)
+        } else {
+            write!(f, "{}: {}", self.instance_path, self.message)
+        }
+    }
+}
+
+/// A schema prepared for validation
+///
+/// Compiling checks that the schema is well formed, resolves `$anchor`s and
+/// compiles regular expressions once, so the result should be kept and reused.
+///
+/// # Example
+///
+/// ```
+/// use cogni_core::CompiledSchema;
+/// use serde_json::json;
+///
+/// let schema = CompiledSchema::compile(&json!({
+///     "type": "object",
+///     "properties": { "age": { "type": "integer", "minimum": 0 } },
+///     "required": ["age"]
+/// }))
+/// .unwrap();
+///
+/// assert!(schema.is_valid(&json!({ "age": 30 })));
+/// let errors = schema.validate(&json!({ "age": -1 })).unwrap_err();
+/// assert_eq!(errors[0].instance_path, "/age");
+/// ```
+#[derive(Debug, Clone)]
+pub struct CompiledSchema {
+    root: Value,
+    patterns: HashMap<String, Regex>,
+    anchors: HashMap<String, String>,
+}
+
+impl CompiledSchema {
+    /// Compile a schema
+    pub fn compile(schema: &Value) -> Result<Self> {
+        let mut compiled = Self {
+            root: schema.clone(),
+            patterns: HashMap::new(),
+            anchors: HashMap::new(),
+        };
+        let mut refs = Vec::new();
+        compiled.prepare(schema, "", &mut refs)?;
+        for (path, reference) in refs {
+            compiled.resolve(&reference).ok_or_else(|| {
+                schema_error(&path, &format!("unresolvable reference '{}'", reference))
+            })?;
+        }
+        Ok(compiled)
+    }
+
+    /// The schema as given
+    pub fn schema(&self) -> &Value {
+        &self.root
+    }
+
+    /// Check a value, returning every error found
+    pub fn validate(&self, instance: &Value) -> std::result::Result<(), Vec<ValidationError>> {
+        let errors = self.errors(instance, &self.root, "", "", 0);
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Check whether a value matches
+    pub fn is_valid(&self, instance: &Value) -> bool {
+        self.errors(instance, &self.root, "", "", 0).is_empty()
+    }
+
+    /// Walk the schema, compiling patterns and collecting anchors and references
+    fn prepare(
+        &mut self,
+        schema: &Value,
+        path: &str,
+        refs: &mut Vec<(String, String)>,
+    ) -> Result<()> {
+        let object = match schema {
+            Value::Bool(_) => return Ok(()),
+            Value::Object(object) => object,
+            _ => {
+                return Err(schema_error(
+                    path,
+                    "a schema must be an object or a boolean",
+                ))
+            }
+        };
+
+        if let Some(keyword) = UNSUPPORTED_KEYWORDS
+            .iter()
+            .find(|keyword| object.contains_key(**keyword))
+        {
+            return Err(schema_error(
+                path,
+                &format!("unsupported keyword '{}'", keyword),
+            ));
+        }
+        if let Some(anchor) = object.get("$anchor").and_then(Value::as_str) {
+            self.anchors.insert(anchor.to_string(), path.to_string());
+        }
+        if let Some(reference) = object.get("$ref") {
+            let reference = reference
+                .as_str()
+                .ok_or_else(|| schema_error(path, "$ref must be a string"))?;
+            if !reference.starts_with('#') {
+                return Err(schema_error(
+                    path,
+                    &format!(
+                        "only references within the schema are supported, got '{}'",
+                        reference
+                    ),
+                ));
+            }
+            refs.push((path.to_string(), reference.to_string()));
+        }
+        if let Some(pattern) = object.get("pattern") {
+            let pattern = pattern
+                .as_str()
+                .ok_or_else(|| schema_error(path, "pattern must be a string"))?;
+            self.compile_pattern(pattern, &format!("{}/pattern", path))?;
+        }
+        if let Some(Value::Object(patterns)) = object.get("patternProperties") {
+            for pattern in patterns.keys() {
+                self.compile_pattern(pattern, &format!("{}/patternProperties", path))?;
+            }
+        }
+        if let Some(types) = object.get("type") {
+            let valid = match types {
+                Value::String(name) => is_type_name(name),
+                Value::Array(names) => names
+                    .iter()
+                    .all(|name| name.as_str().is_some_and(is_type_name)),
+                _ => false,
+            };
+            if !valid {
+                return Err(schema_error(path, &format!("invalid type {}", types)));
+            }
+        }
+
+        for (keyword, value) in object {
+            let keyword_path = format!("{}/{}", path, escape(keyword));
+            match keyword.as_str() {
+                "additionalProperties"
+                | "items"
+                | "contains"
+                | "not"
+                | "if"
+                | "then"
+                | "else"
+                | "propertyNames" => self.prepare(value, &keyword_path, refs)?,
+                "properties" | "patternProperties" | "$defs" | "definitions"
+                | "dependentSchemas" => {
+                    let map = value.as_object().ok_or_else(|| {
+                        schema_error(&keyword_path, &format!("{} must be an object", keyword))
+                    })?;
+                    for (name, subschema) in map {
+                        self.prepare(
+                            subschema,
+                            &format!("{}/{}", keyword_path, escape(name)),
+                            refs,
+                        )?;
+                    }
+                }
+                "allOf" | "anyOf" | "oneOf" | "prefixItems" => {
+                    let list = value
+                        .as_array()
+                        .filter(|list| !list.is_empty())
+                        .ok_or_else(|| {
+                            schema_error(
+                                &keyword_path,
+                                &format!("{} must be a non-empty array", keyword),
+                            )
+                        })?;
+                    for (i, subschema) in list.iter().enumerate() {
+                        self.prepare(subschema, &format!("{}/{}", keyword_path, i), refs)?;
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn compile_pattern(&mut self, pattern: &str, path: &str) -> Result<()> {
+        if !self.patterns.contains_key(pattern) {
+            let regex = Regex::new(pattern).map_err(|e| {
+                schema_error(
+                    path,
+                    &format!(
+                        "invalid or unsupported pattern '{}' (lookaround and \
+                         backreferences are not supported): {}",
+                        pattern, e
+                    ),
+                )
+            })?;
+            self.patterns.insert(pattern.to_string(), regex);
+        }
+        Ok(())
+    }
+
+    /// Find the schema a `$ref` points to, with its JSON pointer
+    fn resolve(&self, reference: &str) -> Option<(&Value, String)> {
+        let fragment = reference.strip_prefix('#')?;
+        if fragment.is_empty() || fragment.starts_with('/') {
+            let pointer = percent_decode(fragment);
+            return self.root.pointer(&pointer).map(|schema| (schema, pointer));
+        }
+        let pointer = self.anchors.get(fragment)?;
+        self.root
+            .pointer(pointer)
+            .map(|schema| (schema, pointer.clone()))
+    }
+
+    fn errors(
+        &self,
+        instance: &Value,
+        schema: &Value,
+        instance_path: &str,
+        schema_path: &str,
+        depth: usize,
+    ) -> Vec<ValidationError> {
+        let mut check = Check {
+            schema: self,
+            instance,
+            instance_path,
+            schema_path,
+            depth,
+            errors: Vec::new(),
+        };
+        match schema {
+            Value::Bool(true) => {}
+            Value::Object(object) => check.object(object),
+            _ => check.fail("", "no value is allowed here".to_string()),
+        }
+        check.errors
+    }
+}
+
+/// Validation of one value against one schema object
+struct Check<'a> {
+    schema: &'a CompiledSchema,
+    instance: &'a Value,
+    instance_path: &'a str,
+    schema_path: &'a str,
+    depth: usize,
+    errors: Vec<ValidationError>,
+}
+
+impl Check<'_> {
+    fn fail(&mut self, keyword: &str, message: String) {
+        let schema_path = if keyword.is_empty() {
+            self.schema_path.to_string()
+        } else {
+            format!("{}/{}", self.schema_path, keyword)
+        };
+        self.errors.push(ValidationError {
+            instance_path: self.instance_path.to_string(),
+            schema_path,
+            message,
+        });
+    }
+
+    /// Errors from validating `instance` against a subschema
+    fn sub(
+        &self,
+        instance: &Value,
+        schema: &Value,
+        instance_path: &str,
+        keyword_path: &str,
+    ) -> Vec<ValidationError> {
+        self.schema.errors(
+            instance,
+            schema,
+            instance_path,
+            &format!("{}/{}", self.schema_path, keyword_path),
+            self.depth,
+        )
+    }
+
+    fn object(&mut self, schema: &Map<String, Value>) {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            self.reference(reference);
+        }
+        self.applicators(schema);
+        self.assertions(schema);
+        match self.instance {
+            Value::Number(_) => self.number(schema),
+            Value::String(text) => self.string(schema, text),
+            Value::Array(items) => self.array(schema, items),
+            Value::Object(object) => self.properties(schema, object),
+            _ => {}
+        }
+    }
+
+    fn reference(&mut self, reference: &str) {
+        if self.depth >= MAX_DEPTH {
+            self.fail("$ref", "schema references nest too deeply".to_string());
+            return;
+        }
+        if let Some((target, pointer)) = self.schema.resolve(reference) {
+            let errors = self.schema.errors(
+                self.instance,
+                target,
+                self.instance_path,
+                &pointer,
+                self.depth + 1,
+            );
+            self.errors.extend(errors);
+        }
+    }
+
+    fn applicators(&mut self, schema: &Map<String, Value>) {
+        let instance = self.instance;
+        let path = self.instance_path;
+
+        if let Some(Value::Array(all)) = schema.get("allOf") {
+            for (i, subschema) in all.iter().enumerate() {
+                let errors = self.sub(instance, subschema, path, &format!("allOf/{}", i));
+                self.errors.extend(errors);
+            }
+        }
+
+        if let Some(Value::Array(any)) = schema.get("anyOf") {
+            let results: Vec<_> = any
+                .iter()
+                .enumerate()
+                .map(|(i, subschema)| self.sub(instance, subschema, path, &format!("anyOf/{}", i)))
+                .collect();
+            if results.iter().all(|errors| !errors.is_empty()) {
+                self.fail(
+                    "anyOf",
+                    "does not match any of the allowed schemas".to_string(),
+                );
+                self.errors.extend(closest(results));
+            }
+        }
+
+        if let Some(Value::Array(one)) = schema.get("oneOf") {
+            let results: Vec<_> = one
+                .iter()
+                .enumerate()
+                .map(|(i, subschema)| self.sub(instance, subschema, path, &format!("oneOf/{}", i)))
+                .collect();
+            let matching: Vec<String> = results
+                .iter()
+                .enumerate()
+                .filter(|(_, errors)| errors.is_empty())
+                .map(|(i, _)| i.to_string())
+                .collect();
+            match matching.len() {
+                1 => {}
+                0 => {
+                    self.fail(
+                        "oneOf",
+                        "does not match any of the allowed schemas".to_string(),
+                    );
+                    self.errors.extend(closest(results));
+                }
+                _ => self.fail(
+                    "oneOf",
+                    format!(
+                        "matches more than one schema in oneOf (at indexes {})",
+                        matching.join(", ")
+                    ),
+                ),
+            }
+        }
+
+        if let Some(not) = schema.get("not") {
+            if self.sub(instance, not, path, "not").is_empty() {
+                self.fail("not", "must not match the schema in not".to_string());
+            }
+        }
+
+        if let Some(condition) = schema.get("if") {
+            let (keyword, branch) = if self.sub(instance, condition, path, "if").is_empty() {
+                ("then", schema.get("then"))
+            } else {
+                ("else", schema.get("else"))
+            };
+            if let Some(branch) = branch {
+                let errors = self.sub(instance, branch, path, keyword);
+                self.errors.extend(errors);
+            }
+        }
+    }
+
+    fn assertions(&mut self, schema: &Map<String, Value>) {
+        if let Some(types) = schema.get("type") {
+            let matches = match types {
+                Value::String(name) => has_type(self.instance, name),
+                Value::Array(names) => names
+                    .iter()
+                    .filter_map(Value::as_str)
+                    .any(|name| has_type(self.instance, name)),
+                _ => true,
+            };
+            if !matches {
+                let expected = match types {
+                    Value::Array(names) => names
+                        .iter()
+                        .filter_map(Value::as_str)
+                        .collect::<Vec<_>>()
+                        .join(" or "),
+                    other => other.as_str().unwrap_or_default().to_string(),
+                };
+                self.fail(
+                    "type",
+                    format!("expected {}, got {}", expected, type_name(self.instance)),
+                );
+            }
+        }
+
+        if let Some(Value::Array(allowed)) = schema.get("enum") {
+            if !allowed.iter().any(|value| json_equal(value, self.instance)) {
+                let allowed: Vec<String> = allowed.iter().map(Value::to_string).collect();
+                self.fail(
+                    "enum",
+                    format!(
+                        "must be one of {}, got {}",
+                        allowed.join(", "),
+                        self.instance
+                    ),
+                );
+            }
+        }
+
+        if let Some(expected) = schema.get("const") {
+            if !json_equal(expected, self.instance) {
+                self.fail(
+                    "const",
+                    format!("must be {}, got {}", expected, self.instance),
+                );
+            }
+        }
+    }
+
+    fn number(&mut self, schema: &Map<String, Value>) {
+        let Some(n) = self.instance.as_f64() else {
+            return;
+        };
+        let bound = |keyword: &str| schema.get(keyword).and_then(Value::as_f64);
+
+        if let Some(min) = bound("minimum") {
+            if n < min {
+                self.fail(
+                    "minimum",
+                    format!("{} is less than the minimum of {}", n, min),
+                );
+            }
+        }
+        if let Some(max) = bound("maximum") {
+            if n > max {
+                self.fail(
+                    "maximum",
+                    format!("{} is greater than the maximum of {}", n, max),
+                );
+            }
+        }
+        if let Some(min) = bound("exclusiveMinimum") {
+            if n <= min {
+                self.fail(
+                    "exclusiveMinimum",
+                    format!("{} must be greater than {}", n, min),
+                );
+            }
+        }
+        if let Some(max) = bound("exclusiveMaximum") {
+            if n >= max {
+                self.fail(
+                    "exclusiveMaximum",
+                    format!("{} must be less than {}", n, max),
+                );
+            }
+        }
+        if let Some(divisor) = bound("multipleOf").filter(|divisor| *divisor > 0.0) {
+            let quotient = n / divisor;
+            if (quotient - quotient.round()).abs() > 1e-9 * quotient.abs().max(1.0) {
+                self.fail(
+                    "multipleOf",
+                    format!("{} is not a multiple of {}", n, divisor),
+                );
+            }
+        }
+    }
+
+    fn string(&mut self, schema: &Map<String, Value>, text: &str) {
+        let length = text.chars().count();
+
+        if let Some(min) = schema.get("minLength").and_then(Value::as_u64) {
+            if (length as u64) < min {
+                self.fail(
+                    "minLength",
+                    format!("must be at least {} characters long, got {}", min, length),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxLength").and_then(Value::as_u64) {
+            if (length as u64) > max {
+                self.fail(
+                    "maxLength",
+                    format!("must be at most {} characters long, got {}", max, length),
+                );
+            }
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str) {
+            if let Some(regex) = self.schema.patterns.get(pattern) {
+                if !regex.is_match(text) {
+                    self.fail(
+                        "pattern",
+                        format!("'{}' does not match '{}'", text, pattern),
+                    );
+                }
+            }
+        }
+        if let Some(format) = schema.get("format").and_then(Value::as_str) {
+            if !matches_format(format, text) {
+                self.fail("format", format!("'{}' is not a valid {}", text, format));
+            }
+        }
+    }
+
+    fn array(&mut self, schema: &Map<String, Value>, items: &[Value]) {
+        let path = self.instance_path;
+
+        if let Some(min) = schema.get("minItems").and_then(Value::as_u64) {
+            if (items.len() as u64) < min {
+                self.fail(
+                    "minItems",
+                    format!("must have at least {} items, got {}", min, items.len()),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxItems").and_then(Value::as_u64) {
+            if (items.len() as u64) > max {
+                self.fail(
+                    "maxItems",
+                    format!("must have at most {} items, got {}", max, items.len()),
+                );
+            }
+        }
+        if schema.get("uniqueItems") == Some(&Value::Bool(true)) {
+            'outer: for (i, item) in items.iter().enumerate() {
+                for (j, other) in items.iter().enumerate().skip(i + 1) {
+                    if json_equal(item, other) {
+                        self.fail(
+                            "uniqueItems",
+                            format!("items at indexes {} and {} are equal", i, j),
+                        );
+                        break 'outer;
+                    }
+                }
+            }
+        }
+
+        let prefix = match schema.get("prefixItems") {
+            Some(Value::Array(prefix)) => prefix.as_slice(),
+            _ => &[],
+        };
+        for (i, (item, subschema)) in items.iter().zip(prefix).enumerate() {
+            let errors = self.sub(
+                item,
+                subschema,
+                &format!("{}/{}", path, i),
+                &format!("prefixItems/{}", i),
+            );
+            self.errors.extend(errors);
+        }
+        if let Some(subschema) = schema.get("items") {
+            for (i, item) in items.iter().enumerate().skip(prefix.len()) {
+                if subschema == &Value::Bool(false) {
+                    self.fail(
+                        "items",
+                        format!(
+                            "must have at most {} items, got {}",
+                            prefix.len(),
+                            items.len()
+                        ),
+                    );
+                    break;
+                }
+                let errors = self.sub(item, subschema, &format!("{}/{}", path, i), "items");
+                self.errors.extend(errors);
+            }
+        }
+
+        if let Some(subschema) = schema.get("contains") {
+            let count = items
+                .iter()
+                .enumerate()
+                .filter(|(i, item)| {
+                    self.sub(item, subschema, &format!("{}/{}", path, i), "contains")
+                        .is_empty()
+                })
+                .count() as u64;
+            let min = schema
+                .get("minContains")
+                .and_then(Value::as_u64)
+                .unwrap_or(1);
+            if count < min {
+                self.fail(
+                    "contains",
+                    format!(
+                        "must contain at least {} matching item{}, found {}",
+                        min,
+                        if min == 1 { "" } else { "s" },
+                        count
+                    ),
+                );
+            }
+            if let Some(max) = schema.get("maxContains").and_then(Value::as_u64) {
+                if count > max {
+                    self.fail(
+                        "maxContains",
+                        format!(
+                            "must contain at most {} matching items, found {}",
+                            max, count
+                        ),
+                    );
+                }
+            }
+        }
+    }
+
+    fn properties(&mut self, schema: &Map<String, Value>, object: &Map<String, Value>) {
+        let path = self.instance_path;
+
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    self.fail("required", format!("missing required property '{}'", name));
+                }
+            }
+        }
+        if let Some(min) = schema.get("minProperties").and_then(Value::as_u64) {
+            if (object.len() as u64) < min {
+                self.fail(
+                    "minProperties",
+                    format!(
+                        "must have at least {} properties, got {}",
+                        min,
+                        object.len()
+                    ),
+                );
+            }
+        }
+        if let Some(max) = schema.get("maxProperties").and_then(Value::as_u64) {
+            if (object.len() as u64) > max {
+                self.fail(
+                    "maxProperties",
+                    format!("must have at most {} properties, got {}", max, object.len()),
+                );
+            }
+        }
+        if let Some(Value::Object(dependencies)) = schema.get("dependentRequired") {
+            for (name, needed) in dependencies {
+                if !object.contains_key(name) {
+                    continue;
+                }
+                for other in needed
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(Value::as_str)
+                {
+                    if !object.contains_key(other) {
+                        self.fail(
+                            &format!("dependentRequired/{}", escape(name)),
+                            format!("'{}' is required when '{}' is present", other, name),
+                        );
+                    }
+                }
+            }
+        }
+        if let Some(Value::Object(dependencies)) = schema.get("dependentSchemas") {
+            for (name, subschema) in dependencies {
+                if object.contains_key(name) {
+                    let errors = self.sub(
+                        self.instance,
+                        subschema,
+                        path,
+                        &format!("dependentSchemas/{}", escape(name)),
+                    );
+                    self.errors.extend(errors);
+                }
+            }
+        }
+
+        let properties = schema.get("properties").and_then(Value::as_object);
+        let patterns = schema.get("patternProperties").and_then(Value::as_object);
+        for (name, value) in object {
+            let value_path = format!("{}/{}", path, escape(name));
+            let mut evaluated = false;
+
+            if let Some(subschema) = properties.and_then(|properties| properties.get(name)) {
+                evaluated = true;
+                let errors = self.sub(
+                    value,
+                    subschema,
+                    &value_path,
+                    &format!("properties/{}", escape(name)),
+                );
+                self.errors.extend(errors);
+            }
+            for (pattern, subschema) in patterns.into_iter().flatten() {
+                let matched = self
+                    .schema
+                    .patterns
+                    .get(pattern)
+                    .is_some_and(|regex| regex.is_match(name));
+                if matched {
+                    evaluated = true;
+                    let errors = self.sub(
+                        value,
+                        subschema,
+                        &value_path,
+                        &format!("patternProperties/{}", escape(pattern)),
+                    );
+                    self.errors.extend(errors);
+                }
+            }
+
+            if !evaluated {
+                match schema.get("additionalProperties") {
+                    Some(Value::Bool(false)) => {
+                        self.fail(
+                            "additionalProperties",
+                            format!("unexpected property '{}'", name),
+                        );
+                    }
+                    Some(subschema) => {
+                        let errors =
+                            self.sub(value, subschema, &value_path, "additionalProperties");
+                        self.errors.extend(errors);
+                    }
+                    None => {}
+                }
+            }
+
+            if let Some(names) = schema.get("propertyNames") {
+                let key = Value::String(name.clone());
+                let errors = self.sub(&key, names, &value_path, "propertyNames");
+                self.errors.extend(errors);
+            }
+        }
+    }
+}
+
+/// The errors of the alternative that came closest to matching
+fn closest(results: Vec<Vec<ValidationError>>) -> Vec<ValidationError> {
+    results.into_iter().min_by_key(Vec::len).unwrap_or_default()
+}
+
+fn schema_error(path: &str, message: &str) -> Error {
+    let path = if path.is_empty() { "(root)" } else { path };
+    Error::Validation(format!("Invalid schema at {}: {}", path, message))
+}
+
+fn is_type_name(name: &str) -> bool {
+    matches!(
+        name,
+        "null" | "boolean" | "object" | "array" | "number" | "string" | "integer"
+    )
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match (name, value) {
+        ("null", Value::Null)
+        | ("boolean", Value::Bool(_))
+        | ("object", Value::Object(_))
+        | ("array", Value::Array(_))
+        | ("number", Value::Number(_))
+        | ("string", Value::String(_)) => true,
+        // 1.0 is an integer in JSON Schema
+        ("integer", Value::Number(n)) => {
+            n.is_i64() || n.is_u64() || n.as_f64().is_some_and(|f| f.fract() == 0.0)
+        }
+        _ => false,
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_i64() || n.is_u64() => "integer",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Equality where numbers compare by value, so `1` equals `1.0`
+fn json_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(x), Value::Number(y)) => match (x.as_i64(), y.as_i64()) {
+            (Some(x), Some(y)) => x == y,
+            _ => x.as_f64() == y.as_f64(),
+        },
+        (Value::Array(x), Value::Array(y)) => {
+            x.len() == y.len() && x.iter().zip(y).all(|(x, y)| json_equal(x, y))
+        }
+        (Value::Object(x), Value::Object(y)) => {
+            x.len() == y.len()
+                && x.iter()
+                    .all(|(key, x)| y.get(key).is_some_and(|y| json_equal(x, y)))
+        }
+        _ => a == b,
+    }
+}
+
+/// Escape a key for use in a JSON pointer
+fn escape(key: &str) -> String {
+    key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Decode `%XX` escapes in a URI fragment
+fn percent_decode(fragment: &str) -> String {
+    let bytes = fragment.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' && i + 2 < bytes.len() {
+            let hex = std::str::from_utf8(&bytes[i + 1..i + 3]).unwrap_or_default();
+            if let Ok(byte) = u8::from_str_radix(hex, 16) {
+                decoded.push(byte);
+                i += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[i]);
+        i += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Check the formats models are commonly asked for; others are annotations
+fn matches_format(format: &str, text: &str) -> bool {
+    static DATE_TIME: OnceLock<Regex> = OnceLock::new();
+    static TIME: OnceLock<Regex> = OnceLock::new();
+    static DATE: OnceLock<Regex> = OnceLock::new();
+    static UUID: OnceLock<Regex> = OnceLock::new();
+    let regex = |cell: &'static OnceLock<Regex>, pattern: &str| {
+        cell.get_or_init(|| Regex::new(pattern).expect("valid format pattern"))
+    };
+
+    match format {
+        "date-time" => {
+            regex(
+                &DATE_TIME,
+                r"^(\d{4})-(\d{2})-(\d{2})[Tt]\d{2}:\d{2}:\d{2}(\.\d+)?([Zz]|[+-]\d{2}:\d{2})$",
+            )
+            .is_match(text)
+                && valid_date(&text[..10])
+        }
+        "date" => regex(&DATE, r"^\d{4}-\d{2}-\d{2}$").is_match(text) && valid_date(text),
+        "time" => regex(&TIME, r"^\d{2}:\d{2}:\d{2}(\.\d+)?([Zz]|[+-]\d{2}:\d{2})$").is_match(text),
+        "email" => match text.split_once('@') {
+            Some((local, domain)) => {
+                !local.is_empty()
+                    && !domain.is_empty()
+                    && !domain.contains('@')
+                    && !text.chars().any(char::is_whitespace)
+            }
+            None => false,
+        },
+        "uuid" => regex(
+            &UUID,
+            r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$",
+        )
+        .is_match(text),
+        "ipv4" => text.parse::<Ipv4Addr>().is_ok(),
+        "ipv6" => text.parse::<Ipv6Addr>().is_ok(),
+        "uri" => match text.split_once(':') {
+            Some((scheme, _)) => {
+                scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                    && scheme
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+                    && !text.chars().any(char::is_whitespace)
+            }
+            None => false,
+        },
+        _ => true,
+    }
+}
+
+/// Check the month and day of a `YYYY-MM-DD` date
+fn valid_date(date: &str) -> bool {
+    let mut parts = date.split('-').map(|part| part.parse::<u32>().unwrap_or(0));
+    let (year, month, day) = match (parts.next(), parts.next(), parts.next()) {
+        (Some(year), Some(month), Some(day)) => (year, month, day),
+        _ => return false,
+    };
+    let leap = year % 4 == 0 && (year % 100 != 0 || year % 400 == 0);
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+    (1..=days).contains(&day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn errors(schema: Value, instance: Value) -> Vec<(String, String)> {
+        match CompiledSchema::compile(&schema)
+            .unwrap()
+            .validate(&instance)
+        {
+            Ok(()) => Vec::new(),
+            Err(errors) => errors
+                .into_iter()
+                .map(|error| (error.instance_path, error.schema_path))
+                .collect(),
+        }
+    }
+
+    fn valid(schema: Value, instance: Value) -> bool {
+        CompiledSchema::compile(&schema)
+            .unwrap()
+            .is_valid(&instance)
+    }
+
+    #[test]
+    fn test_types() {
+        assert!(valid(json!({ "type": "integer" }), json!(3)));
+        assert!(valid(json!({ "type": "integer" }), json!(3.0)));
+        assert!(!valid(json!({ "type": "integer" }), json!(3.5)));
+        assert!(valid(json!({ "type": ["string", "null"] }), json!(null)));
+        assert!(!valid(json!({ "type": "object" }), json!([])));
+        assert!(valid(json!(true), json!("anything")));
+        assert!(!valid(json!(false), json!("anything")));
+
+        let error = CompiledSchema::compile(&json!({ "type": "string" }))
+            .unwrap()
+            .validate(&json!(1))
+            .unwrap_err()
+            .remove(0);
+        assert_eq!(error.to_string(), "(root): expected string, got integer");
+    }
+
+    #[test]
+    fn test_enum_and_const() {
+        let schema = json!({ "enum": ["a", 1, { "x": [1] }] });
+        assert!(valid(schema.clone(), json!(1.0)));
+        assert!(valid(schema.clone(), json!({ "x": [1.0] })));
+        assert!(!valid(schema, json!("b")));
+        assert!(valid(json!({ "const": 2 }), json!(2)));
+        assert!(!valid(json!({ "const": 2 }), json!(3)));
+    }
+
+    #[test]
+    fn test_numbers() {
+        let schema = json!({
+            "minimum": 0,
+            "exclusiveMaximum": 10,
+            "multipleOf": 0.5
+        });
+        assert!(valid(schema.clone(), json!(9.5)));
+        assert_eq!(
+            errors(schema.clone(), json!(-1)),
+            vec![("".into(), "/minimum".into())]
+        );
+        assert_eq!(
+            errors(schema.clone(), json!(10)),
+            vec![("".into(), "/exclusiveMaximum".into())]
+        );
+        assert_eq!(
+            errors(schema, json!(1.25)),
+            vec![("".into(), "/multipleOf".into())]
+        );
+        assert!(valid(json!({ "multipleOf": 0.1 }), json!(0.3)));
+    }
+
+    #[test]
+    fn test_strings() {
+        let schema = json!({ "minLength": 2, "maxLength": 3, "pattern": "^[a-zé]+$" });
+        assert!(valid(schema.clone(), json!("été")));
+        assert!(!valid(schema.clone(), json!("a")));
+        assert!(!valid(schema.clone(), json!("abcd")));
+        assert!(!valid(schema, json!("AB")));
+
+        assert!(valid(
+            json!({ "format": "date-time" }),
+            json!("2024-02-29T12:00:00Z")
+        ));
+        assert!(!valid(
+            json!({ "format": "date-time" }),
+            json!("2023-02-29T12:00:00Z")
+        ));
+        assert!(valid(json!({ "format": "date" }), json!("2024-12-31")));
+        assert!(!valid(json!({ "format": "date" }), json!("2024-13-01")));
+        assert!(valid(
+            json!({ "format": "email" }),
+            json!("ada@example.com")
+        ));
+        assert!(!valid(
+            json!({ "format": "email" }),
+            json!("ada at example")
+        ));
+        assert!(valid(
+            json!({ "format": "uuid" }),
+            json!("67e55044-10b1-426f-9247-bb680e5fe0c8")
+        ));
+        assert!(valid(json!({ "format": "ipv4" }), json!("10.0.0.1")));
+        assert!(!valid(json!({ "format": "ipv6" }), json!("10.0.0.1")));
+        assert!(valid(
+            json!({ "format": "uri" }),
+            json!("https://example.com")
+        ));
+        assert!(valid(json!({ "format": "color" }), json!("blue")));
+    }
+
+    #[test]
+    fn test_arrays() {
+        let schema = json!({
+            "type": "array",
+            "prefixItems": [{ "type": "string" }],
+            "items": { "type": "integer" },
+            "minItems": 2,
+            "uniqueItems": true
+        });
+        assert!(valid(schema.clone(), json!(["a", 1, 2])));
+        assert_eq!(
+            errors(schema.clone(), json!(["a", 1, "b"])),
+            vec![("/2".into(), "/items/type".into())]
+        );
+        assert_eq!(
+            errors(schema.clone(), json!([1, 2])),
+            vec![("/0".into(), "/prefixItems/0/type".into())]
+        );
+        assert_eq!(
+            errors(schema, json!(["a", 1, 1])),
+            vec![("".into(), "/uniqueItems".into())]
+        );
+
+        let tuple = json!({ "prefixItems": [{}, {}], "items": false });
+        assert!(valid(tuple.clone(), json!([1, 2])));
+        assert!(!valid(tuple, json!([1, 2, 3])));
+
+        let contains = json!({ "contains": { "const": 1 }, "minContains": 2, "maxContains": 3 });
+        assert!(valid(contains.clone(), json!([1, 1, 2])));
+        assert!(!valid(contains.clone(), json!([1, 2])));
+        assert!(!valid(contains, json!([1, 1, 1, 1])));
+    }
+
+    #[test]
+    fn test_objects() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "address": {
+                    "type": "object",
+                    "properties": { "zip/code": { "type": "string", "pattern": "^\\d{5}$" } }
+                }
+            },
+            "patternProperties": { "^x-": { "type": "string" } },
+            "additionalProperties": false,
+            "required": ["name"],
+            "dependentRequired": { "address": ["name"] }
+        });
+        assert!(valid(
+            schema.clone(),
+            json!({ "name": "Ada", "x-note": "hi", "address": { "zip/code": "12345" } })
+        ));
+        assert_eq!(
+            errors(schema.clone(), json!({ "address": { "zip/code": "1" } })),
+            vec![
+                ("".into(), "/required".into()),
+                ("".into(), "/dependentRequired/address".into()),
+                (
+                    "/address/zip~1code".into(),
+                    "/properties/address/properties/zip~1code/pattern".into()
+                ),
+            ]
+        );
+        assert_eq!(
+            errors(schema, json!({ "name": "Ada", "age": 3, "x-n": 1 })),
+            vec![
+                ("".into(), "/additionalProperties".into()),
+                ("/x-n".into(), "/patternProperties/^x-/type".into()),
+            ]
+        );
+
+        let names = json!({ "propertyNames": { "maxLength": 3 }, "minProperties": 1 });
+        assert!(valid(names.clone(), json!({ "abc": 1 })));
+        assert!(!valid(names.clone(), json!({ "abcd": 1 })));
+        assert!(!valid(names, json!({})));
+    }
+
+    #[test]
+    fn test_combinators() {
+        let one_of = json!({
+            "oneOf": [
+                { "type": "object", "properties": { "kind": { "const": "circle" }, "r": { "type": "number" } }, "required": ["kind", "r"] },
+                { "type": "object", "properties": { "kind": { "const": "square" }, "side": { "type": "number" } }, "required": ["kind", "side"] }
+            ]
+        });
+        assert!(valid(one_of.clone(), json!({ "kind": "circle", "r": 1 })));
+        assert_eq!(
+            errors(one_of.clone(), json!({ "kind": "circle", "r": "big" })),
+            vec![
+                ("".into(), "/oneOf".into()),
+                ("/r".into(), "/oneOf/0/properties/r/type".into()),
+            ]
+        );
+        assert!(!valid(
+            json!({ "oneOf": [{}, { "type": "string" }] }),
+            json!("x")
+        ));
+
+        let any_of = json!({ "anyOf": [{ "type": "string" }, { "minimum": 5 }] });
+        assert!(valid(any_of.clone(), json!(6)));
+        assert!(!valid(any_of, json!(4)));
+
+        let all_of = json!({ "allOf": [{ "minimum": 1 }, { "maximum": 3 }] });
+        assert!(valid(all_of.clone(), json!(2)));
+        assert!(!valid(all_of, json!(4)));
+
+        assert!(!valid(json!({ "not": { "type": "null" } }), json!(null)));
+
+        let conditional = json!({
+            "if": { "properties": { "country": { "const": "US" } } },
+            "then": { "required": ["zip"] },
+            "else": { "required": ["postcode"] }
+        });
+        assert!(valid(
+            conditional.clone(),
+            json!({ "country": "US", "zip": "1" })
+        ));
+        assert!(!valid(conditional.clone(), json!({ "country": "US" })));
+        assert!(valid(
+            conditional,
+            json!({ "country": "FR", "postcode": "1" })
+        ));
+    }
+
+    #[test]
+    fn test_references() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "home": { "$ref": "#/$defs/Address" },
+                "tree": { "$ref": "#/$defs/Node" },
+                "tag": { "$ref": "#tag" }
+            },
+            "$defs": {
+                "Address": {
+                    "type": "object",
+                    "properties": { "city": { "type": "string" } },
+                    "required": ["city"]
+                },
+                "Node": {
+                    "type": "object",
+                    "properties": { "children": { "type": "array", "items": { "$ref": "#/$defs/Node" } } }
+                },
+                "Tag": { "$anchor": "tag", "type": "string" }
+            }
+        });
+        assert!(valid(
+            schema.clone(),
+            json!({ "home": { "city": "Paris" }, "tree": { "children": [{ "children": [] }] }, "tag": "t" })
+        ));
+        assert_eq!(
+            errors(schema.clone(), json!({ "home": {} })),
+            vec![("/home".into(), "/$defs/Address/required".into())]
+        );
+        assert_eq!(
+            errors(
+                schema.clone(),
+                json!({ "tree": { "children": [{ "children": [1] }] } })
+            ),
+            vec![(
+                "/tree/children/0/children/0".into(),
+                "/$defs/Node/type".into()
+            )]
+        );
+        assert!(!valid(schema, json!({ "tag": 1 })));
+
+        let recursive = json!({
+            "type": "object",
+            "properties": { "next": { "$ref": "#" } }
+        });
+        assert!(valid(recursive.clone(), json!({ "next": { "next": {} } })));
+        assert!(!valid(recursive, json!({ "next": { "next": 1 } })));
+    }
+
+    #[test]
+    fn test_invalid_schemas() {
+        for schema in [
+            json!({ "$ref": "#/$defs/Missing" }),
+            json!({ "$ref": "https://example.com/schema.json" }),
+            json!({ "pattern": "(" }),
+            json!({ "type": "text" }),
+            json!({ "properties": { "a": 1 } }),
+            json!({ "anyOf": [] }),
+        ] {
+            assert!(
+                CompiledSchema::compile(&schema).is_err(),
+                "{} should not compile",
+                schema
+            );
+        }
+
+        let err = CompiledSchema::compile(&json!({ "properties": { "a": { "pattern": "(" } } }))
+            .unwrap_err();
+        assert!(err.to_string().contains("/properties/a/pattern"));
+
+        let err = CompiledSchema::compile(&json!({ "pattern": "^(?!admin$).+" })).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("lookaround and backreferences are not supported"));
+
+        let err = CompiledSchema::compile(&json!({
+            "allOf": [{ "properties": { "a": { "type": "string" } } }],
+            "unevaluatedProperties": false
+        }))
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("unsupported keyword 'unevaluatedProperties'"));
+        assert!(CompiledSchema::compile(&json!({
+            "items": { "prefixItems": [true], "unevaluatedItems": false }
+        }))
+        .is_err());
+    }
+
+    #[test]
+    fn test_reference_cycles_terminate() {
+        let schema = json!({ "$defs": { "a": { "$ref": "#/$defs/b" }, "b": { "$ref": "#/$defs/a" } }, "$ref": "#/$defs/a" });
+        let errors = CompiledSchema::compile(&schema)
+            .unwrap()
+            .validate(&json!(1))
+            .unwrap_err();
+        assert!(errors[0].message.contains("nest too deeply"));
+    }
+}
@@ -0,0 +1,354 @@
+//! Parsing of doc comments, `#[schema(...)]` and the `#[serde(...)]`
+//! attributes that affect the schema
+
+use syn::meta::ParseNestedMeta;
+use syn::{Attribute, Expr, ExprLit, Lit, LitStr, Meta, Token};
+
+/// Keywords accepted in a field's `#[schema(...)]`, with their JSON Schema names
+const SCHEMA_KEYWORDS: &[(&str, &str)] = &[
+    ("minimum", "minimum"),
+    ("maximum", "maximum"),
+    ("exclusive_minimum", "exclusiveMinimum"),
+    ("exclusive_maximum", "exclusiveMaximum"),
+    ("min_length", "minLength"),
+    ("max_length", "maxLength"),
+    ("pattern", "pattern"),
+    ("format", "format"),
+    ("enum", "enum"),
+    ("examples", "examples"),
+    ("min_items", "minItems"),
+    ("max_items", "maxItems"),
+];
+
+/// Description and `#[schema(...)]` keywords of a field or parameter
+#[derive(Default)]
+pub(crate) struct FieldSchema {
+    pub description: Option<String>,
+    pub keywords: Vec<(&'static str, Expr)>,
+}
+
+/// Description and examples of a container
+#[derive(Default)]
+pub(crate) struct ContainerSchema {
+    pub description: Option<String>,
+    pub examples: Vec<Expr>,
+}
+
+/// Container attributes
+#[derive(Default)]
+pub(crate) struct ContainerAttrs {
+    pub rename_all: Option<RenameRule>,
+    pub rename_all_fields: Option<RenameRule>,
+    pub tag: Option<String>,
+    pub content: Option<String>,
+    pub untagged: bool,
+    pub transparent: bool,
+    pub default: bool,
+}
+
+/// Enum variant attributes
+#[derive(Default)]
+pub(crate) struct VariantAttrs {
+    pub rename: Option<String>,
+    pub rename_all: Option<RenameRule>,
+    pub skip: bool,
+}
+
+/// Field attributes
+#[derive(Default)]
+pub(crate) struct FieldAttrs {
+    pub rename: Option<String>,
+    pub skip: bool,
+    pub default: bool,
+    pub flatten: bool,
+}
+
+impl ContainerAttrs {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut parsed = Self::default();
+        for_each_serde(attrs, |meta| {
+            if meta.path.is_ident("rename_all") {
+                parsed.rename_all = Some(RenameRule::parse(&meta)?);
+            } else if meta.path.is_ident("rename_all_fields") {
+                parsed.rename_all_fields = Some(RenameRule::parse(&meta)?);
+            } else if meta.path.is_ident("tag") {
+                parsed.tag = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("content") {
+                parsed.content = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("untagged") {
+                parsed.untagged = true;
+            } else if meta.path.is_ident("transparent") {
+                parsed.transparent = true;
+            } else if meta.path.is_ident("default") {
+                parsed.default = true;
+                skip_value(&meta)?;
+            } else {
+                skip_value(&meta)?;
+            }
+            Ok(())
+        })?;
+        Ok(parsed)
+    }
+}
+
+impl VariantAttrs {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut parsed = Self::default();
+        for_each_serde(attrs, |meta| {
+            if meta.path.is_ident("rename") {
+                parsed.rename = deserialize_name(&meta)?;
+            } else if meta.path.is_ident("rename_all") {
+                parsed.rename_all = Some(RenameRule::parse(&meta)?);
+            } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                parsed.skip = true;
+            } else {
+                skip_value(&meta)?;
+            }
+            Ok(())
+        })?;
+        Ok(parsed)
+    }
+}
+
+impl FieldAttrs {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut parsed = Self::default();
+        for_each_serde(attrs, |meta| {
+            if meta.path.is_ident("rename") {
+                parsed.rename = deserialize_name(&meta)?;
+            } else if meta.path.is_ident("skip") || meta.path.is_ident("skip_deserializing") {
+                parsed.skip = true;
+            } else if meta.path.is_ident("default") {
+                parsed.default = true;
+                skip_value(&meta)?;
+            } else if meta.path.is_ident("flatten") {
+                parsed.flatten = true;
+            } else {
+                skip_value(&meta)?;
+            }
+            Ok(())
+        })?;
+        Ok(parsed)
+    }
+}
+
+/// Case conversion from `rename_all`
+#[derive(Clone, Copy)]
+pub(crate) enum RenameRule {
+    Lower,
+    Upper,
+    Pascal,
+    Camel,
+    Snake,
+    ScreamingSnake,
+    Kebab,
+    ScreamingKebab,
+}
+
+impl RenameRule {
+    fn parse(meta: &ParseNestedMeta) -> syn::Result<Self> {
+        let Some(name) = deserialize_name(meta)? else {
+            return Err(meta.error("expected a rename rule"));
+        };
+        Ok(match name.as_str() {
+            "lowercase" => Self::Lower,
+            "UPPERCASE" => Self::Upper,
+            "PascalCase" => Self::Pascal,
+            "camelCase" => Self::Camel,
+            "snake_case" => Self::Snake,
+            "SCREAMING_SNAKE_CASE" => Self::ScreamingSnake,
+            "kebab-case" => Self::Kebab,
+            "SCREAMING-KEBAB-CASE" => Self::ScreamingKebab,
+            _ => return Err(meta.error(format!("unknown rename rule `{}`", name))),
+        })
+    }
+
+    /// Rename a variant, written in PascalCase
+    pub(crate) fn apply_to_variant(self, variant: &str) -> String {
+        match self {
+            Self::Pascal => variant.to_string(),
+            Self::Lower => variant.to_ascii_lowercase(),
+            Self::Upper => variant.to_ascii_uppercase(),
+            Self::Camel => {
+                let mut chars = variant.chars();
+                match chars.next() {
+                    Some(first) => first.to_ascii_lowercase().to_string() + chars.as_str(),
+                    None => String::new(),
+                }
+            }
+            Self::Snake => {
+                let mut snake = String::new();
+                for (i, c) in variant.char_indices() {
+                    if i > 0 && c.is_uppercase() {
+                        snake.push('_');
+                    }
+                    snake.push(c.to_ascii_lowercase());
+                }
+                snake
+            }
+            Self::ScreamingSnake => Self::Snake.apply_to_variant(variant).to_ascii_uppercase(),
+            Self::Kebab => Self::Snake.apply_to_variant(variant).replace('_', "-"),
+            Self::ScreamingKebab => Self::ScreamingSnake
+                .apply_to_variant(variant)
+                .replace('_', "-"),
+        }
+    }
+
+    /// Rename a field, written in snake_case
+    pub(crate) fn apply_to_field(self, field: &str) -> String {
+        match self {
+            Self::Lower | Self::Snake => field.to_string(),
+            Self::Upper | Self::ScreamingSnake => field.to_ascii_uppercase(),
+            Self::Pascal => {
+                let mut pascal = String::new();
+                let mut capitalize = true;
+                for c in field.chars() {
+                    if c == '_' {
+                        capitalize = true;
+                    } else if capitalize {
+                        pascal.push(c.to_ascii_uppercase());
+                        capitalize = false;
+                    } else {
+                        pascal.push(c);
+                    }
+                }
+                pascal
+            }
+            Self::Camel => Self::Camel.apply_to_variant(&Self::Pascal.apply_to_field(field)),
+            Self::Kebab => field.replace('_', "-"),
+            Self::ScreamingKebab => field.replace('_', "-").to_ascii_uppercase(),
+        }
+    }
+}
+
+impl FieldSchema {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut parsed = Self {
+            description: doc_string(attrs),
+            keywords: Vec::new(),
+        };
+        for_each_attr(attrs, "schema", |meta| {
+            if meta.path.is_ident("description") {
+                parsed.description = Some(string_value(&meta)?);
+                return Ok(());
+            }
+            let keyword = SCHEMA_KEYWORDS
+                .iter()
+                .find(|(name, _)| meta.path.is_ident(name))
+                .map(|(_, keyword)| *keyword)
+                .ok_or_else(|| {
+                    let names: Vec<_> = SCHEMA_KEYWORDS.iter().map(|(name, _)| *name).collect();
+                    meta.error(format!(
+                        "unknown schema attribute; expected `description`, `{}`",
+                        names.join("`, `")
+                    ))
+                })?;
+            parsed.keywords.push((keyword, meta.value()?.parse()?));
+            Ok(())
+        })?;
+        Ok(parsed)
+    }
+}
+
+impl ContainerSchema {
+    pub(crate) fn parse(attrs: &[Attribute]) -> syn::Result<Self> {
+        let mut parsed = Self {
+            description: doc_string(attrs),
+            examples: Vec::new(),
+        };
+        for_each_attr(attrs, "schema", |meta| {
+            if meta.path.is_ident("description") {
+                parsed.description = Some(string_value(&meta)?);
+            } else if meta.path.is_ident("example") {
+                parsed.examples.push(meta.value()?.parse()?);
+            } else {
+                return Err(meta.error("expected `description` or `example`"));
+            }
+            Ok(())
+        })?;
+        Ok(parsed)
+    }
+}
+
+/// Join `///` comments into a single description
+pub(crate) fn doc_string(attrs: &[Attribute]) -> Option<String> {
+    let lines: Vec<String> = attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("doc"))
+        .filter_map(|attr| match &attr.meta {
+            Meta::NameValue(meta) => match &meta.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(text),
+                    ..
+                }) => Some(text.value().trim().to_string()),
+                _ => None,
+            },
+            _ => None,
+        })
+        .collect();
+
+    let text = lines.join("\n").trim().to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Run `f` on each item inside `#[serde(...)]`
+fn for_each_serde(
+    attrs: &[Attribute],
+    f: impl FnMut(ParseNestedMeta) -> syn::Result<()>,
+) -> syn::Result<()> {
+    for_each_attr(attrs, "serde", f)
+}
+
+/// Run `f` on each item inside `#[name(...)]`
+fn for_each_attr(
+    attrs: &[Attribute],
+    name: &str,
+    mut f: impl FnMut(ParseNestedMeta) -> syn::Result<()>,
+) -> syn::Result<()> {
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident(name)) {
+        attr.parse_nested_meta(&mut f)?;
+    }
+    Ok(())
+}
+
+fn string_value(meta: &ParseNestedMeta) -> syn::Result<String> {
+    Ok(meta.value()?.parse::<LitStr>()?.value())
+}
+
+/// Read `key = "name"` or `key(deserialize = "name")`
+fn deserialize_name(meta: &ParseNestedMeta) -> syn::Result<Option<String>> {
+    if meta.input.peek(Token![=]) {
+        return string_value(meta).map(Some);
+    }
+    let mut name = None;
+    meta.parse_nested_meta(|nested| {
+        let value = string_value(&nested)?;
+        if nested.path.is_ident("deserialize") {
+            name = Some(value);
+        }
+        Ok(())
+    })?;
+    Ok(name)
+}
+
+/// Consume the value of an attribute this macro doesn't interpret
+fn skip_value(meta: &ParseNestedMeta) -> syn::Result<()> {
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<syn::Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        meta.parse_nested_meta(|nested| skip_value(&nested))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_camel_case_variant() {
+        assert_eq!(RenameRule::Camel.apply_to_variant("FooBar"), "fooBar");
+        assert_eq!(RenameRule::Camel.apply_to_variant("Élan"), "Élan");
+        assert_eq!(RenameRule::Camel.apply_to_variant(""), "");
+    }
+}
@@ -1,13 +1,38 @@
 //! Derive macros for the Cogni framework
 
 use proc_macro::TokenStream;
-use quote::quote;
-use syn::{parse_macro_input, Data, DeriveInput, Fields, Type};
+use syn::{parse_macro_input, DeriveInput, ItemFn};
+
+mod attr;
+mod schema;
+mod structured;
+mod tool;
 
 /// Derive macro for the StructuredOutput trait
 ///
-/// This macro automatically implements the `StructuredOutput` trait for structs,
-/// generating a JSON Schema based on the struct's fields.
+/// This macro automatically implements the `StructuredOutput` trait for structs
+/// and enums, generating a JSON Schema that matches what serde accepts.
+///
+/// - Structs with named fields become objects; `Option` fields are optional.
+/// - Newtype structs use the schema of the wrapped type, and tuple structs
+///   become fixed-length arrays.
+/// - Enums of unit variants become string enums. Enums with data become
+///   `oneOf` schemas following serde's tagging: external (default),
+///   `#[serde(tag = "...")]`, `#[serde(tag = "...", content = "...")]` or
+///   `#[serde(untagged)]`.
+/// - Fields of other types use that type's `StructuredOutput` schema, stored
+///   once under `$defs` and referenced with `$ref`. Recursive types refer back
+///   to the root.
+/// - `rename`, `rename_all`, `rename_all_fields`, `skip`, `skip_deserializing`,
+///   `default`, `flatten` and `transparent` are honored.
+///
+/// Doc comments on the type, its fields and its variants become
+/// `description`s. Fields also accept `#[schema(...)]` with `minimum`,
+/// `maximum`, `exclusive_minimum`, `exclusive_maximum`, `min_length`,
+/// `max_length`, `pattern`, `format`, `enum`, `examples`, `min_items`,
+/// `max_items` and `description`. On the type, `#[schema(example = ...)]`
+/// takes an expression building an instance and can be repeated; the
+/// instances are returned by `examples()`.
 ///
 /// # Example
 ///
@@ -15,153 +40,74 @@ use syn::{parse_macro_input, Data, DeriveInput, Fields, Type};
 /// use cogni_derive::StructuredOutput;
 /// use serde::{Deserialize, Serialize};
 ///
+/// /// A person mentioned in the text
 /// #[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+/// #[schema(example = Person { name: "Ada".into(), age: 36, email: None })]
 /// struct Person {
+///     /// Full name
+///     #[schema(min_length = 1)]
 ///     name: String,
+///     #[schema(maximum = 150)]
 ///     age: u32,
+///     #[schema(format = "email")]
 ///     email: Option<String>,
 /// }
 /// ```
 ///
 /// This will generate a JSON Schema that describes the Person struct.
-#[proc_macro_derive(StructuredOutput)]
+#[proc_macro_derive(StructuredOutput, attributes(schema))]
 pub fn derive_structured_output(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
-    let name = &input.ident;
-
-    // Only support structs for now
-    let fields = match &input.data {
-        Data::Struct(data) => match &data.fields {
-            Fields::Named(fields) => &fields.named,
-            _ => {
-                return syn::Error::new_spanned(
-                    &input,
-                    "StructuredOutput can only be derived for structs with named fields",
-                )
-                .to_compile_error()
-                .into();
-            }
-        },
-        _ => {
-            return syn::Error::new_spanned(
-                &input,
-                "StructuredOutput can only be derived for structs",
-            )
-            .to_compile_error()
-            .into();
-        }
-    };
-
-    // Generate property definitions for each field
-    let properties = fields.iter().map(|field| {
-        let field_name = field.ident.as_ref().unwrap();
-        let field_name_str = field_name.to_string();
-        let field_type = &field.ty;
-
-        let type_schema = generate_type_schema(field_type);
-
-        quote! {
-            #field_name_str: #type_schema
-        }
-    });
-
-    // Generate required fields array
-    let required_fields = fields.iter().filter_map(|field| {
-        let field_name = field.ident.as_ref().unwrap().to_string();
-        // Check if the field is Option<T>
-        if is_option_type(&field.ty) {
-            None
-        } else {
-            Some(quote! { #field_name })
-        }
-    });
-
-    let expanded = quote! {
-        impl cogni_core::StructuredOutput for #name {
-            fn schema() -> serde_json::Value {
-                serde_json::json!({
-                    "type": "object",
-                    "properties": {
-                        #(#properties,)*
-                    },
-                    "required": [#(#required_fields,)*],
-                    "additionalProperties": false
-                })
-            }
-        }
-    };
-
-    expanded.into()
-}
-
-/// Generate JSON Schema for a given Rust type
-fn generate_type_schema(ty: &Type) -> proc_macro2::TokenStream {
-    match ty {
-        Type::Path(type_path) => {
-            let path = &type_path.path;
-            let segment = path.segments.last().unwrap();
-            let ident = &segment.ident;
-            let ident_str = ident.to_string();
-
-            match ident_str.as_str() {
-                // Primitive types
-                "String" | "str" => quote! { serde_json::json!({ "type": "string" }) },
-                "bool" => quote! { serde_json::json!({ "type": "boolean" }) },
-                "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => {
-                    quote! { serde_json::json!({ "type": "integer" }) }
-                }
-                "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => {
-                    quote! { serde_json::json!({ "type": "integer", "minimum": 0 }) }
-                }
-                "f32" | "f64" => quote! { serde_json::json!({ "type": "number" }) },
 
-                // Handle Option<T>
-                "Option" => {
-                    if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                        if let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first() {
-                            // For Option<T>, we just return the schema for T
-                            // The field won't be in the required array
-                            return generate_type_schema(inner_ty);
-                        }
-                    }
-                    quote! { serde_json::json!({ "type": ["null", "string"] }) }
-                }
-
-                // Handle Vec<T>
-                "Vec" => {
-                    if let syn::PathArguments::AngleBracketed(args) = &segment.arguments {
-                        if let Some(syn::GenericArgument::Type(inner_ty)) = args.args.first() {
-                            let inner_schema = generate_type_schema(inner_ty);
-                            return quote! {
-                                serde_json::json!({
-                                    "type": "array",
-                                    "items": #inner_schema
-                                })
-                            };
-                        }
-                    }
-                    quote! { serde_json::json!({ "type": "array" }) }
-                }
-
-                // Handle HashMap<K, V>
-                "HashMap" | "BTreeMap" => {
-                    quote! { serde_json::json!({ "type": "object" }) }
-                }
-
-                // Default to object for custom types
-                _ => quote! { serde_json::json!({ "type": "object" }) },
-            }
-        }
-        _ => quote! { serde_json::json!({ "type": "object" }) },
-    }
+    structured::expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
 
-/// Check if a type is Option<T>
-fn is_option_type(ty: &Type) -> bool {
-    if let Type::Path(type_path) = ty {
-        if let Some(segment) = type_path.path.segments.last() {
-            return segment.ident == "Option";
-        }
-    }
-    false
+/// Turn a function into a tool executor
+///
+/// The function's doc comment becomes the tool description and each
+/// parameter's doc comment becomes its description in the generated JSON
+/// Schema; `#[schema(...)]` adds the same constraints as on `StructuredOutput`
+/// fields. Parameters must implement `Deserialize`; `Option` parameters are
+/// optional. The return value must implement `Serialize`; if the function
+/// returns a `Result`, the error's `Display` output is reported to the model
+/// as a failed tool result.
+///
+/// The function is kept as-is and a `<Name>Tool` struct implementing
+/// `ToolExecutor` is generated next to it.
+///
+/// # Options
+///
+/// - `name = "..."`: tool name (defaults to the function name)
+/// - `description = "..."`: tool description (defaults to the doc comment)
+/// - `crate = "..."`: path to the `cogni` crate (defaults to `::cogni`)
+///
+/// # Example
+///
+/// ```ignore
+/// /// Get the current weather for a city
+/// #[cogni::tool]
+/// async fn get_weather(
+///     /// City name, e.g. "Paris"
+///     city: String,
+///     /// "celsius" or "fahrenheit"
+///     unit: Option<String>,
+/// ) -> Result<Weather, WeatherError> {
+///     // ...
+/// }
+///
+/// let registry = ToolRegistry::new();
+/// registry.register([GetWeatherTool::new()]).await?;
+/// ```
+#[proc_macro_attribute]
+pub fn tool(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut options = tool::ToolOptions::default();
+    let parser = syn::meta::parser(|meta| options.parse(meta));
+    parse_macro_input!(attr with parser);
+    let function = parse_macro_input!(item as ItemFn);
+
+    tool::expand(options, function)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
@@ -0,0 +1,246 @@
+//! JSON Schema generation for Rust types
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Expr, GenericArgument, PathArguments, Type};
+
+/// Paths used by the generated schema code
+pub(crate) struct SchemaContext {
+    /// Path to the `serde_json` crate
+    pub json: TokenStream,
+    /// Path to `cogni_core::types::structured::__derive`
+    pub runtime: TokenStream,
+}
+
+impl SchemaContext {
+    /// Context for code that depends on `cogni_core` and `serde_json` directly
+    pub(crate) fn core() -> Self {
+        Self {
+            json: quote!(serde_json),
+            runtime: quote!(cogni_core::types::structured::__derive),
+        }
+    }
+
+    /// Generate JSON Schema for a given Rust type
+    ///
+    /// Types that aren't recognised here must implement `StructuredOutput`;
+    /// they are added to the `defs` map in scope and referenced with `$ref`.
+    pub(crate) fn type_schema(&self, ty: &Type) -> TokenStream {
+        let json = &self.json;
+        let runtime = &self.runtime;
+        match ty {
+            Type::Path(type_path) if type_path.qself.is_none() => {
+                let segment = type_path.path.segments.last().unwrap();
+                match segment.ident.to_string().as_str() {
+                    // Primitive types
+                    "String" | "str" => quote! { #json::json!({ "type": "string" }) },
+                    "char" => {
+                        quote! { #json::json!({ "type": "string", "minLength": 1, "maxLength": 1 }) }
+                    }
+                    "bool" => quote! { #json::json!({ "type": "boolean" }) },
+                    "i8" | "i16" | "i32" | "i64" | "i128" | "isize" => {
+                        quote! { #json::json!({ "type": "integer" }) }
+                    }
+                    "u8" | "u16" | "u32" | "u64" | "u128" | "usize" => {
+                        quote! { #json::json!({ "type": "integer", "minimum": 0 }) }
+                    }
+                    "f32" | "f64" => quote! { #json::json!({ "type": "number" }) },
+                    "Value" => quote! { #json::json!({}) },
+
+                    // Option<T> is T or null; the field isn't required either
+                    "Option" => match first_type_argument(ty) {
+                        Some(inner) => {
+                            let inner = self.type_schema(inner);
+                            quote! { #runtime::nullable(#inner) }
+                        }
+                        None => quote! { #json::json!({}) },
+                    },
+                    "Box" | "Rc" | "Arc" | "Cow" => match last_type_argument(ty) {
+                        Some(inner) => self.type_schema(inner),
+                        None => quote! { #json::json!({}) },
+                    },
+
+                    "Vec" | "VecDeque" | "LinkedList" => match first_type_argument(ty) {
+                        Some(inner) => {
+                            let items = self.type_schema(inner);
+                            quote! { #json::json!({ "type": "array", "items": #items }) }
+                        }
+                        None => quote! { #json::json!({ "type": "array" }) },
+                    },
+                    "HashSet" | "BTreeSet" => match first_type_argument(ty) {
+                        Some(inner) => {
+                            let items = self.type_schema(inner);
+                            quote! {
+                                #json::json!({ "type": "array", "items": #items, "uniqueItems": true })
+                            }
+                        }
+                        None => quote! { #json::json!({ "type": "array", "uniqueItems": true }) },
+                    },
+
+                    // Maps serialize as objects keyed by strings
+                    "HashMap" | "BTreeMap" | "Map" => match last_type_argument(ty) {
+                        Some(value) => {
+                            let values = self.type_schema(value);
+                            quote! {
+                                #json::json!({ "type": "object", "additionalProperties": #values })
+                            }
+                        }
+                        None => quote! { #json::json!({ "type": "object" }) },
+                    },
+
+                    // Other types provide their own schema
+                    _ => quote! { #runtime::reference::<#ty>(defs) },
+                }
+            }
+            Type::Array(array) => {
+                let items = self.type_schema(&array.elem);
+                let len = &array.len;
+                quote! {
+                    #json::json!({
+                        "type": "array",
+                        "items": #items,
+                        "minItems": #len,
+                        "maxItems": #len
+                    })
+                }
+            }
+            Type::Slice(slice) => {
+                let items = self.type_schema(&slice.elem);
+                quote! { #json::json!({ "type": "array", "items": #items }) }
+            }
+            Type::Reference(reference) => self.type_schema(&reference.elem),
+            Type::Paren(paren) => self.type_schema(&paren.elem),
+            Type::Group(group) => self.type_schema(&group.elem),
+            Type::Tuple(tuple) if tuple.elems.is_empty() => {
+                quote! { #json::json!({ "type": "null" }) }
+            }
+            Type::Tuple(tuple) => {
+                let elems: Vec<&Type> = tuple.elems.iter().collect();
+                self.tuple_schema(&elems)
+            }
+            _ => quote! { #json::json!({}) },
+        }
+    }
+
+    /// Schema of a field or parameter with its annotations
+    ///
+    /// The annotations describe the value, so for `Option<T>` they apply to
+    /// `T` before `null` is allowed.
+    pub(crate) fn field_schema(
+        &self,
+        ty: &Type,
+        description: Option<&str>,
+        keywords: &[(&str, Expr)],
+    ) -> TokenStream {
+        match option_inner(ty) {
+            Some(inner) => {
+                let runtime = &self.runtime;
+                let schema = self.annotate(self.type_schema(inner), description, keywords);
+                quote! { #runtime::nullable(#schema) }
+            }
+            None => self.annotate(self.type_schema(ty), description, keywords),
+        }
+    }
+
+    /// Add a description and extra keywords to a schema
+    pub(crate) fn annotate(
+        &self,
+        schema: TokenStream,
+        description: Option<&str>,
+        keywords: &[(&str, Expr)],
+    ) -> TokenStream {
+        if description.is_none() && keywords.is_empty() {
+            return schema;
+        }
+        let json = &self.json;
+        let description = description.map(|description| {
+            quote! { object.insert("description".to_string(), #json::json!(#description)); }
+        });
+        let keywords = keywords.iter().map(|(keyword, value)| {
+            quote! { object.insert(#keyword.to_string(), #json::json!(#value)); }
+        });
+        quote! {{
+            let mut schema = #schema;
+            if let Some(object) = schema.as_object_mut() {
+                #description
+                #(#keywords)*
+            }
+            schema
+        }}
+    }
+
+    /// Schema for a fixed-length array, as serde writes tuples
+    pub(crate) fn tuple_schema(&self, elems: &[&Type]) -> TokenStream {
+        let json = &self.json;
+        let len = elems.len();
+        let items = elems.iter().map(|ty| self.type_schema(ty));
+        quote! {
+            #json::json!({
+                "type": "array",
+                "prefixItems": [#(#items),*],
+                "items": false,
+                "minItems": #len,
+                "maxItems": #len
+            })
+        }
+    }
+}
+
+/// Check if a type is Option<T>
+pub(crate) fn is_option_type(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            return segment.ident == "Option";
+        }
+    }
+    false
+}
+
+/// Check if a type is a map, which flattens into additional properties
+pub(crate) fn is_map_type(ty: &Type) -> bool {
+    if let Type::Path(type_path) = ty {
+        if let Some(segment) = type_path.path.segments.last() {
+            return matches!(
+                segment.ident.to_string().as_str(),
+                "HashMap" | "BTreeMap" | "Map"
+            );
+        }
+    }
+    false
+}
+
+/// The inner type of `Option<T>`
+pub(crate) fn option_inner(ty: &Type) -> Option<&Type> {
+    if is_option_type(ty) {
+        first_type_argument(ty)
+    } else {
+        None
+    }
+}
+
+fn type_arguments(ty: &Type) -> impl Iterator<Item = &Type> {
+    let arguments = match ty {
+        Type::Path(type_path) => type_path
+            .path
+            .segments
+            .last()
+            .map(|segment| &segment.arguments),
+        _ => None,
+    };
+    let args = match arguments {
+        Some(PathArguments::AngleBracketed(args)) => Some(&args.args),
+        _ => None,
+    };
+    args.into_iter().flatten().filter_map(|arg| match arg {
+        GenericArgument::Type(ty) => Some(ty),
+        _ => None,
+    })
+}
+
+fn first_type_argument(ty: &Type) -> Option<&Type> {
+    type_arguments(ty).next()
+}
+
+fn last_type_argument(ty: &Type) -> Option<&Type> {
+    type_arguments(ty).last()
+}
@@ -0,0 +1,325 @@
+//! Expansion of `#[derive(StructuredOutput)]`
+
+use crate::attr::{
+    doc_string, ContainerAttrs, ContainerSchema, FieldAttrs, FieldSchema, RenameRule, VariantAttrs,
+};
+use crate::schema::{is_map_type, is_option_type, option_inner, SchemaContext};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{
+    parse_quote, Data, DataEnum, DeriveInput, Field, Fields, FieldsNamed, GenericParam, Type,
+};
+
+pub(crate) fn expand(mut input: DeriveInput) -> syn::Result<TokenStream> {
+    let attrs = ContainerAttrs::parse(&input.attrs)?;
+    let container_schema = ContainerSchema::parse(&input.attrs)?;
+    let ctx = SchemaContext::core();
+
+    let body = match &input.data {
+        Data::Struct(data) if attrs.transparent => {
+            let field = data
+                .fields
+                .iter()
+                .find(|field| FieldAttrs::parse(&field.attrs).is_ok_and(|attrs| !attrs.skip))
+                .ok_or_else(|| {
+                    syn::Error::new_spanned(&input.ident, "transparent struct needs a field")
+                })?;
+            ctx.type_schema(&field.ty)
+        }
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => object_schema(&ctx, fields, attrs.rename_all, &attrs, None)?,
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                ctx.type_schema(&fields.unnamed[0].ty)
+            }
+            Fields::Unnamed(fields) => {
+                let elems: Vec<&Type> = fields.unnamed.iter().map(|field| &field.ty).collect();
+                ctx.tuple_schema(&elems)
+            }
+            Fields::Unit => quote! { serde_json::json!({ "type": "null" }) },
+        },
+        Data::Enum(data) => enum_schema(&ctx, data, &attrs)?,
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "StructuredOutput cannot be derived for unions",
+            ))
+        }
+    };
+
+    // Every type parameter must describe itself
+    for param in input.generics.params.iter_mut() {
+        if let GenericParam::Type(param) = param {
+            param
+                .bounds
+                .push(parse_quote!(cogni_core::StructuredOutput));
+        }
+    }
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let runtime = &ctx.runtime;
+    let body = ctx.annotate(body, container_schema.description.as_deref(), &[]);
+    let examples = (!container_schema.examples.is_empty()).then(|| {
+        let examples = &container_schema.examples;
+        quote! {
+            fn examples() -> Vec<Self> {
+                vec![#(#examples),*]
+            }
+        }
+    });
+
+    Ok(quote! {
+        impl #impl_generics cogni_core::StructuredOutput for #name #ty_generics #where_clause {
+            fn schema() -> serde_json::Value {
+                #runtime::root::<Self>(|defs| {
+                    let _ = &defs;
+                    #body
+                })
+            }
+
+            #examples
+        }
+    })
+}
+
+/// Schema for a struct or struct variant with named fields
+///
+/// `tag` adds the `(field, value)` property of an internally tagged enum.
+fn object_schema(
+    ctx: &SchemaContext,
+    fields: &FieldsNamed,
+    rename_all: Option<RenameRule>,
+    container: &ContainerAttrs,
+    tag: Option<(&str, &str)>,
+) -> syn::Result<TokenStream> {
+    let runtime = &ctx.runtime;
+    let mut statements = Vec::new();
+    let mut additional = quote!(false);
+
+    if let Some((field, value)) = tag {
+        statements.push(quote! {
+            properties.insert(
+                #field.to_string(),
+                serde_json::json!({ "type": "string", "const": #value }),
+            );
+            required.push(#field.into());
+        });
+    }
+
+    for field in &fields.named {
+        let attrs = FieldAttrs::parse(&field.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let ty = &field.ty;
+        let optional = is_option_type(ty) || attrs.default || container.default;
+
+        if attrs.flatten {
+            let inner = option_inner(ty).unwrap_or(ty);
+            if is_map_type(inner) {
+                let schema = ctx.type_schema(inner);
+                additional = quote! { (#schema["additionalProperties"].clone()) };
+            } else {
+                statements.push(quote! {
+                    #runtime::flatten::<#inner>(&mut properties, &mut required, #optional, defs);
+                });
+            }
+            continue;
+        }
+
+        let name = field_name(field, &attrs, rename_all);
+        let field_schema = FieldSchema::parse(&field.attrs)?;
+        let schema = ctx.field_schema(
+            ty,
+            field_schema.description.as_deref(),
+            &field_schema.keywords,
+        );
+        statements.push(quote! {
+            properties.insert(#name.to_string(), #schema);
+        });
+        if !optional {
+            statements.push(quote! { required.push(#name.into()); });
+        }
+    }
+
+    Ok(quote! {{
+        let mut properties = serde_json::Map::new();
+        let mut required: Vec<serde_json::Value> = Vec::new();
+        #(#statements)*
+        serde_json::json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": #additional
+        })
+    }})
+}
+
+fn enum_schema(
+    ctx: &SchemaContext,
+    data: &DataEnum,
+    container: &ContainerAttrs,
+) -> syn::Result<TokenStream> {
+    let mut names = Vec::new();
+    let mut schemas = Vec::new();
+    // Unit variants collapse into a string enum unless they carry descriptions
+    let mut all_unit = true;
+
+    for variant in &data.variants {
+        let attrs = VariantAttrs::parse(&variant.attrs)?;
+        if attrs.skip {
+            continue;
+        }
+        let name = match (&attrs.rename, container.rename_all) {
+            (Some(name), _) => name.clone(),
+            (None, Some(rule)) => rule.apply_to_variant(&variant.ident.to_string()),
+            (None, None) => variant.ident.to_string(),
+        };
+        let rename_fields = attrs.rename_all.or(container.rename_all_fields);
+        let fields_default = ContainerAttrs::default();
+
+        let schema = match (&container.tag, &container.content, container.untagged) {
+            // Untagged: the variant's own shape
+            (_, _, true) => match &variant.fields {
+                Fields::Unit => quote! { serde_json::json!({ "type": "null" }) },
+                fields => variant_data(ctx, fields, rename_fields)?,
+            },
+
+            // Adjacently tagged: {"tag": "Name", "content": ...}
+            (Some(tag), Some(content), false) => {
+                let data = match &variant.fields {
+                    Fields::Unit => None,
+                    fields => Some(variant_data(ctx, fields, rename_fields)?),
+                };
+                match data {
+                    None => quote! {
+                        serde_json::json!({
+                            "type": "object",
+                            "properties": { #tag: { "type": "string", "const": #name } },
+                            "required": [#tag],
+                            "additionalProperties": false
+                        })
+                    },
+                    Some(data) => quote! {
+                        serde_json::json!({
+                            "type": "object",
+                            "properties": {
+                                #tag: { "type": "string", "const": #name },
+                                #content: (#data)
+                            },
+                            "required": [#tag, #content],
+                            "additionalProperties": false
+                        })
+                    },
+                }
+            }
+
+            // Internally tagged: the tag is a property of the variant's object
+            (Some(tag), None, false) => match &variant.fields {
+                Fields::Named(fields) => object_schema(
+                    ctx,
+                    fields,
+                    rename_fields,
+                    &fields_default,
+                    Some((tag, &name)),
+                )?,
+                Fields::Unit => quote! {
+                    serde_json::json!({
+                        "type": "object",
+                        "properties": { #tag: { "type": "string", "const": #name } },
+                        "required": [#tag],
+                        "additionalProperties": false
+                    })
+                },
+                Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                    let runtime = &ctx.runtime;
+                    let ty = &fields.unnamed[0].ty;
+                    quote! {{
+                        let mut properties = serde_json::Map::new();
+                        let mut required: Vec<serde_json::Value> = vec![#tag.into()];
+                        properties.insert(
+                            #tag.to_string(),
+                            serde_json::json!({ "type": "string", "const": #name }),
+                        );
+                        #runtime::flatten::<#ty>(&mut properties, &mut required, false, defs);
+                        serde_json::json!({
+                            "type": "object",
+                            "properties": properties,
+                            "required": required,
+                            "additionalProperties": false
+                        })
+                    }}
+                }
+                Fields::Unnamed(fields) => {
+                    return Err(syn::Error::new_spanned(
+                        fields,
+                        "internally tagged enums cannot contain tuple variants",
+                    ))
+                }
+            },
+
+            // Externally tagged: "Name" or {"Name": ...}
+            _ => match &variant.fields {
+                Fields::Unit => quote! {
+                    serde_json::json!({ "type": "string", "const": #name })
+                },
+                fields => {
+                    all_unit = false;
+                    let data = variant_data(ctx, fields, rename_fields)?;
+                    quote! {
+                        serde_json::json!({
+                            "type": "object",
+                            "properties": { #name: (#data) },
+                            "required": [#name],
+                            "additionalProperties": false
+                        })
+                    }
+                }
+            },
+        };
+        let description = doc_string(&variant.attrs);
+        all_unit &= description.is_none();
+        names.push(name);
+        schemas.push(ctx.annotate(schema, description.as_deref(), &[]));
+    }
+
+    let externally_tagged = container.tag.is_none() && !container.untagged;
+    if externally_tagged && all_unit {
+        return Ok(quote! {
+            serde_json::json!({ "type": "string", "enum": [#(#names),*] })
+        });
+    }
+    Ok(quote! {
+        serde_json::json!({ "oneOf": [#((#schemas)),*] })
+    })
+}
+
+/// Schema for the data carried by a newtype, tuple or struct variant
+fn variant_data(
+    ctx: &SchemaContext,
+    fields: &Fields,
+    rename_all: Option<RenameRule>,
+) -> syn::Result<TokenStream> {
+    Ok(match fields {
+        Fields::Named(fields) => {
+            object_schema(ctx, fields, rename_all, &ContainerAttrs::default(), None)?
+        }
+        Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+            ctx.type_schema(&fields.unnamed[0].ty)
+        }
+        Fields::Unnamed(fields) => {
+            let elems: Vec<&Type> = fields.unnamed.iter().map(|field| &field.ty).collect();
+            ctx.tuple_schema(&elems)
+        }
+        Fields::Unit => quote! { serde_json::json!({ "type": "null" }) },
+    })
+}
+
+fn field_name(field: &Field, attrs: &FieldAttrs, rename_all: Option<RenameRule>) -> String {
+    let ident = field.ident.as_ref().unwrap().to_string();
+    let ident = ident.trim_start_matches("r#");
+    match (&attrs.rename, rename_all) {
+        (Some(name), _) => name.clone(),
+        (None, Some(rule)) => rule.apply_to_field(ident),
+        (None, None) => ident.to_string(),
+    }
+}
@@ -0,0 +1,279 @@
+//! Expansion of the `#[tool]` attribute
+
+use crate::attr::{doc_string, FieldSchema};
+use crate::schema::{is_option_type, SchemaContext};
+use proc_macro2::{Span, TokenStream};
+use quote::{format_ident, quote};
+use syn::meta::ParseNestedMeta;
+use syn::{FnArg, Ident, ItemFn, LitStr, Pat, Path, ReturnType, Type};
+
+/// Options given to `#[tool(...)]`
+#[derive(Default)]
+pub(crate) struct ToolOptions {
+    name: Option<LitStr>,
+    description: Option<LitStr>,
+    krate: Option<Path>,
+}
+
+impl ToolOptions {
+    pub(crate) fn parse(&mut self, meta: ParseNestedMeta) -> syn::Result<()> {
+        if meta.path.is_ident("name") {
+            self.name = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("description") {
+            self.description = Some(meta.value()?.parse()?);
+        } else if meta.path.is_ident("crate") {
+            let path: LitStr = meta.value()?.parse()?;
+            self.krate = Some(path.parse()?);
+        } else {
+            return Err(meta.error("expected `name`, `description` or `crate`"));
+        }
+        Ok(())
+    }
+}
+
+/// A function parameter exposed to the model
+struct Param {
+    ident: Ident,
+    ty: Type,
+    schema: FieldSchema,
+}
+
+pub(crate) fn expand(options: ToolOptions, mut function: ItemFn) -> syn::Result<TokenStream> {
+    let sig = &function.sig;
+    if !sig.generics.params.is_empty() {
+        return Err(syn::Error::new_spanned(
+            &sig.generics,
+            "tool functions cannot be generic",
+        ));
+    }
+
+    let mut params = Vec::new();
+    for input in function.sig.inputs.iter_mut() {
+        let arg = match input {
+            FnArg::Typed(arg) => arg,
+            FnArg::Receiver(receiver) => {
+                return Err(syn::Error::new_spanned(
+                    receiver,
+                    "tool functions cannot take `self`",
+                ))
+            }
+        };
+        let ident = match arg.pat.as_ref() {
+            Pat::Ident(pat) => pat.ident.clone(),
+            pat => {
+                return Err(syn::Error::new_spanned(
+                    pat,
+                    "tool parameters must be plain identifiers",
+                ))
+            }
+        };
+        if let Type::Reference(reference) = arg.ty.as_ref() {
+            return Err(syn::Error::new_spanned(
+                reference,
+                "tool parameters must be owned; use `String` instead of `&str`",
+            ));
+        }
+        // Doc comments aren't allowed on parameters, so take them off here
+        let schema = FieldSchema::parse(&arg.attrs)?;
+        arg.attrs
+            .retain(|attr| !attr.path().is_ident("doc") && !attr.path().is_ident("schema"));
+        params.push(Param {
+            ident,
+            ty: (*arg.ty).clone(),
+            schema,
+        });
+    }
+
+    let krate = options.krate.unwrap_or_else(|| syn::parse_quote!(::cogni));
+    let sig = &function.sig;
+    let fn_name = &sig.ident;
+    let vis = &function.vis;
+    let tool_name = options
+        .name
+        .map(|name| name.value())
+        .unwrap_or_else(|| fn_name.to_string().trim_start_matches("r#").to_string());
+    let description = options
+        .description
+        .map(|description| description.value())
+        .or_else(|| doc_string(&function.attrs))
+        .unwrap_or_default();
+    let struct_name = format_ident!("{}Tool", pascal_case(&fn_name.to_string()));
+    let args_name = format_ident!("__{}Args", struct_name);
+
+    let ctx = SchemaContext {
+        json: quote!(#krate::__private::serde_json),
+        runtime: quote!(#krate::types::structured::__derive),
+    };
+    let json = &ctx.json;
+    let runtime = &ctx.runtime;
+    let properties = params.iter().map(|param| {
+        let name = param.ident.to_string();
+        let schema = ctx.field_schema(
+            &param.ty,
+            param.schema.description.as_deref(),
+            &param.schema.keywords,
+        );
+        quote! { #name: (#schema) }
+    });
+    let required = params
+        .iter()
+        .filter(|param| !is_option_type(&param.ty))
+        .map(|param| param.ident.to_string());
+
+    let idents: Vec<_> = params.iter().map(|param| &param.ident).collect();
+    let types = params.iter().map(|param| &param.ty);
+    let serde_path = LitStr::new(
+        &format!("{}::__private::serde", quote!(#krate)).replace(' ', ""),
+        Span::call_site(),
+    );
+    let args_struct = quote! {
+        #[derive(#krate::__private::serde::Deserialize)]
+        #[serde(crate = #serde_path)]
+        #[allow(non_camel_case_types)]
+        struct #args_name {
+            #(#idents: #types,)*
+        }
+    };
+    let bind_args = if params.is_empty() {
+        quote! { let _ = args; }
+    } else {
+        quote! {
+            let #args_name { #(#idents,)* } = #json::from_value(args).map_err(|e| {
+                #krate::tools::ToolError::InvalidArguments {
+                    tool: #tool_name.to_string(),
+                    message: e.to_string(),
+                    source: Some(Box::new(e)),
+                }
+            })?;
+        }
+    };
+
+    let call = if sig.asyncness.is_some() {
+        quote! { #fn_name(#(#idents),*).await }
+    } else {
+        quote! { #fn_name(#(#idents),*) }
+    };
+    let output = if returns_result(&sig.output) {
+        quote! {
+            match #call {
+                Ok(output) => output,
+                Err(e) => {
+                    return Err(#krate::tools::ToolError::ExecutionFailed {
+                        tool: #tool_name.to_string(),
+                        message: e.to_string(),
+                        source: None,
+                    })
+                }
+            }
+        }
+    } else {
+        call
+    };
+    let body = quote! {
+        #bind_args
+        let output = #output;
+        #json::to_value(output).map_err(|e| #krate::tools::ToolError::ExecutionFailed {
+            tool: #tool_name.to_string(),
+            message: format!("Failed to serialize output: {}", e),
+            source: Some(Box::new(e)),
+        })
+    };
+    let build = if sig.asyncness.is_some() {
+        quote! {
+            build_async(|args: #json::Value| async move { #body })
+        }
+    } else {
+        quote! {
+            build_sync(|args: #json::Value| { #body })
+        }
+    };
+
+    let doc = format!("Tool executor for [`{}`]", fn_name);
+
+    Ok(quote! {
+        #function
+
+        #[doc = #doc]
+        #vis struct #struct_name {
+            inner: #krate::tools::FunctionExecutor,
+        }
+
+        impl #struct_name {
+            /// Create the tool
+            #vis fn new() -> Self {
+                #args_struct
+
+                let defs = &mut #json::Map::new();
+                let parameters = #json::json!({
+                    "type": "object",
+                    "properties": {
+                        #(#properties,)*
+                    },
+                    "required": [#(#required,)*],
+                });
+                let parameters = #runtime::with_defs(parameters, ::core::mem::take(defs));
+                let inner = #krate::tools::FunctionExecutorBuilder::new(#tool_name)
+                    .description(#description)
+                    .parameters(parameters)
+                    .#build;
+                Self { inner }
+            }
+        }
+
+        impl ::core::default::Default for #struct_name {
+            fn default() -> Self {
+                Self::new()
+            }
+        }
+
+        #[#krate::__private::async_trait::async_trait]
+        impl #krate::tools::ToolExecutor for #struct_name {
+            async fn execute(
+                &self,
+                call: &#krate::tools::ToolCall,
+            ) -> #krate::tools::error::Result<#krate::tools::ToolResult> {
+                self.inner.execute(call).await
+            }
+
+            fn tool(&self) -> &#krate::tools::Tool {
+                self.inner.tool()
+            }
+
+            async fn validate(
+                &self,
+                args: &#json::Value,
+            ) -> #krate::tools::error::Result<()> {
+                self.inner.validate(args).await
+            }
+        }
+    })
+}
+
+/// Check whether a return type is a `Result`
+fn returns_result(output: &ReturnType) -> bool {
+    match output {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Path(path) => path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+fn pascal_case(name: &str) -> String {
+    name.trim_start_matches("r#")
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect()
+}
@@ -28,7 +28,10 @@ fn test_simple_struct_schema() {
     assert_eq!(schema["properties"]["name"]["type"], "string");
     assert_eq!(schema["properties"]["age"]["type"], "integer");
     assert_eq!(schema["properties"]["age"]["minimum"], 0);
-    assert_eq!(schema["properties"]["email"]["type"], "string");
+    assert_eq!(
+        schema["properties"]["email"]["type"],
+        json!(["string", "null"])
+    );
     assert_eq!(schema["additionalProperties"], false);
 
     // Required fields should not include optional fields
@@ -52,7 +55,10 @@ fn test_complex_struct_schema() {
     assert_eq!(schema["properties"]["tags"]["items"]["type"], "string");
     assert_eq!(schema["properties"]["active"]["type"], "boolean");
     assert_eq!(schema["properties"]["score"]["type"], "number");
-    assert_eq!(schema["properties"]["metadata"]["type"], "string");
+    assert_eq!(
+        schema["properties"]["metadata"]["type"],
+        json!(["string", "null"])
+    );
 
     // Check required fields
     let required = schema["required"].as_array().unwrap();
@@ -64,6 +70,22 @@ fn test_complex_struct_schema() {
     assert!(!required.contains(&json!("metadata")));
 }
 
+#[test]
+fn test_explicit_null_round_trips() {
+    let response = cogni_core::Response::text(r#"{"name":"a","age":3,"email":null}"#);
+    let person: Person = response.parse_structured().unwrap();
+    assert_eq!(person.email, None);
+
+    let response = cogni_core::Response::text(r#"{"name":"a","age":3,"email":7}"#);
+    assert!(response.parse_structured::<Person>().is_err());
+
+    let response = cogni_core::Response::text(
+        r#"{"name":"Acme","headquarters":{"street":"1 Main","city":"X"},"offices":[],"ceo":null}"#,
+    );
+    let company: Company = response.parse_structured().unwrap();
+    assert!(company.ceo.is_none());
+}
+
 #[test]
 fn test_nested_option() {
     #[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
@@ -74,8 +96,11 @@ fn test_nested_option() {
 
     let schema = WithNestedOption::schema();
 
-    // Optional field should still have the inner type schema
-    assert_eq!(schema["properties"]["optional"]["type"], "array");
+    // Optional field has the inner type schema and also allows null
+    assert_eq!(
+        schema["properties"]["optional"]["type"],
+        json!(["array", "null"])
+    );
     assert_eq!(schema["properties"]["optional"]["items"]["type"], "string");
 
     // But it shouldn't be in required
@@ -83,3 +108,446 @@ fn test_nested_option() {
     assert_eq!(required.len(), 1);
     assert!(required.contains(&json!("required")));
 }
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+struct Address {
+    street: String,
+    city: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+struct Company {
+    name: String,
+    headquarters: Address,
+    offices: Vec<Address>,
+    ceo: Option<Person>,
+}
+
+#[test]
+fn test_nested_types_use_defs() {
+    let schema = Company::schema();
+
+    assert_eq!(
+        schema["properties"]["headquarters"],
+        json!({ "$ref": "#/$defs/Address" })
+    );
+    assert_eq!(
+        schema["properties"]["offices"]["items"],
+        json!({ "$ref": "#/$defs/Address" })
+    );
+    assert_eq!(
+        schema["properties"]["ceo"],
+        json!({ "anyOf": [{ "$ref": "#/$defs/Person" }, { "type": "null" }] })
+    );
+    assert_eq!(schema["$defs"]["Address"], Address::schema());
+    assert_eq!(schema["$defs"]["Person"], Person::schema());
+    assert_eq!(schema["$defs"].as_object().unwrap().len(), 2);
+    assert!(Address::schema().get("$defs").is_none());
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+struct Department {
+    company: Company,
+    backup: Address,
+}
+
+#[test]
+fn test_nested_defs_are_hoisted() {
+    let schema = Department::schema();
+    let defs = schema["$defs"].as_object().unwrap();
+
+    let mut names: Vec<_> = defs.keys().cloned().collect();
+    names.sort();
+    assert_eq!(names, vec!["Address", "Company", "Person"]);
+    assert!(defs["Company"].get("$defs").is_none());
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+struct TreeNode {
+    label: String,
+    children: Vec<TreeNode>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+struct Forest {
+    trees: Vec<TreeNode>,
+}
+
+#[test]
+fn test_recursive_types() {
+    let schema = TreeNode::schema();
+    assert_eq!(
+        schema["properties"]["children"]["items"],
+        json!({ "$ref": "#" })
+    );
+    assert!(schema.get("$defs").is_none());
+
+    let schema = Forest::schema();
+    assert_eq!(
+        schema["properties"]["trees"]["items"],
+        json!({ "$ref": "#/$defs/TreeNode" })
+    );
+    assert_eq!(
+        schema["$defs"]["TreeNode"]["properties"]["children"]["items"],
+        json!({ "$ref": "#/$defs/TreeNode" })
+    );
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+enum Priority {
+    Low,
+    VeryHigh,
+    #[serde(rename = "meh")]
+    Medium,
+    #[serde(skip)]
+    #[allow(dead_code)]
+    Unknown,
+}
+
+#[test]
+fn test_unit_enum() {
+    assert_eq!(
+        Priority::schema(),
+        json!({ "type": "string", "enum": ["LOW", "VERY_HIGH", "meh"] })
+    );
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+enum Shape {
+    Empty,
+    Circle(f64),
+    Point(i32, i32),
+    Rect { width: f64, height: f64 },
+}
+
+#[test]
+fn test_externally_tagged_enum() {
+    let schema = Shape::schema();
+    let variants = schema["oneOf"].as_array().unwrap();
+    assert_eq!(variants.len(), 4);
+    assert_eq!(variants[0], json!({ "type": "string", "const": "Empty" }));
+    assert_eq!(
+        variants[1]["properties"]["Circle"],
+        json!({ "type": "number" })
+    );
+    assert_eq!(variants[1]["required"], json!(["Circle"]));
+    assert_eq!(
+        variants[2]["properties"]["Point"]["prefixItems"],
+        json!([{ "type": "integer" }, { "type": "integer" }])
+    );
+    assert_eq!(variants[3]["properties"]["Rect"]["type"], "object");
+    assert_eq!(
+        variants[3]["properties"]["Rect"]["required"],
+        json!(["width", "height"])
+    );
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+#[serde(
+    tag = "type",
+    rename_all = "snake_case",
+    rename_all_fields = "camelCase"
+)]
+enum Event {
+    Started,
+    Moved { from_x: i32, to_x: i32 },
+    Labeled(Address),
+}
+
+#[test]
+fn test_internally_tagged_enum() {
+    let schema = Event::schema();
+    let variants = schema["oneOf"].as_array().unwrap();
+    assert_eq!(
+        variants[0],
+        json!({
+            "type": "object",
+            "properties": { "type": { "type": "string", "const": "started" } },
+            "required": ["type"],
+            "additionalProperties": false
+        })
+    );
+    assert_eq!(
+        variants[1]["properties"]["type"],
+        json!({ "type": "string", "const": "moved" })
+    );
+    assert_eq!(variants[1]["required"], json!(["type", "fromX", "toX"]));
+    assert_eq!(variants[2]["required"], json!(["type", "street", "city"]));
+    assert_eq!(variants[2]["properties"]["city"]["type"], "string");
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+#[serde(tag = "kind", content = "data")]
+enum Message {
+    Ping,
+    Text(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+#[serde(untagged)]
+enum Value {
+    Number(f64),
+    Pair(String, String),
+    Nothing,
+}
+
+#[test]
+fn test_adjacent_and_untagged_enums() {
+    let schema = Message::schema();
+    let variants = schema["oneOf"].as_array().unwrap();
+    assert_eq!(variants[0]["required"], json!(["kind"]));
+    assert_eq!(
+        variants[1]["properties"],
+        json!({
+            "kind": { "type": "string", "const": "Text" },
+            "data": { "type": "string" }
+        })
+    );
+    assert_eq!(variants[1]["required"], json!(["kind", "data"]));
+
+    let schema = Value::schema();
+    let variants = schema["oneOf"].as_array().unwrap();
+    assert_eq!(variants[0], json!({ "type": "number" }));
+    assert_eq!(variants[1]["type"], "array");
+    assert_eq!(variants[2], json!({ "type": "null" }));
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, StructuredOutput)]
+struct Audit {
+    created_by: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+#[serde(rename_all = "camelCase")]
+struct Record {
+    record_id: u64,
+    #[serde(rename = "Title")]
+    title: String,
+    #[serde(default)]
+    retries: u32,
+    #[serde(skip)]
+    #[allow(dead_code)]
+    cache: Vec<u8>,
+    #[serde(flatten)]
+    audit: Audit,
+    #[serde(flatten)]
+    extra: std::collections::HashMap<String, i64>,
+}
+
+#[test]
+fn test_serde_field_attributes() {
+    let schema = Record::schema();
+    let properties = schema["properties"].as_object().unwrap();
+
+    let mut names: Vec<_> = properties.keys().cloned().collect();
+    names.sort();
+    assert_eq!(names, vec!["Title", "created_by", "recordId", "retries"]);
+    assert_eq!(
+        schema["required"],
+        json!(["recordId", "Title", "created_by"])
+    );
+    assert_eq!(schema["additionalProperties"], json!({ "type": "integer" }));
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+#[serde(default)]
+struct Settings {
+    verbose: bool,
+    level: u8,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            verbose: false,
+            level: 1,
+        }
+    }
+}
+
+#[test]
+fn test_container_default() {
+    assert_eq!(Settings::schema()["required"], json!([]));
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+struct UserId(String);
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+struct Coordinates(f64, f64, Option<f64>);
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+#[serde(transparent)]
+struct Wrapper {
+    inner: Vec<UserId>,
+}
+
+#[test]
+fn test_tuple_and_newtype_structs() {
+    assert_eq!(UserId::schema(), json!({ "type": "string" }));
+    assert_eq!(
+        Coordinates::schema(),
+        json!({
+            "type": "array",
+            "prefixItems": [
+                { "type": "number" },
+                { "type": "number" },
+                { "type": ["number", "null"] }
+            ],
+            "items": false,
+            "minItems": 3,
+            "maxItems": 3
+        })
+    );
+    assert_eq!(
+        Wrapper::schema(),
+        json!({
+            "type": "array",
+            "items": { "$ref": "#/$defs/UserId" },
+            "$defs": { "UserId": { "type": "string" } }
+        })
+    );
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+struct Page<T> {
+    items: Vec<T>,
+    next: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+struct Search {
+    people: Page<Person>,
+}
+
+#[test]
+fn test_generic_types() {
+    let schema = Search::schema();
+    assert_eq!(
+        schema["properties"]["people"],
+        json!({ "$ref": "#/$defs/Page_Person" })
+    );
+    assert_eq!(
+        schema["$defs"]["Page_Person"]["properties"]["items"]["items"],
+        json!({ "$ref": "#/$defs/Person" })
+    );
+    assert!(schema["$defs"]["Person"].is_object());
+}
+
+/// A product listing
+///
+/// Prices are in cents.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, StructuredOutput)]
+#[schema(example = Product { sku: "AB-1234".into(), price: 999, tags: vec!["sale".into()], size: None })]
+#[schema(example = Product::sample())]
+struct Product {
+    /// Stock keeping unit
+    #[schema(pattern = "^[A-Z]{2}-[0-9]{4}$", examples = ["AB-1234"])]
+    sku: String,
+    #[schema(minimum = 1, maximum = 1_000_000, description = "Price in cents")]
+    price: u32,
+    #[schema(min_items = 1, max_items = 5)]
+    tags: Vec<String>,
+    #[schema(enum = ["S", "M", "L"])]
+    size: Option<String>,
+}
+
+impl Product {
+    fn sample() -> Self {
+        Self {
+            sku: "CD-5678".into(),
+            price: 150,
+            tags: vec!["new".into()],
+            size: Some("M".into()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+struct Contact {
+    #[schema(format = "email", min_length = 3, max_length = 254)]
+    email: String,
+    #[schema(exclusive_minimum = -90.5, exclusive_maximum = 90.5)]
+    latitude: f64,
+}
+
+#[test]
+fn test_descriptions_from_doc_comments() {
+    let schema = Product::schema();
+    assert_eq!(
+        schema["description"],
+        "A product listing\n\nPrices are in cents."
+    );
+    assert_eq!(
+        schema["properties"]["sku"]["description"],
+        "Stock keeping unit"
+    );
+    assert_eq!(
+        schema["properties"]["price"]["description"],
+        "Price in cents"
+    );
+    assert!(schema["properties"]["tags"].get("description").is_none());
+}
+
+#[test]
+fn test_schema_constraints() {
+    let schema = Product::schema();
+    assert_eq!(
+        schema["properties"]["sku"],
+        json!({
+            "type": "string",
+            "description": "Stock keeping unit",
+            "pattern": "^[A-Z]{2}-[0-9]{4}$",
+            "examples": ["AB-1234"]
+        })
+    );
+    assert_eq!(schema["properties"]["price"]["minimum"], 1);
+    assert_eq!(schema["properties"]["price"]["maximum"], 1_000_000);
+    assert_eq!(schema["properties"]["tags"]["minItems"], 1);
+    assert_eq!(schema["properties"]["tags"]["maxItems"], 5);
+    assert_eq!(
+        schema["properties"]["size"]["enum"],
+        json!(["S", "M", "L", null])
+    );
+
+    let schema = Contact::schema();
+    assert_eq!(
+        schema["properties"]["email"],
+        json!({ "type": "string", "format": "email", "minLength": 3, "maxLength": 254 })
+    );
+    assert_eq!(schema["properties"]["latitude"]["exclusiveMinimum"], -90.5);
+    assert_eq!(schema["properties"]["latitude"]["exclusiveMaximum"], 90.5);
+}
+
+#[test]
+fn test_container_examples() {
+    let examples = Product::examples();
+    assert_eq!(examples.len(), 2);
+    assert_eq!(examples[0].sku, "AB-1234");
+    assert_eq!(examples[1], Product::sample());
+    assert!(Contact::examples().is_empty());
+}
+
+/// How urgent a ticket is
+#[derive(Debug, Clone, Serialize, Deserialize, StructuredOutput)]
+enum Urgency {
+    /// Can wait until next week
+    Low,
+    /// Needs attention today
+    High,
+}
+
+#[test]
+fn test_variant_descriptions() {
+    assert_eq!(
+        Urgency::schema(),
+        json!({
+            "description": "How urgent a ticket is",
+            "oneOf": [
+                { "type": "string", "const": "Low", "description": "Can wait until next week" },
+                { "type": "string", "const": "High", "description": "Needs attention today" }
+            ]
+        })
+    );
+}
@@ -1,92 +1,71 @@
 //! Caching middleware for response caching
-
-use crate::{BoxFuture, Layer, Service};
-use cogni_core::{Error, Request, Response};
+//!
+//! Responses are stored in a [`CacheBackend`]: [`MemoryCache`] keeps them in
+//! process, [`DiskCache`] persists them so they survive restarts and can be
+//! shared between processes.
+
+use crate::{BoxFuture, BoxStream, Layer, Service};
+use async_trait::async_trait;
+use cogni_core::{
+    ContentDelta, Error, MetadataDelta, Provider, Request, Response, ResponseMetadata,
+    StreamAccumulator, StreamEvent, ToolCallDelta,
+};
+use futures::StreamExt;
+use futures_core::Stream;
 use indexmap::IndexMap;
-use sha2::{Digest, Sha256};
+use std::fmt;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
-use tracing::{debug, trace};
+use tokio::sync::Mutex;
+use tracing::{debug, trace, warn};
 
-/// Cache middleware layer
-#[derive(Debug, Clone)]
-pub struct CacheLayer {
-    /// Cache instance
-    cache: Arc<RwLock<ResponseCache>>,
-}
+mod disk;
+mod key;
 
-/// Cache key for requests
-#[derive(Debug, Clone, PartialEq, Eq, Hash)]
-pub struct CacheKey(String);
-
-impl CacheKey {
-    /// Create a cache key from a request
-    pub fn from_request(request: &Request) -> Self {
-        let mut hasher = Sha256::new();
-
-        // Hash the model
-        hasher.update(request.model.0.as_bytes());
-        hasher.update(b"|");
-
-        // Hash messages
-        for msg in &request.messages {
-            // Use a byte representation for role instead of format!
-            match msg.role {
-                cogni_core::Role::System => hasher.update(b"0"),
-                cogni_core::Role::User => hasher.update(b"1"),
-                cogni_core::Role::Assistant => hasher.update(b"2"),
-                cogni_core::Role::Tool => hasher.update(b"3"),
-                _ => hasher.update(b"9"), // Unknown roles
-            }
-            hasher.update(b"|");
+pub use disk::DiskCache;
+pub use key::{CacheKey, CacheKeyOptions};
 
-            match &msg.content {
-                cogni_core::Content::Text(text) => {
-                    hasher.update(b"text:");
-                    hasher.update(text.as_bytes());
-                }
-                cogni_core::Content::Image(image) => {
-                    hasher.update(b"image:");
-                    if let Some(url) = &image.url {
-                        hasher.update(url.as_bytes());
-                    }
-                }
-                cogni_core::Content::Audio(audio) => {
-                    hasher.update(b"audio:");
-                    hasher.update(audio.data.as_bytes());
-                }
-                cogni_core::Content::Multiple(parts) => {
-                    hasher.update(b"multiple:");
-                    hasher.update((parts.len() as u32).to_le_bytes());
-                }
-            }
-            hasher.update(b"|");
-        }
+/// Storage for cached responses
+///
+/// Each backend applies its own expiry and eviction. Backend errors never fail
+/// a request: [`CacheService`] logs them and calls the inner service.
+#[async_trait]
+pub trait CacheBackend: Send + Sync {
+    /// Look up a response, or `None` if it is missing or expired
+    async fn get(&self, key: &CacheKey) -> Result<Option<Response>, Error>;
 
-        // Hash temperature if set
-        if let Some(temp) = request.parameters.temperature {
-            hasher.update(b"temp:");
-            hasher.update(temp.to_le_bytes());
-            hasher.update(b"|");
-        }
+    /// Store a response
+    async fn put(&self, key: CacheKey, response: Response) -> Result<(), Error>;
 
-        // Hash max_tokens if set
-        if let Some(max) = request.parameters.max_tokens {
-            hasher.update(b"max:");
-            hasher.update(max.to_le_bytes());
-            hasher.update(b"|");
-        }
+    /// Remove a response
+    async fn remove(&self, key: &CacheKey) -> Result<(), Error>;
 
-        // Hash tools
-        for tool in &request.tools {
-            hasher.update(b"tool:");
-            hasher.update(tool.name.as_bytes());
-            hasher.update(b"|");
-        }
+    /// Remove every response
+    async fn clear(&self) -> Result<(), Error>;
+}
 
-        let result = hasher.finalize();
-        CacheKey(format!("{:x}", result))
+/// Cache middleware layer
+#[derive(Clone)]
+pub struct CacheLayer {
+    /// Where responses are stored
+    backend: Arc<dyn CacheBackend>,
+    /// Whether streaming calls are cached too
+    streaming: bool,
+    /// Which parts of a request make up its key
+    key_options: CacheKeyOptions,
+    /// Whether sampled requests without a seed bypass the cache
+    deterministic_only: bool,
+}
+
+impl fmt::Debug for CacheLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CacheLayer")
+            .field("streaming", &self.streaming)
+            .field("key_options", &self.key_options)
+            .field("deterministic_only", &self.deterministic_only)
+            .finish_non_exhaustive()
     }
 }
 
@@ -171,10 +150,15 @@ impl ResponseCache {
     }
 
     /// Remove an entry
-    fn remove(&mut self, key: &CacheKey) {
+    pub fn remove(&mut self, key: &CacheKey) {
         self.entries.shift_remove(key);
     }
 
+    /// Remove all entries
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
     /// Clear expired entries
     pub fn clear_expired(&mut self) {
         let now = Instant::now();
@@ -189,12 +173,252 @@ impl ResponseCache {
     }
 }
 
+/// In-memory [`CacheBackend`] with TTL and LRU eviction
+#[derive(Debug)]
+pub struct MemoryCache {
+    cache: Mutex<ResponseCache>,
+}
+
+impl MemoryCache {
+    /// Create a cache holding up to `max_size` responses for `ttl` each
+    pub fn new(max_size: usize, ttl: Duration) -> Self {
+        Self {
+            cache: Mutex::new(ResponseCache::new(max_size, ttl)),
+        }
+    }
+}
+
+#[async_trait]
+impl CacheBackend for MemoryCache {
+    async fn get(&self, key: &CacheKey) -> Result<Option<Response>, Error> {
+        Ok(self.cache.lock().await.get(key))
+    }
+
+    async fn put(&self, key: CacheKey, response: Response) -> Result<(), Error> {
+        self.cache.lock().await.put(key, response);
+        Ok(())
+    }
+
+    async fn remove(&self, key: &CacheKey) -> Result<(), Error> {
+        self.cache.lock().await.remove(key);
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), Error> {
+        self.cache.lock().await.clear();
+        Ok(())
+    }
+}
+
 impl CacheLayer {
-    /// Create a new cache layer
+    /// Create a new cache layer backed by a [`MemoryCache`]
     pub fn new(max_size: usize, ttl: Duration) -> Self {
+        Self::with_backend(MemoryCache::new(max_size, ttl))
+    }
+
+    /// Create a cache layer storing responses in `backend`
+    pub fn with_backend(backend: impl CacheBackend + 'static) -> Self {
         Self {
-            cache: Arc::new(RwLock::new(ResponseCache::new(max_size, ttl))),
+            backend: Arc::new(backend),
+            streaming: false,
+            key_options: CacheKeyOptions::default(),
+            deterministic_only: false,
+        }
+    }
+
+    /// Choose which parts of a request make up its cache key
+    pub fn with_key_options(mut self, options: CacheKeyOptions) -> Self {
+        self.key_options = options;
+        self
+    }
+
+    /// Bypass the cache for sampled requests, unless they set a seed
+    ///
+    /// A request is sampled unless its temperature is zero. Leaving the
+    /// temperature unset counts as sampling, since most providers then
+    /// default to 1.0. Sampled responses differ from call to call, so
+    /// replaying one may not be what the caller wants.
+    pub fn deterministic_only(mut self) -> Self {
+        self.deterministic_only = true;
+        self
+    }
+
+    /// Cache the accumulated result of streaming calls as well
+    ///
+    /// Only streams that end with [`StreamEvent::Done`] are stored; one that
+    /// fails or stops early may be truncated. Providers that route streams
+    /// through the cache, such as those built from a `cogni` configuration,
+    /// call [`stream`](Self::stream) when this is set.
+    pub fn with_streaming(mut self) -> Self {
+        self.streaming = true;
+        self
+    }
+
+    /// Whether streaming calls are cached
+    pub fn caches_streams(&self) -> bool {
+        self.streaming
+    }
+
+    /// The backend responses are stored in
+    pub fn backend(&self) -> &Arc<dyn CacheBackend> {
+        &self.backend
+    }
+
+    /// The key a request is cached under, or `None` if it bypasses the cache
+    ///
+    /// Requests whose key can't be derived bypass the cache.
+    pub fn key_for(&self, request: &Request) -> Option<CacheKey> {
+        let parameters = &request.parameters;
+        let sampled = !matches!(parameters.temperature, Some(t) if t <= 0.0);
+        if self.deterministic_only && sampled && parameters.seed.is_none() {
+            trace!("Bypassing cache for sampled request without a seed");
+            return None;
+        }
+        match self.key_options.key(request) {
+            Ok(key) => Some(key),
+            Err(e) => {
+                warn!("Bypassing cache for request without a cache key: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Stream a request through the cache
+    ///
+    /// A cached response is replayed as stream events. Otherwise the
+    /// provider's stream is passed through and, once it finishes without an
+    /// error, the accumulated response is stored.
+    pub async fn stream<P>(
+        &self,
+        provider: &P,
+        request: Request,
+    ) -> Result<BoxStream<Result<StreamEvent, Error>>, Error>
+    where
+        P: Provider,
+        P::Stream: 'static,
+    {
+        let Some(key) = self.key_for(&request) else {
+            return Ok(Box::pin(provider.stream(request).await?));
+        };
+        if let Some(response) = lookup(self.backend.as_ref(), &key).await {
+            return Ok(Box::pin(futures::stream::iter(
+                replay(response).into_iter().map(Ok),
+            )));
+        }
+
+        let inner = provider.stream(request).await?;
+        Ok(Box::pin(CachingStream {
+            inner: Box::pin(inner),
+            key: Some(key),
+            backend: self.backend.clone(),
+            accumulator: StreamAccumulator::new(),
+            metadata: ResponseMetadata::default(),
+        }))
+    }
+}
+
+/// Look up a key, treating backend errors as misses
+async fn lookup(backend: &dyn CacheBackend, key: &CacheKey) -> Option<Response> {
+    match backend.get(key).await {
+        Ok(response) => response,
+        Err(e) => {
+            warn!(cache_key = %key.0, error = %e, "Cache lookup failed");
+            None
+        }
+    }
+}
+
+/// Store a response, logging backend errors
+async fn store(backend: &dyn CacheBackend, key: CacheKey, response: Response) {
+    let cache_key = key.0.clone();
+    if let Err(e) = backend.put(key, response).await {
+        warn!(cache_key = %cache_key, error = %e, "Failed to cache response");
+    }
+}
+
+/// The stream events that reproduce a cached response
+fn replay(response: Response) -> Vec<StreamEvent> {
+    let mut events = vec![StreamEvent::Metadata(MetadataDelta {
+        model: response.metadata.model,
+        id: response.metadata.id,
+        custom: response.metadata.custom,
+    })];
+    if !response.content.is_empty() {
+        events.push(StreamEvent::Content(ContentDelta {
+            text: response.content,
+        }));
+    }
+    events.extend(
+        response
+            .tool_calls
+            .into_iter()
+            .enumerate()
+            .map(|(index, call)| {
+                StreamEvent::ToolCall(ToolCallDelta {
+                    index,
+                    id: Some(call.id),
+                    name: Some(call.name),
+                    arguments: Some(call.arguments),
+                })
+            }),
+    );
+    events.push(StreamEvent::Done);
+    events
+}
+
+/// A provider stream that caches its accumulated response once it sends
+/// [`StreamEvent::Done`]
+struct CachingStream {
+    inner: BoxStream<Result<StreamEvent, Error>>,
+    /// Taken once the response is stored or the stream fails or ends early
+    key: Option<CacheKey>,
+    backend: Arc<dyn CacheBackend>,
+    accumulator: StreamAccumulator,
+    metadata: ResponseMetadata,
+}
+
+impl CachingStream {
+    fn finish(&mut self) {
+        let Some(key) = self.key.take() else {
+            return;
+        };
+        let response = Response {
+            content: self.accumulator.content().to_string(),
+            tool_calls: self.accumulator.tool_calls(),
+            metadata: std::mem::take(&mut self.metadata),
+        };
+        let backend = self.backend.clone();
+        tokio::spawn(async move { store(backend.as_ref(), key, response).await });
+    }
+}
+
+impl Stream for CachingStream {
+    type Item = Result<StreamEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let polled = self.inner.poll_next_unpin(cx);
+        match &polled {
+            Poll::Ready(Some(Ok(event))) => {
+                if let StreamEvent::Metadata(delta) = event {
+                    if delta.model.is_some() {
+                        self.metadata.model = delta.model.clone();
+                    }
+                    if delta.id.is_some() {
+                        self.metadata.id = delta.id.clone();
+                    }
+                    self.metadata.custom.extend(delta.custom.clone());
+                }
+                let done = matches!(event, StreamEvent::Done);
+                let _ = self.accumulator.process_event(event.clone());
+                if done {
+                    self.finish();
+                }
+            }
+            // A stream that fails or ends without `Done` may be truncated
+            Poll::Ready(Some(Err(_))) | Poll::Ready(None) => self.key = None,
+            Poll::Pending => {}
         }
+        polled
     }
 }
 
@@ -204,7 +428,7 @@ impl<S> Layer<S> for CacheLayer {
     fn layer(&self, inner: S) -> Self::Service {
         CacheService {
             inner,
-            cache: self.cache.clone(),
+            cache: self.clone(),
         }
     }
 }
@@ -213,7 +437,7 @@ impl<S> Layer<S> for CacheLayer {
 #[derive(Clone)]
 pub struct CacheService<S> {
     inner: S,
-    cache: Arc<RwLock<ResponseCache>>,
+    cache: CacheLayer,
 }
 
 impl<S> Service<Request> for CacheService<S>
@@ -226,19 +450,19 @@ where
     type Future = BoxFuture<Result<Self::Response, Self::Error>>;
 
     fn call(&mut self, request: Request) -> Self::Future {
-        let cache = self.cache.clone();
-        let key = CacheKey::from_request(&request);
-
-        // Check cache first
-        let cache_check = cache.clone();
-        let key_check = key.clone();
+        let backend = self.cache.backend.clone();
+        let key = self.cache.key_for(&request);
 
         // Clone the inner service for the async block
         let mut inner = self.inner.clone();
 
         Box::pin(async move {
+            let Some(key) = key else {
+                return inner.call(request).await;
+            };
+
             // Try to get from cache
-            if let Some(response) = cache_check.write().await.get(&key_check) {
+            if let Some(response) = lookup(backend.as_ref(), &key).await {
                 return Ok(response);
             }
 
@@ -246,7 +470,7 @@ where
             let response = inner.call(request).await?;
 
             // Cache the response
-            cache.write().await.put(key, response.clone());
+            store(backend.as_ref(), key, response.clone()).await;
 
             Ok(response)
         })
@@ -309,9 +533,9 @@ mod tests {
         let request2 = create_test_request("Hello");
         let request3 = create_test_request("World");
 
-        let key1 = CacheKey::from_request(&request1);
-        let key2 = CacheKey::from_request(&request2);
-        let key3 = CacheKey::from_request(&request3);
+        let key1 = CacheKey::from_request(&request1).unwrap();
+        let key2 = CacheKey::from_request(&request2).unwrap();
+        let key3 = CacheKey::from_request(&request3).unwrap();
 
         // Same requests should produce same keys
         assert_eq!(key1, key2);
@@ -330,9 +554,9 @@ mod tests {
         let mut request3 = create_test_request("Hello");
         request3.parameters.max_tokens = Some(100);
 
-        let key1 = CacheKey::from_request(&request1);
-        let key2 = CacheKey::from_request(&request2);
-        let key3 = CacheKey::from_request(&request3);
+        let key1 = CacheKey::from_request(&request1).unwrap();
+        let key2 = CacheKey::from_request(&request2).unwrap();
+        let key3 = CacheKey::from_request(&request3).unwrap();
 
         // Different temperatures should produce different keys
         assert_ne!(key1, key2);
@@ -353,6 +577,7 @@ mod tests {
                     data: None,
                     mime_type: "image/jpeg".into(),
                 }),
+                tool_calls: Vec::new(),
                 metadata: Default::default(),
             })
             .build();
@@ -365,13 +590,14 @@ mod tests {
                     data: "base64-audio-data".into(),
                     mime_type: "audio/mpeg".into(),
                 }),
+                tool_calls: Vec::new(),
                 metadata: Default::default(),
             })
             .build();
 
-        let key1 = CacheKey::from_request(&text_request);
-        let key2 = CacheKey::from_request(&image_request);
-        let key3 = CacheKey::from_request(&audio_request);
+        let key1 = CacheKey::from_request(&text_request).unwrap();
+        let key2 = CacheKey::from_request(&image_request).unwrap();
+        let key3 = CacheKey::from_request(&audio_request).unwrap();
 
         // Different content types should produce different keys
         assert_ne!(key1, key2);
@@ -587,4 +813,190 @@ mod tests {
         // Count should still be 1 since cache was hit
         assert_eq!(shared_count.load(Ordering::SeqCst), 1);
     }
+
+    #[tokio::test]
+    async fn test_cache_layer_with_backend() {
+        let dir = tempfile::tempdir().unwrap();
+        let call_count = Arc::new(AtomicUsize::new(0));
+        let mock_service = MockService {
+            call_count: call_count.clone(),
+            response: create_test_response("persisted"),
+        };
+        let request = create_test_request("test");
+
+        let layer = CacheLayer::with_backend(DiskCache::new(dir.path()).unwrap());
+        layer
+            .layer(mock_service.clone())
+            .call(request.clone())
+            .await
+            .unwrap();
+
+        // A fresh layer over the same directory, as after a restart
+        let layer = CacheLayer::with_backend(DiskCache::new(dir.path()).unwrap());
+        let response = layer.layer(mock_service).call(request).await.unwrap();
+        assert_eq!(response.content, "persisted");
+        assert_eq!(call_count.load(Ordering::SeqCst), 1);
+    }
+
+    #[derive(Clone)]
+    struct StreamingProvider {
+        call_count: Arc<AtomicUsize>,
+        fail: bool,
+        /// Whether the stream ends with `Done`
+        done: bool,
+    }
+
+    #[async_trait]
+    impl Provider for StreamingProvider {
+        type Stream = BoxStream<Result<StreamEvent, Error>>;
+
+        async fn request(&self, _request: Request) -> Result<Response, Error> {
+            unreachable!("only streams are used")
+        }
+
+        async fn stream(&self, _request: Request) -> Result<Self::Stream, Error> {
+            self.call_count.fetch_add(1, Ordering::SeqCst);
+            let mut events = vec![
+                Ok(StreamEvent::Metadata(MetadataDelta {
+                    model: Some("test-model".into()),
+                    ..Default::default()
+                })),
+                Ok(StreamEvent::Content(ContentDelta { text: "Hel".into() })),
+                Ok(StreamEvent::Content(ContentDelta { text: "lo".into() })),
+                Ok(StreamEvent::ToolCall(ToolCallDelta {
+                    index: 0,
+                    id: Some("call_1".into()),
+                    name: Some("lookup".into()),
+                    arguments: Some("{}".into()),
+                })),
+            ];
+            if self.fail {
+                events.push(Err(Error::Timeout));
+            }
+            if self.done {
+                events.push(Ok(StreamEvent::Done));
+            }
+            Ok(Box::pin(futures::stream::iter(events)))
+        }
+    }
+
+    async fn accumulate(stream: BoxStream<Result<StreamEvent, Error>>) -> (String, usize) {
+        let events: Vec<_> = stream.collect().await;
+        let mut accumulator = StreamAccumulator::new();
+        for event in events.into_iter().flatten() {
+            accumulator.process_event(event).unwrap();
+        }
+        (
+            accumulator.content().to_string(),
+            accumulator.tool_calls().len(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_stream_caching() {
+        let call_count = Arc::new(AtomicUsize::new(0));
+        let provider = StreamingProvider {
+            call_count: call_count.clone(),
+            fail: false,
+            done: true,
+        };
+        let layer = CacheLayer::new(10, Duration::from_secs(60)).with_streaming();
+        assert!(layer.caches_streams());
+        let request = create_test_request("test");
+
+        let first = layer.stream(&provider, request.clone()).await.unwrap();
+        assert_eq!(accumulate(first).await, ("Hello".to_string(), 1));
+        // The response is stored in the background once the stream ends
+        sleep(Duration::from_millis(20)).await;
+
+        let second = layer.stream(&provider, request.clone()).await.unwrap();
+        assert_eq!(accumulate(second).await, ("Hello".to_string(), 1));
+        assert_eq!(call_count.load(Ordering::SeqCst), 1);
+
+        // Streamed results also answer non-streaming calls
+        let cached = layer
+            .backend()
+            .get(&CacheKey::from_request(&request).unwrap())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(cached.metadata.model.as_deref(), Some("test-model"));
+        assert_eq!(cached.tool_calls[0].name, "lookup");
+    }
+
+    #[tokio::test]
+    async fn test_failed_streams_are_not_cached() {
+        let call_count = Arc::new(AtomicUsize::new(0));
+        let layer = CacheLayer::new(10, Duration::from_secs(60)).with_streaming();
+        let request = create_test_request("test");
+
+        // Streams that fail, or end without `Done` as when cut off
+        for (fail, done) in [(true, true), (false, false)] {
+            call_count.store(0, Ordering::SeqCst);
+            let provider = StreamingProvider {
+                call_count: call_count.clone(),
+                fail,
+                done,
+            };
+            for _ in 0..2 {
+                let stream = layer.stream(&provider, request.clone()).await.unwrap();
+                accumulate(stream).await;
+                sleep(Duration::from_millis(20)).await;
+            }
+            assert_eq!(call_count.load(Ordering::SeqCst), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_deterministic_only_bypasses_sampled_requests() {
+        let call_count = Arc::new(AtomicUsize::new(0));
+        let mock_service = MockService {
+            call_count: call_count.clone(),
+            response: create_test_response("test"),
+        };
+        let layer = CacheLayer::new(10, Duration::from_secs(60)).deterministic_only();
+        let mut service = layer.layer(mock_service);
+
+        let mut sampled = create_test_request("test");
+        sampled.parameters.temperature = Some(0.7);
+        service.call(sampled.clone()).await.unwrap();
+        service.call(sampled.clone()).await.unwrap();
+        assert_eq!(call_count.load(Ordering::SeqCst), 2);
+        assert!(layer.key_for(&sampled).is_none());
+
+        // Providers sample by default when no temperature is given
+        let unset = create_test_request("test");
+        assert_eq!(unset.parameters.temperature, None);
+        assert!(layer.key_for(&unset).is_none());
+
+        // A seed makes sampling repeatable, and zero temperature is greedy
+        let mut seeded = sampled.clone();
+        seeded.parameters.seed = Some(1);
+        let mut greedy = sampled;
+        greedy.parameters.temperature = Some(0.0);
+        for request in [seeded, greedy] {
+            service.call(request.clone()).await.unwrap();
+            service.call(request).await.unwrap();
+        }
+        assert_eq!(call_count.load(Ordering::SeqCst), 4);
+    }
+
+    #[tokio::test]
+    async fn test_key_options_share_entries() {
+        let call_count = Arc::new(AtomicUsize::new(0));
+        let mock_service = MockService {
+            call_count: call_count.clone(),
+            response: create_test_response("test"),
+        };
+        let layer = CacheLayer::new(10, Duration::from_secs(60))
+            .with_key_options(CacheKeyOptions::new().ignore("parameters.temperature"));
+        let mut service = layer.layer(mock_service);
+
+        for temperature in [0.1, 0.5, 0.9] {
+            let mut request = create_test_request("test");
+            request.parameters.temperature = Some(temperature);
+            service.call(request).await.unwrap();
+        }
+        assert_eq!(call_count.load(Ordering::SeqCst), 1);
+    }
 }
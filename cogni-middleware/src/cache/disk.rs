@@ -0,0 +1,319 @@
+//! On-disk response cache
+
+use super::{CacheBackend, CacheKey};
+use async_trait::async_trait;
+use cogni_core::{Error, Response};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::fs;
+use tracing::{debug, trace, warn};
+use uuid::Uuid;
+
+/// A cached response as stored on disk
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    /// Expiry time in milliseconds since the Unix epoch
+    expires_at: u64,
+    response: Response,
+}
+
+/// [`CacheBackend`] storing each response as a JSON file named by its key
+///
+/// Entries expire after the TTL (24 hours by default). When the files grow
+/// past `max_bytes` (100 MiB by default) the least recently used are removed;
+/// reading an entry counts as a use. Files are written atomically, so several
+/// processes can share a cache directory.
+///
+/// # Example
+///
+/// ```no_run
+/// use cogni_middleware::cache::{CacheLayer, DiskCache};
+/// use std::time::Duration;
+///
+/// let cache = DiskCache::new(".cache/cogni")
+///     .unwrap()
+///     .ttl(Duration::from_secs(7 * 24 * 60 * 60))
+///     .max_bytes(512 * 1024 * 1024);
+/// let layer = CacheLayer::with_backend(cache).with_streaming();
+/// ```
+#[derive(Debug, Clone)]
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Duration,
+    max_bytes: u64,
+}
+
+impl DiskCache {
+    /// Create a cache in `dir`, creating the directory if needed
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self, Error> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(|e| {
+            Error::Storage(format!(
+                "Failed to create cache directory {}: {}",
+                dir.display(),
+                e
+            ))
+        })?;
+        Ok(Self {
+            dir,
+            ttl: Duration::from_secs(24 * 60 * 60),
+            max_bytes: 100 * 1024 * 1024,
+        })
+    }
+
+    /// Set how long entries stay valid
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Set the total size of entries kept before the oldest are evicted
+    pub fn max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// The cache directory
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Remove expired and unreadable entries, returning how many were removed
+    pub async fn clear_expired(&self) -> Result<usize, Error> {
+        let now = now_millis();
+        let mut removed = 0;
+        for (path, _, _) in self.entries().await? {
+            let expired = match fs::read(&path).await {
+                Ok(bytes) => serde_json::from_slice::<DiskEntry>(&bytes)
+                    .map(|entry| entry.expires_at <= now)
+                    .unwrap_or(true),
+                Err(_) => continue,
+            };
+            if expired && fs::remove_file(&path).await.is_ok() {
+                removed += 1;
+            }
+        }
+        debug!(removed, "Removed expired cache entries");
+        Ok(removed)
+    }
+
+    fn path(&self, key: &CacheKey) -> PathBuf {
+        self.dir.join(format!("{}.json", key.as_str()))
+    }
+
+    /// Cache files with their size and last use
+    async fn entries(&self) -> Result<Vec<(PathBuf, u64, SystemTime)>, Error> {
+        let mut dir = fs::read_dir(&self.dir).await.map_err(storage_error)?;
+        let mut entries = Vec::new();
+        while let Some(entry) = dir.next_entry().await.map_err(storage_error)? {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some("json") {
+                continue;
+            }
+            // Another process may remove the file between listing and stat
+            if let Ok(metadata) = entry.metadata().await {
+                let used = metadata.modified().unwrap_or(UNIX_EPOCH);
+                entries.push((path, metadata.len(), used));
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Remove least recently used entries until the cache fits in `max_bytes`
+    async fn evict(&self) -> Result<(), Error> {
+        let mut entries = self.entries().await?;
+        let mut total: u64 = entries.iter().map(|(_, size, _)| size).sum();
+        if total <= self.max_bytes {
+            return Ok(());
+        }
+
+        entries.sort_by_key(|(_, _, used)| *used);
+        for (path, size, _) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if fs::remove_file(&path).await.is_ok() {
+                debug!(path = %path.display(), "Evicted cache entry");
+            }
+            total = total.saturating_sub(size);
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl CacheBackend for DiskCache {
+    async fn get(&self, key: &CacheKey) -> Result<Option<Response>, Error> {
+        let path = self.path(key);
+        let bytes = match fs::read(&path).await {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                trace!(cache_key = %key.as_str(), "Cache miss");
+                return Ok(None);
+            }
+            Err(e) => return Err(storage_error(e)),
+        };
+
+        let entry = match serde_json::from_slice::<DiskEntry>(&bytes) {
+            Ok(entry) if entry.expires_at > now_millis() => entry,
+            Ok(_) => {
+                trace!(cache_key = %key.as_str(), "Cache entry expired");
+                let _ = fs::remove_file(&path).await;
+                return Ok(None);
+            }
+            Err(e) => {
+                warn!(cache_key = %key.as_str(), error = %e, "Removing unreadable cache entry");
+                let _ = fs::remove_file(&path).await;
+                return Ok(None);
+            }
+        };
+
+        // Mark the entry as recently used for eviction
+        let touched = tokio::task::spawn_blocking(move || {
+            std::fs::File::options()
+                .write(true)
+                .open(&path)?
+                .set_modified(SystemTime::now())
+        })
+        .await;
+        if let Ok(Err(e)) = touched {
+            trace!(cache_key = %key.as_str(), error = %e, "Failed to update cache entry time");
+        }
+
+        trace!(cache_key = %key.as_str(), "Cache hit");
+        Ok(Some(entry.response))
+    }
+
+    async fn put(&self, key: CacheKey, response: Response) -> Result<(), Error> {
+        let expires_at = now_millis().saturating_add(self.ttl.as_millis() as u64);
+        let json = serde_json::to_vec(&DiskEntry {
+            expires_at,
+            response,
+        })
+        .map_err(|e| Error::Serialization {
+            message: format!("Failed to serialize cached response: {}", e),
+            source: Some(Box::new(e)),
+        })?;
+
+        // Write to a uniquely named file first so readers in other processes
+        // never see a partial entry
+        let path = self.path(&key);
+        let temp_path = self
+            .dir
+            .join(format!("{}.{}.tmp", key.as_str(), Uuid::new_v4()));
+        fs::write(&temp_path, &json).await.map_err(storage_error)?;
+        if let Err(e) = fs::rename(&temp_path, &path).await {
+            let _ = fs::remove_file(&temp_path).await;
+            return Err(storage_error(e));
+        }
+        debug!(cache_key = %key.as_str(), bytes = json.len(), "Cached response on disk");
+
+        self.evict().await
+    }
+
+    async fn remove(&self, key: &CacheKey) -> Result<(), Error> {
+        match fs::remove_file(self.path(key)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+            Err(e) => Err(storage_error(e)),
+        }
+    }
+
+    async fn clear(&self) -> Result<(), Error> {
+        for (path, _, _) in self.entries().await? {
+            match fs::remove_file(&path).await {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(storage_error(e)),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as u64
+}
+
+fn storage_error(e: std::io::Error) -> Error {
+    Error::Storage(format!("Response cache I/O failed: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(name: &str) -> CacheKey {
+        CacheKey(name.to_string())
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_and_ttl() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(dir.path()).unwrap();
+
+        let mut response = Response::text("cached");
+        response.metadata.model = Some("test-model".to_string());
+        cache.put(key("a"), response.clone()).await.unwrap();
+        assert_eq!(cache.get(&key("a")).await.unwrap(), Some(response.clone()));
+        assert_eq!(cache.get(&key("b")).await.unwrap(), None);
+
+        // A second cache on the same directory sees the entry
+        let other = DiskCache::new(dir.path()).unwrap();
+        assert_eq!(other.get(&key("a")).await.unwrap(), Some(response.clone()));
+
+        let short = DiskCache::new(dir.path())
+            .unwrap()
+            .ttl(Duration::from_millis(20));
+        short.put(key("c"), response).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(40)).await;
+        assert_eq!(short.clear_expired().await.unwrap(), 1);
+        assert_eq!(short.get(&key("c")).await.unwrap(), None);
+        assert!(short.get(&key("a")).await.unwrap().is_some());
+
+        cache.remove(&key("a")).await.unwrap();
+        cache.remove(&key("a")).await.unwrap();
+        assert_eq!(cache.get(&key("a")).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let entry_size = {
+            let cache = DiskCache::new(dir.path()).unwrap();
+            cache.put(key("probe"), Response::text("x")).await.unwrap();
+            let size = std::fs::metadata(cache.path(&key("probe"))).unwrap().len();
+            cache.clear().await.unwrap();
+            size
+        };
+
+        let cache = DiskCache::new(dir.path())
+            .unwrap()
+            .max_bytes(entry_size * 2);
+        cache.put(key("a"), Response::text("x")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        cache.put(key("b"), Response::text("x")).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        assert!(cache.get(&key("a")).await.unwrap().is_some());
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        cache.put(key("c"), Response::text("x")).await.unwrap();
+
+        assert!(cache.get(&key("a")).await.unwrap().is_some());
+        assert!(cache.get(&key("b")).await.unwrap().is_none());
+        assert!(cache.get(&key("c")).await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_entries_are_misses() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache = DiskCache::new(dir.path()).unwrap();
+        std::fs::write(cache.path(&key("bad")), "not json").unwrap();
+
+        assert_eq!(cache.get(&key("bad")).await.unwrap(), None);
+        assert!(!cache.path(&key("bad")).exists());
+    }
+}
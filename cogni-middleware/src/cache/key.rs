@@ -0,0 +1,333 @@
+//! Cache key derivation
+
+use cogni_core::{Error, Request};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeSet;
+
+/// Cache key for requests
+///
+/// A SHA-256 digest of the request serialized as canonical JSON, so every
+/// field — parameters, tool schemas, response format, message content and
+/// metadata — distinguishes requests. Use [`CacheKeyOptions`] to leave fields
+/// out or to namespace keys.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CacheKey(pub(crate) String);
+
+impl CacheKey {
+    /// Create a cache key from a request, hashing every field
+    ///
+    /// Fails if the request can't be serialized.
+    pub fn from_request(request: &Request) -> Result<Self, Error> {
+        CacheKeyOptions::default().key(request)
+    }
+
+    /// The key as a hex string
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Controls which parts of a request make up its [`CacheKey`]
+///
+/// Fields are named by dotted paths into the request's JSON form, such as
+/// `parameters.temperature` or `model`. A path through a list applies to each
+/// element, so `messages.metadata` means the metadata of every message.
+///
+/// # Example
+///
+/// ```
+/// use cogni_middleware::cache::{CacheKeyOptions, CacheLayer};
+/// use std::time::Duration;
+///
+/// // Share entries across temperatures, but never across tenants
+/// let options = CacheKeyOptions::new()
+///     .ignore("parameters.temperature")
+///     .ignore("messages.metadata.custom.request_id")
+///     .namespace("tenant-42");
+/// let layer = CacheLayer::new(1000, Duration::from_secs(300)).with_key_options(options);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CacheKeyOptions {
+    only: BTreeSet<String>,
+    ignored: BTreeSet<String>,
+    namespace: Option<String>,
+}
+
+impl CacheKeyOptions {
+    /// Hash every field of the request
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Hash only the given fields
+    ///
+    /// May be called several times; fields from every call are included.
+    pub fn only(mut self, fields: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.only.extend(fields.into_iter().map(Into::into));
+        self
+    }
+
+    /// Leave a field out of the key
+    pub fn ignore(mut self, field: impl Into<String>) -> Self {
+        self.ignored.insert(field.into());
+        self
+    }
+
+    /// Keep keys for different namespaces, such as tenants, apart
+    pub fn namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = Some(namespace.into());
+        self
+    }
+
+    /// Derive the key for a request
+    ///
+    /// Fails if the request can't be serialized.
+    pub fn key(&self, request: &Request) -> Result<CacheKey, Error> {
+        let mut value = serde_json::to_value(request)?;
+        if !self.only.is_empty() {
+            let mut picked = Value::Object(Map::new());
+            for field in &self.only {
+                pick(&value, &mut picked, &segments(field));
+            }
+            value = picked;
+        }
+        for field in &self.ignored {
+            remove(&mut value, &segments(field));
+        }
+
+        let mut hasher = Sha256::new();
+        if let Some(namespace) = &self.namespace {
+            hasher.update(b"namespace:");
+            hasher.update((namespace.len() as u64).to_le_bytes());
+            hasher.update(namespace.as_bytes());
+        }
+        hasher.update(b"request:");
+        hash_canonical(&value, &mut hasher);
+        Ok(CacheKey(format!("{:x}", hasher.finalize())))
+    }
+}
+
+fn segments(path: &str) -> Vec<&str> {
+    path.split('.').filter(|s| !s.is_empty()).collect()
+}
+
+/// Copy the value at `path` from `source` into the same place in `target`
+fn pick(source: &Value, target: &mut Value, path: &[&str]) {
+    let Some((first, rest)) = path.split_first() else {
+        *target = source.clone();
+        return;
+    };
+    match source {
+        Value::Object(fields) => {
+            let Some(child) = fields.get(*first) else {
+                return;
+            };
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            let slot = target
+                .as_object_mut()
+                .expect("target is an object")
+                .entry(first.to_string())
+                .or_insert(Value::Null);
+            pick(child, slot, rest);
+        }
+        Value::Array(items) => {
+            if !matches!(target, Value::Array(existing) if existing.len() == items.len()) {
+                *target = Value::Array(vec![Value::Null; items.len()]);
+            }
+            if let Value::Array(slots) = target {
+                for (item, slot) in items.iter().zip(slots) {
+                    pick(item, slot, path);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Remove the value at `path`, descending into every element of lists
+fn remove(value: &mut Value, path: &[&str]) {
+    let Some((first, rest)) = path.split_first() else {
+        return;
+    };
+    match value {
+        Value::Object(fields) if rest.is_empty() => {
+            fields.remove(*first);
+        }
+        Value::Object(fields) => {
+            if let Some(child) = fields.get_mut(*first) {
+                remove(child, rest);
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                remove(item, path);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Hash a value with object keys in sorted order
+///
+/// Map order in serialized JSON depends on `HashMap` iteration, so it can't
+/// be hashed as text directly.
+fn hash_canonical(value: &Value, hasher: &mut Sha256) {
+    match value {
+        Value::Object(fields) => {
+            let mut keys: Vec<&String> = fields.keys().collect();
+            keys.sort();
+            hasher.update(b"{");
+            for key in keys {
+                hash_canonical(&Value::String(key.clone()), hasher);
+                hasher.update(b":");
+                hash_canonical(&fields[key], hasher);
+                hasher.update(b",");
+            }
+            hasher.update(b"}");
+        }
+        Value::Array(items) => {
+            hasher.update(b"[");
+            for item in items {
+                hash_canonical(item, hasher);
+                hasher.update(b",");
+            }
+            hasher.update(b"]");
+        }
+        // Scalars serialize unambiguously
+        scalar => hasher.update(scalar.to_string().as_bytes()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cogni_core::{Content, Function, Image, Message, Parameters, ResponseFormat, Tool};
+    use serde_json::json;
+
+    fn request() -> Request {
+        Request::builder()
+            .model("test-model")
+            .message(Message::system("Be brief"))
+            .message(Message::user("Hello"))
+            .build()
+    }
+
+    fn key(request: &Request) -> CacheKey {
+        CacheKey::from_request(request).unwrap()
+    }
+
+    fn tool(schema: Value) -> Tool {
+        Tool {
+            name: "lookup".to_string(),
+            description: "Look something up".to_string(),
+            function: Function {
+                parameters: schema,
+                returns: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_every_field_distinguishes_requests() {
+        let base = request();
+        let variants: Vec<fn(&mut Request)> = vec![
+            |r| r.parameters.top_p = Some(0.9),
+            |r| r.parameters.stop = Some(vec!["END".to_string()]),
+            |r| r.parameters.seed = Some(7),
+            |r| r.parameters.presence_penalty = Some(0.5),
+            |r| r.parameters.frequency_penalty = Some(0.5),
+            |r| r.response_format = Some(ResponseFormat::JsonObject),
+            |r| {
+                r.messages[1]
+                    .metadata
+                    .custom
+                    .insert("user".to_string(), "a".to_string());
+            },
+            |r| r.messages[1].content = Content::Multiple(vec![Content::Text("Hi".into())]),
+        ];
+        for (i, change) in variants.iter().enumerate() {
+            let mut changed = base.clone();
+            change(&mut changed);
+            assert_ne!(key(&base), key(&changed), "variant {} collided", i);
+        }
+
+        // Tool schemas count, not just names
+        let mut a = base.clone();
+        a.tools.push(tool(json!({ "type": "object" })));
+        let mut b = base.clone();
+        b.tools
+            .push(tool(json!({ "type": "object", "required": ["q"] })));
+        assert_ne!(key(&a), key(&b));
+
+        // Images are hashed by data as well as URL
+        let image = |data: &str| {
+            let mut r = base.clone();
+            r.messages[1].content = Content::Image(Image {
+                data: Some(data.to_string()),
+                url: None,
+                mime_type: "image/png".to_string(),
+            });
+            r
+        };
+        assert_ne!(key(&image("aaaa")), key(&image("bbbb")));
+
+        // Multiple parts are hashed by content, not count
+        let parts = |text: &str| {
+            let mut r = base.clone();
+            r.messages[1].content = Content::Multiple(vec![Content::Text(text.into())]);
+            r
+        };
+        assert_ne!(key(&parts("one")), key(&parts("two")));
+    }
+
+    #[test]
+    fn test_key_is_stable_across_map_order() {
+        let mut a = request();
+        let mut b = request();
+        for i in 0..20 {
+            a.messages[1]
+                .metadata
+                .custom
+                .insert(format!("k{}", i), i.to_string());
+        }
+        for i in (0..20).rev() {
+            b.messages[1]
+                .metadata
+                .custom
+                .insert(format!("k{}", i), i.to_string());
+        }
+        assert_eq!(key(&a), key(&b));
+    }
+
+    #[test]
+    fn test_ignore_only_and_namespace() {
+        let base = request();
+        let mut warmer = base.clone();
+        warmer.parameters = Parameters::builder().temperature(0.9).build();
+
+        let options = CacheKeyOptions::new().ignore("parameters.temperature");
+        assert_eq!(options.key(&base).unwrap(), options.key(&warmer).unwrap());
+        assert_ne!(options.key(&base).unwrap(), key(&base));
+
+        let mut tagged = base.clone();
+        tagged.messages[1]
+            .metadata
+            .custom
+            .insert("request_id".to_string(), "123".to_string());
+        let options = CacheKeyOptions::new().ignore("messages.metadata");
+        assert_eq!(options.key(&base).unwrap(), options.key(&tagged).unwrap());
+
+        let options = CacheKeyOptions::new().only(["model", "messages.content"]);
+        assert_eq!(options.key(&warmer).unwrap(), options.key(&tagged).unwrap());
+        let mut other = base.clone();
+        other.messages[1].content = Content::Text("Bye".into());
+        assert_ne!(options.key(&base).unwrap(), options.key(&other).unwrap());
+
+        let tenant = |name: &str| CacheKeyOptions::new().namespace(name).key(&base).unwrap();
+        assert_ne!(tenant("a"), tenant("b"));
+        assert_eq!(tenant("a"), tenant("a"));
+    }
+}
@@ -17,7 +17,9 @@ pub mod retry;
 pub mod state;
 
 // Re-export middleware implementations
-pub use cache::{CacheLayer, CacheService};
+pub use cache::{
+    CacheBackend, CacheKey, CacheKeyOptions, CacheLayer, CacheService, DiskCache, MemoryCache,
+};
 pub use logging::{LogLevel, LoggingLayer, LoggingService};
 pub use rate_limit::{RateLimitLayer, RateLimitService};
 pub use retry::{RetryConfig, RetryLayer, RetryService};
@@ -539,6 +539,7 @@ mod tests {
                     data: None,
                     mime_type: "image/jpeg".into(),
                 }),
+                tool_calls: Vec::new(),
                 metadata: Default::default(),
             })
             .message(Message {
@@ -547,6 +548,7 @@ mod tests {
                     data: "base64-audio".into(),
                     mime_type: "audio/mpeg".into(),
                 }),
+                tool_calls: Vec::new(),
                 metadata: Default::default(),
             })
             .message(Message {
@@ -559,6 +561,7 @@ mod tests {
                         mime_type: "image/jpeg".into(),
                     }),
                 ]),
+                tool_calls: Vec::new(),
                 metadata: Default::default(),
             })
             .build();
@@ -124,16 +124,22 @@ impl<S> StateService<S> {
         request: &Request,
         response: &Response,
     ) -> Result<(), Error> {
-        // Add user message from request (last message)
-        if let Some(user_msg) = request.messages.last() {
-            if !state.messages.iter().any(|m| m == user_msg) {
-                state.add_message(user_msg.clone());
-            }
+        // Add the request's new messages, such as a user message or the
+        // results of the previous turn's tool calls. A request that repeats
+        // the whole history only adds what follows it; repeated messages
+        // within a turn, like answering "ok" twice, are kept.
+        let held = if request.messages.starts_with(state.messages()) {
+            state.messages().len()
+        } else {
+            0
+        };
+        for message in &request.messages[held..] {
+            state.add_message(message.clone());
         }
 
-        // Add assistant response
-        if !response.content.is_empty() {
-            state.add_message(cogni_core::Message::assistant(&response.content));
+        // Add assistant response, keeping any tool calls it requested
+        if !response.content.is_empty() || response.has_tool_calls() {
+            state.add_message(response.to_message());
         }
 
         // Update token count
@@ -196,10 +202,10 @@ where
             let original_messages = request.messages.clone();
 
             // Include conversation history if configured
-            if config.include_history && !state.messages.is_empty() {
+            if config.include_history && !state.messages().is_empty() {
                 let history_messages = if let Some(max) = config.max_history_messages {
                     state
-                        .messages
+                        .messages()
                         .iter()
                         .rev()
                         .take(max)
@@ -207,7 +213,7 @@ where
                         .cloned()
                         .collect::<Vec<_>>()
                 } else {
-                    state.messages.clone()
+                    state.messages().to_vec()
                 };
 
                 // Prepend history to request messages
@@ -309,9 +315,9 @@ mod tests {
 
         // Check that state was saved
         let state = store.load(&conversation_id).await.unwrap();
-        assert_eq!(state.messages.len(), 2); // User + Assistant
-        assert_eq!(state.messages[0].role, Role::User);
-        assert_eq!(state.messages[1].role, Role::Assistant);
+        assert_eq!(state.messages().len(), 2); // User + Assistant
+        assert_eq!(state.messages()[0].role, Role::User);
+        assert_eq!(state.messages()[1].role, Role::Assistant);
     }
 
     #[tokio::test]
@@ -347,6 +353,36 @@ mod tests {
 
         // Check that history was included
         let updated_state = store.load(&conversation_id).await.unwrap();
-        assert_eq!(updated_state.messages.len(), 4); // 2 original + 2 new
+        assert_eq!(updated_state.messages().len(), 4); // 2 original + 2 new
+    }
+
+    fn tagged(mut message: Message, conversation_id: Uuid) -> Message {
+        message
+            .metadata
+            .custom
+            .insert("conversation_id".to_string(), conversation_id.to_string());
+        message
+    }
+
+    #[tokio::test]
+    async fn test_state_middleware_keeps_repeated_messages() {
+        let store = Arc::new(MemoryStore::new());
+        let conversation_id = Uuid::new_v4();
+        let mut service = StateLayer::new(store.clone()).layer(EchoService);
+
+        for _ in 0..2 {
+            let request = Request::new(vec![tagged(Message::user("ok"), conversation_id)]);
+            service.call(request).await.unwrap();
+        }
+        let state = store.load(&conversation_id).await.unwrap();
+        assert_eq!(state.messages().len(), 4);
+
+        // Resending the full history only adds the new message
+        let mut messages = state.messages().to_vec();
+        messages.push(Message::user("ok"));
+        service.call(Request::new(messages)).await.unwrap();
+        let state = store.load(&conversation_id).await.unwrap();
+        assert_eq!(state.messages().len(), 6);
+        assert_eq!(state.messages()[4].content.as_text(), Some("ok"));
     }
 }
@@ -177,12 +177,29 @@ fn convert_messages(messages: &[Message]) -> (Vec<AnthropicMessage>, Option<Stri
             Role::Assistant => {
                 anthropic_messages.push(AnthropicMessage {
                     role: "assistant".to_string(),
-                    content: convert_content(&msg.content),
+                    content: convert_assistant_content(msg),
                 });
             }
             Role::Tool => {
-                if let Some(anthropic_msg) = convert_tool_message(msg) {
-                    anthropic_messages.push(anthropic_msg);
+                let Some(block) = convert_tool_result(msg) else {
+                    continue;
+                };
+                // Results answering one assistant turn share a user message
+                match anthropic_messages.last_mut() {
+                    Some(AnthropicMessage {
+                        role,
+                        content: AnthropicContent::Blocks(blocks),
+                    }) if role == "user"
+                        && blocks
+                            .iter()
+                            .all(|b| matches!(b, ContentBlock::ToolResult { .. })) =>
+                    {
+                        blocks.push(block);
+                    }
+                    _ => anthropic_messages.push(AnthropicMessage {
+                        role: "user".to_string(),
+                        content: AnthropicContent::Blocks(vec![block]),
+                    }),
                 }
             }
             _ => {
@@ -195,20 +212,42 @@ fn convert_messages(messages: &[Message]) -> (Vec<AnthropicMessage>, Option<Stri
     (anthropic_messages, system_message)
 }
 
-/// Convert a tool message to Anthropic format
-fn convert_tool_message(msg: &Message) -> Option<AnthropicMessage> {
-    if let Some(tool_call_id) = &msg.metadata.tool_call_id {
-        if let Content::Text(text) = &msg.content {
-            return Some(AnthropicMessage {
-                role: "user".to_string(),
-                content: AnthropicContent::Blocks(vec![ContentBlock::ToolResult {
-                    tool_use_id: tool_call_id.clone(),
-                    content: text.clone(),
-                }]),
-            });
+/// Convert an assistant message, adding a `tool_use` block per tool call
+fn convert_assistant_content(msg: &Message) -> AnthropicContent {
+    if msg.tool_calls.is_empty() {
+        return convert_content(&msg.content);
+    }
+
+    let mut blocks = Vec::new();
+    if let AnthropicContent::Text(text) = convert_content(&msg.content) {
+        if !text.is_empty() {
+            blocks.push(ContentBlock::Text { text });
         }
     }
-    None
+    blocks.extend(msg.tool_calls.iter().map(|call| {
+        ContentBlock::ToolUse {
+            id: call.id.clone(),
+            name: call.name.clone(),
+            // Anthropic requires an object; arguments that aren't one are dropped
+            input: serde_json::from_str::<Value>(&call.arguments)
+                .ok()
+                .filter(Value::is_object)
+                .unwrap_or_else(|| Value::Object(Default::default())),
+        }
+    }));
+    AnthropicContent::Blocks(blocks)
+}
+
+/// Convert a tool message to a `tool_result` block
+fn convert_tool_result(msg: &Message) -> Option<ContentBlock> {
+    let tool_call_id = msg.metadata.tool_call_id.as_ref()?;
+    let Content::Text(text) = &msg.content else {
+        return None;
+    };
+    Some(ContentBlock::ToolResult {
+        tool_use_id: tool_call_id.clone(),
+        content: text.clone(),
+    })
 }
 
 /// Convert tools and response format to Anthropic format
@@ -334,3 +373,51 @@ impl RequestConverter for AnthropicConverter {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn call(id: &str, arguments: &str) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            name: "get_weather".to_string(),
+            arguments: arguments.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_assistant_tool_calls() {
+        let request = Request::builder()
+            .message(Message::system("Be brief"))
+            .message(Message::user("Weather in Paris and Rome?"))
+            .message(Message::assistant_with_tools(
+                "Checking both.",
+                [call("a", r#"{"city":"Paris"}"#), call("b", "")],
+            ))
+            .message(Message::tool("21C", "a"))
+            .message(Message::tool("25C", "b"))
+            .message(Message::user("Thanks"))
+            .build();
+
+        let body = serde_json::to_value(to_anthropic_request(&request)).unwrap();
+        assert_eq!(body["system"], "Be brief");
+        assert_eq!(
+            body["messages"],
+            json!([
+                { "role": "user", "content": "Weather in Paris and Rome?" },
+                { "role": "assistant", "content": [
+                    { "type": "text", "text": "Checking both." },
+                    { "type": "tool_use", "id": "a", "name": "get_weather", "input": { "city": "Paris" } },
+                    { "type": "tool_use", "id": "b", "name": "get_weather", "input": {} },
+                ] },
+                { "role": "user", "content": [
+                    { "type": "tool_result", "tool_use_id": "a", "content": "21C" },
+                    { "type": "tool_result", "tool_use_id": "b", "content": "25C" },
+                ] },
+                { "role": "user", "content": "Thanks" },
+            ])
+        );
+    }
+}
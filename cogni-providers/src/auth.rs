@@ -0,0 +1,303 @@
+//! Credential providers for authenticating provider requests
+//!
+//! Most providers authenticate with a static API key, but some deployments
+//! (for example Azure OpenAI with Entra ID) use short-lived bearer tokens that
+//! must be refreshed. The [`CredentialProvider`] trait abstracts over both so
+//! that providers can ask for a fresh [`Credential`] before every request.
+//!
+//! # Example
+//!
+//! ```no_run
+//! use cogni_providers::auth::{AccessToken, RefreshingCredential};
+//! use std::time::Duration;
+//!
+//! // Wrap any async token source; tokens are cached until shortly before expiry.
+//! let credential = RefreshingCredential::new(|| async {
+//!     let token = std::env::var("AZURE_ACCESS_TOKEN").unwrap_or_default();
+//!     Ok(AccessToken::new(token).expires_in(Duration::from_secs(3600)))
+//! });
+//! ```
+
+use async_trait::async_trait;
+use cogni_core::Error;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Default margin before expiry at which cached tokens are refreshed
+pub const DEFAULT_REFRESH_MARGIN: Duration = Duration::from_secs(300);
+
+/// A credential to attach to an outgoing request
+#[derive(Clone, PartialEq, Eq)]
+pub enum Credential {
+    /// Sent as `Authorization: Bearer <token>`
+    Bearer(String),
+    /// Sent verbatim as a custom header, e.g. `api-key: <key>`
+    Header {
+        /// Header name
+        name: String,
+        /// Header value
+        value: String,
+    },
+}
+
+impl Credential {
+    /// Create a bearer token credential
+    pub fn bearer(token: impl Into<String>) -> Self {
+        Self::Bearer(token.into())
+    }
+
+    /// Create a credential sent in a custom header
+    pub fn header(name: impl Into<String>, value: impl Into<String>) -> Self {
+        Self::Header {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+
+    /// Insert this credential into a header map
+    pub fn apply(&self, headers: &mut HeaderMap) -> Result<(), Error> {
+        match self {
+            Credential::Bearer(token) => {
+                headers.insert(
+                    AUTHORIZATION,
+                    HeaderValue::from_str(&format!("Bearer {}", token))
+                        .map_err(|e| Error::Configuration(format!("Invalid API key: {}", e)))?,
+                );
+            }
+            Credential::Header { name, value } => {
+                let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                    Error::Configuration(format!("Invalid auth header name: {}", e))
+                })?;
+                headers.insert(
+                    name,
+                    HeaderValue::from_str(value)
+                        .map_err(|e| Error::Configuration(format!("Invalid API key: {}", e)))?,
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // Never print secrets
+        match self {
+            Credential::Bearer(_) => f.write_str("Credential::Bearer(***)"),
+            Credential::Header { name, .. } => write!(f, "Credential::Header({}: ***)", name),
+        }
+    }
+}
+
+/// Source of credentials for provider requests
+///
+/// Implementations are called before every request and may return cached
+/// values; they are responsible for refreshing expired tokens.
+#[async_trait]
+pub trait CredentialProvider: Send + Sync {
+    /// Get a credential that is valid for the next request
+    async fn credential(&self) -> Result<Credential, Error>;
+}
+
+/// A credential that never changes, such as an API key
+#[derive(Debug, Clone)]
+pub struct StaticCredential(Credential);
+
+impl StaticCredential {
+    /// Create a static credential
+    pub fn new(credential: Credential) -> Self {
+        Self(credential)
+    }
+}
+
+#[async_trait]
+impl CredentialProvider for StaticCredential {
+    async fn credential(&self) -> Result<Credential, Error> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A short-lived access token
+#[derive(Clone)]
+pub struct AccessToken {
+    /// The token value
+    pub token: String,
+    /// When the token expires, if known
+    pub expires_at: Option<Instant>,
+}
+
+impl AccessToken {
+    /// Create a token with no known expiry
+    pub fn new(token: impl Into<String>) -> Self {
+        Self {
+            token: token.into(),
+            expires_at: None,
+        }
+    }
+
+    /// Set the expiry relative to now
+    pub fn expires_in(mut self, ttl: Duration) -> Self {
+        self.expires_at = Some(Instant::now() + ttl);
+        self
+    }
+
+    fn needs_refresh(&self, margin: Duration) -> bool {
+        match self.expires_at {
+            Some(expires_at) => Instant::now() + margin >= expires_at,
+            None => false,
+        }
+    }
+}
+
+impl fmt::Debug for AccessToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AccessToken")
+            .field("token", &"***")
+            .field("expires_at", &self.expires_at)
+            .finish()
+    }
+}
+
+/// Fetches new access tokens
+#[async_trait]
+pub trait TokenSource: Send + Sync {
+    /// Fetch a new access token
+    async fn fetch_token(&self) -> Result<AccessToken, Error>;
+}
+
+#[async_trait]
+impl<F, Fut> TokenSource for F
+where
+    F: Fn() -> Fut + Send + Sync,
+    Fut: Future<Output = Result<AccessToken, Error>> + Send,
+{
+    async fn fetch_token(&self) -> Result<AccessToken, Error> {
+        (self)().await
+    }
+}
+
+/// A bearer credential backed by a [`TokenSource`], refreshed before expiry
+///
+/// Tokens are cached and shared between concurrent requests; only one refresh
+/// is in flight at a time.
+pub struct RefreshingCredential<S> {
+    source: S,
+    refresh_margin: Duration,
+    cached: Mutex<Option<AccessToken>>,
+}
+
+impl<S: TokenSource> RefreshingCredential<S> {
+    /// Create a refreshing credential from a token source
+    pub fn new(source: S) -> Self {
+        Self {
+            source,
+            refresh_margin: DEFAULT_REFRESH_MARGIN,
+            cached: Mutex::new(None),
+        }
+    }
+
+    /// Set how long before expiry the token is refreshed
+    pub fn with_refresh_margin(mut self, margin: Duration) -> Self {
+        self.refresh_margin = margin;
+        self
+    }
+
+    /// Drop the cached token so the next request fetches a new one
+    pub async fn invalidate(&self) {
+        *self.cached.lock().await = None;
+    }
+}
+
+#[async_trait]
+impl<S: TokenSource> CredentialProvider for RefreshingCredential<S> {
+    async fn credential(&self) -> Result<Credential, Error> {
+        let mut cached = self.cached.lock().await;
+
+        if let Some(token) = cached.as_ref() {
+            if !token.needs_refresh(self.refresh_margin) {
+                return Ok(Credential::Bearer(token.token.clone()));
+            }
+        }
+
+        let token = self.source.fetch_token().await?;
+        let credential = Credential::Bearer(token.token.clone());
+        *cached = Some(token);
+        Ok(credential)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn test_credential_apply() {
+        let mut headers = HeaderMap::new();
+        Credential::bearer("abc").apply(&mut headers).unwrap();
+        assert_eq!(headers.get(AUTHORIZATION).unwrap(), "Bearer abc");
+
+        let mut headers = HeaderMap::new();
+        Credential::header("api-key", "xyz")
+            .apply(&mut headers)
+            .unwrap();
+        assert_eq!(headers.get("api-key").unwrap(), "xyz");
+    }
+
+    #[test]
+    fn test_credential_debug_hides_secret() {
+        let debug = format!("{:?}", Credential::header("api-key", "secret"));
+        assert!(!debug.contains("secret"));
+    }
+
+    #[tokio::test]
+    async fn test_refreshing_credential_caches_until_expiry() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let credential = RefreshingCredential::new(move || {
+            let counter = counter.clone();
+            async move {
+                let n = counter.fetch_add(1, Ordering::SeqCst);
+                Ok(AccessToken::new(format!("token-{}", n)).expires_in(Duration::from_secs(3600)))
+            }
+        });
+
+        assert_eq!(
+            credential.credential().await.unwrap(),
+            Credential::bearer("token-0")
+        );
+        assert_eq!(
+            credential.credential().await.unwrap(),
+            Credential::bearer("token-0")
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        credential.invalidate().await;
+        assert_eq!(
+            credential.credential().await.unwrap(),
+            Credential::bearer("token-1")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_refreshing_credential_refreshes_near_expiry() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let credential = RefreshingCredential::new(move || {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(1, Ordering::SeqCst);
+                Ok(AccessToken::new("short").expires_in(Duration::from_secs(60)))
+            }
+        });
+
+        // Expiry (60s) falls inside the default 5 minute margin
+        credential.credential().await.unwrap();
+        credential.credential().await.unwrap();
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
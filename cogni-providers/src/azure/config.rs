@@ -0,0 +1,141 @@
+//! Azure OpenAI provider configuration
+
+use crate::auth::{Credential, CredentialProvider, StaticCredential};
+use crate::constants::AZURE_OPENAI_DEFAULT_API_VERSION;
+use cogni_core::Model;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Configuration for the Azure OpenAI provider
+///
+/// Azure routes requests by deployment name rather than model name. Each
+/// request's `model` is looked up in [`deployments`](Self::deployments); if it
+/// isn't mapped, the model name itself is used as the deployment name, which
+/// matches the common convention of naming deployments after their model.
+#[derive(Clone)]
+pub struct AzureOpenAIConfig {
+    /// Resource endpoint, e.g. `https://my-resource.openai.azure.com`
+    pub endpoint: String,
+    /// REST API version sent as the `api-version` query parameter
+    pub api_version: String,
+    /// Mapping from model names to deployment names
+    pub deployments: HashMap<String, String>,
+    /// Deployment used for requests whose model name is empty
+    pub default_deployment: Option<String>,
+    /// Source of credentials for each request
+    pub credential: Arc<dyn CredentialProvider>,
+}
+
+impl AzureOpenAIConfig {
+    /// Create a configuration that authenticates with an `api-key` header
+    pub fn new(endpoint: impl Into<String>, api_key: impl Into<String>) -> Self {
+        Self::with_credential(
+            endpoint,
+            Arc::new(StaticCredential::new(Credential::header(
+                "api-key",
+                api_key.into(),
+            ))),
+        )
+    }
+
+    /// Create a configuration with a custom credential provider
+    ///
+    /// Use this with [`RefreshingCredential`](crate::auth::RefreshingCredential)
+    /// or [`EntraIdClientSecret`](crate::azure::EntraIdClientSecret) for Entra ID
+    /// bearer tokens.
+    pub fn with_credential(
+        endpoint: impl Into<String>,
+        credential: Arc<dyn CredentialProvider>,
+    ) -> Self {
+        Self {
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            api_version: AZURE_OPENAI_DEFAULT_API_VERSION.to_string(),
+            deployments: HashMap::new(),
+            default_deployment: None,
+            credential,
+        }
+    }
+
+    /// Set the API version
+    pub fn with_api_version(mut self, version: impl Into<String>) -> Self {
+        self.api_version = version.into();
+        self
+    }
+
+    /// Map a model name to a deployment name
+    pub fn with_deployment(
+        mut self,
+        model: impl Into<String>,
+        deployment: impl Into<String>,
+    ) -> Self {
+        self.deployments.insert(model.into(), deployment.into());
+        self
+    }
+
+    /// Set the deployment used for requests whose model name is empty
+    ///
+    /// An empty model name is how a request leaves the model unset; any
+    /// named model, including the default `gpt-4`, is routed by name.
+    pub fn with_default_deployment(mut self, deployment: impl Into<String>) -> Self {
+        self.default_deployment = Some(deployment.into());
+        self
+    }
+
+    /// Resolve the deployment name for a model
+    pub fn deployment_for(&self, model: &Model) -> String {
+        if let Some(deployment) = self.deployments.get(&model.0) {
+            return deployment.clone();
+        }
+        match &self.default_deployment {
+            Some(default) if model.0.is_empty() => default.clone(),
+            _ => model.0.clone(),
+        }
+    }
+
+    /// Get the chat completions URL for a deployment
+    pub fn chat_url(&self, deployment: &str) -> String {
+        format!(
+            "{}/openai/deployments/{}/chat/completions?api-version={}",
+            self.endpoint, deployment, self.api_version
+        )
+    }
+}
+
+impl fmt::Debug for AzureOpenAIConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AzureOpenAIConfig")
+            .field("endpoint", &self.endpoint)
+            .field("api_version", &self.api_version)
+            .field("deployments", &self.deployments)
+            .field("default_deployment", &self.default_deployment)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deployment_resolution() {
+        let config = AzureOpenAIConfig::new("https://res.openai.azure.com/", "key")
+            .with_deployment("gpt-4o", "prod-gpt4o")
+            .with_default_deployment("fallback");
+
+        assert_eq!(config.deployment_for(&Model::new("gpt-4o")), "prod-gpt4o");
+        assert_eq!(config.deployment_for(&Model::new("gpt-35")), "gpt-35");
+        assert_eq!(config.deployment_for(&Model::default()), "gpt-4");
+        assert_eq!(config.deployment_for(&Model::new("")), "fallback");
+    }
+
+    #[test]
+    fn test_chat_url() {
+        let config = AzureOpenAIConfig::new("https://res.openai.azure.com/", "key")
+            .with_api_version("2024-06-01");
+        assert_eq!(
+            config.chat_url("prod"),
+            "https://res.openai.azure.com/openai/deployments/prod/chat/completions?api-version=2024-06-01"
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Microsoft Entra ID token acquisition for Azure OpenAI
+
+use crate::auth::{AccessToken, TokenSource};
+use crate::constants::AZURE_COGNITIVE_SERVICES_SCOPE;
+use crate::error;
+use async_trait::async_trait;
+use cogni_core::Error;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Fetches Entra ID tokens using the OAuth2 client credentials flow
+///
+/// Wrap this in a [`RefreshingCredential`](crate::auth::RefreshingCredential)
+/// so that tokens are cached and refreshed before they expire.
+///
+/// # Example
+///
+/// ```no_run
+/// use cogni_providers::auth::RefreshingCredential;
+/// use cogni_providers::azure::{AzureOpenAIConfig, EntraIdClientSecret};
+/// use std::sync::Arc;
+///
+/// let source = EntraIdClientSecret::new("tenant-id", "client-id", "client-secret");
+/// let config = AzureOpenAIConfig::with_credential(
+///     "https://my-resource.openai.azure.com",
+///     Arc::new(RefreshingCredential::new(source)),
+/// );
+/// ```
+pub struct EntraIdClientSecret {
+    client: reqwest::Client,
+    authority: String,
+    tenant_id: String,
+    client_id: String,
+    client_secret: String,
+    scope: String,
+}
+
+impl EntraIdClientSecret {
+    /// Create a token source for the given app registration
+    pub fn new(
+        tenant_id: impl Into<String>,
+        client_id: impl Into<String>,
+        client_secret: impl Into<String>,
+    ) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            authority: "https://login.microsoftonline.com".to_string(),
+            tenant_id: tenant_id.into(),
+            client_id: client_id.into(),
+            client_secret: client_secret.into(),
+            scope: AZURE_COGNITIVE_SERVICES_SCOPE.to_string(),
+        }
+    }
+
+    /// Use a different authority host, e.g. for sovereign clouds
+    pub fn with_authority(mut self, authority: impl Into<String>) -> Self {
+        self.authority = authority.into().trim_end_matches('/').to_string();
+        self
+    }
+
+    /// Request a different scope
+    pub fn with_scope(mut self, scope: impl Into<String>) -> Self {
+        self.scope = scope.into();
+        self
+    }
+
+    fn token_url(&self) -> String {
+        format!("{}/{}/oauth2/v2.0/token", self.authority, self.tenant_id)
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: Option<u64>,
+}
+
+#[async_trait]
+impl TokenSource for EntraIdClientSecret {
+    async fn fetch_token(&self) -> Result<AccessToken, Error> {
+        let response = self
+            .client
+            .post(self.token_url())
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("scope", self.scope.as_str()),
+            ])
+            .send()
+            .await
+            .map_err(|e| error::network_error_with_context(e, "Entra ID token request failed"))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(error::provider_error_from_status(
+                "AzureOpenAI",
+                status,
+                &body,
+            ));
+        }
+
+        let token: TokenResponse = response.json().await.map_err(error::network_error)?;
+        let access_token = AccessToken::new(token.access_token);
+        Ok(match token.expires_in {
+            Some(secs) => access_token.expires_in(Duration::from_secs(secs)),
+            None => access_token,
+        })
+    }
+}
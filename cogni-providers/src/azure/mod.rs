@@ -0,0 +1,13 @@
+//! Azure OpenAI provider implementation
+//!
+//! Azure OpenAI speaks the OpenAI chat completions protocol but routes
+//! requests to named deployments and authenticates with either an `api-key`
+//! header or Microsoft Entra ID bearer tokens.
+
+mod config;
+mod entra;
+mod provider;
+
+pub use config::AzureOpenAIConfig;
+pub use entra::EntraIdClientSecret;
+pub use provider::AzureOpenAI;
@@ -0,0 +1,167 @@
+//! Azure OpenAI provider implementation
+//!
+//! This module reuses the OpenAI request conversion and response parsing
+//! pipeline, adding Azure's deployment-based routing and authentication.
+
+use crate::azure::config::AzureOpenAIConfig;
+use crate::http::{HttpClient, ReqwestClient};
+use crate::openai::{OpenAIConverter, OpenAIParser, OpenAIStream};
+use crate::traits::{RequestConverter, ResponseParser};
+use async_trait::async_trait;
+use cogni_core::{Error, Provider, Request, Response};
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use std::sync::Arc;
+
+/// Azure OpenAI provider for chat completions
+///
+/// This provider supports:
+/// - Deployment-based routing with model-to-deployment mapping
+/// - `api-key` authentication
+/// - Microsoft Entra ID bearer tokens with automatic refresh
+/// - Function/tool calling, structured output and streaming
+///
+/// # Example
+///
+/// ```no_run
+/// use cogni_providers::AzureOpenAI;
+/// use cogni_providers::azure::AzureOpenAIConfig;
+/// use cogni_providers::http::ReqwestClient;
+/// use std::sync::Arc;
+///
+/// let config = AzureOpenAIConfig::new("https://my-resource.openai.azure.com", "api-key")
+///     .with_deployment("gpt-4o", "my-gpt4o-deployment");
+/// let client = Arc::new(ReqwestClient::new().expect("Failed to create client"));
+/// let provider = AzureOpenAI::new(config, client);
+/// ```
+#[derive(Clone)]
+pub struct AzureOpenAI {
+    client: Arc<dyn HttpClient>,
+    config: AzureOpenAIConfig,
+    converter: OpenAIConverter,
+    parser: OpenAIParser,
+}
+
+impl AzureOpenAI {
+    /// Create a new Azure OpenAI provider with the given configuration and client
+    pub fn new(config: AzureOpenAIConfig, client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            client,
+            config,
+            converter: OpenAIConverter,
+            parser: OpenAIParser,
+        }
+    }
+
+    /// Create a new Azure OpenAI provider with an endpoint and API key
+    pub fn with_api_key(
+        endpoint: impl Into<String>,
+        api_key: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let client = Arc::new(ReqwestClient::new().map_err(|e| Error::Provider {
+            provider: "AzureOpenAI".to_string(),
+            message: format!("Failed to create HTTP client: {}", e),
+            retry_after: None,
+            source: Some(Box::new(e)),
+        })?);
+        Ok(Self::new(AzureOpenAIConfig::new(endpoint, api_key), client))
+    }
+
+    /// Get the provider configuration
+    pub fn config(&self) -> &AzureOpenAIConfig {
+        &self.config
+    }
+
+    /// Create headers with a fresh credential
+    async fn create_headers(&self) -> Result<HeaderMap, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        self.config
+            .credential
+            .credential()
+            .await?
+            .apply(&mut headers)?;
+        Ok(headers)
+    }
+}
+
+#[async_trait]
+impl Provider for AzureOpenAI {
+    type Stream = OpenAIStream;
+
+    async fn request(&self, request: Request) -> Result<Response, Error> {
+        let url = self
+            .config
+            .chat_url(&self.config.deployment_for(&request.model));
+        let mut body = self.converter.convert_request(request).await?;
+        body["stream"] = serde_json::json!(false);
+
+        let headers = self.create_headers().await?;
+        let response = self.client.post(&url, headers, body).await?;
+
+        self.parser.parse_response(response).await
+    }
+
+    async fn stream(&self, request: Request) -> Result<Self::Stream, Error> {
+        let url = self
+            .config
+            .chat_url(&self.config.deployment_for(&request.model));
+        let mut body = self.converter.convert_request(request).await?;
+        body["stream"] = serde_json::json!(true);
+
+        let headers = self.create_headers().await?;
+        let event_source = self.client.post_event_stream(&url, headers, body).await?;
+
+        Ok(OpenAIStream::new(event_source))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::auth::{AccessToken, RefreshingCredential};
+    use crate::testing::MockHttpClient;
+    use cogni_core::Message;
+
+    #[tokio::test]
+    async fn test_request_uses_deployment_url_and_api_key() {
+        let client = Arc::new(MockHttpClient::new());
+        let config = AzureOpenAIConfig::new("https://res.openai.azure.com", "secret")
+            .with_deployment("gpt-4o", "prod");
+        let provider = AzureOpenAI::new(config, client.clone());
+
+        let request = Request::builder()
+            .model("gpt-4o")
+            .message(Message::user("hello"))
+            .build();
+        let response = provider.request(request).await.unwrap();
+        assert_eq!(response.content, "ok");
+
+        let sent = &client.requests()[0];
+        assert_eq!(
+            sent.url,
+            "https://res.openai.azure.com/openai/deployments/prod/chat/completions?api-version=2024-10-21"
+        );
+        assert_eq!(sent.headers.get("api-key").unwrap(), "secret");
+        assert!(sent.headers.get("authorization").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_request_with_bearer_token() {
+        let client = Arc::new(MockHttpClient::new());
+        let credential = RefreshingCredential::new(|| async { Ok(AccessToken::new("entra")) });
+        let config = AzureOpenAIConfig::with_credential(
+            "https://res.openai.azure.com",
+            Arc::new(credential),
+        );
+        let provider = AzureOpenAI::new(config, client.clone());
+
+        let request = Request::builder()
+            .model("gpt-4o")
+            .message(Message::user("hello"))
+            .build();
+        provider.request(request).await.unwrap();
+
+        let sent = &client.requests()[0];
+        assert_eq!(sent.headers.get("authorization").unwrap(), "Bearer entra");
+    }
+}
@@ -19,16 +19,37 @@
 //! // With all options
 //! # let custom_client = Arc::new(cogni_providers::http::ReqwestClient::new().unwrap());
 //! let provider = OpenAIBuilder::new("api-key")
-//!     .base_url("https://custom.openai.azure.com")
+//!     .base_url("https://openai-proxy.example.com/v1")
 //!     .organization("org-123")
 //!     .default_model("gpt-4")
 //!     .with_client(custom_client)
 //!     .build()
 //!     .unwrap();
+//!
+//! // With transport settings shared across providers
+//! use cogni_providers::builder::AnthropicBuilder;
+//! use cogni_providers::http::ReqwestClientBuilder;
+//! use std::time::Duration;
+//!
+//! let transport = ReqwestClientBuilder::new()
+//!     .connect_timeout(Duration::from_secs(5))
+//!     .https_proxy("http://proxy.internal:3128");
+//! let openai = OpenAIBuilder::new("api-key")
+//!     .with_transport(transport.clone())
+//!     .build()
+//!     .unwrap();
+//! let anthropic = AnthropicBuilder::new("api-key")
+//!     .with_transport(transport)
+//!     .build()
+//!     .unwrap();
 //! ```
 
-use crate::http::HttpClient;
+use crate::auth::CredentialProvider;
+use crate::http::{HttpClient, ReqwestClientBuilder};
 use cogni_core::Error;
+use reqwest::header::HeaderMap;
+use reqwest_eventsource::EventSource;
+use serde_json::Value;
 use std::sync::Arc;
 
 /// Common builder trait for all providers
@@ -45,6 +66,26 @@ pub trait ProviderBuilder: Sized {
     /// useful for testing or special networking requirements.
     fn with_client(self, client: Arc<dyn HttpClient>) -> Self;
 
+    /// Configure the default HTTP transport
+    ///
+    /// Timeouts, proxies, default headers, TLS and pool settings apply to
+    /// every request the provider sends. The builders in this crate keep the
+    /// settings until [`build`](Self::build), which returns an error if they
+    /// are invalid; a client set with [`with_client`](Self::with_client) takes
+    /// precedence over them.
+    ///
+    /// The default implementation builds a client from `transport` right
+    /// away and passes it to [`with_client`](Self::with_client). If the
+    /// client can't be built, the client passed instead fails every request
+    /// with the build error, so the provider never sends requests without
+    /// the configured proxy or TLS settings.
+    fn with_transport(self, transport: ReqwestClientBuilder) -> Self {
+        match transport.build() {
+            Ok(client) => self.with_client(Arc::new(client)),
+            Err(e) => self.with_client(Arc::new(FailedTransport(e.to_string()))),
+        }
+    }
+
     /// Build the provider
     ///
     /// Consumes the builder and returns the configured provider,
@@ -52,10 +93,45 @@ pub trait ProviderBuilder: Sized {
     fn build(self) -> Result<Self::Provider, Error>;
 }
 
+/// Client standing in for a transport that failed to build
+struct FailedTransport(String);
+
+impl FailedTransport {
+    fn error(&self) -> Error {
+        Error::Configuration(format!("HTTP transport failed to build: {}", self.0))
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpClient for FailedTransport {
+    async fn post(&self, _url: &str, _headers: HeaderMap, _body: Value) -> Result<Value, Error> {
+        Err(self.error())
+    }
+
+    async fn post_raw(
+        &self,
+        _url: &str,
+        _headers: HeaderMap,
+        _body: Value,
+    ) -> Result<reqwest::Response, Error> {
+        Err(self.error())
+    }
+
+    async fn post_event_stream(
+        &self,
+        _url: &str,
+        _headers: HeaderMap,
+        _body: Value,
+    ) -> Result<EventSource, Error> {
+        Err(self.error())
+    }
+}
+
 /// Builder for constructing OpenAI providers
 ///
 /// This builder provides a fluent interface for configuring OpenAI providers
-/// with support for custom endpoints (Azure OpenAI), organizations, and HTTP clients.
+/// with support for custom endpoints, organizations, and HTTP clients. For
+/// Azure OpenAI deployments use [`AzureOpenAIBuilder`].
 ///
 /// # Example
 ///
@@ -74,6 +150,7 @@ pub struct OpenAIBuilder {
     organization: Option<String>,
     default_model: Option<String>,
     client: Option<Arc<dyn HttpClient>>,
+    transport: Option<ReqwestClientBuilder>,
 }
 
 impl OpenAIBuilder {
@@ -85,6 +162,7 @@ impl OpenAIBuilder {
             organization: None,
             default_model: None,
             client: None,
+            transport: None,
         }
     }
 
@@ -112,9 +190,14 @@ impl OpenAIBuilder {
         self
     }
 
+    /// Configure the default HTTP transport
+    pub fn with_transport(mut self, transport: ReqwestClientBuilder) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
     /// Build the OpenAI provider
     pub fn build(self) -> Result<crate::OpenAI, Error> {
-        use crate::http::ReqwestClient;
         use crate::openai::OpenAIConfig;
 
         let mut config = OpenAIConfig::new(self.api_key);
@@ -128,15 +211,7 @@ impl OpenAIBuilder {
         // Note: default_model is stored in the builder but not used in OpenAIConfig
         // It could be used if OpenAI provider supported per-instance default models
 
-        let client = match self.client {
-            Some(client) => client,
-            None => Arc::new(ReqwestClient::new().map_err(|e| Error::Provider {
-                provider: "OpenAI".to_string(),
-                message: format!("Failed to create HTTP client: {}", e),
-                retry_after: None,
-                source: Some(Box::new(e)),
-            })?),
-        };
+        let client = resolve_client("OpenAI", self.client, self.transport)?;
 
         Ok(crate::OpenAI::new(config, client))
     }
@@ -152,11 +227,260 @@ impl ProviderBuilder for OpenAIBuilder {
         }
     }
 
+    fn with_transport(self, transport: ReqwestClientBuilder) -> Self {
+        Self {
+            transport: Some(transport),
+            ..self
+        }
+    }
+
     fn build(self) -> Result<Self::Provider, Error> {
         OpenAIBuilder::build(self)
     }
 }
 
+/// Builder for constructing Azure OpenAI providers
+///
+/// This builder configures the resource endpoint, API version, authentication
+/// and the mapping from request model names to Azure deployment names.
+///
+/// # Example
+///
+/// ```no_run
+/// use cogni_providers::builder::AzureOpenAIBuilder;
+///
+/// let provider = AzureOpenAIBuilder::new("https://my-resource.openai.azure.com")
+///     .api_key("azure-key")
+///     .deployment("gpt-4o", "my-gpt4o-deployment")
+///     .api_version("2024-10-21")
+///     .build()
+///     .unwrap();
+/// ```
+pub struct AzureOpenAIBuilder {
+    endpoint: String,
+    api_key: Option<String>,
+    credential: Option<Arc<dyn CredentialProvider>>,
+    api_version: Option<String>,
+    deployments: Vec<(String, String)>,
+    default_deployment: Option<String>,
+    client: Option<Arc<dyn HttpClient>>,
+    transport: Option<ReqwestClientBuilder>,
+}
+
+impl AzureOpenAIBuilder {
+    /// Create a new Azure OpenAI builder for a resource endpoint
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            api_key: None,
+            credential: None,
+            api_version: None,
+            deployments: Vec::new(),
+            default_deployment: None,
+            client: None,
+            transport: None,
+        }
+    }
+
+    /// Authenticate with an `api-key` header
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.api_key = Some(key.into());
+        self
+    }
+
+    /// Authenticate with a custom credential provider (e.g. Entra ID tokens)
+    pub fn credential(mut self, credential: Arc<dyn CredentialProvider>) -> Self {
+        self.credential = Some(credential);
+        self
+    }
+
+    /// Set the API version
+    pub fn api_version(mut self, version: impl Into<String>) -> Self {
+        self.api_version = Some(version.into());
+        self
+    }
+
+    /// Map a model name to a deployment name
+    pub fn deployment(mut self, model: impl Into<String>, deployment: impl Into<String>) -> Self {
+        self.deployments.push((model.into(), deployment.into()));
+        self
+    }
+
+    /// Set the deployment used for requests whose model name is empty
+    pub fn default_deployment(mut self, deployment: impl Into<String>) -> Self {
+        self.default_deployment = Some(deployment.into());
+        self
+    }
+
+    /// Set a custom HTTP client
+    pub fn with_client(mut self, client: Arc<dyn HttpClient>) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Configure the default HTTP transport
+    pub fn with_transport(mut self, transport: ReqwestClientBuilder) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Build the Azure OpenAI provider
+    pub fn build(self) -> Result<crate::AzureOpenAI, Error> {
+        use crate::azure::AzureOpenAIConfig;
+
+        let mut config = match (self.credential, self.api_key) {
+            (Some(credential), _) => AzureOpenAIConfig::with_credential(self.endpoint, credential),
+            (None, Some(api_key)) => AzureOpenAIConfig::new(self.endpoint, api_key),
+            (None, None) => {
+                return Err(Error::Configuration(
+                    "Azure OpenAI requires an API key or credential provider".to_string(),
+                ))
+            }
+        };
+
+        if let Some(version) = self.api_version {
+            config = config.with_api_version(version);
+        }
+        for (model, deployment) in self.deployments {
+            config = config.with_deployment(model, deployment);
+        }
+        if let Some(deployment) = self.default_deployment {
+            config = config.with_default_deployment(deployment);
+        }
+
+        let client = resolve_client("AzureOpenAI", self.client, self.transport)?;
+
+        Ok(crate::AzureOpenAI::new(config, client))
+    }
+}
+
+impl ProviderBuilder for AzureOpenAIBuilder {
+    type Provider = crate::AzureOpenAI;
+
+    fn with_client(self, client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            client: Some(client),
+            ..self
+        }
+    }
+
+    fn with_transport(self, transport: ReqwestClientBuilder) -> Self {
+        Self {
+            transport: Some(transport),
+            ..self
+        }
+    }
+
+    fn build(self) -> Result<Self::Provider, Error> {
+        AzureOpenAIBuilder::build(self)
+    }
+}
+
+/// Builder for constructing OpenAI-compatible providers
+///
+/// This builder configures a backend that speaks the OpenAI chat completions
+/// protocol, such as vLLM, llama.cpp, Groq, Together, Mistral or OpenRouter.
+///
+/// # Example
+///
+/// ```no_run
+/// use cogni_providers::builder::OpenAICompatibleBuilder;
+/// use cogni_providers::compatible::Quirks;
+///
+/// let provider = OpenAICompatibleBuilder::new("vllm", "http://gpu-box:8000/v1")
+///     .quirks(Quirks::vllm())
+///     .header("X-Request-Source", "batch")
+///     .build()
+///     .unwrap();
+/// ```
+pub struct OpenAICompatibleBuilder {
+    config: crate::compatible::OpenAICompatibleConfig,
+    client: Option<Arc<dyn HttpClient>>,
+    transport: Option<ReqwestClientBuilder>,
+}
+
+impl OpenAICompatibleBuilder {
+    /// Create a new builder for a named backend
+    pub fn new(name: impl Into<String>, base_url: impl Into<String>) -> Self {
+        Self::from_config(crate::compatible::OpenAICompatibleConfig::new(
+            name, base_url,
+        ))
+    }
+
+    /// Start from an existing configuration, e.g. a preset
+    pub fn from_config(config: crate::compatible::OpenAICompatibleConfig) -> Self {
+        Self {
+            config,
+            client: None,
+            transport: None,
+        }
+    }
+
+    /// Set the API key
+    pub fn api_key(mut self, key: impl Into<String>) -> Self {
+        self.config = self.config.with_api_key(key);
+        self
+    }
+
+    /// Set the organization ID
+    pub fn organization(mut self, org: impl Into<String>) -> Self {
+        self.config = self.config.with_organization(org);
+        self
+    }
+
+    /// Set the quirks profile
+    pub fn quirks(mut self, quirks: crate::compatible::Quirks) -> Self {
+        self.config = self.config.with_quirks(quirks);
+        self
+    }
+
+    /// Add a header to every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.config = self.config.with_header(name, value);
+        self
+    }
+
+    /// Set a custom HTTP client
+    pub fn with_client(mut self, client: Arc<dyn HttpClient>) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Configure the default HTTP transport
+    pub fn with_transport(mut self, transport: ReqwestClientBuilder) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
+    /// Build the OpenAI-compatible provider
+    pub fn build(self) -> Result<crate::OpenAICompatible, Error> {
+        let client = resolve_client(&self.config.name, self.client, self.transport)?;
+        Ok(crate::OpenAICompatible::new(self.config, client))
+    }
+}
+
+impl ProviderBuilder for OpenAICompatibleBuilder {
+    type Provider = crate::OpenAICompatible;
+
+    fn with_client(self, client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            client: Some(client),
+            ..self
+        }
+    }
+
+    fn with_transport(self, transport: ReqwestClientBuilder) -> Self {
+        Self {
+            transport: Some(transport),
+            ..self
+        }
+    }
+
+    fn build(self) -> Result<Self::Provider, Error> {
+        OpenAICompatibleBuilder::build(self)
+    }
+}
+
 /// Builder for constructing Anthropic providers
 ///
 /// This builder provides a fluent interface for configuring Anthropic providers
@@ -178,6 +502,7 @@ pub struct AnthropicBuilder {
     version: Option<String>,
     default_model: Option<String>,
     client: Option<Arc<dyn HttpClient>>,
+    transport: Option<ReqwestClientBuilder>,
 }
 
 impl AnthropicBuilder {
@@ -189,6 +514,7 @@ impl AnthropicBuilder {
             version: None,
             default_model: None,
             client: None,
+            transport: None,
         }
     }
 
@@ -216,10 +542,15 @@ impl AnthropicBuilder {
         self
     }
 
+    /// Configure the default HTTP transport
+    pub fn with_transport(mut self, transport: ReqwestClientBuilder) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
     /// Build the Anthropic provider
     pub fn build(self) -> Result<crate::Anthropic, Error> {
         use crate::anthropic::AnthropicConfig;
-        use crate::http::ReqwestClient;
 
         let config = AnthropicConfig {
             api_key: self.api_key,
@@ -231,15 +562,7 @@ impl AnthropicBuilder {
                 .unwrap_or_else(|| "claude-3-sonnet-20240229".to_string()),
         };
 
-        let client = match self.client {
-            Some(client) => client,
-            None => Arc::new(ReqwestClient::new().map_err(|e| Error::Provider {
-                provider: "Anthropic".to_string(),
-                message: format!("Failed to create HTTP client: {}", e),
-                retry_after: None,
-                source: Some(Box::new(e)),
-            })?),
-        };
+        let client = resolve_client("Anthropic", self.client, self.transport)?;
 
         Ok(crate::Anthropic::new(config, client))
     }
@@ -255,6 +578,13 @@ impl ProviderBuilder for AnthropicBuilder {
         }
     }
 
+    fn with_transport(self, transport: ReqwestClientBuilder) -> Self {
+        Self {
+            transport: Some(transport),
+            ..self
+        }
+    }
+
     fn build(self) -> Result<Self::Provider, Error> {
         AnthropicBuilder::build(self)
     }
@@ -287,6 +617,7 @@ pub struct OllamaBuilder {
     base_url: Option<String>,
     default_model: Option<String>,
     client: Option<Arc<dyn HttpClient>>,
+    transport: Option<ReqwestClientBuilder>,
 }
 
 impl OllamaBuilder {
@@ -296,6 +627,7 @@ impl OllamaBuilder {
             base_url: None,
             default_model: None,
             client: None,
+            transport: None,
         }
     }
 
@@ -310,6 +642,12 @@ impl OllamaBuilder {
         self.default_model = Some(model.into());
         self
     }
+
+    /// Configure the default HTTP transport
+    pub fn with_transport(mut self, transport: ReqwestClientBuilder) -> Self {
+        self.transport = Some(transport);
+        self
+    }
 }
 
 impl ProviderBuilder for OllamaBuilder {
@@ -320,9 +658,13 @@ impl ProviderBuilder for OllamaBuilder {
         self
     }
 
+    fn with_transport(mut self, transport: ReqwestClientBuilder) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
     fn build(self) -> Result<Self::Provider, Error> {
         use crate::constants::{OLLAMA_DEFAULT_BASE_URL, OLLAMA_DEFAULT_MODEL};
-        use crate::http::ReqwestClient;
         use crate::ollama::OllamaConfig;
 
         let config = OllamaConfig {
@@ -334,15 +676,7 @@ impl ProviderBuilder for OllamaBuilder {
                 .unwrap_or_else(|| OLLAMA_DEFAULT_MODEL.to_string()),
         };
 
-        let client = match self.client {
-            Some(client) => client,
-            None => Arc::new(ReqwestClient::new().map_err(|e| Error::Provider {
-                provider: "Ollama".to_string(),
-                message: format!("Failed to create HTTP client: {}", e),
-                retry_after: None,
-                source: Some(Box::new(e)),
-            })?),
-        };
+        let client = resolve_client("Ollama", self.client, self.transport)?;
 
         Ok(crate::Ollama::new(config, client))
     }
@@ -353,3 +687,76 @@ impl Default for OllamaBuilder {
         Self::new()
     }
 }
+
+/// Resolve the HTTP client for a builder
+///
+/// An explicitly injected client takes precedence; otherwise a
+/// [`ReqwestClient`](crate::http::ReqwestClient) is built from the transport
+/// settings, falling back to the defaults.
+pub(crate) fn resolve_client(
+    provider: &str,
+    client: Option<Arc<dyn HttpClient>>,
+    transport: Option<ReqwestClientBuilder>,
+) -> Result<Arc<dyn HttpClient>, Error> {
+    if let Some(client) = client {
+        return Ok(client);
+    }
+    let client = transport
+        .unwrap_or_default()
+        .build()
+        .map_err(|e| Error::Provider {
+            provider: provider.to_string(),
+            message: format!("Failed to create HTTP client: {}", e),
+            retry_after: None,
+            source: Some(Box::new(e)),
+        })?;
+    Ok(Arc::new(client))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A builder from outside this crate, relying on the trait defaults
+    #[derive(Default)]
+    struct ExternalBuilder {
+        client: Option<Arc<dyn HttpClient>>,
+    }
+
+    impl ProviderBuilder for ExternalBuilder {
+        type Provider = ();
+
+        fn with_client(mut self, client: Arc<dyn HttpClient>) -> Self {
+            self.client = Some(client);
+            self
+        }
+
+        fn build(self) -> Result<Self::Provider, Error> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_default_with_transport_sets_client() {
+        let builder = ExternalBuilder::default().with_transport(ReqwestClientBuilder::new());
+        assert!(builder.client.is_some());
+
+        // An invalid transport must not fall back to a default client
+        let invalid = ReqwestClientBuilder::new().header("bad header", "x");
+        let builder = ExternalBuilder::default().with_transport(invalid);
+        let client = builder.client.unwrap();
+        let result = client
+            .post("http://localhost", HeaderMap::new(), Value::Null)
+            .await;
+        assert!(matches!(result, Err(Error::Configuration(_))));
+    }
+
+    #[test]
+    fn test_invalid_transport_fails_build() {
+        let invalid = ReqwestClientBuilder::new().header("bad header", "x");
+        assert!(OpenAIBuilder::new("key")
+            .with_transport(invalid)
+            .build()
+            .is_err());
+    }
+}
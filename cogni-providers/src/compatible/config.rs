@@ -0,0 +1,111 @@
+//! OpenAI-compatible provider configuration
+
+use crate::compatible::quirks::{AuthScheme, Quirks};
+use crate::constants::*;
+
+/// Configuration for an OpenAI-compatible backend
+#[derive(Debug, Clone)]
+pub struct OpenAICompatibleConfig {
+    /// Name used in error messages, e.g. `"groq"`
+    pub name: String,
+    /// Base URL including the API version prefix, e.g. `http://localhost:8000/v1`
+    pub base_url: String,
+    /// Optional API key; local servers often don't need one
+    pub api_key: Option<String>,
+    /// Optional organization ID, sent as `OpenAI-Organization`
+    pub organization_id: Option<String>,
+    /// Backend-specific differences from the OpenAI API
+    pub quirks: Quirks,
+}
+
+impl OpenAICompatibleConfig {
+    /// Create a configuration for a backend with default (OpenAI) behaviour
+    pub fn new(name: impl Into<String>, base_url: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            api_key: None,
+            organization_id: None,
+            quirks: Quirks::default(),
+        }
+    }
+
+    /// Preset for a vLLM server
+    pub fn vllm(base_url: impl Into<String>) -> Self {
+        Self::new("vllm", base_url).with_quirks(Quirks::vllm())
+    }
+
+    /// Preset for a llama.cpp server
+    pub fn llama_cpp(base_url: impl Into<String>) -> Self {
+        Self::new("llama.cpp", base_url).with_quirks(Quirks::llama_cpp())
+    }
+
+    /// Preset for Groq
+    pub fn groq(api_key: impl Into<String>) -> Self {
+        Self::new("groq", GROQ_BASE_URL)
+            .with_api_key(api_key)
+            .with_quirks(Quirks::groq())
+    }
+
+    /// Preset for Together AI
+    pub fn together(api_key: impl Into<String>) -> Self {
+        Self::new("together", TOGETHER_BASE_URL)
+            .with_api_key(api_key)
+            .with_quirks(Quirks::together())
+    }
+
+    /// Preset for Mistral
+    pub fn mistral(api_key: impl Into<String>) -> Self {
+        Self::new("mistral", MISTRAL_BASE_URL)
+            .with_api_key(api_key)
+            .with_quirks(Quirks::mistral())
+    }
+
+    /// Preset for OpenRouter
+    pub fn openrouter(api_key: impl Into<String>) -> Self {
+        Self::new("openrouter", OPENROUTER_BASE_URL)
+            .with_api_key(api_key)
+            .with_quirks(Quirks::openrouter())
+    }
+
+    /// Set the API key
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Set the organization ID
+    pub fn with_organization(mut self, org: impl Into<String>) -> Self {
+        self.organization_id = Some(org.into());
+        self
+    }
+
+    /// Replace the quirks profile
+    pub fn with_quirks(mut self, quirks: Quirks) -> Self {
+        self.quirks = quirks;
+        self
+    }
+
+    /// Add a header to every request
+    pub fn with_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.quirks = self.quirks.header(name, value);
+        self
+    }
+
+    /// Set how the API key is sent
+    pub fn with_auth(mut self, auth: AuthScheme) -> Self {
+        self.quirks.auth = auth;
+        self
+    }
+
+    /// Get the URL for chat completions
+    pub fn chat_url(&self) -> String {
+        format!("{}/chat/completions", self.base_url)
+    }
+}
+
+impl Default for OpenAICompatibleConfig {
+    fn default() -> Self {
+        Self::vllm(VLLM_DEFAULT_BASE_URL)
+    }
+}
@@ -0,0 +1,15 @@
+//! Generic provider for OpenAI-compatible backends
+//!
+//! Backends such as vLLM, llama.cpp, Groq, Together, Mistral and OpenRouter
+//! expose an OpenAI-style chat completions API with small differences, which
+//! are described by a [`Quirks`] profile.
+
+mod config;
+mod provider;
+mod quirks;
+mod stream;
+
+pub use config::OpenAICompatibleConfig;
+pub use provider::OpenAICompatible;
+pub use quirks::{AuthScheme, JsonSchemaSupport, Quirks, ToolCallStreaming};
+pub use stream::OpenAICompatibleStream;
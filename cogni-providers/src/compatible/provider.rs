@@ -0,0 +1,242 @@
+//! OpenAI-compatible provider implementation
+//!
+//! Many inference servers and hosted APIs implement the OpenAI chat
+//! completions protocol with small differences. This provider reuses the
+//! OpenAI conversion pipeline and applies a [`Quirks`](crate::compatible::Quirks)
+//! profile to smooth over them.
+
+use crate::compatible::{
+    config::OpenAICompatibleConfig, quirks::AuthScheme, stream::OpenAICompatibleStream,
+};
+use crate::http::{HttpClient, ReqwestClient};
+use crate::openai::{OpenAIConverter, OpenAIParser};
+use crate::traits::{RequestConverter, ResponseParser};
+use async_trait::async_trait;
+use cogni_core::{Error, Provider, Request, Response};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Provider for OpenAI-compatible backends
+///
+/// Presets are available for vLLM, llama.cpp, Groq, Together, Mistral and
+/// OpenRouter; any other backend can be described with a custom
+/// [`Quirks`](crate::compatible::Quirks) profile.
+///
+/// # Example
+///
+/// ```no_run
+/// use cogni_providers::OpenAICompatible;
+/// use cogni_providers::compatible::{OpenAICompatibleConfig, Quirks};
+/// use cogni_providers::http::ReqwestClient;
+/// use std::sync::Arc;
+///
+/// // Hosted backend with a preset
+/// let groq = OpenAICompatible::groq("gsk-...");
+///
+/// // Self-hosted server with custom quirks
+/// let config = OpenAICompatibleConfig::new("internal", "http://llm.internal/v1")
+///     .with_quirks(Quirks::default().strip_param("seed"));
+/// let client = Arc::new(ReqwestClient::new().expect("Failed to create client"));
+/// let provider = OpenAICompatible::new(config, client);
+/// ```
+#[derive(Clone)]
+pub struct OpenAICompatible {
+    client: Arc<dyn HttpClient>,
+    config: OpenAICompatibleConfig,
+    converter: OpenAIConverter,
+    parser: OpenAIParser,
+}
+
+impl OpenAICompatible {
+    /// Create a new provider with the given configuration and client
+    pub fn new(config: OpenAICompatibleConfig, client: Arc<dyn HttpClient>) -> Self {
+        Self {
+            client,
+            config,
+            converter: OpenAIConverter,
+            parser: OpenAIParser,
+        }
+    }
+
+    /// Create a provider with the default HTTP client
+    pub fn from_config(config: OpenAICompatibleConfig) -> Result<Self, Error> {
+        let client = Arc::new(ReqwestClient::new().map_err(|e| Error::Provider {
+            provider: config.name.clone(),
+            message: format!("Failed to create HTTP client: {}", e),
+            retry_after: None,
+            source: Some(Box::new(e)),
+        })?);
+        Ok(Self::new(config, client))
+    }
+
+    /// Create a provider for a vLLM server
+    pub fn vllm(base_url: impl Into<String>) -> Result<Self, Error> {
+        Self::from_config(OpenAICompatibleConfig::vllm(base_url))
+    }
+
+    /// Create a provider for a llama.cpp server
+    pub fn llama_cpp(base_url: impl Into<String>) -> Result<Self, Error> {
+        Self::from_config(OpenAICompatibleConfig::llama_cpp(base_url))
+    }
+
+    /// Create a provider for Groq
+    pub fn groq(api_key: impl Into<String>) -> Result<Self, Error> {
+        Self::from_config(OpenAICompatibleConfig::groq(api_key))
+    }
+
+    /// Create a provider for Together AI
+    pub fn together(api_key: impl Into<String>) -> Result<Self, Error> {
+        Self::from_config(OpenAICompatibleConfig::together(api_key))
+    }
+
+    /// Create a provider for Mistral
+    pub fn mistral(api_key: impl Into<String>) -> Result<Self, Error> {
+        Self::from_config(OpenAICompatibleConfig::mistral(api_key))
+    }
+
+    /// Create a provider for OpenRouter
+    pub fn openrouter(api_key: impl Into<String>) -> Result<Self, Error> {
+        Self::from_config(OpenAICompatibleConfig::openrouter(api_key))
+    }
+
+    /// Get the provider configuration
+    pub fn config(&self) -> &OpenAICompatibleConfig {
+        &self.config
+    }
+
+    async fn build_body(&self, request: Request, stream: bool) -> Result<Value, Error> {
+        let mut body = self.converter.convert_request(request).await?;
+        body["stream"] = serde_json::json!(stream);
+        self.config.quirks.apply(&mut body);
+        Ok(body)
+    }
+
+    fn create_headers(&self) -> Result<HeaderMap, Error> {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+
+        if let Some(api_key) = &self.config.api_key {
+            let value = match &self.config.quirks.auth {
+                AuthScheme::Bearer => Some((AUTHORIZATION, format!("Bearer {}", api_key))),
+                AuthScheme::Header(name) => Some((header_name(name)?, api_key.clone())),
+                AuthScheme::None => None,
+            };
+            if let Some((name, value)) = value {
+                headers.insert(
+                    name,
+                    HeaderValue::from_str(&value)
+                        .map_err(|e| Error::Configuration(format!("Invalid API key: {}", e)))?,
+                );
+            }
+        }
+
+        if let Some(org) = &self.config.organization_id {
+            headers.insert(
+                "OpenAI-Organization",
+                HeaderValue::from_str(org)
+                    .map_err(|e| Error::Configuration(format!("Invalid organization ID: {}", e)))?,
+            );
+        }
+
+        for (name, value) in &self.config.quirks.extra_headers {
+            headers.insert(
+                header_name(name)?,
+                HeaderValue::from_str(value).map_err(|e| {
+                    Error::Configuration(format!("Invalid value for header {}: {}", name, e))
+                })?,
+            );
+        }
+
+        Ok(headers)
+    }
+}
+
+fn header_name(name: &str) -> Result<HeaderName, Error> {
+    HeaderName::from_bytes(name.as_bytes())
+        .map_err(|e| Error::Configuration(format!("Invalid header name {}: {}", name, e)))
+}
+
+#[async_trait]
+impl Provider for OpenAICompatible {
+    type Stream = OpenAICompatibleStream;
+
+    async fn request(&self, request: Request) -> Result<Response, Error> {
+        let body = self.build_body(request, false).await?;
+        let headers = self.create_headers()?;
+        let response = self
+            .client
+            .post(&self.config.chat_url(), headers, body)
+            .await?;
+
+        self.parser.parse_response(response).await
+    }
+
+    async fn stream(&self, request: Request) -> Result<Self::Stream, Error> {
+        let body = self.build_body(request, true).await?;
+        let headers = self.create_headers()?;
+        let event_source = self
+            .client
+            .post_event_stream(&self.config.chat_url(), headers, body)
+            .await?;
+
+        Ok(OpenAICompatibleStream::new(
+            event_source,
+            self.config.quirks.tool_call_streaming,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::compatible::Quirks;
+    use crate::testing::MockHttpClient;
+    use cogni_core::{Message, Parameters};
+
+    #[tokio::test]
+    async fn test_request_applies_quirks_and_headers() {
+        let client = Arc::new(MockHttpClient::new());
+        let config = OpenAICompatibleConfig::mistral("key")
+            .with_organization("org-1")
+            .with_header("X-Tenant", "acme");
+        let provider = OpenAICompatible::new(config, client.clone());
+
+        let request = Request::builder()
+            .model("mistral-large-latest")
+            .message(Message::user("hi"))
+            .parameters(Parameters {
+                seed: Some(42),
+                ..Default::default()
+            })
+            .build();
+        provider.request(request).await.unwrap();
+
+        let sent = &client.requests()[0];
+        let (headers, body) = (&sent.headers, &sent.body);
+        assert_eq!(sent.url, "https://api.mistral.ai/v1/chat/completions");
+        assert_eq!(headers.get(AUTHORIZATION).unwrap(), "Bearer key");
+        assert_eq!(headers.get("OpenAI-Organization").unwrap(), "org-1");
+        assert_eq!(headers.get("X-Tenant").unwrap(), "acme");
+        assert_eq!(body["random_seed"], 42);
+        assert!(body.get("seed").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_custom_auth_header_and_no_key() {
+        let client = Arc::new(MockHttpClient::new());
+        let config = OpenAICompatibleConfig::new("custom", "http://localhost:9000/v1")
+            .with_api_key("secret")
+            .with_quirks(Quirks::default().auth(AuthScheme::Header("x-api-key".into())));
+        let provider = OpenAICompatible::new(config, client.clone());
+        provider.request(Request::new(vec![])).await.unwrap();
+
+        let local = OpenAICompatible::new(OpenAICompatibleConfig::default(), client.clone());
+        local.request(Request::new(vec![])).await.unwrap();
+
+        let sent = client.requests();
+        assert_eq!(sent[0].headers.get("x-api-key").unwrap(), "secret");
+        assert!(sent[0].headers.get(AUTHORIZATION).is_none());
+        assert!(sent[1].headers.get(AUTHORIZATION).is_none());
+    }
+}
@@ -0,0 +1,231 @@
+//! Per-vendor differences between OpenAI-compatible backends
+
+use serde_json::{json, Value};
+
+/// How much of OpenAI's structured output API a backend supports
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum JsonSchemaSupport {
+    /// `response_format: {"type": "json_schema", ...}` is accepted as-is
+    #[default]
+    Full,
+    /// Schemas are passed inline as `{"type": "json_object", "schema": ...}`
+    InlineSchema,
+    /// Only `{"type": "json_object"}` is accepted; schemas are dropped
+    JsonObjectOnly,
+    /// `response_format` is not supported and is removed from the request
+    None,
+}
+
+/// How a backend streams tool calls
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ToolCallStreaming {
+    /// OpenAI-style deltas keyed by `index`, with arguments split over chunks
+    #[default]
+    Incremental,
+    /// Each chunk carries one or more complete tool calls, `index` may be missing
+    Complete,
+}
+
+/// How the API key is sent
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum AuthScheme {
+    /// `Authorization: Bearer <key>`
+    #[default]
+    Bearer,
+    /// The key is sent verbatim in a custom header, e.g. `x-api-key`
+    Header(String),
+    /// No authentication header is sent
+    None,
+}
+
+/// Differences from the OpenAI chat completions API for a specific backend
+///
+/// Quirks are applied to the request body produced by
+/// [`OpenAIConverter`](crate::openai::OpenAIConverter) and to the streaming
+/// parser, so one provider implementation can talk to many backends.
+///
+/// # Example
+///
+/// ```
+/// use cogni_providers::compatible::{JsonSchemaSupport, Quirks};
+///
+/// let quirks = Quirks::default()
+///     .strip_param("seed")
+///     .json_schema(JsonSchemaSupport::JsonObjectOnly)
+///     .header("X-Tenant", "acme");
+/// ```
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Quirks {
+    /// Top-level request parameters the backend rejects
+    pub unsupported_params: Vec<String>,
+    /// Parameters the backend expects under a different name, as `(openai, vendor)`
+    pub renamed_params: Vec<(String, String)>,
+    /// Structured output support level
+    pub json_schema: JsonSchemaSupport,
+    /// Tool call streaming format
+    pub tool_call_streaming: ToolCallStreaming,
+    /// Headers added to every request
+    pub extra_headers: Vec<(String, String)>,
+    /// How the API key is sent
+    pub auth: AuthScheme,
+}
+
+impl Quirks {
+    /// Quirks for vLLM's OpenAI-compatible server
+    pub fn vllm() -> Self {
+        Self::default()
+    }
+
+    /// Quirks for the llama.cpp server
+    pub fn llama_cpp() -> Self {
+        Self::default().strip_param("n")
+    }
+
+    /// Quirks for Groq
+    pub fn groq() -> Self {
+        Self::default()
+            .strip_param("n")
+            .json_schema(JsonSchemaSupport::JsonObjectOnly)
+            .tool_call_streaming(ToolCallStreaming::Complete)
+    }
+
+    /// Quirks for Together AI
+    pub fn together() -> Self {
+        Self::default().json_schema(JsonSchemaSupport::InlineSchema)
+    }
+
+    /// Quirks for Mistral
+    pub fn mistral() -> Self {
+        Self::default()
+            .rename_param("seed", "random_seed")
+            .tool_call_streaming(ToolCallStreaming::Complete)
+    }
+
+    /// Quirks for OpenRouter
+    pub fn openrouter() -> Self {
+        Self::default()
+    }
+
+    /// Strip a parameter the backend rejects
+    pub fn strip_param(mut self, param: impl Into<String>) -> Self {
+        self.unsupported_params.push(param.into());
+        self
+    }
+
+    /// Send a parameter under a different name
+    pub fn rename_param(mut self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        self.renamed_params.push((from.into(), to.into()));
+        self
+    }
+
+    /// Set the structured output support level
+    pub fn json_schema(mut self, support: JsonSchemaSupport) -> Self {
+        self.json_schema = support;
+        self
+    }
+
+    /// Set the tool call streaming format
+    pub fn tool_call_streaming(mut self, format: ToolCallStreaming) -> Self {
+        self.tool_call_streaming = format;
+        self
+    }
+
+    /// Add a header to every request
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra_headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Set how the API key is sent
+    pub fn auth(mut self, auth: AuthScheme) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Rewrite an OpenAI request body for this backend
+    pub fn apply(&self, body: &mut Value) {
+        let Some(obj) = body.as_object_mut() else {
+            return;
+        };
+
+        for param in &self.unsupported_params {
+            obj.remove(param);
+        }
+        for (from, to) in &self.renamed_params {
+            if let Some(value) = obj.remove(from) {
+                obj.insert(to.clone(), value);
+            }
+        }
+
+        if let Some(format) = obj.remove("response_format") {
+            let is_schema = format["type"] == "json_schema";
+            let rewritten = match self.json_schema {
+                JsonSchemaSupport::Full => Some(format),
+                JsonSchemaSupport::InlineSchema if is_schema => Some(json!({
+                    "type": "json_object",
+                    "schema": format["json_schema"]["schema"],
+                })),
+                JsonSchemaSupport::InlineSchema => Some(format),
+                JsonSchemaSupport::JsonObjectOnly => Some(json!({ "type": "json_object" })),
+                JsonSchemaSupport::None => None,
+            };
+            if let Some(format) = rewritten {
+                obj.insert("response_format".to_string(), format);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema_body() -> Value {
+        json!({
+            "model": "m",
+            "seed": 7,
+            "n": 1,
+            "response_format": {
+                "type": "json_schema",
+                "json_schema": { "name": "response", "strict": true, "schema": { "type": "object" } }
+            }
+        })
+    }
+
+    #[test]
+    fn test_strip_and_rename_params() {
+        let mut body = schema_body();
+        Quirks::default()
+            .strip_param("n")
+            .rename_param("seed", "random_seed")
+            .apply(&mut body);
+
+        assert!(body.get("n").is_none());
+        assert!(body.get("seed").is_none());
+        assert_eq!(body["random_seed"], 7);
+    }
+
+    #[test]
+    fn test_json_schema_levels() {
+        let mut body = schema_body();
+        Quirks::together().apply(&mut body);
+        assert_eq!(
+            body["response_format"],
+            json!({ "type": "json_object", "schema": { "type": "object" } })
+        );
+
+        let mut body = schema_body();
+        Quirks::groq().apply(&mut body);
+        assert_eq!(body["response_format"], json!({ "type": "json_object" }));
+
+        let mut body = schema_body();
+        Quirks::default()
+            .json_schema(JsonSchemaSupport::None)
+            .apply(&mut body);
+        assert!(body.get("response_format").is_none());
+
+        let mut body = schema_body();
+        Quirks::vllm().apply(&mut body);
+        assert_eq!(body["response_format"]["type"], "json_schema");
+    }
+}
@@ -0,0 +1,243 @@
+//! Streaming implementation for OpenAI-compatible backends
+
+use crate::compatible::quirks::ToolCallStreaming;
+use crate::error;
+use cogni_core::{ContentDelta, Error, MetadataDelta, StreamEvent, ToolCallDelta};
+use futures::Stream;
+use reqwest_eventsource::{Event, EventSource};
+use serde_json::Value;
+use std::collections::VecDeque;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Streaming response from an OpenAI-compatible backend
+///
+/// Unlike [`OpenAIStream`](crate::openai::OpenAIStream), this parser tolerates
+/// missing fields and emits every tool call in a chunk, since backends differ
+/// in how they split tool calls across chunks.
+pub struct OpenAICompatibleStream {
+    inner: EventSource,
+    tool_call_streaming: ToolCallStreaming,
+    pending: VecDeque<StreamEvent>,
+    next_tool_index: usize,
+    metadata_sent: bool,
+    finished: bool,
+}
+
+impl OpenAICompatibleStream {
+    /// Create a new stream
+    pub fn new(event_source: EventSource, tool_call_streaming: ToolCallStreaming) -> Self {
+        Self {
+            inner: event_source,
+            tool_call_streaming,
+            pending: VecDeque::new(),
+            next_tool_index: 0,
+            metadata_sent: false,
+            finished: false,
+        }
+    }
+
+    /// End the stream with [`StreamEvent::Done`], whether or not the backend
+    /// sent `[DONE]`
+    fn finish(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            self.pending.push_back(StreamEvent::Done);
+        }
+    }
+
+    fn parse_data(&mut self, data: &str) -> Result<(), Error> {
+        let data = data.trim();
+        if data.is_empty() {
+            return Ok(());
+        }
+        if data == "[DONE]" {
+            self.finish();
+            return Ok(());
+        }
+
+        let chunk: Value = serde_json::from_str(data).map_err(error::serialization_error)?;
+
+        if !self.metadata_sent {
+            let model = chunk["model"].as_str().map(String::from);
+            let id = chunk["id"].as_str().map(String::from);
+            if model.is_some() || id.is_some() {
+                self.metadata_sent = true;
+                self.pending.push_back(StreamEvent::Metadata(MetadataDelta {
+                    model,
+                    id,
+                    ..Default::default()
+                }));
+            }
+        }
+
+        let delta = &chunk["choices"][0]["delta"];
+
+        if let Some(text) = delta["content"].as_str() {
+            if !text.is_empty() {
+                self.pending.push_back(StreamEvent::Content(ContentDelta {
+                    text: text.to_string(),
+                }));
+            }
+        }
+
+        if let Some(tool_calls) = delta["tool_calls"].as_array() {
+            for (position, tc) in tool_calls.iter().enumerate() {
+                let index = match self.tool_call_streaming {
+                    ToolCallStreaming::Incremental => {
+                        tc["index"].as_u64().map(|i| i as usize).unwrap_or(position)
+                    }
+                    ToolCallStreaming::Complete => {
+                        let index = self.next_tool_index;
+                        self.next_tool_index += 1;
+                        index
+                    }
+                };
+
+                let arguments = match &tc["function"]["arguments"] {
+                    Value::String(s) => Some(s.clone()),
+                    Value::Null => None,
+                    // Some servers send arguments as an object instead of a string
+                    other => Some(other.to_string()),
+                };
+
+                self.pending.push_back(StreamEvent::ToolCall(ToolCallDelta {
+                    index,
+                    id: tc["id"].as_str().map(String::from),
+                    name: tc["function"]["name"].as_str().map(String::from),
+                    arguments,
+                }));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Stream for OpenAICompatibleStream {
+    type Item = Result<StreamEvent, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Poll::Ready(Some(Ok(event)));
+            }
+            if self.finished {
+                self.inner.close();
+                return Poll::Ready(None);
+            }
+
+            match Pin::new(&mut self.inner).poll_next(cx) {
+                Poll::Ready(Some(Ok(Event::Open))) => continue,
+                Poll::Ready(Some(Ok(Event::Message(msg)))) => {
+                    if let Err(e) = self.parse_data(&msg.data) {
+                        return Poll::Ready(Some(Err(e)));
+                    }
+                }
+                Poll::Ready(Some(Err(reqwest_eventsource::Error::StreamEnded))) => {
+                    self.finish();
+                }
+                Poll::Ready(Some(Err(e))) => {
+                    return Poll::Ready(Some(Err(Error::Network {
+                        message: format!("EventSource error: {}", e),
+                        source: None,
+                    })))
+                }
+                Poll::Ready(None) => {
+                    self.finish();
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stream(format: ToolCallStreaming) -> OpenAICompatibleStream {
+        let source = EventSource::new(reqwest::Client::new().get("http://localhost")).unwrap();
+        OpenAICompatibleStream::new(source, format)
+    }
+
+    fn tool_calls(stream: &OpenAICompatibleStream) -> Vec<ToolCallDelta> {
+        stream
+            .pending
+            .iter()
+            .filter_map(|e| match e {
+                StreamEvent::ToolCall(tc) => Some(tc.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_complete_tool_calls_without_index() {
+        let mut s = stream(ToolCallStreaming::Complete);
+        s.parse_data(
+            r#"{"id":"1","model":"m","choices":[{"delta":{"tool_calls":[
+            {"id":"a","function":{"name":"f","arguments":"{}"}},
+            {"id":"b","function":{"name":"g","arguments":{"x":1}}}]}}]}"#,
+        )
+        .unwrap();
+        s.parse_data(r#"{"choices":[{"delta":{"tool_calls":[{"id":"c","function":{"name":"h","arguments":"{}"}}]}}]}"#)
+            .unwrap();
+
+        let calls = tool_calls(&s);
+        assert_eq!(
+            calls.iter().map(|c| c.index).collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+        assert_eq!(calls[1].arguments.as_deref(), Some(r#"{"x":1}"#));
+        assert!(matches!(s.pending[0], StreamEvent::Metadata(_)));
+    }
+
+    #[test]
+    fn test_incremental_tool_calls_use_index() {
+        let mut s = stream(ToolCallStreaming::Incremental);
+        s.parse_data(r#"{"choices":[{"delta":{"tool_calls":[{"index":1,"id":"a","function":{"name":"f"}}]}}]}"#)
+            .unwrap();
+        s.parse_data(
+            r#"{"choices":[{"delta":{"tool_calls":[{"index":1,"function":{"arguments":"{}"}}]}}]}"#,
+        )
+        .unwrap();
+
+        let calls = tool_calls(&s);
+        assert_eq!(calls[0].index, 1);
+        assert_eq!(calls[1].index, 1);
+        assert_eq!(calls[1].id, None);
+    }
+
+    #[test]
+    fn test_done_and_content() {
+        let mut s = stream(ToolCallStreaming::Incremental);
+        s.parse_data(r#"{"choices":[{"delta":{"content":"hi"}}]}"#)
+            .unwrap();
+        s.parse_data("[DONE]").unwrap();
+        assert!(s.finished);
+        assert_eq!(
+            s.pending.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                StreamEvent::Content(ContentDelta {
+                    text: "hi".to_string()
+                }),
+                StreamEvent::Done
+            ]
+        );
+
+        // The end of the connection doesn't repeat it
+        s.pending.clear();
+        s.finish();
+        assert!(s.pending.is_empty());
+    }
+
+    #[test]
+    fn test_end_without_done_marker() {
+        let mut s = stream(ToolCallStreaming::Incremental);
+        s.parse_data(r#"{"choices":[{"delta":{"content":"hi"}}]}"#)
+            .unwrap();
+        s.finish();
+        assert_eq!(s.pending.back(), Some(&StreamEvent::Done));
+    }
+}
@@ -20,3 +20,27 @@ pub const OPENAI_DEFAULT_MODEL: &str = "gpt-3.5-turbo";
 
 /// Default max tokens if not specified
 pub const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// Default Azure OpenAI REST API version
+pub const AZURE_OPENAI_DEFAULT_API_VERSION: &str = "2024-10-21";
+
+/// OAuth scope for Azure Cognitive Services when using Entra ID
+pub const AZURE_COGNITIVE_SERVICES_SCOPE: &str = "https://cognitiveservices.azure.com/.default";
+
+/// Default vLLM server base URL
+pub const VLLM_DEFAULT_BASE_URL: &str = "http://localhost:8000/v1";
+
+/// Default llama.cpp server base URL
+pub const LLAMA_CPP_DEFAULT_BASE_URL: &str = "http://localhost:8080/v1";
+
+/// Groq OpenAI-compatible base URL
+pub const GROQ_BASE_URL: &str = "https://api.groq.com/openai/v1";
+
+/// Together AI base URL
+pub const TOGETHER_BASE_URL: &str = "https://api.together.xyz/v1";
+
+/// Mistral base URL
+pub const MISTRAL_BASE_URL: &str = "https://api.mistral.ai/v1";
+
+/// OpenRouter base URL
+pub const OPENROUTER_BASE_URL: &str = "https://openrouter.ai/api/v1";
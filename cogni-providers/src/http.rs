@@ -4,10 +4,14 @@ use crate::error;
 use bytes::Bytes;
 use cogni_core::Error;
 use futures::Stream;
-use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+use reqwest::RequestBuilder;
 use reqwest_eventsource::EventSource;
 use serde_json::Value;
+use std::fmt;
+use std::future::Future;
 use std::pin::Pin;
+use std::time::Duration;
 
 /// Type alias for response streams
 pub type ResponseStream = Pin<Box<dyn Stream<Item = Result<Bytes, reqwest::Error>> + Send>>;
@@ -35,7 +39,43 @@ pub trait HttpClient: Send + Sync {
     ) -> Result<EventSource, Error>;
 }
 
+/// Default overall request timeout
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(300);
+
+tokio::task_local! {
+    static REQUEST_TIMEOUT: Duration;
+}
+
+/// Run a future with a per-request timeout override
+///
+/// Requests sent by [`ReqwestClient`] while `future` runs use `timeout`
+/// instead of the client's overall timeout. This works through any provider
+/// because the override travels with the task rather than the request.
+///
+/// # Example
+///
+/// ```no_run
+/// use cogni_core::{Message, Provider, Request};
+/// use cogni_providers::{http::with_request_timeout, OpenAI};
+/// use std::time::Duration;
+///
+/// # async fn example() -> Result<(), cogni_core::Error> {
+/// let provider = OpenAI::with_api_key("sk-...")?;
+/// let request = Request::builder().message(Message::user("Write a novel")).build();
+/// let response = with_request_timeout(Duration::from_secs(900), provider.request(request)).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn with_request_timeout<F: Future>(timeout: Duration, future: F) -> F::Output {
+    REQUEST_TIMEOUT.scope(timeout, future).await
+}
+
+fn request_timeout() -> Option<Duration> {
+    REQUEST_TIMEOUT.try_with(|timeout| *timeout).ok()
+}
+
 /// Default HTTP client implementation using reqwest
+#[derive(Clone)]
 pub struct ReqwestClient {
     client: reqwest::Client,
 }
@@ -43,12 +83,312 @@ pub struct ReqwestClient {
 impl ReqwestClient {
     /// Create a new HTTP client
     pub fn new() -> Result<Self, Error> {
-        let client = reqwest::Client::builder()
-            .timeout(std::time::Duration::from_secs(300))
-            .build()
-            .map_err(error::network_error)?;
+        ReqwestClientBuilder::new().build()
+    }
+
+    /// Create a builder for a configured HTTP client
+    pub fn builder() -> ReqwestClientBuilder {
+        ReqwestClientBuilder::new()
+    }
+
+    fn post_request(&self, url: &str, headers: HeaderMap, body: &Value) -> RequestBuilder {
+        let mut request = self.client.post(url).headers(headers).json(body);
+        if let Some(timeout) = request_timeout() {
+            request = request.timeout(timeout);
+        }
+        request
+    }
+}
+
+#[derive(Clone)]
+enum ClientIdentity {
+    Pem { certificate: Vec<u8>, key: Vec<u8> },
+    Pkcs12 { der: Vec<u8>, password: String },
+}
+
+/// Builder for [`ReqwestClient`] transport settings
+///
+/// Settings are validated when [`build`](Self::build) is called, so the
+/// builder can be passed to provider builders and stored in configuration.
+///
+/// # Example
+///
+/// ```no_run
+/// use cogni_providers::http::ReqwestClientBuilder;
+/// use std::time::Duration;
+///
+/// let client = ReqwestClientBuilder::new()
+///     .connect_timeout(Duration::from_secs(5))
+///     .read_timeout(Duration::from_secs(60))
+///     .timeout(Duration::from_secs(600))
+///     .https_proxy("http://proxy.internal:3128")
+///     .header("X-Tenant-Id", "acme")
+///     .pool_max_idle_per_host(16)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Clone)]
+pub struct ReqwestClientBuilder {
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    proxies: Vec<(ProxyScheme, String)>,
+    proxy_auth: Option<(String, String)>,
+    no_system_proxy: bool,
+    headers: Vec<(String, String)>,
+    root_certificates: Vec<Vec<u8>>,
+    built_in_root_certificates: bool,
+    identity: Option<ClientIdentity>,
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout: Option<Duration>,
+    user_agent: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ProxyScheme {
+    Http,
+    Https,
+    All,
+}
+
+impl Default for ReqwestClientBuilder {
+    fn default() -> Self {
+        Self {
+            timeout: Some(DEFAULT_TIMEOUT),
+            connect_timeout: None,
+            read_timeout: None,
+            proxies: Vec::new(),
+            proxy_auth: None,
+            no_system_proxy: false,
+            headers: Vec::new(),
+            root_certificates: Vec::new(),
+            built_in_root_certificates: true,
+            identity: None,
+            pool_max_idle_per_host: None,
+            pool_idle_timeout: None,
+            user_agent: None,
+        }
+    }
+}
+
+impl ReqwestClientBuilder {
+    /// Create a builder with the default 300s overall timeout
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the overall timeout for a request, from connecting to the end of the body
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Disable the overall timeout
+    pub fn no_timeout(mut self) -> Self {
+        self.timeout = None;
+        self
+    }
+
+    /// Set the timeout for establishing a connection
+    pub fn connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum time to wait between reads, useful for long streams
+    pub fn read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Route plain HTTP requests through a proxy
+    pub fn http_proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxies.push((ProxyScheme::Http, url.into()));
+        self
+    }
+
+    /// Route HTTPS requests through a proxy
+    pub fn https_proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxies.push((ProxyScheme::Https, url.into()));
+        self
+    }
+
+    /// Route all requests through a proxy
+    pub fn proxy(mut self, url: impl Into<String>) -> Self {
+        self.proxies.push((ProxyScheme::All, url.into()));
+        self
+    }
+
+    /// Set basic auth credentials for the configured proxies
+    pub fn proxy_auth(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        self.proxy_auth = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Ignore proxies configured through environment variables
+    pub fn no_system_proxy(mut self) -> Self {
+        self.no_system_proxy = true;
+        self
+    }
+
+    /// Add a header sent with every request, e.g. for tracing or tenancy
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Trust an additional PEM-encoded root certificate
+    pub fn add_root_certificate_pem(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.root_certificates.push(pem.into());
+        self
+    }
+
+    /// Trust only explicitly added root certificates
+    pub fn disable_built_in_root_certificates(mut self) -> Self {
+        self.built_in_root_certificates = false;
+        self
+    }
+
+    /// Present a client certificate (mTLS) from PEM certificate chain and PKCS#8 key
+    pub fn identity_pem(
+        mut self,
+        certificate: impl Into<Vec<u8>>,
+        key: impl Into<Vec<u8>>,
+    ) -> Self {
+        self.identity = Some(ClientIdentity::Pem {
+            certificate: certificate.into(),
+            key: key.into(),
+        });
+        self
+    }
+
+    /// Present a client certificate (mTLS) from a PKCS#12 archive
+    pub fn identity_pkcs12(mut self, der: impl Into<Vec<u8>>, password: impl Into<String>) -> Self {
+        self.identity = Some(ClientIdentity::Pkcs12 {
+            der: der.into(),
+            password: password.into(),
+        });
+        self
+    }
 
-        Ok(Self { client })
+    /// Set the maximum number of idle connections kept per host
+    pub fn pool_max_idle_per_host(mut self, max: usize) -> Self {
+        self.pool_max_idle_per_host = Some(max);
+        self
+    }
+
+    /// Set how long idle connections are kept in the pool
+    pub fn pool_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.pool_idle_timeout = Some(timeout);
+        self
+    }
+
+    /// Set the `User-Agent` header
+    pub fn user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Build the HTTP client
+    pub fn build(self) -> Result<ReqwestClient, Error> {
+        let mut builder = reqwest::Client::builder();
+
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.read_timeout {
+            builder = builder.read_timeout(timeout);
+        }
+
+        if self.no_system_proxy {
+            builder = builder.no_proxy();
+        }
+        for (scheme, url) in &self.proxies {
+            let proxy = match scheme {
+                ProxyScheme::Http => reqwest::Proxy::http(url),
+                ProxyScheme::Https => reqwest::Proxy::https(url),
+                ProxyScheme::All => reqwest::Proxy::all(url),
+            }
+            .map_err(|e| Error::Configuration(format!("Invalid proxy URL {}: {}", url, e)))?;
+            let proxy = match &self.proxy_auth {
+                Some((username, password)) => proxy.basic_auth(username, password),
+                None => proxy,
+            };
+            builder = builder.proxy(proxy);
+        }
+
+        if !self.headers.is_empty() {
+            let mut headers = HeaderMap::new();
+            for (name, value) in &self.headers {
+                let name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| {
+                    Error::Configuration(format!("Invalid header name {}: {}", name, e))
+                })?;
+                let value = HeaderValue::from_str(value).map_err(|e| {
+                    Error::Configuration(format!("Invalid value for header {}: {}", name, e))
+                })?;
+                headers.insert(name, value);
+            }
+            builder = builder.default_headers(headers);
+        }
+
+        builder = builder.tls_built_in_root_certs(self.built_in_root_certificates);
+        for pem in &self.root_certificates {
+            let certificate = reqwest::Certificate::from_pem(pem)
+                .map_err(|e| Error::Configuration(format!("Invalid root certificate: {}", e)))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        if let Some(identity) = &self.identity {
+            let identity = match identity {
+                ClientIdentity::Pem { certificate, key } => {
+                    reqwest::Identity::from_pkcs8_pem(certificate, key)
+                }
+                ClientIdentity::Pkcs12 { der, password } => {
+                    reqwest::Identity::from_pkcs12_der(der, password)
+                }
+            }
+            .map_err(|e| Error::Configuration(format!("Invalid client identity: {}", e)))?;
+            builder = builder.identity(identity);
+        }
+
+        if let Some(max) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max);
+        }
+        if let Some(timeout) = self.pool_idle_timeout {
+            builder = builder.pool_idle_timeout(timeout);
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+
+        let client = builder.build().map_err(error::network_error)?;
+        Ok(ReqwestClient { client })
+    }
+}
+
+impl fmt::Debug for ReqwestClientBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReqwestClientBuilder")
+            .field("timeout", &self.timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("read_timeout", &self.read_timeout)
+            .field("proxies", &self.proxies)
+            .field("no_system_proxy", &self.no_system_proxy)
+            .field(
+                "headers",
+                &self
+                    .headers
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            )
+            .field("root_certificates", &self.root_certificates.len())
+            .field("identity", &self.identity.is_some())
+            .field("pool_max_idle_per_host", &self.pool_max_idle_per_host)
+            .field("pool_idle_timeout", &self.pool_idle_timeout)
+            .finish_non_exhaustive()
     }
 }
 
@@ -56,10 +396,7 @@ impl ReqwestClient {
 impl HttpClient for ReqwestClient {
     async fn post(&self, url: &str, headers: HeaderMap, body: Value) -> Result<Value, Error> {
         let response = self
-            .client
-            .post(url)
-            .headers(headers)
-            .json(&body)
+            .post_request(url, headers, &body)
             .send()
             .await
             .map_err(error::network_error)?;
@@ -83,10 +420,7 @@ impl HttpClient for ReqwestClient {
         body: Value,
     ) -> Result<reqwest::Response, Error> {
         let response = self
-            .client
-            .post(url)
-            .headers(headers)
-            .json(&body)
+            .post_request(url, headers, &body)
             .send()
             .await
             .map_err(error::network_error)?;
@@ -111,16 +445,9 @@ impl HttpClient for ReqwestClient {
     ) -> Result<EventSource, Error> {
         use reqwest_eventsource::RequestBuilderExt;
 
-        let mut request = self.client.post(url);
-
-        // Add headers
-        for (key, value) in headers.iter() {
-            request = request.header(key, value);
-        }
-
         // Create EventSource
-        let event_source = request
-            .json(&body)
+        let event_source = self
+            .post_request(url, headers, &body)
             .eventsource()
             .map_err(|e| Error::Network {
                 message: format!("Failed to create event source: {}", e),
@@ -149,3 +476,54 @@ pub fn create_headers(api_key: &str, additional: Option<HeaderMap>) -> Result<He
 
     Ok(headers)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_defaults() {
+        let builder = ReqwestClientBuilder::new();
+        assert_eq!(builder.timeout, Some(DEFAULT_TIMEOUT));
+        assert!(builder.build().is_ok());
+    }
+
+    #[test]
+    fn test_builder_with_transport_settings() {
+        let client = ReqwestClientBuilder::new()
+            .connect_timeout(Duration::from_secs(5))
+            .read_timeout(Duration::from_secs(30))
+            .proxy("http://proxy.internal:3128")
+            .proxy_auth("user", "pass")
+            .header("X-Tenant-Id", "acme")
+            .pool_max_idle_per_host(4)
+            .pool_idle_timeout(Duration::from_secs(90))
+            .user_agent("cogni-test")
+            .build();
+        assert!(client.is_ok());
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_settings() {
+        let err = ReqwestClientBuilder::new()
+            .header("bad header", "x")
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::Configuration(_)));
+
+        let err = ReqwestClientBuilder::new()
+            .add_root_certificate_pem("not a certificate")
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(err, Error::Configuration(_)));
+    }
+
+    #[tokio::test]
+    async fn test_request_timeout_scope() {
+        assert_eq!(request_timeout(), None);
+        let inner = with_request_timeout(Duration::from_secs(7), async { request_timeout() }).await;
+        assert_eq!(inner, Some(Duration::from_secs(7)));
+    }
+}
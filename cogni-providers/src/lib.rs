@@ -2,21 +2,28 @@
 
 #![warn(missing_docs)]
 
+pub mod auth;
 pub mod builder;
 pub mod config_builder;
 pub mod constants;
 pub mod error;
 pub mod http;
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing;
 pub mod traits;
 pub mod utils;
 
 // Provider implementations
 pub mod anthropic;
+pub mod azure;
+pub mod compatible;
 pub mod ollama;
 pub mod openai;
 
 // Re-export provider types
 pub use anthropic::Anthropic;
+pub use azure::AzureOpenAI;
+pub use compatible::OpenAICompatible;
 pub use ollama::Ollama;
 pub use openai::OpenAI;
 
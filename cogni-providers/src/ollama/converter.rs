@@ -146,10 +146,23 @@ pub fn to_ollama_request(request: &Request) -> OllamaRequest {
                     .join("\n"),
             };
 
+            let tool_calls = (!msg.tool_calls.is_empty()).then(|| {
+                msg.tool_calls
+                    .iter()
+                    .map(|call| OllamaToolCall {
+                        function: OllamaFunctionCall {
+                            name: call.name.clone(),
+                            arguments: serde_json::from_str(&call.arguments)
+                                .unwrap_or_else(|_| Value::Object(Default::default())),
+                        },
+                    })
+                    .collect()
+            });
+
             OllamaMessage {
                 role,
                 content,
-                tool_calls: None,
+                tool_calls,
             }
         })
         .collect();
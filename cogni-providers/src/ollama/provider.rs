@@ -9,8 +9,8 @@ use cogni_core::{Error, Provider, Request, Response};
 use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
 use std::sync::Arc;
 
-use crate::builder::ProviderBuilder;
-use crate::http::{HttpClient, ReqwestClient};
+use crate::builder::{resolve_client, ProviderBuilder};
+use crate::http::{HttpClient, ReqwestClient, ReqwestClientBuilder};
 use crate::ollama::{
     config::OllamaConfig, converter::OllamaConverter, parser::OllamaParser, stream::OllamaStream,
 };
@@ -155,6 +155,7 @@ pub struct OllamaBuilder {
     base_url: String,
     model: Option<String>,
     client: Option<Arc<dyn HttpClient>>,
+    transport: Option<ReqwestClientBuilder>,
 }
 
 impl OllamaBuilder {
@@ -164,6 +165,7 @@ impl OllamaBuilder {
             base_url,
             model: None,
             client: None,
+            transport: None,
         }
     }
 
@@ -182,15 +184,18 @@ impl ProviderBuilder for OllamaBuilder {
         self
     }
 
+    fn with_transport(mut self, transport: ReqwestClientBuilder) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
     fn build(self) -> Result<Self::Provider, Error> {
         let config = OllamaConfig {
             base_url: self.base_url,
             default_model: self.model.unwrap_or_else(|| "llama2".to_string()),
         };
 
-        let client = self.client.unwrap_or_else(|| {
-            Arc::new(ReqwestClient::new().expect("Failed to create HTTP client"))
-        });
+        let client = resolve_client("Ollama", self.client, self.transport)?;
 
         Ok(Ollama::new(config, client))
     }
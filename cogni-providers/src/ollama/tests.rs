@@ -241,6 +241,41 @@ mod config_tests {
     }
 }
 
+#[cfg(test)]
+mod converter_tests {
+    use super::super::converter::to_ollama_request;
+    use cogni_core::{Message, Request, ToolCall};
+    use serde_json::json;
+
+    #[test]
+    fn test_assistant_tool_calls() {
+        let call = ToolCall {
+            id: "call_0".to_string(),
+            name: "get_weather".to_string(),
+            arguments: r#"{"city":"Paris"}"#.to_string(),
+        };
+        let request = Request::builder()
+            .message(Message::user("Weather in Paris?"))
+            .message(Message::assistant_with_tools("", [call]))
+            .message(Message::tool("21C", "call_0"))
+            .build();
+
+        let body = serde_json::to_value(to_ollama_request(&request)).unwrap();
+        assert_eq!(
+            body["messages"][1],
+            json!({
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{ "function": { "name": "get_weather", "arguments": { "city": "Paris" } } }],
+            })
+        );
+        assert_eq!(
+            body["messages"][2],
+            json!({ "role": "tool", "content": "21C" })
+        );
+    }
+}
+
 #[cfg(test)]
 mod stream_tests {
     use super::super::converter::OllamaStreamResponse;
@@ -382,6 +417,35 @@ mod integration_tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_ollama_structured_output() {
+        if !super::provider_tests::ollama_is_available().await {
+            eprintln!("Skipping test - Ollama not available");
+            return;
+        }
+
+        let provider = Ollama::local().unwrap();
+        let request = Request::builder()
+            .model(Model::new("llama3.2"))
+            .messages(vec![Message::user(
+                "What is 2+2? Answer with your confidence.",
+            )])
+            .response_format(ResponseFormat::JsonSchema {
+                schema: TestOutput::schema(),
+                strict: true,
+            })
+            .try_build()
+            .unwrap();
+
+        // Note: schema adherence depends on the model
+        if let Ok(response) = provider.request(request).await {
+            if let Ok(output) = response.parse_structured::<TestOutput>() {
+                assert!(!output.answer.is_empty());
+                assert!(output.confidence.is_finite());
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_ollama_with_system_message() {
         if !super::provider_tests::ollama_is_available().await {
@@ -116,6 +116,27 @@ impl OpenAIConverter {
             msg["tool_call_id"] = json!(tool_call_id);
         }
 
+        // Assistant turns that called tools; content may be empty
+        if !message.tool_calls.is_empty() {
+            if msg["content"] == json!("") {
+                msg["content"] = Value::Null;
+            }
+            msg["tool_calls"] = message
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    json!({
+                        "id": call.id,
+                        "type": "function",
+                        "function": {
+                            "name": call.name,
+                            "arguments": call.arguments,
+                        },
+                    })
+                })
+                .collect();
+        }
+
         Ok(msg)
     }
 
@@ -171,3 +192,41 @@ impl OpenAIConverter {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cogni_core::ToolCall;
+
+    #[tokio::test]
+    async fn test_assistant_tool_calls() {
+        let call = ToolCall {
+            id: "call_1".to_string(),
+            name: "get_weather".to_string(),
+            arguments: r#"{"city":"Paris"}"#.to_string(),
+        };
+        let request = Request::builder()
+            .message(Message::user("Weather in Paris?"))
+            .message(Message::assistant_with_tools("", [call]))
+            .message(Message::tool(r#"{"temp":21}"#, "call_1"))
+            .build();
+
+        let body = OpenAIConverter.convert_request(request).await.unwrap();
+        assert_eq!(
+            body["messages"][1],
+            json!({
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": { "name": "get_weather", "arguments": "{\"city\":\"Paris\"}" },
+                }],
+            })
+        );
+        assert_eq!(
+            body["messages"][2],
+            json!({ "role": "tool", "content": "{\"temp\":21}", "tool_call_id": "call_1" })
+        );
+    }
+}
@@ -7,4 +7,7 @@ mod provider;
 mod stream;
 
 pub use config::OpenAIConfig;
+pub use converter::OpenAIConverter;
+pub use parser::OpenAIParser;
 pub use provider::OpenAI;
+pub use stream::OpenAIStream;
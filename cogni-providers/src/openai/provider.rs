@@ -11,6 +11,7 @@ use crate::openai::{
 use crate::traits::{RequestConverter, ResponseParser};
 use async_trait::async_trait;
 use cogni_core::{Error, Provider, Request, Response};
+use reqwest::header::{HeaderMap, HeaderValue};
 use std::sync::Arc;
 
 /// OpenAI provider for chat completions
@@ -20,7 +21,10 @@ use std::sync::Arc;
 /// - Function/tool calling
 /// - Structured output with JSON mode
 /// - Streaming responses
-/// - Custom Azure OpenAI deployments
+/// - Custom OpenAI-compatible base URLs
+///
+/// For Azure OpenAI deployments use [`AzureOpenAI`](crate::AzureOpenAI), which
+/// handles deployment routing and Azure authentication.
 ///
 /// # Example
 ///
@@ -69,6 +73,24 @@ impl OpenAI {
         })?);
         Ok(Self::new(OpenAIConfig::new(api_key), client))
     }
+
+    /// Create headers, including the organization ID when configured
+    fn create_headers(&self) -> Result<HeaderMap, Error> {
+        let additional = match &self.config.organization_id {
+            Some(org) => {
+                let mut headers = HeaderMap::new();
+                headers.insert(
+                    "OpenAI-Organization",
+                    HeaderValue::from_str(org).map_err(|e| {
+                        Error::Configuration(format!("Invalid organization ID: {}", e))
+                    })?,
+                );
+                Some(headers)
+            }
+            None => None,
+        };
+        create_headers(&self.config.api_key, additional)
+    }
 }
 
 #[async_trait]
@@ -79,7 +101,7 @@ impl Provider for OpenAI {
         let mut body = self.converter.convert_request(request).await?;
         body["stream"] = serde_json::json!(false);
 
-        let headers = create_headers(&self.config.api_key, None)?;
+        let headers = self.create_headers()?;
         let response = self
             .client
             .post(&self.config.chat_url(), headers, body)
@@ -92,7 +114,7 @@ impl Provider for OpenAI {
         let mut body = self.converter.convert_request(request).await?;
         body["stream"] = serde_json::json!(true);
 
-        let headers = create_headers(&self.config.api_key, None)?;
+        let headers = self.create_headers()?;
         let url = self.config.chat_url();
 
         let event_source = self.client.post_event_stream(&url, headers, body).await?;
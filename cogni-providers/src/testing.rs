@@ -0,0 +1,107 @@
+//! Test doubles for code built on [`HttpClient`]
+//!
+//! Enabled by the `test-util` feature.
+
+use crate::http::HttpClient;
+use cogni_core::Error;
+use reqwest::header::HeaderMap;
+use reqwest_eventsource::EventSource;
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+/// A POST request received by [`MockHttpClient`]
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    /// Request URL
+    pub url: String,
+    /// Request headers
+    pub headers: HeaderMap,
+    /// JSON request body
+    pub body: Value,
+}
+
+/// An HTTP client that replays scripted replies and records every request
+///
+/// Replies to [`HttpClient::post`] are consumed in order. Once they run out,
+/// each request gets a chat completion saying "ok" for the model named in
+/// its body. Streaming requests are recorded and then fail.
+#[derive(Debug, Default)]
+pub struct MockHttpClient {
+    replies: Mutex<VecDeque<Result<Value, Error>>>,
+    requests: Mutex<Vec<RecordedRequest>>,
+}
+
+impl MockHttpClient {
+    /// Create a client with no scripted replies
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a JSON reply
+    pub fn with_reply(self, reply: Value) -> Self {
+        self.replies.lock().unwrap().push_back(Ok(reply));
+        self
+    }
+
+    /// Queue a failure
+    pub fn with_error(self, error: Error) -> Self {
+        self.replies.lock().unwrap().push_back(Err(error));
+        self
+    }
+
+    /// Requests received so far, oldest first
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn record(&self, url: &str, headers: HeaderMap, body: Value) {
+        self.requests.lock().unwrap().push(RecordedRequest {
+            url: url.to_string(),
+            headers,
+            body,
+        });
+    }
+}
+
+fn unsupported(method: &str) -> Error {
+    Error::Network {
+        message: format!("MockHttpClient does not support {}", method),
+        source: None,
+    }
+}
+
+#[async_trait::async_trait]
+impl HttpClient for MockHttpClient {
+    async fn post(&self, url: &str, headers: HeaderMap, body: Value) -> Result<Value, Error> {
+        let model = body["model"].clone();
+        self.record(url, headers, body);
+        self.replies.lock().unwrap().pop_front().unwrap_or_else(|| {
+            Ok(json!({
+                "id": "1",
+                "model": model,
+                "choices": [{ "message": { "content": "ok" }, "finish_reason": "stop" }]
+            }))
+        })
+    }
+
+    async fn post_raw(
+        &self,
+        url: &str,
+        headers: HeaderMap,
+        body: Value,
+    ) -> Result<reqwest::Response, Error> {
+        self.record(url, headers, body);
+        Err(unsupported("post_raw"))
+    }
+
+    async fn post_event_stream(
+        &self,
+        url: &str,
+        headers: HeaderMap,
+        body: Value,
+    ) -> Result<EventSource, Error> {
+        self.record(url, headers, body);
+        Err(unsupported("post_event_stream"))
+    }
+}
@@ -0,0 +1,293 @@
+//! Conversation branching: forks, alternatives and edit history
+
+use crate::{ConversationState, StateError, StateResult};
+use chrono::{DateTime, Utc};
+use cogni_core::{Message, Role};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// A message in the conversation tree
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MessageNode {
+    /// Unique identifier for this node
+    pub id: Uuid,
+    /// The node this message follows, or `None` for a root
+    pub parent: Option<Uuid>,
+    /// The message itself
+    pub message: Message,
+    /// When this node was added
+    pub created_at: DateTime<Utc>,
+}
+
+impl MessageNode {
+    pub(crate) fn new(parent: Option<Uuid>, message: Message, created_at: DateTime<Utc>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            parent,
+            message,
+            created_at,
+        }
+    }
+}
+
+impl ConversationState {
+    /// Every message node on every branch, parents before their children
+    pub fn nodes(&self) -> &[MessageNode] {
+        &self.nodes
+    }
+
+    /// Look up a message node by ID
+    pub fn node(&self, id: Uuid) -> Option<&MessageNode> {
+        self.nodes.iter().find(|n| n.id == id)
+    }
+
+    /// ID of the last message on the current branch
+    pub fn head(&self) -> Option<Uuid> {
+        self.head
+    }
+
+    /// Nodes on the current branch, oldest first
+    pub fn branch(&self) -> Vec<&MessageNode> {
+        self.branch.iter().map(|&i| &self.nodes[i]).collect()
+    }
+
+    /// Nodes that directly follow `parent`, oldest first
+    ///
+    /// `None` lists the roots of the conversation.
+    pub fn children(&self, parent: Option<Uuid>) -> Vec<&MessageNode> {
+        self.nodes.iter().filter(|n| n.parent == parent).collect()
+    }
+
+    /// Alternatives for the message at `index` on the current branch
+    ///
+    /// The result includes the message itself, oldest first.
+    pub fn siblings(&self, index: usize) -> StateResult<Vec<&MessageNode>> {
+        let node = self.branch_node(index)?;
+        Ok(self.children(node.parent))
+    }
+
+    /// Fork the conversation before the message at `index`
+    ///
+    /// The current branch is cut back to its first `index` messages; the
+    /// messages after that stay in the tree as another branch. The next
+    /// message added becomes an alternative to the one at `index`.
+    pub fn fork_at(&mut self, index: usize) -> StateResult<()> {
+        if index > self.branch.len() {
+            return Err(StateError::invalid_state(format!(
+                "cannot fork at message {} of {}",
+                index,
+                self.branch.len()
+            )));
+        }
+        self.head = index.checked_sub(1).map(|i| self.nodes[self.branch[i]].id);
+        self.rebuild_branch();
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Replace the message at `index` with an edited version
+    ///
+    /// The original message and everything after it are kept as another
+    /// branch. Returns the ID of the new message, which becomes the head.
+    pub fn edit_message(&mut self, index: usize, message: Message) -> StateResult<Uuid> {
+        self.branch_node(index)?;
+        self.fork_at(index)?;
+        Ok(self.add_message(message))
+    }
+
+    /// Switch to the branch containing the node `id`
+    ///
+    /// The current branch continues past `id` along the most recently added
+    /// child at each step.
+    pub fn switch_branch(&mut self, id: Uuid) -> StateResult<()> {
+        if self.node(id).is_none() {
+            return Err(StateError::invalid_state(format!(
+                "message {} is not in the conversation",
+                id
+            )));
+        }
+
+        let mut head = id;
+        while let Some(child) = self.nodes.iter().rev().find(|n| n.parent == Some(head)) {
+            head = child.id;
+        }
+        self.head = Some(head);
+        self.rebuild_branch();
+        self.updated_at = Utc::now();
+        Ok(())
+    }
+
+    /// Rewind the current branch to the last user message
+    ///
+    /// The assistant's reply and any tool messages after it stay in the tree,
+    /// so a regenerated reply becomes an alternative to the previous one.
+    pub fn rewind_last_turn(&mut self) -> StateResult<()> {
+        let index = self
+            .messages
+            .iter()
+            .rposition(|m| m.role == Role::User)
+            .ok_or_else(|| StateError::invalid_state("no user message to regenerate from"))?;
+        self.fork_at(index + 1)
+    }
+
+    fn branch_node(&self, index: usize) -> StateResult<&MessageNode> {
+        self.branch
+            .get(index)
+            .map(|&i| &self.nodes[i])
+            .ok_or_else(|| {
+                StateError::invalid_state(format!(
+                    "no message {} on a branch of {}",
+                    index,
+                    self.branch.len()
+                ))
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> ConversationState {
+        let mut state = ConversationState::new();
+        state.add_messages([
+            Message::system("Be brief"),
+            Message::user("Hi"),
+            Message::assistant("Hello!"),
+            Message::user("Tell me a joke"),
+            Message::assistant("No."),
+        ]);
+        state
+    }
+
+    fn texts(state: &ConversationState) -> Vec<&str> {
+        state
+            .messages()
+            .iter()
+            .map(|m| m.content.as_text().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_edit_message_keeps_original_branch() {
+        let mut state = conversation();
+        let original = state.branch()[3].id;
+
+        state
+            .edit_message(3, Message::user("Tell me a story"))
+            .unwrap();
+        assert_eq!(
+            texts(&state),
+            ["Be brief", "Hi", "Hello!", "Tell me a story"]
+        );
+
+        let siblings = state.siblings(3).unwrap();
+        assert_eq!(siblings.len(), 2);
+        assert_eq!(siblings[0].id, original);
+
+        state.switch_branch(original).unwrap();
+        assert_eq!(
+            texts(&state),
+            ["Be brief", "Hi", "Hello!", "Tell me a joke", "No."]
+        );
+        assert_eq!(state.nodes().len(), 6);
+    }
+
+    #[test]
+    fn test_rewind_last_turn() {
+        let mut state = conversation();
+        state.rewind_last_turn().unwrap();
+        assert_eq!(state.messages().len(), 4);
+
+        state.add_message(Message::assistant("Why did the chicken..."));
+        let alternatives: Vec<_> = state
+            .siblings(4)
+            .unwrap()
+            .iter()
+            .map(|n| n.message.content.as_text().unwrap())
+            .collect();
+        assert_eq!(alternatives, ["No.", "Why did the chicken..."]);
+
+        assert!(ConversationState::new().rewind_last_turn().is_err());
+    }
+
+    #[test]
+    fn test_fork_at_bounds() {
+        let mut state = conversation();
+        assert!(state.fork_at(6).is_err());
+        assert!(state.siblings(5).is_err());
+
+        state.fork_at(0).unwrap();
+        assert!(state.messages().is_empty());
+        state.add_message(Message::user("Start over"));
+        assert_eq!(state.children(None).len(), 2);
+    }
+
+    #[test]
+    fn test_pop_message() {
+        let mut state = conversation();
+        state.fork_at(4).unwrap();
+        state.add_message(Message::assistant("Maybe later."));
+
+        // The popped reply had no children, so it is removed from the tree
+        assert_eq!(
+            state.pop_message().unwrap().content.as_text(),
+            Some("Maybe later.")
+        );
+        assert_eq!(state.nodes().len(), 5);
+
+        // The user message still leads to "No.", so it is only stepped past
+        state.pop_message();
+        assert_eq!(state.messages().len(), 3);
+        assert_eq!(state.nodes().len(), 5);
+    }
+
+    #[test]
+    fn test_set_system_message() {
+        let mut state = ConversationState::new();
+        state.add_message(Message::user("hi"));
+        state.set_system_message(Some("one"));
+        state.set_system_message(Some("two"));
+        assert_eq!(texts(&state), ["two", "hi"]);
+
+        state.set_system_message(None);
+        assert_eq!(state.messages(), [Message::user("hi")]);
+    }
+
+    #[test]
+    fn test_serialization_round_trip() {
+        let mut state = conversation();
+        state.edit_message(1, Message::user("Hey there")).unwrap();
+
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: ConversationState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.nodes(), state.nodes());
+        assert_eq!(restored.messages(), state.messages());
+    }
+
+    #[test]
+    fn test_legacy_state_migrates() {
+        let id = Uuid::new_v4();
+        let legacy = serde_json::json!({
+            "id": id,
+            "messages": [Message::user("Hi"), Message::assistant("Hello!")],
+            "metadata": { "title": null, "tags": [], "agent_config": null, "token_count": 7, "custom": {} },
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z",
+        });
+
+        let state: ConversationState = serde_json::from_value(legacy).unwrap();
+        assert_eq!(state.id, id);
+        assert_eq!(texts(&state), ["Hi", "Hello!"]);
+        assert_eq!(state.nodes()[1].parent, Some(state.nodes()[0].id));
+        assert_eq!(state.head(), Some(state.nodes()[1].id));
+        assert_eq!(state.metadata.token_count, Some(7));
+    }
+
+    #[test]
+    fn test_invalid_tree_is_rejected() {
+        let mut value = serde_json::to_value(conversation()).unwrap();
+        value["head"] = serde_json::json!(Uuid::new_v4());
+        assert!(serde_json::from_value::<ConversationState>(value).is_err());
+    }
+}
@@ -0,0 +1,466 @@
+//! Encryption at rest for conversation state
+//!
+//! [`EncryptedStore`] seals each conversation with AES-256-GCM before handing
+//! it to a [`BlobStore`]. Every blob starts with a small header naming the key
+//! it was sealed with, so keys can be rotated without rewriting old data:
+//!
+//! ```text
+//! "CGNE" | version (1) | key ID length (1) | key ID | nonce (12) | ciphertext
+//! ```
+//!
+//! The header and the conversation ID are authenticated along with the
+//! ciphertext, so a blob can't be relabelled or moved to another ID.
+
+use crate::store::next_version;
+use crate::{BlobStore, ConversationState, StateError, StateResult, StateStore};
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::fmt;
+use tokio::sync::Mutex;
+use tracing::{debug, trace, warn};
+use uuid::Uuid;
+
+const MAGIC: &[u8; 4] = b"CGNE";
+const FORMAT_VERSION: u8 = 1;
+const NONCE_LEN: usize = 12;
+
+/// A 256-bit AES-GCM key
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    /// Create a key from raw bytes
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Generate a random key
+    pub fn generate() -> Self {
+        Self(Aes256Gcm::generate_key(&mut OsRng).into())
+    }
+
+    /// Raw key bytes
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+}
+
+impl fmt::Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// Source of encryption keys
+///
+/// New data is always sealed with the current key. Older keys only need to
+/// stay available for as long as data sealed with them is still stored.
+#[async_trait]
+pub trait KeyProvider: Send + Sync {
+    /// ID of the key used to encrypt new data
+    ///
+    /// IDs are recorded in each blob's header and must be 1 to 255 bytes.
+    fn current_key_id(&self) -> &str;
+
+    /// Look up a key by ID
+    async fn key(&self, key_id: &str) -> StateResult<EncryptionKey>;
+}
+
+/// A fixed set of keys held in memory
+#[derive(Debug, Clone)]
+pub struct KeyRing {
+    current: String,
+    keys: HashMap<String, EncryptionKey>,
+}
+
+impl KeyRing {
+    /// Create a key ring that encrypts with `key`
+    pub fn new(key_id: impl Into<String>, key: EncryptionKey) -> Self {
+        let current = key_id.into();
+        Self {
+            keys: HashMap::from([(current.clone(), key)]),
+            current,
+        }
+    }
+
+    /// Add an older key that is still needed for decryption
+    pub fn with_key(mut self, key_id: impl Into<String>, key: EncryptionKey) -> Self {
+        self.keys.entry(key_id.into()).or_insert(key);
+        self
+    }
+}
+
+#[async_trait]
+impl KeyProvider for KeyRing {
+    fn current_key_id(&self) -> &str {
+        &self.current
+    }
+
+    async fn key(&self, key_id: &str) -> StateResult<EncryptionKey> {
+        self.keys
+            .get(key_id)
+            .cloned()
+            .ok_or_else(|| StateError::encryption(format!("unknown key ID '{}'", key_id)))
+    }
+}
+
+/// State store that encrypts conversations before storing them
+///
+/// Conversations are serialized to JSON, sealed with the key provider's
+/// current key and written to the underlying [`BlobStore`]. Loading reads the
+/// key ID from the blob header and decrypts with that key.
+///
+/// ```no_run
+/// # async fn example() -> cogni_state::StateResult<()> {
+/// use cogni_state::{EncryptedStore, EncryptionKey, FileBlobStore, KeyRing};
+///
+/// let keys = KeyRing::new("2025-01", EncryptionKey::generate());
+/// let store = EncryptedStore::new(FileBlobStore::new("./conversations")?, keys);
+/// # Ok(())
+/// # }
+/// ```
+///
+/// Metadata is encrypted too, so listing and querying decrypt every
+/// conversation. Writes are serialized within this store value only; a blob
+/// store shared between processes needs its own coordination for
+/// [`save_if_version`](StateStore::save_if_version) to be reliable.
+pub struct EncryptedStore<S, K = KeyRing> {
+    inner: S,
+    keys: K,
+    write_lock: Mutex<()>,
+}
+
+impl<S: BlobStore, K: KeyProvider> EncryptedStore<S, K> {
+    /// Wrap a blob store, encrypting with keys from `keys`
+    pub fn new(inner: S, keys: K) -> Self {
+        Self {
+            inner,
+            keys,
+            write_lock: Mutex::new(()),
+        }
+    }
+
+    /// The underlying blob store
+    pub fn inner(&self) -> &S {
+        &self.inner
+    }
+
+    /// The key provider
+    pub fn keys(&self) -> &K {
+        &self.keys
+    }
+
+    /// Re-encrypt every conversation not sealed with the current key
+    ///
+    /// Returns the number of conversations rewritten. Once this succeeds,
+    /// older keys can be retired.
+    pub async fn rotate(&self) -> StateResult<usize> {
+        let current = self.keys.current_key_id();
+        let _lock = self.write_lock.lock().await;
+        let mut rewritten = 0;
+        for id in self.inner.ids().await? {
+            let blob = match self.inner.get(&id).await {
+                Ok(blob) => blob,
+                Err(StateError::NotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            if Header::parse(&blob)?.key_id == current {
+                continue;
+            }
+            let plaintext = self.open(&id, &blob).await?;
+            self.inner
+                .put(&id, self.seal(&id, &plaintext).await?)
+                .await?;
+            rewritten += 1;
+        }
+        debug!(
+            "Re-encrypted {} conversations with key '{}'",
+            rewritten, current
+        );
+        Ok(rewritten)
+    }
+
+    /// Encrypt and store a conversation under the lock, returning its new version
+    async fn write(&self, state: &ConversationState, expected: Option<u64>) -> StateResult<u64> {
+        let _lock = self.write_lock.lock().await;
+        let current = match self.stored_version(&state.id).await {
+            Ok(version) => version,
+            Err(StateError::NotFound(_)) => 0,
+            Err(e) => return Err(e),
+        };
+        let version = next_version(state.id, current, expected)?;
+
+        let mut state = state.clone();
+        state.version = version;
+        let blob = self.seal(&state.id, &serde_json::to_vec(&state)?).await?;
+        self.inner.put(&state.id, blob).await?;
+        Ok(version)
+    }
+
+    /// Version of the stored copy of a conversation
+    async fn stored_version(&self, id: &Uuid) -> StateResult<u64> {
+        #[derive(serde::Deserialize)]
+        struct Versioned {
+            #[serde(default)]
+            version: u64,
+        }
+
+        let blob = self.inner.get(id).await?;
+        Ok(serde_json::from_slice::<Versioned>(&self.open(id, &blob).await?)?.version)
+    }
+
+    async fn seal(&self, id: &Uuid, plaintext: &[u8]) -> StateResult<Vec<u8>> {
+        let key_id = self.keys.current_key_id();
+        let key_len = u8::try_from(key_id.len())
+            .ok()
+            .filter(|&len| len > 0)
+            .ok_or_else(|| {
+                StateError::encryption(format!("key ID must be 1 to 255 bytes: '{}'", key_id))
+            })?;
+        let cipher = Aes256Gcm::new(self.keys.key(key_id).await?.as_bytes().into());
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+        let mut blob = Vec::with_capacity(6 + key_id.len() + NONCE_LEN + plaintext.len() + 16);
+        blob.extend_from_slice(MAGIC);
+        blob.push(FORMAT_VERSION);
+        blob.push(key_len);
+        blob.extend_from_slice(key_id.as_bytes());
+
+        let aad = associated_data(&blob, id);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| StateError::encryption("encryption failed"))?;
+        blob.extend_from_slice(&nonce);
+        blob.extend_from_slice(&ciphertext);
+        Ok(blob)
+    }
+
+    async fn open(&self, id: &Uuid, blob: &[u8]) -> StateResult<Vec<u8>> {
+        let header = Header::parse(blob)?;
+        let cipher = Aes256Gcm::new(self.keys.key(header.key_id).await?.as_bytes().into());
+        let (nonce, ciphertext) = blob[header.len..].split_at(NONCE_LEN);
+
+        let aad = associated_data(&blob[..header.len], id);
+        cipher
+            .decrypt(
+                Nonce::from_slice(nonce),
+                Payload {
+                    msg: ciphertext,
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| {
+                StateError::encryption(format!(
+                    "failed to decrypt conversation {} with key '{}'",
+                    id, header.key_id
+                ))
+            })
+    }
+}
+
+/// Header fields and the offset of the nonce that follows them
+struct Header<'a> {
+    key_id: &'a str,
+    len: usize,
+}
+
+impl<'a> Header<'a> {
+    fn parse(blob: &'a [u8]) -> StateResult<Self> {
+        if blob.len() < 6 || &blob[..4] != MAGIC {
+            return Err(StateError::encryption("missing encryption header"));
+        }
+        if blob[4] != FORMAT_VERSION {
+            return Err(StateError::encryption(format!(
+                "unsupported format version {}",
+                blob[4]
+            )));
+        }
+        let len = 6 + blob[5] as usize;
+        if blob.len() < len + NONCE_LEN {
+            return Err(StateError::encryption("truncated encryption header"));
+        }
+        let key_id = std::str::from_utf8(&blob[6..len])
+            .map_err(|_| StateError::encryption("key ID is not valid UTF-8"))?;
+        Ok(Self { key_id, len })
+    }
+}
+
+fn associated_data(header: &[u8], id: &Uuid) -> Vec<u8> {
+    [header, id.as_bytes()].concat()
+}
+
+#[async_trait]
+impl<S: BlobStore, K: KeyProvider> StateStore for EncryptedStore<S, K> {
+    async fn save(&self, state: &ConversationState) -> StateResult<()> {
+        trace!("Saving encrypted conversation {}", state.id);
+        self.write(state, None).await.map(|_| ())
+    }
+
+    async fn save_if_version(&self, state: &ConversationState, expected: u64) -> StateResult<u64> {
+        trace!(
+            "Saving encrypted conversation {} if at version {}",
+            state.id,
+            expected
+        );
+        self.write(state, Some(expected)).await
+    }
+
+    async fn load(&self, id: &Uuid) -> StateResult<ConversationState> {
+        trace!("Loading encrypted conversation {}", id);
+        let blob = self.inner.get(id).await?;
+        let state: ConversationState = serde_json::from_slice(&self.open(id, &blob).await?)?;
+        if state.id != *id {
+            return Err(StateError::InvalidState(
+                "ID mismatch: blob contains different conversation".to_string(),
+            ));
+        }
+        Ok(state)
+    }
+
+    async fn delete(&self, id: &Uuid) -> StateResult<()> {
+        let _lock = self.write_lock.lock().await;
+        self.inner.remove(id).await
+    }
+
+    async fn delete_if_version(&self, id: &Uuid, expected: u64) -> StateResult<()> {
+        let _lock = self.write_lock.lock().await;
+        next_version(*id, self.stored_version(id).await?, Some(expected))?;
+        self.inner.remove(id).await
+    }
+
+    async fn list(&self) -> StateResult<Vec<ConversationState>> {
+        let mut states = Vec::new();
+        for id in self.inner.ids().await? {
+            match self.load(&id).await {
+                Ok(state) => states.push(state),
+                Err(StateError::NotFound(_)) => {}
+                Err(e) => warn!("Failed to load encrypted conversation {}: {}", id, e),
+            }
+        }
+        states.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        Ok(states)
+    }
+
+    async fn size_of(&self, id: &Uuid) -> StateResult<u64> {
+        self.inner.len(id).await
+    }
+
+    async fn exists(&self, id: &Uuid) -> StateResult<bool> {
+        self.inner.contains(id).await
+    }
+
+    async fn list_ids(&self) -> StateResult<Vec<Uuid>> {
+        self.inner.ids().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryBlobStore;
+    use cogni_core::Message;
+
+    fn conversation() -> ConversationState {
+        let mut state = ConversationState::new();
+        state.set_title("Account recovery");
+        state.add_message(Message::user("My card number is 4111 1111 1111 1111"));
+        state
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_hides_plaintext() {
+        let store = EncryptedStore::new(
+            MemoryBlobStore::new(),
+            KeyRing::new("k1", EncryptionKey::generate()),
+        );
+        let state = conversation();
+        store.save(&state).await.unwrap();
+
+        let blob = store.inner().get(&state.id).await.unwrap();
+        assert!(blob.starts_with(b"CGNE\x01\x02k1"));
+        let haystack = String::from_utf8_lossy(&blob);
+        assert!(!haystack.contains("4111"));
+        assert!(!haystack.contains("Account recovery"));
+
+        let loaded = store.load(&state.id).await.unwrap();
+        assert_eq!(loaded.messages(), state.messages());
+        assert_eq!(store.list().await.unwrap().len(), 1);
+
+        store.delete(&state.id).await.unwrap();
+        assert!(matches!(
+            store.load(&state.id).await,
+            Err(StateError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_key_rotation() {
+        let old = EncryptionKey::generate();
+        let blobs = MemoryBlobStore::new();
+        let state = conversation();
+        EncryptedStore::new(blobs.clone(), KeyRing::new("old", old.clone()))
+            .save(&state)
+            .await
+            .unwrap();
+
+        // Data sealed with a retired key still loads
+        let rotated = EncryptedStore::new(
+            blobs.clone(),
+            KeyRing::new("new", EncryptionKey::generate()).with_key("old", old),
+        );
+        assert_eq!(rotated.load(&state.id).await.unwrap().id, state.id);
+
+        assert_eq!(rotated.rotate().await.unwrap(), 1);
+        assert_eq!(rotated.rotate().await.unwrap(), 0);
+        let blob = blobs.get(&state.id).await.unwrap();
+        assert_eq!(Header::parse(&blob).unwrap().key_id, "new");
+
+        // Without the key the conversation can't be read
+        let missing = EncryptedStore::new(blobs, KeyRing::new("other", EncryptionKey::generate()));
+        assert!(matches!(
+            missing.load(&state.id).await,
+            Err(StateError::Encryption(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_tampering_is_detected() {
+        let store = EncryptedStore::new(
+            MemoryBlobStore::new(),
+            KeyRing::new("k1", EncryptionKey::generate()),
+        );
+        let state = conversation();
+        store.save(&state).await.unwrap();
+        let mut blob = store.inner().get(&state.id).await.unwrap();
+
+        // A blob copied to another ID fails authentication
+        let other = Uuid::new_v4();
+        store.inner().put(&other, blob.clone()).await.unwrap();
+        assert!(matches!(
+            store.load(&other).await,
+            Err(StateError::Encryption(_))
+        ));
+
+        let last = blob.len() - 1;
+        blob[last] ^= 1;
+        store.inner().put(&state.id, blob).await.unwrap();
+        assert!(matches!(
+            store.load(&state.id).await,
+            Err(StateError::Encryption(_))
+        ));
+
+        store.inner().put(&state.id, b"{}".to_vec()).await.unwrap();
+        assert!(matches!(
+            store.load(&state.id).await,
+            Err(StateError::Encryption(_))
+        ));
+    }
+}
@@ -38,6 +38,29 @@ pub enum StateError {
     /// Configuration error
     #[error("Configuration error: {0}")]
     Configuration(String),
+
+    /// Invalid query, such as a malformed cursor
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+
+    /// Input that isn't valid in the expected interchange format
+    #[error("Invalid format: {0}")]
+    InvalidFormat(String),
+
+    /// A conditional save found a different version than expected
+    #[error("Version conflict for {id}: expected version {expected}, found {actual}")]
+    Conflict {
+        /// Conversation that was being saved
+        id: Uuid,
+        /// Version the caller expected to replace
+        expected: u64,
+        /// Version currently stored, zero if none
+        actual: u64,
+    },
+
+    /// Encryption or decryption failure, such as a missing key
+    #[error("Encryption error: {0}")]
+    Encryption(String),
 }
 
 impl StateError {
@@ -60,6 +83,21 @@ impl StateError {
     pub fn configuration(msg: impl Into<String>) -> Self {
         Self::Configuration(msg.into())
     }
+
+    /// Create an invalid query error
+    pub fn invalid_query(msg: impl Into<String>) -> Self {
+        Self::InvalidQuery(msg.into())
+    }
+
+    /// Create an invalid format error
+    pub fn invalid_format(msg: impl Into<String>) -> Self {
+        Self::InvalidFormat(msg.into())
+    }
+
+    /// Create an encryption error
+    pub fn encryption(msg: impl Into<String>) -> Self {
+        Self::Encryption(msg.into())
+    }
 }
 
 #[cfg(test)]
@@ -83,6 +121,25 @@ mod tests {
 
         let err = StateError::Configuration("Missing API key".to_string());
         assert_eq!(err.to_string(), "Configuration error: Missing API key");
+
+        let err = StateError::InvalidQuery("bad cursor".to_string());
+        assert_eq!(err.to_string(), "Invalid query: bad cursor");
+
+        let err = StateError::invalid_format("missing 'messages' array");
+        assert_eq!(err.to_string(), "Invalid format: missing 'messages' array");
+
+        let err = StateError::Conflict {
+            id,
+            expected: 3,
+            actual: 4,
+        };
+        assert_eq!(
+            err.to_string(),
+            format!("Version conflict for {}: expected version 3, found 4", id)
+        );
+
+        let err = StateError::encryption("unknown key ID 'k1'");
+        assert_eq!(err.to_string(), "Encryption error: unknown key ID 'k1'");
     }
 
     #[test]
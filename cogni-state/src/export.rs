@@ -0,0 +1,784 @@
+//! Import and export of conversations in common interchange formats
+//!
+//! Conversations can be written as OpenAI chat fine-tuning JSONL, Anthropic
+//! Messages API payloads, ShareGPT datasets or Markdown transcripts, and read
+//! back from any of them. The formats themselves carry the current branch.
+//!
+//! Everything they have no place for goes in a `cogni` sidecar: an extra
+//! top-level field in the JSON formats, and an HTML comment at the top of a
+//! Markdown transcript. It holds the conversation ID, title, tags and
+//! timestamps, the whole message tree with other branches, and a full copy of
+//! every message the format can't represent exactly, such as one with
+//! metadata or images in a text-only format. Importing a sidecar restores all
+//! of it, so an export/import round trip with the sidecar is lossless in
+//! every format.
+//!
+//! ```no_run
+//! # async fn example() -> cogni_state::StateResult<()> {
+//! use cogni_state::{ExportFormat, Exporter, MemoryStore, StateQuery};
+//!
+//! let store = MemoryStore::new();
+//! let mut file = tokio::fs::File::create("train.jsonl").await?;
+//! let query = StateQuery::new().with_any_tags(["reviewed"]);
+//! Exporter::new(ExportFormat::OpenAi)
+//!     .with_sidecar(false)
+//!     .export_store(&store, &query, &mut file)
+//!     .await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::{
+    ConversationState, MessageNode, StateError, StateMetadata, StateQuery, StateResult, StateStore,
+};
+use chrono::{DateTime, Utc};
+use cogni_core::{Content, Message};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+mod anthropic;
+mod markdown;
+mod openai;
+mod sharegpt;
+
+/// Name of the field holding cogni-specific data in exported records
+const SIDECAR_FIELD: &str = "cogni";
+
+/// Conversations loaded per page by [`Exporter::export_store`] when the
+/// query sets no limit
+const EXPORT_PAGE_SIZE: usize = 100;
+
+/// Interchange formats for conversations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ExportFormat {
+    /// OpenAI chat fine-tuning JSONL: one `{"messages": [...]}` per line
+    OpenAi,
+    /// Anthropic Messages API `{"system": ..., "messages": [...]}`, one per line
+    Anthropic,
+    /// ShareGPT `{"conversations": [{"from", "value"}]}` records in a JSON array
+    ShareGpt,
+    /// Human-readable Markdown transcripts
+    Markdown,
+}
+
+impl ExportFormat {
+    /// Conventional file extension for a bulk export
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::OpenAi | Self::Anthropic => "jsonl",
+            Self::ShareGpt => "json",
+            Self::Markdown => "md",
+        }
+    }
+}
+
+impl fmt::Display for ExportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::OpenAi => "openai",
+            Self::Anthropic => "anthropic",
+            Self::ShareGpt => "sharegpt",
+            Self::Markdown => "markdown",
+        })
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = StateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "openai" | "jsonl" => Ok(Self::OpenAi),
+            "anthropic" => Ok(Self::Anthropic),
+            "sharegpt" => Ok(Self::ShareGpt),
+            "markdown" | "md" => Ok(Self::Markdown),
+            _ => Err(StateError::invalid_format(format!(
+                "unknown format '{}', expected openai, anthropic, sharegpt or markdown",
+                s
+            ))),
+        }
+    }
+}
+
+/// Cogni-specific data carried alongside an exported conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Sidecar {
+    id: Uuid,
+    metadata: StateMetadata,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    /// Every message node in stored order, including other branches
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    nodes: Vec<SidecarNode>,
+    /// Last node of the exported branch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    head: Option<Uuid>,
+}
+
+/// A message node in a [`Sidecar`]
+///
+/// The message is left out for nodes on the exported branch whose message the
+/// format itself carries exactly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct SidecarNode {
+    id: Uuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<Uuid>,
+    created_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    message: Option<Message>,
+}
+
+impl Sidecar {
+    /// Sidecar for `state`, given the branch messages read back from an
+    /// export without one
+    fn new(state: &ConversationState, carried: &[Message]) -> Self {
+        let exact: HashSet<usize> = if carried.len() == state.branch.len() {
+            state
+                .branch
+                .iter()
+                .zip(carried)
+                .filter(|(&i, message)| state.nodes[i].message == **message)
+                .map(|(&i, _)| i)
+                .collect()
+        } else {
+            HashSet::new()
+        };
+
+        let nodes = state
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, node)| SidecarNode {
+                id: node.id,
+                parent: node.parent,
+                created_at: node.created_at,
+                message: (!exact.contains(&i)).then(|| node.message.clone()),
+            })
+            .collect();
+
+        Self {
+            id: state.id,
+            metadata: state.metadata.clone(),
+            created_at: state.created_at,
+            updated_at: state.updated_at,
+            nodes,
+            head: state.head,
+        }
+    }
+}
+
+/// Writes conversations in an [`ExportFormat`]
+#[derive(Debug, Clone)]
+pub struct Exporter {
+    format: ExportFormat,
+    sidecar: bool,
+}
+
+impl Exporter {
+    /// Create an exporter that includes the `cogni` sidecar
+    pub fn new(format: ExportFormat) -> Self {
+        Self {
+            format,
+            sidecar: true,
+        }
+    }
+
+    /// Include or leave out the `cogni` sidecar
+    ///
+    /// Leave it out when the output goes to a consumer that rejects unknown
+    /// fields, such as a fine-tuning API.
+    pub fn with_sidecar(mut self, sidecar: bool) -> Self {
+        self.sidecar = sidecar;
+        self
+    }
+
+    /// The format conversations are written in
+    pub fn format(&self) -> ExportFormat {
+        self.format
+    }
+
+    /// Export a single conversation
+    ///
+    /// JSON formats produce one compact JSON object without a trailing
+    /// newline.
+    pub fn export(&self, state: &ConversationState) -> StateResult<String> {
+        let plain = self.render(state, None)?;
+        if !self.sidecar {
+            return Ok(plain);
+        }
+
+        // Messages the format reproduces exactly needn't be repeated
+        let carried = match import_conversation(&plain, self.format) {
+            Ok(imported) => imported.messages().to_vec(),
+            Err(_) => Vec::new(),
+        };
+        let sidecar = Sidecar::new(state, &carried);
+        self.render(state, Some(&sidecar))
+    }
+
+    /// Write the current branch of a conversation, with a sidecar if given
+    fn render(&self, state: &ConversationState, sidecar: Option<&Sidecar>) -> StateResult<String> {
+        let messages = state.messages();
+        let mut record = match self.format {
+            ExportFormat::OpenAi => {
+                let mut record = Map::new();
+                record.insert("messages".into(), openai::export(messages).into());
+                record
+            }
+            ExportFormat::Anthropic => anthropic::export(messages),
+            ExportFormat::ShareGpt => {
+                let mut record = Map::new();
+                record.insert("id".into(), state.id.to_string().into());
+                record.insert("conversations".into(), sharegpt::export(messages).into());
+                record
+            }
+            ExportFormat::Markdown => {
+                return markdown::export(state.metadata.title.as_deref(), messages, sidecar);
+            }
+        };
+
+        if let Some(sidecar) = sidecar {
+            record.insert(SIDECAR_FIELD.into(), serde_json::to_value(sidecar)?);
+        }
+        Ok(serde_json::to_string(&record)?)
+    }
+
+    /// Export every conversation matching `query` to `writer`
+    ///
+    /// Conversations are loaded one page at a time and written as they're
+    /// loaded, so large stores are never held in memory at once. The query's
+    /// limit sets the page size; all pages are exported. Conversations that
+    /// disappear mid-export are skipped. Returns the number exported.
+    pub async fn export_store<S, W>(
+        &self,
+        store: &S,
+        query: &StateQuery,
+        writer: &mut W,
+    ) -> StateResult<usize>
+    where
+        S: StateStore + ?Sized,
+        W: AsyncWrite + Unpin + Send,
+    {
+        let mut query = query.clone();
+        if query.limit.is_none() {
+            query.limit = Some(EXPORT_PAGE_SIZE);
+        }
+
+        let mut count = 0;
+        if self.format == ExportFormat::ShareGpt {
+            writer.write_all(b"[").await?;
+        }
+        loop {
+            let page = store.query(&query).await?;
+            for summary in &page.conversations {
+                let state = match store.load(&summary.id).await {
+                    Ok(state) => state,
+                    Err(StateError::NotFound(_)) => {
+                        warn!("Conversation {} was deleted during export", summary.id);
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                };
+
+                let separator: &[u8] = match (self.format, count) {
+                    (ExportFormat::ShareGpt, 0) => b"\n",
+                    (ExportFormat::ShareGpt, _) => b",\n",
+                    (ExportFormat::Markdown, 0) => b"",
+                    (ExportFormat::Markdown, _) => b"\n",
+                    _ => b"",
+                };
+                writer.write_all(separator).await?;
+                writer.write_all(self.export(&state)?.as_bytes()).await?;
+                if matches!(self.format, ExportFormat::OpenAi | ExportFormat::Anthropic) {
+                    writer.write_all(b"\n").await?;
+                }
+                count += 1;
+            }
+
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        if self.format == ExportFormat::ShareGpt {
+            writer.write_all(b"\n]\n").await?;
+        }
+        writer.flush().await?;
+
+        debug!("Exported {} conversations as {}", count, self.format);
+        Ok(count)
+    }
+}
+
+/// Import a single conversation
+///
+/// If the input has a `cogni` sidecar, the conversation keeps its original
+/// ID, metadata and timestamps; otherwise it gets a new ID.
+pub fn import_conversation(input: &str, format: ExportFormat) -> StateResult<ConversationState> {
+    let mut states = import_conversations(input, format)?;
+    match states.len() {
+        1 => Ok(states.remove(0)),
+        n => Err(StateError::invalid_format(format!(
+            "expected one conversation, found {}",
+            n
+        ))),
+    }
+}
+
+/// Import every conversation in a bulk export
+///
+/// Accepts the output of [`Exporter::export_store`]: JSON Lines for the
+/// OpenAI and Anthropic formats, a JSON array (or a single object) for
+/// ShareGPT, and concatenated transcripts for Markdown.
+pub fn import_conversations(
+    input: &str,
+    format: ExportFormat,
+) -> StateResult<Vec<ConversationState>> {
+    let records: Vec<Map<String, Value>> = match format {
+        ExportFormat::Markdown => {
+            return markdown::split(input)
+                .map(|transcript| {
+                    let (sidecar, messages) = markdown::import(transcript)?;
+                    build_state(messages, sidecar)
+                })
+                .collect();
+        }
+        ExportFormat::ShareGpt if input.trim_start().starts_with('[') => {
+            serde_json::from_str(input)?
+        }
+        ExportFormat::ShareGpt => vec![serde_json::from_str(input)?],
+        ExportFormat::OpenAi | ExportFormat::Anthropic => input
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?,
+    };
+
+    records
+        .into_iter()
+        .map(|mut record| {
+            let sidecar = record
+                .remove(SIDECAR_FIELD)
+                .map(serde_json::from_value)
+                .transpose()?;
+            let messages = match format {
+                ExportFormat::OpenAi => openai::import(array_field(&record, "messages")?)?,
+                ExportFormat::Anthropic => anthropic::import(&record)?,
+                _ => sharegpt::import(array_field(&record, "conversations")?)?,
+            };
+            build_state(messages, sidecar)
+        })
+        .collect()
+}
+
+/// Assemble an imported conversation, restoring sidecar data if present
+fn build_state(messages: Vec<Message>, sidecar: Option<Sidecar>) -> StateResult<ConversationState> {
+    let Some(sidecar) = sidecar else {
+        let mut state = ConversationState::new();
+        state.add_messages(messages);
+        return Ok(state);
+    };
+
+    let mut state = ConversationState::with_id(sidecar.id);
+    if sidecar.nodes.is_empty() {
+        state.add_messages(messages);
+    } else {
+        // Nodes on the exported branch take their messages from the format,
+        // in order, unless the sidecar has a more exact copy
+        let parents: HashMap<Uuid, Option<Uuid>> =
+            sidecar.nodes.iter().map(|n| (n.id, n.parent)).collect();
+        let branch: HashSet<Uuid> =
+            std::iter::successors(sidecar.head, |id| parents.get(id).copied().flatten()).collect();
+
+        let mut messages = messages.into_iter();
+        let nodes = sidecar
+            .nodes
+            .into_iter()
+            .map(|node| {
+                let imported = if branch.contains(&node.id) {
+                    messages.next()
+                } else {
+                    None
+                };
+                let message = node.message.or(imported).ok_or_else(|| {
+                    StateError::invalid_format(format!(
+                        "no message for node {} in conversation {}",
+                        node.id, sidecar.id
+                    ))
+                })?;
+                Ok(MessageNode {
+                    id: node.id,
+                    parent: node.parent,
+                    message,
+                    created_at: node.created_at,
+                })
+            })
+            .collect::<StateResult<_>>()?;
+        state
+            .set_nodes(nodes, sidecar.head)
+            .map_err(StateError::invalid_format)?;
+    }
+    state.metadata = sidecar.metadata;
+    state.created_at = sidecar.created_at;
+    state.updated_at = sidecar.updated_at;
+    Ok(state)
+}
+
+fn array_field<'a>(record: &'a Map<String, Value>, field: &str) -> StateResult<&'a [Value]> {
+    record
+        .get(field)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .ok_or_else(|| StateError::invalid_format(format!("missing '{}' array", field)))
+}
+
+fn str_field<'a>(value: &'a Value, field: &str) -> StateResult<&'a str> {
+    value
+        .get(field)
+        .and_then(Value::as_str)
+        .ok_or_else(|| StateError::invalid_format(format!("missing '{}' string", field)))
+}
+
+/// Text of a message's content, with non-text parts as placeholders
+fn content_text(content: &Content) -> String {
+    match content {
+        Content::Text(text) => text.clone(),
+        Content::Image(_) => "[image]".to_string(),
+        Content::Audio(_) => "[audio]".to_string(),
+        Content::Multiple(parts) => parts
+            .iter()
+            .map(content_text)
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Tool call arguments as JSON, keeping arguments that aren't valid JSON as a
+/// string
+fn arguments_value(arguments: &str) -> Value {
+    serde_json::from_str(arguments).unwrap_or_else(|_| Value::String(arguments.to_string()))
+}
+
+/// Inverse of [`arguments_value`]
+fn arguments_string(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryStore;
+    use cogni_core::{Image, ToolCall};
+
+    fn conversation() -> ConversationState {
+        let mut state = ConversationState::new();
+        state.set_title("Weather");
+        state.add_tag("support");
+        state.metadata.custom.insert("team".into(), "ops".into());
+        let mut question = Message::user("Weather in Paris?");
+        question
+            .metadata
+            .custom
+            .insert("channel".into(), "web".into());
+        state.add_messages([
+            Message::system("Be brief"),
+            question,
+            Message::assistant_with_tools(
+                "Checking.",
+                [ToolCall {
+                    id: "call_1".into(),
+                    name: "weather".into(),
+                    arguments: r#"{"city":"Paris"}"#.into(),
+                }],
+            ),
+            Message::tool("18C and sunny", "call_1"),
+            Message::assistant("It's 18C and sunny."),
+        ]);
+        state
+    }
+
+    const FORMATS: [ExportFormat; 4] = [
+        ExportFormat::OpenAi,
+        ExportFormat::Anthropic,
+        ExportFormat::ShareGpt,
+        ExportFormat::Markdown,
+    ];
+
+    #[test]
+    fn test_round_trip_restores_sidecar() {
+        let state = conversation();
+        for format in FORMATS {
+            let exported = Exporter::new(format).export(&state).unwrap();
+            let imported = import_conversation(&exported, format).unwrap();
+
+            assert_eq!(imported.id, state.id, "{}", format);
+            assert_eq!(imported.metadata, state.metadata, "{}", format);
+            assert_eq!(imported.created_at, state.created_at, "{}", format);
+            assert_eq!(imported.updated_at, state.updated_at, "{}", format);
+            assert_eq!(imported.messages(), state.messages(), "{}", format);
+        }
+    }
+
+    #[test]
+    fn test_openai_multimodal_round_trip() {
+        let mut state = ConversationState::new();
+        state.add_message(Message {
+            content: Content::Multiple(vec![
+                Content::Text("What is this?".into()),
+                Content::Image(Image {
+                    data: Some("aGVsbG8=".into()),
+                    url: None,
+                    mime_type: "image/png".into(),
+                }),
+            ]),
+            ..Message::user("")
+        });
+
+        let exported = Exporter::new(ExportFormat::OpenAi).export(&state).unwrap();
+        assert!(exported.contains("data:image/png;base64,aGVsbG8="));
+        let imported = import_conversation(&exported, ExportFormat::OpenAi).unwrap();
+        assert_eq!(imported.messages(), state.messages());
+    }
+
+    #[test]
+    fn test_round_trip_keeps_branches_and_content() {
+        let mut state = conversation();
+        state.fork_at(4).unwrap();
+        state.add_message(Message {
+            content: Content::Multiple(vec![Content::Image(Image {
+                data: None,
+                url: Some("https://example.com/cat.png".into()),
+                mime_type: "image/png".into(),
+            })]),
+            ..Message::assistant("")
+        });
+
+        for format in FORMATS {
+            let exported = Exporter::new(format).export(&state).unwrap();
+            let imported = import_conversation(&exported, format).unwrap();
+            assert_eq!(imported.nodes(), state.nodes(), "{}", format);
+            assert_eq!(imported.head(), state.head(), "{}", format);
+        }
+
+        // Only messages the format loses details of are repeated
+        let exported = Exporter::new(ExportFormat::OpenAi).export(&state).unwrap();
+        assert_eq!(exported.matches("Be brief").count(), 1);
+        assert_eq!(exported.matches("Weather in Paris?").count(), 2);
+        assert_eq!(exported.matches("cat.png").count(), 2);
+    }
+
+    mod round_trip {
+        use super::*;
+        use cogni_core::{Audio, Role};
+        use proptest::prelude::*;
+
+        fn text() -> impl Strategy<Value = String> {
+            "[a-z #*`\\\n<!->]{0,12}"
+        }
+
+        fn content() -> impl Strategy<Value = Content> {
+            let part = prop_oneof![
+                text().prop_map(Content::Text),
+                (text(), any::<bool>()).prop_map(|(text, inline)| {
+                    Content::Image(Image {
+                        data: inline.then(|| "aGVsbG8=".into()),
+                        url: (!inline).then(|| format!("https://example.com/{}.png", text)),
+                        mime_type: "image/png".into(),
+                    })
+                }),
+                Just(Content::Audio(Audio {
+                    data: "aGVsbG8=".into(),
+                    mime_type: "audio/wav".into(),
+                })),
+            ];
+            prop_oneof![
+                part.clone(),
+                prop::collection::vec(part, 0..3).prop_map(Content::Multiple),
+            ]
+        }
+
+        fn message() -> impl Strategy<Value = Message> {
+            let role = prop_oneof![
+                Just(Role::System),
+                Just(Role::User),
+                Just(Role::Assistant),
+                Just(Role::Tool),
+            ];
+            let calls = prop::collection::vec(("[a-z_]{1,6}", text()), 0..2);
+            (role, content(), calls, prop::option::of(text())).prop_map(
+                |(role, content, calls, tag)| {
+                    let mut message = Message::text(role, "");
+                    message.content = content;
+                    if role == Role::Assistant {
+                        message.tool_calls = calls
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, (name, arguments))| ToolCall {
+                                id: format!("call_{}", i),
+                                name,
+                                arguments,
+                            })
+                            .collect();
+                    }
+                    if role == Role::Tool {
+                        message.metadata.tool_call_id = Some("call_0".into());
+                    }
+                    if let Some(tag) = tag {
+                        message.metadata.custom.insert("tag".into(), tag);
+                    }
+                    message
+                },
+            )
+        }
+
+        /// A change to a conversation's message tree
+        #[derive(Debug, Clone)]
+        enum Edit {
+            Add(Message),
+            Fork(usize),
+            Replace(usize, Message),
+            Switch(usize),
+            Pop,
+        }
+
+        fn edit() -> impl Strategy<Value = Edit> {
+            prop_oneof![
+                3 => message().prop_map(Edit::Add),
+                1 => any::<usize>().prop_map(Edit::Fork),
+                1 => (any::<usize>(), message()).prop_map(|(i, m)| Edit::Replace(i, m)),
+                1 => any::<usize>().prop_map(Edit::Switch),
+                1 => Just(Edit::Pop),
+            ]
+        }
+
+        fn state() -> impl Strategy<Value = ConversationState> {
+            prop::collection::vec(edit(), 0..12).prop_map(|edits| {
+                let mut state = conversation();
+                for edit in edits {
+                    let len = state.messages().len() + 1;
+                    match edit {
+                        Edit::Add(message) => {
+                            state.add_message(message);
+                        }
+                        Edit::Fork(i) => state.fork_at(i % len).unwrap(),
+                        Edit::Replace(i, message) => {
+                            let _ = state.edit_message(i % len, message);
+                        }
+                        Edit::Switch(i) => {
+                            let id = state.nodes()[i % state.nodes().len()].id;
+                            state.switch_branch(id).unwrap();
+                        }
+                        Edit::Pop => {
+                            state.pop_message();
+                        }
+                    }
+                }
+                state
+            })
+        }
+
+        proptest! {
+            #[test]
+            fn test_round_trip_is_lossless(state in state()) {
+                let expected = serde_json::to_value(&state).unwrap();
+                for format in FORMATS {
+                    let exported = Exporter::new(format).export(&state).unwrap();
+                    let imported = import_conversation(&exported, format).unwrap();
+                    prop_assert_eq!(
+                        serde_json::to_value(&imported).unwrap(),
+                        expected.clone(),
+                        "{}",
+                        format
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_export_without_sidecar() {
+        let state = conversation();
+        let exported = Exporter::new(ExportFormat::OpenAi)
+            .with_sidecar(false)
+            .export(&state)
+            .unwrap();
+        assert!(!exported.contains("cogni"));
+
+        let imported = import_conversation(&exported, ExportFormat::OpenAi).unwrap();
+        assert_ne!(imported.id, state.id);
+        assert!(imported.metadata.title.is_none());
+        assert_eq!(
+            imported.messages()[3],
+            Message::tool("18C and sunny", "call_1")
+        );
+    }
+
+    #[test]
+    fn test_markdown_escapes_structural_lines() {
+        let mut state = ConversationState::new();
+        state.add_messages([
+            Message::user("Format this:\n## User\n\\# literal"),
+            Message::assistant("# Heading\n**Tool call** `x` (`y`)"),
+        ]);
+
+        let exported = Exporter::new(ExportFormat::Markdown)
+            .export(&state)
+            .unwrap();
+        assert!(exported.contains("\n\\## User\n"));
+        let imported = import_conversation(&exported, ExportFormat::Markdown).unwrap();
+        assert_eq!(imported.messages(), state.messages());
+    }
+
+    #[test]
+    fn test_format_names() {
+        for format in FORMATS {
+            assert_eq!(format.to_string().parse::<ExportFormat>().unwrap(), format);
+        }
+        assert!(matches!(
+            "csv".parse::<ExportFormat>(),
+            Err(StateError::InvalidFormat(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_export_store_pages_through_matches() {
+        let store = MemoryStore::new();
+        for i in 0..5 {
+            let mut state = conversation();
+            state.set_title(format!("Conversation {}", i));
+            if i % 2 == 0 {
+                state.add_tag("export");
+            }
+            store.save(&state).await.unwrap();
+        }
+
+        let query = StateQuery::new().with_any_tags(["export"]).with_limit(2);
+        for format in FORMATS {
+            let mut output = Vec::new();
+            let count = Exporter::new(format)
+                .export_store(&store, &query, &mut output)
+                .await
+                .unwrap();
+            assert_eq!(count, 3, "{}", format);
+
+            let output = String::from_utf8(output).unwrap();
+            let imported = import_conversations(&output, format).unwrap();
+            assert_eq!(imported.len(), 3, "{}", format);
+            for state in imported {
+                assert!(state.metadata.tags.contains(&"export".to_string()));
+                assert_eq!(state.messages().len(), 5);
+            }
+        }
+    }
+}
@@ -0,0 +1,190 @@
+//! Anthropic Messages API format
+
+use super::{content_text, str_field};
+use crate::{StateError, StateResult};
+use cogni_core::{Content, Image, Message, Role, ToolCall};
+use serde_json::{json, Map, Value};
+
+/// Build a `{"system": ..., "messages": [...]}` record
+///
+/// System messages are joined into the top-level `system` field and
+/// consecutive tool results share one user message, as the API expects.
+pub(super) fn export(messages: &[Message]) -> Map<String, Value> {
+    let system: Vec<String> = messages
+        .iter()
+        .filter(|m| m.role == Role::System)
+        .map(|m| content_text(&m.content))
+        .collect();
+
+    let mut out: Vec<Value> = Vec::new();
+    for message in messages {
+        match message.role {
+            Role::System => {}
+            Role::Tool => {
+                let block = json!({
+                    "type": "tool_result",
+                    "tool_use_id": message.metadata.tool_call_id.as_deref().unwrap_or_default(),
+                    "content": content_text(&message.content),
+                });
+                match out.last_mut() {
+                    Some(last) if is_tool_results(last) => {
+                        if let Some(blocks) = last["content"].as_array_mut() {
+                            blocks.push(block);
+                        }
+                    }
+                    _ => out.push(json!({ "role": "user", "content": [block] })),
+                }
+            }
+            Role::Assistant if !message.tool_calls.is_empty() => {
+                let mut blocks = export_blocks(&message.content);
+                blocks.retain(|b| b["type"] != "text" || b["text"] != "");
+                blocks.extend(message.tool_calls.iter().map(|call| {
+                    json!({
+                        "type": "tool_use",
+                        "id": call.id,
+                        "name": call.name,
+                        "input": super::arguments_value(&call.arguments),
+                    })
+                }));
+                out.push(json!({ "role": "assistant", "content": blocks }));
+            }
+            _ => {
+                let role = match message.role {
+                    Role::Assistant => "assistant",
+                    _ => "user",
+                };
+                let content = match &message.content {
+                    Content::Text(text) => Value::String(text.clone()),
+                    content => export_blocks(content).into(),
+                };
+                out.push(json!({ "role": role, "content": content }));
+            }
+        }
+    }
+
+    let mut record = Map::new();
+    if !system.is_empty() {
+        record.insert("system".into(), system.join("\n\n").into());
+    }
+    record.insert("messages".into(), out.into());
+    record
+}
+
+fn is_tool_results(message: &Value) -> bool {
+    message["role"] == "user"
+        && message["content"]
+            .as_array()
+            .is_some_and(|blocks| blocks.iter().all(|b| b["type"] == "tool_result"))
+}
+
+fn export_blocks(content: &Content) -> Vec<Value> {
+    match content {
+        Content::Text(text) => vec![json!({ "type": "text", "text": text })],
+        Content::Image(image) => {
+            let source = match (&image.data, &image.url) {
+                (Some(data), _) => json!({
+                    "type": "base64",
+                    "media_type": image.mime_type,
+                    "data": data,
+                }),
+                (None, url) => json!({ "type": "url", "url": url }),
+            };
+            vec![json!({ "type": "image", "source": source })]
+        }
+        Content::Audio(_) => vec![json!({ "type": "text", "text": content_text(content) })],
+        Content::Multiple(parts) => parts.iter().flat_map(export_blocks).collect(),
+    }
+}
+
+pub(super) fn import(record: &Map<String, Value>) -> StateResult<Vec<Message>> {
+    let mut messages = Vec::new();
+    match record.get("system") {
+        Some(Value::String(system)) => messages.push(Message::system(system.clone())),
+        Some(Value::Array(blocks)) => {
+            let text: Vec<&str> = blocks.iter().filter_map(|b| b["text"].as_str()).collect();
+            messages.push(Message::system(text.join("\n\n")));
+        }
+        _ => {}
+    }
+
+    for value in super::array_field(record, "messages")? {
+        let role = match str_field(value, "role")? {
+            "user" => Role::User,
+            "assistant" => Role::Assistant,
+            other => {
+                return Err(StateError::invalid_format(format!(
+                    "unknown Anthropic role '{}'",
+                    other
+                )))
+            }
+        };
+        let blocks = match &value["content"] {
+            Value::String(text) => {
+                messages.push(Message::text(role, text.clone()));
+                continue;
+            }
+            Value::Array(blocks) => blocks,
+            _ => {
+                return Err(StateError::invalid_format(
+                    "missing Anthropic message content",
+                ))
+            }
+        };
+
+        let mut parts = Vec::new();
+        let mut calls = Vec::new();
+        for block in blocks {
+            match str_field(block, "type")? {
+                "text" => parts.push(Content::Text(str_field(block, "text")?.to_string())),
+                "image" => parts.push(import_image(&block["source"])?),
+                "tool_use" => calls.push(ToolCall {
+                    id: str_field(block, "id")?.to_string(),
+                    name: str_field(block, "name")?.to_string(),
+                    arguments: super::arguments_string(&block["input"]),
+                }),
+                "tool_result" => {
+                    let content = match &block["content"] {
+                        Value::String(text) => text.clone(),
+                        Value::Array(blocks) => blocks
+                            .iter()
+                            .filter_map(|b| b["text"].as_str())
+                            .collect::<Vec<_>>()
+                            .join("\n"),
+                        _ => String::new(),
+                    };
+                    messages.push(Message::tool(content, str_field(block, "tool_use_id")?));
+                }
+                _ => {}
+            }
+        }
+
+        if parts.is_empty() && calls.is_empty() {
+            continue;
+        }
+        let mut message = Message::text(role, "");
+        message.content = match parts.len() {
+            0 => Content::Text(String::new()),
+            1 => parts.remove(0),
+            _ => Content::Multiple(parts),
+        };
+        message.tool_calls = calls;
+        messages.push(message);
+    }
+    Ok(messages)
+}
+
+fn import_image(source: &Value) -> StateResult<Content> {
+    let image = match str_field(source, "type")? {
+        "base64" => Image {
+            data: Some(str_field(source, "data")?.to_string()),
+            url: None,
+            mime_type: str_field(source, "media_type")?.to_string(),
+        },
+        _ => Image {
+            data: None,
+            url: Some(str_field(source, "url")?.to_string()),
+            mime_type: "image/*".to_string(),
+        },
+    };
+    Ok(Content::Image(image))
+}
@@ -0,0 +1,209 @@
+//! Markdown transcripts
+//!
+//! Each message is a `## Role` section. Lines in message text that would be
+//! read as structure are escaped with a leading backslash, which Markdown
+//! renders as the original text.
+
+use super::{content_text, Sidecar};
+use crate::{StateError, StateResult};
+use cogni_core::{Message, Role, ToolCall};
+
+const SIDECAR_PREFIX: &str = "<!-- cogni:";
+const SIDECAR_SUFFIX: &str = "-->";
+const TOOL_CALL_PREFIX: &str = "**Tool call** ";
+
+pub(super) fn export(
+    title: Option<&str>,
+    messages: &[Message],
+    sidecar: Option<&Sidecar>,
+) -> StateResult<String> {
+    let mut out = String::new();
+    if let Some(sidecar) = sidecar {
+        // `-->` can only occur inside JSON strings, where it can be escaped
+        let json = serde_json::to_string(sidecar)?.replace("-->", "--\\u003e");
+        out.push_str(&format!("{} {} {}\n", SIDECAR_PREFIX, json, SIDECAR_SUFFIX));
+    }
+    out.push_str(&format!("# {}\n", title.unwrap_or("Conversation")));
+
+    for message in messages {
+        let heading = match message.role {
+            Role::System => "System".to_string(),
+            Role::Assistant => "Assistant".to_string(),
+            Role::Tool => match &message.metadata.tool_call_id {
+                Some(id) => format!("Tool `{}`", id),
+                None => "Tool".to_string(),
+            },
+            _ => "User".to_string(),
+        };
+        out.push_str(&format!("\n## {}\n", heading));
+
+        let text = content_text(&message.content);
+        if !text.is_empty() {
+            out.push('\n');
+            for line in text.lines() {
+                if is_structural(line) {
+                    out.push('\\');
+                }
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        for call in &message.tool_calls {
+            out.push_str(&format!(
+                "\n{}`{}` (`{}`)\n\n```json\n{}\n```\n",
+                TOOL_CALL_PREFIX, call.name, call.id, call.arguments
+            ));
+        }
+    }
+    Ok(out)
+}
+
+/// Whether a line of message text would be mistaken for transcript structure,
+/// ignoring any backslashes already escaping it
+fn is_structural(line: &str) -> bool {
+    let line = line.trim_start_matches('\\');
+    line.starts_with("# ")
+        || line.starts_with("## ")
+        || line.starts_with(TOOL_CALL_PREFIX)
+        || line.starts_with(SIDECAR_PREFIX)
+}
+
+/// Split concatenated transcripts at each sidecar or untitled `# ` heading
+pub(super) fn split(input: &str) -> impl Iterator<Item = &str> {
+    let mut starts = Vec::new();
+    let mut offset = 0;
+    let mut after_sidecar = false;
+    for line in input.split_inclusive('\n') {
+        if line.starts_with(SIDECAR_PREFIX) {
+            starts.push(offset);
+            after_sidecar = true;
+        } else if line.starts_with("# ") {
+            if !after_sidecar {
+                starts.push(offset);
+            }
+            after_sidecar = false;
+        } else if !line.trim().is_empty() {
+            after_sidecar = false;
+        }
+        offset += line.len();
+    }
+
+    let ends: Vec<usize> = starts
+        .iter()
+        .skip(1)
+        .copied()
+        .chain([input.len()])
+        .collect();
+    starts
+        .into_iter()
+        .zip(ends)
+        .map(move |(start, end)| &input[start..end])
+}
+
+/// A `## Role` section being read
+struct Section {
+    role: Role,
+    tool_call_id: Option<String>,
+    lines: Vec<String>,
+    tool_calls: Vec<ToolCall>,
+}
+
+impl Section {
+    fn new(heading: &str) -> StateResult<Self> {
+        let (role, tool_call_id) = match heading.trim() {
+            "System" => (Role::System, None),
+            "User" => (Role::User, None),
+            "Assistant" => (Role::Assistant, None),
+            "Tool" => (Role::Tool, None),
+            heading => match heading
+                .strip_prefix("Tool `")
+                .and_then(|id| id.strip_suffix('`'))
+            {
+                Some(id) => (Role::Tool, Some(id.to_string())),
+                None => {
+                    return Err(StateError::invalid_format(format!(
+                        "unknown transcript heading '## {}'",
+                        heading
+                    )))
+                }
+            },
+        };
+        Ok(Self {
+            role,
+            tool_call_id,
+            lines: Vec::new(),
+            tool_calls: Vec::new(),
+        })
+    }
+
+    fn into_message(self) -> Message {
+        let text = self.lines.join("\n");
+        let mut message = Message::text(self.role, text.trim_matches('\n'));
+        message.metadata.tool_call_id = self.tool_call_id;
+        message.tool_calls = self.tool_calls;
+        message
+    }
+}
+
+pub(super) fn import(transcript: &str) -> StateResult<(Option<Sidecar>, Vec<Message>)> {
+    let mut sidecar = None;
+    let mut messages = Vec::new();
+    let mut section: Option<Section> = None;
+    let mut lines = transcript.lines();
+
+    while let Some(line) = lines.next() {
+        if let Some(json) = line.strip_prefix(SIDECAR_PREFIX) {
+            let json = json
+                .trim_end()
+                .strip_suffix(SIDECAR_SUFFIX)
+                .ok_or_else(|| StateError::invalid_format("unterminated cogni sidecar comment"))?;
+            sidecar = Some(serde_json::from_str(json.trim())?);
+        } else if line.starts_with("# ") {
+            // The title is restored from the sidecar, if there is one
+        } else if let Some(heading) = line.strip_prefix("## ") {
+            messages.extend(section.take().map(Section::into_message));
+            section = Some(Section::new(heading)?);
+        } else if let Some(call) = line.strip_prefix(TOOL_CALL_PREFIX) {
+            let section = section
+                .as_mut()
+                .ok_or_else(|| StateError::invalid_format("tool call outside a message"))?;
+            section.tool_calls.push(parse_tool_call(call, &mut lines)?);
+        } else if let Some(section) = section.as_mut() {
+            let unescaped = match line.strip_prefix('\\') {
+                Some(rest) if is_structural(line) => rest,
+                _ => line,
+            };
+            section.lines.push(unescaped.to_string());
+        } else if !line.trim().is_empty() {
+            return Err(StateError::invalid_format(
+                "transcript text before the first message heading",
+            ));
+        }
+    }
+    messages.extend(section.map(Section::into_message));
+    Ok((sidecar, messages))
+}
+
+/// Parse "`name` (`id`)" and the fenced arguments block that follows it
+fn parse_tool_call<'a>(
+    header: &str,
+    lines: &mut impl Iterator<Item = &'a str>,
+) -> StateResult<ToolCall> {
+    let malformed = || StateError::invalid_format(format!("malformed tool call '{}'", header));
+    let (name, id) = header
+        .strip_prefix('`')
+        .and_then(|rest| rest.split_once("` (`"))
+        .and_then(|(name, rest)| Some((name, rest.strip_suffix("`)")?)))
+        .ok_or_else(malformed)?;
+
+    if !lines.by_ref().any(|line| line.starts_with("```")) {
+        return Err(malformed());
+    }
+    let arguments: Vec<&str> = lines.by_ref().take_while(|line| *line != "```").collect();
+
+    Ok(ToolCall {
+        id: id.to_string(),
+        name: name.to_string(),
+        arguments: arguments.join("\n"),
+    })
+}
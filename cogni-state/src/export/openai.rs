@@ -0,0 +1,171 @@
+//! OpenAI chat fine-tuning format
+
+use super::str_field;
+use crate::{StateError, StateResult};
+use cogni_core::{Audio, Content, Image, Message, Role, ToolCall};
+use serde_json::{json, Value};
+
+pub(super) fn export(messages: &[Message]) -> Vec<Value> {
+    messages.iter().map(export_message).collect()
+}
+
+fn export_message(message: &Message) -> Value {
+    let role = match message.role {
+        Role::System => "system",
+        Role::Assistant => "assistant",
+        Role::Tool => "tool",
+        _ => "user",
+    };
+    let mut msg = json!({ "role": role, "content": export_content(&message.content) });
+
+    if let Some(name) = &message.metadata.name {
+        msg["name"] = name.clone().into();
+    }
+    if let Some(id) = &message.metadata.tool_call_id {
+        msg["tool_call_id"] = id.clone().into();
+    }
+    if !message.tool_calls.is_empty() {
+        if msg["content"] == json!("") {
+            msg["content"] = Value::Null;
+        }
+        msg["tool_calls"] = message
+            .tool_calls
+            .iter()
+            .map(|call| {
+                json!({
+                    "id": call.id,
+                    "type": "function",
+                    "function": { "name": call.name, "arguments": call.arguments },
+                })
+            })
+            .collect();
+    }
+    msg
+}
+
+fn export_content(content: &Content) -> Value {
+    match content {
+        Content::Text(text) => text.clone().into(),
+        Content::Multiple(parts) => parts.iter().map(export_part).collect(),
+        part => Value::Array(vec![export_part(part)]),
+    }
+}
+
+fn export_part(content: &Content) -> Value {
+    match content {
+        Content::Text(text) => json!({ "type": "text", "text": text }),
+        Content::Image(image) => {
+            let url = match (&image.url, &image.data) {
+                (Some(url), _) => url.clone(),
+                (None, Some(data)) => format!("data:{};base64,{}", image.mime_type, data),
+                (None, None) => String::new(),
+            };
+            json!({ "type": "image_url", "image_url": { "url": url } })
+        }
+        Content::Audio(audio) => json!({
+            "type": "input_audio",
+            "input_audio": {
+                "data": audio.data,
+                "format": audio.mime_type.strip_prefix("audio/").unwrap_or(&audio.mime_type),
+            },
+        }),
+        Content::Multiple(parts) => json!({
+            "type": "text",
+            "text": super::content_text(&Content::Multiple(parts.clone())),
+        }),
+    }
+}
+
+pub(super) fn import(messages: &[Value]) -> StateResult<Vec<Message>> {
+    messages.iter().map(import_message).collect()
+}
+
+fn import_message(value: &Value) -> StateResult<Message> {
+    let role = match str_field(value, "role")? {
+        "system" | "developer" => Role::System,
+        "user" => Role::User,
+        "assistant" => Role::Assistant,
+        "tool" => Role::Tool,
+        other => {
+            return Err(StateError::invalid_format(format!(
+                "unknown OpenAI role '{}'",
+                other
+            )))
+        }
+    };
+
+    let mut message = Message::text(role, "");
+    message.content = import_content(&value["content"])?;
+    message.metadata.name = value["name"].as_str().map(String::from);
+    message.metadata.tool_call_id = value["tool_call_id"].as_str().map(String::from);
+    if let Some(calls) = value["tool_calls"].as_array() {
+        message.tool_calls = calls
+            .iter()
+            .map(|call| {
+                let function = &call["function"];
+                Ok(ToolCall {
+                    id: call["id"].as_str().unwrap_or_default().to_string(),
+                    name: str_field(function, "name")?.to_string(),
+                    arguments: super::arguments_string(&function["arguments"]),
+                })
+            })
+            .collect::<StateResult<_>>()?;
+    }
+    Ok(message)
+}
+
+fn import_content(value: &Value) -> StateResult<Content> {
+    match value {
+        Value::Null => Ok(Content::Text(String::new())),
+        Value::String(text) => Ok(Content::Text(text.clone())),
+        Value::Array(parts) => {
+            let mut parts = parts
+                .iter()
+                .map(import_part)
+                .collect::<StateResult<Vec<_>>>()?;
+            Ok(match parts.len() {
+                1 => parts.remove(0),
+                _ => Content::Multiple(parts),
+            })
+        }
+        _ => Err(StateError::invalid_format(
+            "unsupported OpenAI message content",
+        )),
+    }
+}
+
+fn import_part(part: &Value) -> StateResult<Content> {
+    match str_field(part, "type")? {
+        "text" => Ok(Content::Text(str_field(part, "text")?.to_string())),
+        "image_url" => {
+            let url = str_field(&part["image_url"], "url")?;
+            let image = match url
+                .strip_prefix("data:")
+                .and_then(|rest| rest.split_once(";base64,"))
+            {
+                Some((mime_type, data)) => Image {
+                    data: Some(data.to_string()),
+                    url: None,
+                    mime_type: mime_type.to_string(),
+                },
+                None => Image {
+                    data: None,
+                    url: Some(url.to_string()),
+                    mime_type: "image/*".to_string(),
+                },
+            };
+            Ok(Content::Image(image))
+        }
+        "input_audio" => {
+            let audio = &part["input_audio"];
+            Ok(Content::Audio(Audio {
+                data: str_field(audio, "data")?.to_string(),
+                mime_type: format!("audio/{}", str_field(audio, "format")?),
+            }))
+        }
+        other => Err(StateError::invalid_format(format!(
+            "unsupported OpenAI content part '{}'",
+            other
+        ))),
+    }
+}
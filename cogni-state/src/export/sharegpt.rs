@@ -0,0 +1,102 @@
+//! ShareGPT dataset format
+//!
+//! Tool calls follow the common `function_call`/`observation` turn
+//! convention, with call IDs kept in the `function_call` JSON.
+
+use super::{content_text, str_field};
+use crate::{StateError, StateResult};
+use cogni_core::{Message, Role, ToolCall};
+use serde_json::{json, Value};
+use std::collections::VecDeque;
+
+pub(super) fn export(messages: &[Message]) -> Vec<Value> {
+    let mut turns = Vec::new();
+    for message in messages {
+        let text = content_text(&message.content);
+        let from = match message.role {
+            Role::System => "system",
+            Role::Assistant => "gpt",
+            Role::Tool => "observation",
+            _ => "human",
+        };
+        if message.tool_calls.is_empty() || !text.is_empty() {
+            turns.push(json!({ "from": from, "value": text }));
+        }
+        if !message.tool_calls.is_empty() {
+            let mut calls: Vec<Value> = message
+                .tool_calls
+                .iter()
+                .map(|call| {
+                    json!({
+                        "id": call.id,
+                        "name": call.name,
+                        "arguments": super::arguments_value(&call.arguments),
+                    })
+                })
+                .collect();
+            let value = match calls.len() {
+                1 => calls.remove(0),
+                _ => calls.into(),
+            };
+            turns.push(json!({ "from": "function_call", "value": value.to_string() }));
+        }
+    }
+    turns
+}
+
+pub(super) fn import(turns: &[Value]) -> StateResult<Vec<Message>> {
+    let mut messages: Vec<Message> = Vec::new();
+    let mut pending: VecDeque<String> = VecDeque::new();
+    let mut previous = "";
+
+    for turn in turns {
+        let from = str_field(turn, "from")?;
+        let value = str_field(turn, "value")?;
+        match from {
+            "system" => messages.push(Message::system(value)),
+            "human" | "user" => messages.push(Message::user(value)),
+            "gpt" | "assistant" => messages.push(Message::assistant(value)),
+            "function_call" => {
+                let calls = match serde_json::from_str(value)? {
+                    Value::Array(calls) => calls,
+                    call => vec![call],
+                };
+                let calls = calls
+                    .iter()
+                    .enumerate()
+                    .map(|(i, call)| {
+                        Ok(ToolCall {
+                            id: call["id"]
+                                .as_str()
+                                .map(String::from)
+                                .unwrap_or_else(|| format!("call_{}_{}", messages.len(), i)),
+                            name: str_field(call, "name")?.to_string(),
+                            arguments: super::arguments_string(&call["arguments"]),
+                        })
+                    })
+                    .collect::<StateResult<Vec<_>>>()?;
+                pending.extend(calls.iter().map(|c| c.id.clone()));
+
+                // A reply and the calls it makes are one message
+                match messages.last_mut() {
+                    Some(last) if matches!(previous, "gpt" | "assistant") => {
+                        last.tool_calls = calls
+                    }
+                    _ => messages.push(Message::assistant_with_tools("", calls)),
+                }
+            }
+            "observation" | "tool" | "function_response" => match pending.pop_front() {
+                Some(id) => messages.push(Message::tool(value, id)),
+                None => messages.push(Message::text(Role::Tool, value)),
+            },
+            other => {
+                return Err(StateError::invalid_format(format!(
+                    "unknown ShareGPT speaker '{}'",
+                    other
+                )))
+            }
+        }
+        previous = from;
+    }
+    Ok(messages)
+}
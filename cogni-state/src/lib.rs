@@ -3,12 +3,26 @@
 //! This crate provides conversation state management capabilities for building
 //! stateful AI agents with memory across sessions.
 
+pub mod branch;
+pub mod encryption;
 pub mod error;
+pub mod export;
+pub mod query;
+pub mod retention;
 pub mod store;
 pub mod types;
 
+pub use branch::MessageNode;
+pub use encryption::{EncryptedStore, EncryptionKey, KeyProvider, KeyRing};
 pub use error::{StateError, StateResult};
-pub use store::{FileStore, MemoryStore, StateStore};
+pub use export::{import_conversation, import_conversations, ExportFormat, Exporter};
+pub use query::{ConversationSummary, QueryPage, SortOrder, StateQuery};
+pub use retention::{
+    DeletionReason, RetentionEvent, RetentionPolicy, RetentionSweeper, LEGAL_HOLD_TAG,
+};
+#[cfg(feature = "redis")]
+pub use store::RedisStore;
+pub use store::{BlobStore, FileBlobStore, FileStore, MemoryBlobStore, MemoryStore, StateStore};
 pub use types::{ConversationState, StateMetadata};
 
 #[cfg(test)]
@@ -19,7 +33,7 @@ mod tests {
     #[tokio::test]
     async fn test_conversation_state_creation() {
         let state = ConversationState::new();
-        assert!(state.messages.is_empty());
+        assert!(state.messages().is_empty());
         assert!(state.metadata.title.is_none());
         assert!(state.metadata.tags.is_empty());
     }
@@ -30,11 +44,12 @@ mod tests {
         let message = Message {
             role: Role::User,
             content: Content::Text("Hello".to_string()),
+            tool_calls: Vec::new(),
             metadata: Metadata::default(),
         };
 
         state.add_message(message.clone());
-        assert_eq!(state.messages.len(), 1);
-        assert_eq!(state.messages[0].content, message.content);
+        assert_eq!(state.messages().len(), 1);
+        assert_eq!(state.messages()[0].content, message.content);
     }
 }
@@ -0,0 +1,443 @@
+//! Querying, paginating and searching stored conversations
+
+use crate::{ConversationState, StateError, StateMetadata, StateResult};
+use chrono::{DateTime, Utc};
+use cogni_core::Content;
+use serde::de::IgnoredAny;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use uuid::Uuid;
+
+/// Filters, ordering and pagination for [`StateStore::query`]
+///
+/// Every filter that is set must match. Text filters are case-insensitive
+/// substring matches.
+///
+/// [`StateStore::query`]: crate::StateStore::query
+///
+/// # Examples
+///
+/// ```
+/// use cogni_state::{SortOrder, StateQuery};
+///
+/// let query = StateQuery::new()
+///     .with_any_tags(["support", "billing"])
+///     .with_text("refund")
+///     .with_sort(SortOrder::CreatedDesc)
+///     .with_limit(20);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StateQuery {
+    /// Match conversations with at least one of these tags
+    pub any_tags: Vec<String>,
+    /// Match conversations with all of these tags
+    pub all_tags: Vec<String>,
+    /// Match conversations whose title contains this text
+    pub title: Option<String>,
+    /// Match conversations whose title or any message contains this text
+    pub text: Option<String>,
+    /// Match conversations created at or after this time
+    pub created_after: Option<DateTime<Utc>>,
+    /// Match conversations created before this time
+    pub created_before: Option<DateTime<Utc>>,
+    /// Match conversations updated at or after this time
+    pub updated_after: Option<DateTime<Utc>>,
+    /// Match conversations updated before this time
+    pub updated_before: Option<DateTime<Utc>>,
+    /// Match conversations whose custom metadata has these values
+    pub custom: HashMap<String, String>,
+    /// Order of the results
+    pub sort: SortOrder,
+    /// Maximum number of results per page
+    pub limit: Option<usize>,
+    /// Continue after the page that returned this cursor
+    pub cursor: Option<String>,
+}
+
+/// Order of query results
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Most recently updated first
+    #[default]
+    UpdatedDesc,
+    /// Least recently updated first
+    UpdatedAsc,
+    /// Newest first
+    CreatedDesc,
+    /// Oldest first
+    CreatedAsc,
+}
+
+/// A conversation's metadata without its messages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    /// Conversation ID
+    pub id: Uuid,
+    /// Metadata about the conversation
+    pub metadata: StateMetadata,
+    /// Number of messages stored, across all branches
+    pub message_count: usize,
+    /// When the conversation was created
+    pub created_at: DateTime<Utc>,
+    /// When the conversation was last updated
+    pub updated_at: DateTime<Utc>,
+}
+
+/// One page of query results
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryPage {
+    /// Matching conversations in this page
+    pub conversations: Vec<ConversationSummary>,
+    /// Cursor for the next page, if there are more results
+    pub next_cursor: Option<String>,
+}
+
+impl StateQuery {
+    /// Create a query that matches every conversation
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match conversations with at least one of these tags
+    pub fn with_any_tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.any_tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Match conversations with all of these tags
+    pub fn with_all_tags(mut self, tags: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.all_tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    /// Match conversations whose title contains `title`
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Match conversations whose title or any message contains `text`
+    pub fn with_text(mut self, text: impl Into<String>) -> Self {
+        self.text = Some(text.into());
+        self
+    }
+
+    /// Match conversations created in `[after, before)`; either bound may be open
+    pub fn with_created(
+        mut self,
+        after: Option<DateTime<Utc>>,
+        before: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.created_after = after;
+        self.created_before = before;
+        self
+    }
+
+    /// Match conversations updated in `[after, before)`; either bound may be open
+    pub fn with_updated(
+        mut self,
+        after: Option<DateTime<Utc>>,
+        before: Option<DateTime<Utc>>,
+    ) -> Self {
+        self.updated_after = after;
+        self.updated_before = before;
+        self
+    }
+
+    /// Match conversations whose custom metadata has `key` set to `value`
+    pub fn with_custom(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.custom.insert(key.into(), value.into());
+        self
+    }
+
+    /// Set the order of the results
+    pub fn with_sort(mut self, sort: SortOrder) -> Self {
+        self.sort = sort;
+        self
+    }
+
+    /// Limit the number of results per page
+    pub fn with_limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    /// Continue after the page that returned `cursor`
+    pub fn with_cursor(mut self, cursor: impl Into<String>) -> Self {
+        self.cursor = Some(cursor.into());
+        self
+    }
+
+    /// Check the filters that only need a conversation's metadata
+    // `Option::is_none_or` needs a newer Rust than the MSRV
+    #[allow(clippy::unnecessary_map_or)]
+    pub fn matches_summary(&self, summary: &ConversationSummary) -> bool {
+        let tags = &summary.metadata.tags;
+        let in_range = |time, after: Option<DateTime<Utc>>, before: Option<DateTime<Utc>>| {
+            after.map_or(true, |after| time >= after) && before.map_or(true, |before| time < before)
+        };
+
+        (self.any_tags.is_empty() || self.any_tags.iter().any(|t| tags.contains(t)))
+            && self.all_tags.iter().all(|t| tags.contains(t))
+            && self.title.as_deref().map_or(true, |title| {
+                contains(summary.metadata.title.as_deref(), title)
+            })
+            && in_range(summary.created_at, self.created_after, self.created_before)
+            && in_range(summary.updated_at, self.updated_after, self.updated_before)
+            && self
+                .custom
+                .iter()
+                .all(|(k, v)| summary.metadata.custom.get(k) == Some(v))
+    }
+
+    /// Check every filter against a conversation
+    pub fn matches(&self, state: &ConversationState) -> bool {
+        self.matches_summary(&ConversationSummary::from(state)) && self.matches_text(state)
+    }
+
+    /// Check the text filter, which searches the title and every message
+    pub(crate) fn matches_text(&self, state: &ConversationState) -> bool {
+        let Some(text) = self.text.as_deref() else {
+            return true;
+        };
+        contains(state.metadata.title.as_deref(), text)
+            || state
+                .nodes()
+                .iter()
+                .any(|node| content_contains(&node.message.content, text))
+    }
+
+    /// Run the query over conversations held in memory
+    pub(crate) fn execute<'a>(
+        &self,
+        states: impl IntoIterator<Item = &'a ConversationState>,
+    ) -> StateResult<QueryPage> {
+        let matching = states
+            .into_iter()
+            .filter(|state| self.matches(state))
+            .map(ConversationSummary::from)
+            .collect();
+        self.paginate(matching)
+    }
+
+    /// Sort matching conversations and cut out the requested page
+    pub fn paginate(&self, mut matching: Vec<ConversationSummary>) -> StateResult<QueryPage> {
+        matching.sort_by(|a, b| self.compare(a, b));
+
+        if let Some(cursor) = &self.cursor {
+            let after = Cursor::decode(cursor)?;
+            matching.retain(|s| self.compare_to_cursor(s, &after) == Ordering::Greater);
+        }
+
+        let limit = self.limit.unwrap_or(usize::MAX);
+        let next_cursor = (limit > 0 && matching.len() > limit).then(|| {
+            let last = &matching[limit - 1];
+            Cursor {
+                time: self.sort_time(last),
+                id: last.id,
+            }
+            .encode()
+        });
+        matching.truncate(limit);
+
+        Ok(QueryPage {
+            conversations: matching,
+            next_cursor,
+        })
+    }
+
+    fn sort_time(&self, summary: &ConversationSummary) -> DateTime<Utc> {
+        match self.sort {
+            SortOrder::UpdatedDesc | SortOrder::UpdatedAsc => summary.updated_at,
+            SortOrder::CreatedDesc | SortOrder::CreatedAsc => summary.created_at,
+        }
+    }
+
+    fn compare(&self, a: &ConversationSummary, b: &ConversationSummary) -> Ordering {
+        self.compare_keys((self.sort_time(a), a.id), (self.sort_time(b), b.id))
+    }
+
+    fn compare_to_cursor(&self, summary: &ConversationSummary, cursor: &Cursor) -> Ordering {
+        self.compare_keys(
+            (self.sort_time(summary), summary.id),
+            (cursor.time, cursor.id),
+        )
+    }
+
+    fn compare_keys(&self, a: (DateTime<Utc>, Uuid), b: (DateTime<Utc>, Uuid)) -> Ordering {
+        match self.sort {
+            SortOrder::UpdatedDesc | SortOrder::CreatedDesc => b.cmp(&a),
+            SortOrder::UpdatedAsc | SortOrder::CreatedAsc => a.cmp(&b),
+        }
+    }
+}
+
+/// Position of the last conversation on a page
+struct Cursor {
+    time: DateTime<Utc>,
+    id: Uuid,
+}
+
+impl Cursor {
+    fn encode(&self) -> String {
+        format!(
+            "{}_{}",
+            self.time.timestamp_nanos_opt().unwrap_or_default(),
+            self.id
+        )
+    }
+
+    fn decode(cursor: &str) -> StateResult<Self> {
+        let invalid = || StateError::invalid_query(format!("invalid cursor: {}", cursor));
+        let (nanos, id) = cursor.split_once('_').ok_or_else(invalid)?;
+        Ok(Self {
+            time: DateTime::from_timestamp_nanos(nanos.parse().map_err(|_| invalid())?),
+            id: Uuid::parse_str(id).map_err(|_| invalid())?,
+        })
+    }
+}
+
+impl From<&ConversationState> for ConversationSummary {
+    fn from(state: &ConversationState) -> Self {
+        Self {
+            id: state.id,
+            metadata: state.metadata.clone(),
+            message_count: state.nodes().len(),
+            created_at: state.created_at,
+            updated_at: state.updated_at,
+        }
+    }
+}
+
+impl ConversationSummary {
+    /// Read a summary from a serialized conversation without keeping its messages
+    pub fn from_json(json: &str) -> StateResult<Self> {
+        #[derive(Deserialize)]
+        struct SummaryRepr {
+            id: Uuid,
+            #[serde(default)]
+            nodes: Vec<IgnoredAny>,
+            #[serde(default)]
+            messages: Vec<IgnoredAny>,
+            metadata: StateMetadata,
+            created_at: DateTime<Utc>,
+            updated_at: DateTime<Utc>,
+        }
+
+        let repr: SummaryRepr = serde_json::from_str(json)?;
+        Ok(Self {
+            id: repr.id,
+            metadata: repr.metadata,
+            message_count: repr.nodes.len().max(repr.messages.len()),
+            created_at: repr.created_at,
+            updated_at: repr.updated_at,
+        })
+    }
+}
+
+fn contains(haystack: Option<&str>, needle: &str) -> bool {
+    haystack.is_some_and(|h| h.to_lowercase().contains(&needle.to_lowercase()))
+}
+
+fn content_contains(content: &Content, needle: &str) -> bool {
+    match content {
+        Content::Text(text) => contains(Some(text), needle),
+        Content::Multiple(parts) => parts.iter().any(|part| content_contains(part, needle)),
+        Content::Image(_) | Content::Audio(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use cogni_core::Message;
+
+    fn state(title: &str, tags: &[&str], text: &str, age_days: i64) -> ConversationState {
+        let mut state = ConversationState::new();
+        state.set_title(title);
+        for tag in tags {
+            state.add_tag(*tag);
+        }
+        state.add_message(Message::user(text));
+        state.created_at = Utc::now() - Duration::days(age_days);
+        state.updated_at = state.created_at;
+        state
+    }
+
+    fn ids(page: &QueryPage) -> Vec<Uuid> {
+        page.conversations.iter().map(|s| s.id).collect()
+    }
+
+    #[test]
+    fn test_filters() {
+        let mut refund = state("Billing", &["support", "billing"], "I want a REFUND", 3);
+        refund.set_custom("tier", "gold");
+        refund.updated_at = refund.created_at;
+        let states = [
+            refund,
+            state("Login help", &["support"], "Password reset", 1),
+            state("Chat", &[], "Hello", 10),
+        ];
+
+        let run = |query: StateQuery| ids(&query.execute(&states).unwrap());
+        assert_eq!(run(StateQuery::new()).len(), 3);
+        assert_eq!(
+            run(StateQuery::new().with_any_tags(["billing", "support"])).len(),
+            2
+        );
+        assert_eq!(
+            run(StateQuery::new().with_all_tags(["billing", "support"])),
+            [states[0].id]
+        );
+        assert_eq!(run(StateQuery::new().with_title("login")), [states[1].id]);
+        assert_eq!(run(StateQuery::new().with_text("refund")), [states[0].id]);
+        assert_eq!(run(StateQuery::new().with_text("chat")), [states[2].id]);
+        assert_eq!(
+            run(StateQuery::new().with_custom("tier", "gold")),
+            [states[0].id]
+        );
+        assert_eq!(
+            run(StateQuery::new().with_created(Some(Utc::now() - Duration::days(5)), None)),
+            [states[1].id, states[0].id]
+        );
+    }
+
+    #[test]
+    fn test_sort_and_pagination() {
+        let states: Vec<_> = (0..5).map(|i| state("t", &[], "m", i)).collect();
+        let query = StateQuery::new()
+            .with_sort(SortOrder::CreatedAsc)
+            .with_limit(2);
+
+        let mut seen = Vec::new();
+        let mut page = query.execute(&states).unwrap();
+        loop {
+            seen.extend(ids(&page));
+            match page.next_cursor {
+                Some(cursor) => page = query.clone().with_cursor(cursor).execute(&states).unwrap(),
+                None => break,
+            }
+        }
+
+        let oldest_first: Vec<_> = states.iter().rev().map(|s| s.id).collect();
+        assert_eq!(seen, oldest_first);
+    }
+
+    #[test]
+    fn test_invalid_cursor() {
+        let result = StateQuery::new().with_cursor("nope").paginate(Vec::new());
+        assert!(matches!(result, Err(StateError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn test_summary_from_json() {
+        let state = state("Title", &["a"], "Hello", 0);
+        let json = serde_json::to_string(&state).unwrap();
+        assert_eq!(
+            ConversationSummary::from_json(&json).unwrap(),
+            ConversationSummary::from(&state)
+        );
+    }
+}
@@ -0,0 +1,522 @@
+//! Conversation expiry and retention policies
+//!
+//! A [`RetentionSweeper`] applies a [`RetentionPolicy`] to any
+//! [`StateStore`], deleting conversations that have expired or fall outside
+//! the policy's limits. Conversations tagged with the policy's legal-hold tag
+//! are never deleted.
+
+use crate::{ConversationSummary, StateError, StateResult, StateStore};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+/// Tag that exempts a conversation from deletion by default
+pub const LEGAL_HOLD_TAG: &str = "legal-hold";
+
+/// Capacity of the channel that carries [`RetentionEvent`]s
+const EVENT_CAPACITY: usize = 256;
+
+/// Limits on which conversations a store keeps
+///
+/// Per-conversation expiry (`StateMetadata::expires_at`) always applies; the
+/// other limits apply when set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetentionPolicy {
+    /// Delete conversations created longer ago than this
+    pub max_age: Option<Duration>,
+    /// Keep at most this many conversations with each tag, most recently
+    /// updated first
+    ///
+    /// Each tag is counted over every conversation, so one over the limit
+    /// for any of its tags is deleted.
+    pub max_count_per_tag: BTreeMap<String, usize>,
+    /// Delete the least recently updated conversations while the store holds
+    /// more than this many bytes
+    pub max_total_bytes: Option<u64>,
+    /// Conversations with this tag are never deleted
+    pub legal_hold_tag: String,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_age: None,
+            max_count_per_tag: BTreeMap::new(),
+            max_total_bytes: None,
+            legal_hold_tag: LEGAL_HOLD_TAG.to_string(),
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Create a policy that only enforces per-conversation expiry
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delete conversations created longer ago than `max_age`
+    pub fn with_max_age(mut self, max_age: Duration) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Keep at most `max` conversations tagged `tag`
+    pub fn with_max_count_per_tag(mut self, tag: impl Into<String>, max: usize) -> Self {
+        self.max_count_per_tag.insert(tag.into(), max);
+        self
+    }
+
+    /// Keep the store's total size at or below `max_bytes`
+    pub fn with_max_total_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_total_bytes = Some(max_bytes);
+        self
+    }
+
+    /// Use a different tag to exempt conversations from deletion
+    pub fn with_legal_hold_tag(mut self, tag: impl Into<String>) -> Self {
+        self.legal_hold_tag = tag.into();
+        self
+    }
+}
+
+/// Why a conversation was deleted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DeletionReason {
+    /// Its `expires_at` time passed
+    Expired,
+    /// It is older than the policy's maximum age
+    MaxAge,
+    /// More conversations have this tag than the policy allows
+    TagLimit(String),
+    /// The store exceeded the policy's total size
+    TotalSize,
+}
+
+/// A conversation deleted by a sweep
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionEvent {
+    /// ID of the deleted conversation
+    pub id: Uuid,
+    /// Why it was deleted
+    pub reason: DeletionReason,
+    /// When it was deleted
+    pub deleted_at: DateTime<Utc>,
+}
+
+/// Applies a [`RetentionPolicy`] to a store, once or periodically
+///
+/// # Examples
+///
+/// ```no_run
+/// use chrono::Duration;
+/// use cogni_state::{MemoryStore, RetentionPolicy, RetentionSweeper};
+/// use std::sync::Arc;
+///
+/// # async fn example() {
+/// let store = Arc::new(MemoryStore::new());
+/// let sweeper = RetentionSweeper::new(store, RetentionPolicy::new().with_max_age(Duration::days(30)));
+/// let mut events = sweeper.subscribe();
+/// let handle = sweeper.spawn(std::time::Duration::from_secs(3600));
+///
+/// while let Ok(event) = events.recv().await {
+///     println!("deleted {} ({:?})", event.id, event.reason);
+/// }
+/// # handle.abort();
+/// # }
+/// ```
+pub struct RetentionSweeper {
+    store: Arc<dyn StateStore>,
+    policy: RetentionPolicy,
+    events: broadcast::Sender<RetentionEvent>,
+}
+
+impl RetentionSweeper {
+    /// Create a sweeper for a store
+    pub fn new(store: Arc<dyn StateStore>, policy: RetentionPolicy) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            store,
+            policy,
+            events,
+        }
+    }
+
+    /// The policy this sweeper applies
+    pub fn policy(&self) -> &RetentionPolicy {
+        &self.policy
+    }
+
+    /// Receive an event for every conversation deleted from now on
+    pub fn subscribe(&self) -> broadcast::Receiver<RetentionEvent> {
+        self.events.subscribe()
+    }
+
+    /// Sweep the store once, returning the deleted conversations
+    pub async fn sweep(&self) -> StateResult<Vec<RetentionEvent>> {
+        self.sweep_at(Utc::now()).await
+    }
+
+    /// Sweep the store as if the current time were `now`
+    pub async fn sweep_at(&self, now: DateTime<Utc>) -> StateResult<Vec<RetentionEvent>> {
+        let hold = &self.policy.legal_hold_tag;
+        let summaries = self.store.list_summaries().await?;
+        let (held, mut candidates): (Vec<_>, Vec<_>) = summaries
+            .into_iter()
+            .partition(|s| s.metadata.tags.contains(hold));
+
+        // Most recently updated first, so limits keep the newest
+        candidates.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+
+        let mut doomed: Vec<(ConversationSummary, DeletionReason)> = Vec::new();
+        let mut kept = Vec::new();
+        for summary in candidates {
+            match self.time_reason(&summary, now) {
+                Some(reason) => doomed.push((summary, reason)),
+                None => kept.push(summary),
+            }
+        }
+        let candidates = kept;
+
+        // Count every tag over all candidates before deleting any, so the
+        // result doesn't depend on the order the limits are checked in
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        let mut kept = Vec::new();
+        for summary in candidates {
+            let mut over = None;
+            for (tag, max) in &self.policy.max_count_per_tag {
+                if summary.metadata.tags.contains(tag) {
+                    let count = counts.entry(tag).or_default();
+                    *count += 1;
+                    if *count > *max && over.is_none() {
+                        over = Some(tag.clone());
+                    }
+                }
+            }
+            match over {
+                Some(tag) => doomed.push((summary, DeletionReason::TagLimit(tag))),
+                None => kept.push(summary),
+            }
+        }
+        let mut candidates = kept;
+
+        if let Some(max_bytes) = self.policy.max_total_bytes {
+            let mut total = 0;
+            let mut sizes = HashMap::new();
+            for summary in held.iter().chain(&candidates) {
+                let size = match self.store.size_of(&summary.id).await {
+                    Ok(size) => size,
+                    Err(StateError::NotFound(_)) => 0,
+                    Err(e) => return Err(e),
+                };
+                sizes.insert(summary.id, size);
+                total += size;
+            }
+            while total > max_bytes {
+                let Some(oldest) = candidates.pop() else {
+                    break;
+                };
+                total -= sizes[&oldest.id];
+                doomed.push((oldest, DeletionReason::TotalSize));
+            }
+        }
+
+        let mut deleted = Vec::new();
+        for (listed, reason) in doomed {
+            // The listing may be stale, so check the current copy and delete
+            // only that version
+            let id = listed.id;
+            let state = match self.store.load(&id).await {
+                Ok(state) => state,
+                Err(StateError::NotFound(_)) => continue,
+                Err(e) => return Err(e),
+            };
+            if !self.still_applies(&reason, &listed, &ConversationSummary::from(&state), now) {
+                debug!("Retention kept conversation {}: changed since listing", id);
+                continue;
+            }
+            match self.store.delete_if_version(&id, state.version).await {
+                Ok(()) => {}
+                Err(StateError::NotFound(_)) | Err(StateError::Conflict { .. }) => continue,
+                Err(e) => return Err(e),
+            }
+            debug!("Retention deleted conversation {} ({:?})", id, reason);
+            let event = RetentionEvent {
+                id,
+                reason,
+                deleted_at: Utc::now(),
+            };
+            // No subscribers is fine
+            let _ = self.events.send(event.clone());
+            deleted.push(event);
+        }
+        Ok(deleted)
+    }
+
+    /// Sweep the store every `interval` on a background task
+    ///
+    /// Failed sweeps are logged and retried at the next interval. Abort the
+    /// returned handle to stop sweeping.
+    pub fn spawn(self, interval: std::time::Duration) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.sweep().await {
+                    warn!("Retention sweep failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Whether `reason` still holds for the current copy of a conversation
+    /// that was listed as `listed`
+    fn still_applies(
+        &self,
+        reason: &DeletionReason,
+        listed: &ConversationSummary,
+        current: &ConversationSummary,
+        now: DateTime<Utc>,
+    ) -> bool {
+        if current.metadata.tags.contains(&self.policy.legal_hold_tag) {
+            return false;
+        }
+        match reason {
+            DeletionReason::Expired | DeletionReason::MaxAge => {
+                self.time_reason(current, now).is_some()
+            }
+            // Limits rank conversations by when they were last updated
+            DeletionReason::TagLimit(tag) => {
+                current.metadata.tags.contains(tag) && current.updated_at == listed.updated_at
+            }
+            DeletionReason::TotalSize => current.updated_at == listed.updated_at,
+        }
+    }
+
+    fn time_reason(
+        &self,
+        summary: &ConversationSummary,
+        now: DateTime<Utc>,
+    ) -> Option<DeletionReason> {
+        if summary.metadata.expires_at.is_some_and(|at| at <= now) {
+            return Some(DeletionReason::Expired);
+        }
+        self.policy
+            .max_age
+            .filter(|max_age| now - summary.created_at > *max_age)
+            .map(|_| DeletionReason::MaxAge)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ConversationState, MemoryStore};
+    use async_trait::async_trait;
+    use cogni_core::Message;
+
+    /// Store that puts a conversation on hold right after listing it
+    struct HoldAfterList {
+        inner: MemoryStore,
+        target: Uuid,
+    }
+
+    #[async_trait]
+    impl StateStore for HoldAfterList {
+        async fn save(&self, state: &ConversationState) -> StateResult<()> {
+            self.inner.save(state).await
+        }
+
+        async fn save_if_version(
+            &self,
+            state: &ConversationState,
+            expected: u64,
+        ) -> StateResult<u64> {
+            self.inner.save_if_version(state, expected).await
+        }
+
+        async fn load(&self, id: &Uuid) -> StateResult<ConversationState> {
+            self.inner.load(id).await
+        }
+
+        async fn delete(&self, id: &Uuid) -> StateResult<()> {
+            self.inner.delete(id).await
+        }
+
+        async fn list(&self) -> StateResult<Vec<ConversationState>> {
+            self.inner.list().await
+        }
+
+        async fn list_summaries(&self) -> StateResult<Vec<ConversationSummary>> {
+            let summaries = self.inner.list_summaries().await?;
+            let mut state = self.inner.load(&self.target).await?;
+            state.add_tag(LEGAL_HOLD_TAG);
+            self.inner.save(&state).await?;
+            Ok(summaries)
+        }
+    }
+
+    async fn save(store: &MemoryStore, tags: &[&str], age_days: i64) -> Uuid {
+        let mut state = ConversationState::new();
+        for tag in tags {
+            state.add_tag(*tag);
+        }
+        state.add_message(Message::user("x".repeat(100)));
+        state.created_at = Utc::now() - Duration::days(age_days);
+        state.updated_at = state.created_at;
+        store.save(&state).await.unwrap();
+        state.id
+    }
+
+    #[tokio::test]
+    async fn test_expiry_and_max_age() {
+        let store = Arc::new(MemoryStore::new());
+        let fresh = save(&store, &[], 1).await;
+        let old = save(&store, &[], 40).await;
+        let held = save(&store, &[LEGAL_HOLD_TAG], 40).await;
+
+        let mut expiring = ConversationState::new();
+        expiring.expire_in(Duration::hours(1));
+        store.save(&expiring).await.unwrap();
+
+        let sweeper = RetentionSweeper::new(
+            store.clone(),
+            RetentionPolicy::new().with_max_age(Duration::days(30)),
+        );
+        let mut events = sweeper.subscribe();
+
+        let deleted = sweeper
+            .sweep_at(Utc::now() + Duration::hours(2))
+            .await
+            .unwrap();
+        let mut reasons: Vec<_> = deleted.iter().map(|e| (e.id, e.reason.clone())).collect();
+        reasons.sort_by_key(|(id, _)| *id != old);
+        assert_eq!(
+            reasons,
+            [
+                (old, DeletionReason::MaxAge),
+                (expiring.id, DeletionReason::Expired)
+            ]
+        );
+        assert_eq!(events.recv().await.unwrap(), deleted[0]);
+
+        assert!(store.exists(&fresh).await.unwrap());
+        assert!(store.exists(&held).await.unwrap());
+        assert_eq!(store.len().await, 2);
+    }
+
+    #[tokio::test]
+    async fn test_tag_limit_keeps_newest() {
+        let store = Arc::new(MemoryStore::new());
+        let newest = save(&store, &["chat"], 1).await;
+        let middle = save(&store, &["chat"], 2).await;
+        let oldest = save(&store, &["chat"], 3).await;
+        let other = save(&store, &["other"], 4).await;
+
+        let sweeper = RetentionSweeper::new(
+            store.clone(),
+            RetentionPolicy::new().with_max_count_per_tag("chat", 2),
+        );
+        let deleted = sweeper.sweep().await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].id, oldest);
+        assert_eq!(deleted[0].reason, DeletionReason::TagLimit("chat".into()));
+
+        for id in [newest, middle, other] {
+            assert!(store.exists(&id).await.unwrap());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tag_limits_count_every_conversation() {
+        let store = Arc::new(MemoryStore::new());
+        let newest = save(&store, &["a"], 1).await;
+        let both = save(&store, &["a", "b"], 2).await;
+        let oldest = save(&store, &["b"], 3).await;
+
+        // `both` is over the limit for "a", and still counts towards "b"
+        let sweeper = RetentionSweeper::new(
+            store.clone(),
+            RetentionPolicy::new()
+                .with_max_count_per_tag("b", 1)
+                .with_max_count_per_tag("a", 1),
+        );
+        let mut deleted: Vec<_> = sweeper
+            .sweep()
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| (e.id, e.reason))
+            .collect();
+        deleted.sort_by_key(|(id, _)| *id != both);
+        assert_eq!(
+            deleted,
+            [
+                (both, DeletionReason::TagLimit("a".into())),
+                (oldest, DeletionReason::TagLimit("b".into()))
+            ]
+        );
+        assert!(store.exists(&newest).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_total_size_deletes_least_recent() {
+        let store = Arc::new(MemoryStore::new());
+        let held = save(&store, &[LEGAL_HOLD_TAG], 9).await;
+        let old = save(&store, &[], 5).await;
+        let new = save(&store, &[], 1).await;
+
+        let size = store.size_of(&new).await.unwrap();
+        let sweeper = RetentionSweeper::new(
+            store.clone(),
+            RetentionPolicy::new().with_max_total_bytes(size * 2 + size / 2),
+        );
+        let deleted = sweeper.sweep().await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].id, old);
+        assert!(store.exists(&held).await.unwrap());
+        assert!(store.exists(&new).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_hold_placed_after_listing_is_respected() {
+        let inner = MemoryStore::new();
+        let held = save(&inner, &["chat"], 40).await;
+        let old = save(&inner, &["chat"], 50).await;
+        let store = Arc::new(HoldAfterList {
+            inner,
+            target: held,
+        });
+
+        let sweeper = RetentionSweeper::new(
+            store.clone(),
+            RetentionPolicy::new().with_max_age(Duration::days(30)),
+        );
+        let deleted = sweeper.sweep().await.unwrap();
+        assert_eq!(deleted.len(), 1);
+        assert_eq!(deleted[0].id, old);
+        assert!(store.exists(&held).await.unwrap());
+        assert!(!store.exists(&old).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_spawned_sweeper_emits_events() {
+        let store = Arc::new(MemoryStore::new());
+        let mut state = ConversationState::new();
+        state.set_expiry(Utc::now() - Duration::seconds(1));
+        store.save(&state).await.unwrap();
+
+        let sweeper = RetentionSweeper::new(store.clone(), RetentionPolicy::new());
+        let mut events = sweeper.subscribe();
+        let handle = sweeper.spawn(std::time::Duration::from_millis(10));
+
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.id, state.id);
+        assert_eq!(event.reason, DeletionReason::Expired);
+        handle.abort();
+        assert!(store.is_empty().await);
+    }
+}
@@ -1,30 +1,96 @@
 //! State storage implementations
 
-use crate::{ConversationState, StateError, StateResult};
+use crate::{
+    ConversationState, ConversationSummary, QueryPage, StateError, StateQuery, StateResult,
+};
 use async_trait::async_trait;
 use uuid::Uuid;
 
+mod blob;
 mod file;
 mod memory;
+#[cfg(feature = "redis")]
+mod redis;
 
+#[cfg(feature = "redis")]
+pub use self::redis::RedisStore;
+pub use blob::{BlobStore, FileBlobStore, MemoryBlobStore};
 pub use file::FileStore;
 pub use memory::MemoryStore;
 
+/// Version to store over `current`, checking it against `expected` if given
+pub(crate) fn next_version(id: Uuid, current: u64, expected: Option<u64>) -> StateResult<u64> {
+    match expected {
+        Some(expected) if expected != current => Err(StateError::Conflict {
+            id,
+            expected,
+            actual: current,
+        }),
+        _ => Ok(current + 1),
+    }
+}
+
 /// Trait for storing and retrieving conversation states
 #[async_trait]
 pub trait StateStore: Send + Sync {
     /// Save a conversation state
+    ///
+    /// The stored copy's [`version`](ConversationState::version) is one more
+    /// than the version it replaces, whatever `state.version` says.
     async fn save(&self, state: &ConversationState) -> StateResult<()>;
 
+    /// Save a conversation only if the stored version is still `expected`
+    ///
+    /// Use zero for a conversation that must not exist yet. This is a
+    /// compare-and-swap: if another writer saved first, nothing is written
+    /// and [`StateError::Conflict`] is returned. On success the stored copy
+    /// has version `expected + 1`, which is returned.
+    async fn save_if_version(&self, state: &ConversationState, expected: u64) -> StateResult<u64>;
+
     /// Load a conversation state by ID
     async fn load(&self, id: &Uuid) -> StateResult<ConversationState>;
 
     /// Delete a conversation state
     async fn delete(&self, id: &Uuid) -> StateResult<()>;
 
+    /// Delete a conversation only if the stored version is still `expected`
+    ///
+    /// If another writer saved first, nothing is deleted and
+    /// [`StateError::Conflict`] is returned. The default implementation
+    /// checks the version and then deletes, so a write landing in between is
+    /// lost; the built-in stores check and delete atomically.
+    async fn delete_if_version(&self, id: &Uuid, expected: u64) -> StateResult<()> {
+        let current = self.load(id).await?.version;
+        next_version(*id, current, Some(expected))?;
+        self.delete(id).await
+    }
+
     /// List all conversation states
     async fn list(&self) -> StateResult<Vec<ConversationState>>;
 
+    /// Find conversations matching a query, one page at a time
+    ///
+    /// The default implementation loads every conversation; stores with an
+    /// index should override it.
+    async fn query(&self, query: &StateQuery) -> StateResult<QueryPage> {
+        let states = self.list().await?;
+        query.execute(&states)
+    }
+
+    /// List every conversation's metadata without its messages
+    async fn list_summaries(&self) -> StateResult<Vec<ConversationSummary>> {
+        let states = self.list().await?;
+        Ok(states.iter().map(ConversationSummary::from).collect())
+    }
+
+    /// Approximate storage size of a conversation in bytes
+    ///
+    /// The default implementation measures the conversation's JSON.
+    async fn size_of(&self, id: &Uuid) -> StateResult<u64> {
+        let state = self.load(id).await?;
+        Ok(serde_json::to_vec(&state)?.len() as u64)
+    }
+
     /// Find conversations by tags
     async fn find_by_tags(&self, tags: &[String]) -> StateResult<Vec<ConversationState>> {
         let all_states = self.list().await?;
@@ -81,6 +147,7 @@ mod tests {
         state.add_message(Message {
             role: Role::User,
             content: Content::Text("Hello".to_string()),
+            tool_calls: Vec::new(),
             metadata: Metadata::default(),
         });
         state
@@ -0,0 +1,205 @@
+//! Raw byte storage for serialized conversations
+
+use super::file::write_atomic;
+use crate::{StateError, StateResult};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::fs;
+use tokio::sync::RwLock;
+use tracing::debug;
+use uuid::Uuid;
+
+/// Trait for storing opaque byte blobs keyed by conversation ID
+///
+/// Blob stores know nothing about the format of what they hold, which lets
+/// layers such as [`EncryptedStore`](crate::EncryptedStore) transform
+/// conversations before they reach the backend.
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    /// Store a blob, replacing any previous one
+    async fn put(&self, id: &Uuid, bytes: Vec<u8>) -> StateResult<()>;
+
+    /// Fetch a blob by ID
+    async fn get(&self, id: &Uuid) -> StateResult<Vec<u8>>;
+
+    /// Delete a blob
+    async fn remove(&self, id: &Uuid) -> StateResult<()>;
+
+    /// List the IDs of every stored blob
+    async fn ids(&self) -> StateResult<Vec<Uuid>>;
+
+    /// Size of a blob in bytes
+    async fn len(&self, id: &Uuid) -> StateResult<u64> {
+        Ok(self.get(id).await?.len() as u64)
+    }
+
+    /// Check if a blob exists
+    async fn contains(&self, id: &Uuid) -> StateResult<bool> {
+        match self.get(id).await {
+            Ok(_) => Ok(true),
+            Err(StateError::NotFound(_)) => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// File-based blob store
+///
+/// Each blob is written atomically to a `<uuid>.bin` file in a directory.
+#[derive(Debug, Clone)]
+pub struct FileBlobStore {
+    base_path: PathBuf,
+}
+
+impl FileBlobStore {
+    /// Extension of blob files
+    const EXTENSION: &'static str = "bin";
+
+    /// Create a new blob store at the given path
+    ///
+    /// The directory will be created if it doesn't exist.
+    pub fn new(base_path: impl AsRef<Path>) -> StateResult<Self> {
+        let base_path = base_path.as_ref().to_path_buf();
+        std::fs::create_dir_all(&base_path)
+            .map_err(|e| StateError::Configuration(format!("Failed to create directory: {}", e)))?;
+
+        debug!("Initialized file blob store at: {:?}", base_path);
+        Ok(Self { base_path })
+    }
+
+    fn get_file_path(&self, id: &Uuid) -> PathBuf {
+        self.base_path.join(format!("{}.{}", id, Self::EXTENSION))
+    }
+}
+
+#[async_trait]
+impl BlobStore for FileBlobStore {
+    async fn put(&self, id: &Uuid, bytes: Vec<u8>) -> StateResult<()> {
+        write_atomic(&self.get_file_path(id), &bytes).await
+    }
+
+    async fn get(&self, id: &Uuid) -> StateResult<Vec<u8>> {
+        match fs::read(self.get_file_path(id)).await {
+            Ok(bytes) => Ok(bytes),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StateError::NotFound(*id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn remove(&self, id: &Uuid) -> StateResult<()> {
+        match fs::remove_file(self.get_file_path(id)).await {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StateError::NotFound(*id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn ids(&self) -> StateResult<Vec<Uuid>> {
+        let mut entries = fs::read_dir(&self.base_path).await?;
+        let mut ids = Vec::new();
+
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|s| s.to_str()) != Some(Self::EXTENSION) {
+                continue;
+            }
+            if let Some(id) = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .and_then(|s| Uuid::parse_str(s).ok())
+            {
+                ids.push(id);
+            }
+        }
+
+        Ok(ids)
+    }
+
+    async fn len(&self, id: &Uuid) -> StateResult<u64> {
+        match fs::metadata(self.get_file_path(id)).await {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StateError::NotFound(*id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn contains(&self, id: &Uuid) -> StateResult<bool> {
+        Ok(self.get_file_path(id).exists())
+    }
+}
+
+/// In-memory blob store
+///
+/// Blobs are lost when the store is dropped; mainly useful for testing.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryBlobStore {
+    blobs: Arc<RwLock<HashMap<Uuid, Vec<u8>>>>,
+}
+
+impl MemoryBlobStore {
+    /// Create a new empty blob store
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[async_trait]
+impl BlobStore for MemoryBlobStore {
+    async fn put(&self, id: &Uuid, bytes: Vec<u8>) -> StateResult<()> {
+        self.blobs.write().await.insert(*id, bytes);
+        Ok(())
+    }
+
+    async fn get(&self, id: &Uuid) -> StateResult<Vec<u8>> {
+        self.blobs
+            .read()
+            .await
+            .get(id)
+            .cloned()
+            .ok_or(StateError::NotFound(*id))
+    }
+
+    async fn remove(&self, id: &Uuid) -> StateResult<()> {
+        self.blobs
+            .write()
+            .await
+            .remove(id)
+            .map(|_| ())
+            .ok_or(StateError::NotFound(*id))
+    }
+
+    async fn ids(&self) -> StateResult<Vec<Uuid>> {
+        Ok(self.blobs.read().await.keys().copied().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_file_blob_store() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let store = FileBlobStore::new(temp_dir.path()).unwrap();
+        let id = Uuid::new_v4();
+
+        assert!(matches!(store.get(&id).await, Err(StateError::NotFound(_))));
+        store.put(&id, vec![1, 2, 3]).await.unwrap();
+        assert_eq!(store.get(&id).await.unwrap(), [1, 2, 3]);
+        assert_eq!(store.len(&id).await.unwrap(), 3);
+        assert_eq!(store.ids().await.unwrap(), [id]);
+
+        // Unrelated files in the directory are ignored
+        std::fs::write(temp_dir.path().join("notes.txt"), "hi").unwrap();
+        assert_eq!(store.ids().await.unwrap().len(), 1);
+
+        store.remove(&id).await.unwrap();
+        assert!(!store.contains(&id).await.unwrap());
+        assert!(matches!(
+            store.remove(&id).await,
+            Err(StateError::NotFound(_))
+        ));
+    }
+}
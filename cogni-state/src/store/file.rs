@@ -1,20 +1,54 @@
 //! File-based state storage implementation
 
-use crate::{ConversationState, StateError, StateResult, StateStore};
+use super::next_version;
+use crate::{
+    ConversationState, ConversationSummary, QueryPage, StateError, StateQuery, StateResult,
+    StateStore,
+};
 use async_trait::async_trait;
+use fs4::fs_std::FileExt;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
+use tokio::sync::{Mutex, MutexGuard};
 use tracing::{debug, error, trace, warn};
 use uuid::Uuid;
 
+/// Name of the summary index kept alongside the conversation files
+const INDEX_FILE: &str = "index.json";
+
+/// Name of the file locked while the store is written to
+const LOCK_FILE: &str = ".lock";
+
 /// File-based state store implementation
 ///
 /// This store persists conversation states as JSON files in a directory.
 /// Each conversation is stored as a separate file named by its UUID.
+///
+/// Conversation metadata is also kept in an `index.json` file so that
+/// [`list_summaries`](StateStore::list_summaries) and
+/// [`query`](StateStore::query) don't have to read every conversation. The
+/// index is brought back in step with the files whenever it's read.
+///
+/// Writes take an advisory lock on a `.lock` file in the directory, so
+/// several processes can share a store without losing updates. Files are
+/// replaced by renaming a uniquely named temp file over them, which means
+/// readers never see a partial write and don't need the lock.
 #[derive(Debug, Clone)]
 pub struct FileStore {
     base_path: PathBuf,
+    write_lock: Arc<Mutex<()>>,
+}
+
+/// Exclusive write access to a [`FileStore`] directory
+///
+/// The in-process mutex orders tasks sharing this store; the file lock keeps
+/// out other processes. Both are released on drop.
+struct StoreLock<'a> {
+    _guard: MutexGuard<'a, ()>,
+    _file: std::fs::File,
 }
 
 impl FileStore {
@@ -29,7 +63,10 @@ impl FileStore {
             .map_err(|e| StateError::Configuration(format!("Failed to create directory: {}", e)))?;
 
         debug!("Initialized file store at: {:?}", base_path);
-        Ok(Self { base_path })
+        Ok(Self {
+            base_path,
+            write_lock: Arc::new(Mutex::new(())),
+        })
     }
 
     /// Get the file path for a conversation
@@ -44,38 +81,209 @@ impl FileStore {
 
         while let Some(entry) = entries.next_entry().await? {
             let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("json") {
+            if path.extension().and_then(|s| s.to_str()) == Some("json")
+                && path.file_name().and_then(|s| s.to_str()) != Some(INDEX_FILE)
+            {
                 files.push(path);
             }
         }
 
         Ok(files)
     }
-}
 
-#[async_trait]
-impl StateStore for FileStore {
-    async fn save(&self, state: &ConversationState) -> StateResult<()> {
+    /// Lock the store for writing, waiting for other writers to finish
+    async fn lock(&self) -> StateResult<StoreLock<'_>> {
+        let guard = self.write_lock.lock().await;
+        let path = self.base_path.join(LOCK_FILE);
+        let file = tokio::task::spawn_blocking(move || {
+            let file = std::fs::OpenOptions::new()
+                .create(true)
+                .truncate(false)
+                .write(true)
+                .open(path)?;
+            file.lock_exclusive()?;
+            Ok::<_, std::io::Error>(file)
+        })
+        .await
+        .map_err(|e| StateError::lock_contention(format!("Failed to lock store: {}", e)))??;
+
+        Ok(StoreLock {
+            _guard: guard,
+            _file: file,
+        })
+    }
+
+    /// Version of the stored copy of a conversation, zero if there is none
+    async fn stored_version(&self, id: &Uuid) -> StateResult<u64> {
+        #[derive(serde::Deserialize)]
+        struct Versioned {
+            #[serde(default)]
+            version: u64,
+        }
+
+        match fs::read(self.get_file_path(id)).await {
+            Ok(json) => Ok(serde_json::from_slice::<Versioned>(&json)?.version),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Write a conversation under the lock, returning its new version
+    async fn write(&self, state: &ConversationState, expected: Option<u64>) -> StateResult<u64> {
         let path = self.get_file_path(&state.id);
-        trace!("Saving conversation {} to file: {:?}", state.id, path);
+        let _lock = self.lock().await?;
+        let version = next_version(state.id, self.stored_version(&state.id).await?, expected)?;
+
+        let mut state = state.clone();
+        state.version = version;
+        let json = serde_json::to_string_pretty(&state)?;
+        write_atomic(&path, json.as_bytes()).await?;
+        self.update_index(state.id, Some(ConversationSummary::from(&state)))
+            .await?;
+        Ok(version)
+    }
+
+    /// Delete a conversation under the lock, checking its version if given
+    async fn remove(&self, id: &Uuid, expected: Option<u64>) -> StateResult<()> {
+        let path = self.get_file_path(id);
+        let _lock = self.lock().await?;
+        if expected.is_some() {
+            next_version(*id, self.stored_version(id).await?, expected)?;
+        }
+
+        match fs::remove_file(&path).await {
+            Ok(()) => {
+                self.update_index(*id, None).await?;
+                debug!("Deleted conversation {} from file: {:?}", id, path);
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StateError::NotFound(*id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Rebuild the summary index from the conversation files
+    pub async fn rebuild_index(&self) -> StateResult<()> {
+        let _lock = self.lock().await?;
+        let mut index = HashMap::new();
+        self.reconcile_index(&mut index).await?;
+        self.write_index(&index).await
+    }
+
+    fn index_path(&self) -> PathBuf {
+        self.base_path.join(INDEX_FILE)
+    }
+
+    /// Read the index, treating a missing or unreadable one as empty
+    async fn read_index(&self) -> HashMap<Uuid, ConversationSummary> {
+        let json = match fs::read_to_string(self.index_path()).await {
+            Ok(json) => json,
+            Err(_) => return HashMap::new(),
+        };
+        match serde_json::from_str::<Vec<ConversationSummary>>(&json) {
+            Ok(summaries) => summaries.into_iter().map(|s| (s.id, s)).collect(),
+            Err(e) => {
+                warn!("Ignoring unreadable index {:?}: {}", self.index_path(), e);
+                HashMap::new()
+            }
+        }
+    }
+
+    async fn write_index(&self, index: &HashMap<Uuid, ConversationSummary>) -> StateResult<()> {
+        let summaries: Vec<_> = index.values().collect();
+        write_atomic(&self.index_path(), &serde_json::to_vec(&summaries)?).await
+    }
 
-        // Serialize to pretty JSON
-        let json = serde_json::to_string_pretty(state)?;
+    /// Insert or remove one conversation's index entry
+    ///
+    /// The caller must hold the store lock.
+    async fn update_index(
+        &self,
+        id: Uuid,
+        summary: Option<ConversationSummary>,
+    ) -> StateResult<()> {
+        let mut index = self.read_index().await;
+        match summary {
+            Some(summary) => index.insert(id, summary),
+            None => index.remove(&id),
+        };
+        self.write_index(&index).await
+    }
 
-        // Write atomically by writing to temp file first
-        let temp_path = path.with_extension("tmp");
+    /// Drop entries without a file and add files without an entry
+    ///
+    /// Returns whether the index changed.
+    async fn reconcile_index(
+        &self,
+        index: &mut HashMap<Uuid, ConversationSummary>,
+    ) -> StateResult<bool> {
+        let ids: HashSet<Uuid> = self.list_ids().await?.into_iter().collect();
+        let before = index.len();
+        index.retain(|id, _| ids.contains(id));
+        let mut changed = index.len() != before;
+
+        let missing: Vec<Uuid> = ids
+            .into_iter()
+            .filter(|id| !index.contains_key(id))
+            .collect();
+        for id in missing {
+            let summary = match fs::read_to_string(self.get_file_path(&id)).await {
+                Ok(json) => ConversationSummary::from_json(&json),
+                Err(e) => Err(e.into()),
+            };
+            match summary {
+                Ok(summary) => {
+                    index.insert(id, summary);
+                    changed = true;
+                }
+                Err(e) => warn!("Failed to index conversation {}: {}", id, e),
+            }
+        }
+        Ok(changed)
+    }
+}
+
+/// Write a file by renaming a fully written temp file over it
+///
+/// Each write uses its own temp file, so concurrent writers never share one.
+pub(super) async fn write_atomic(path: &Path, bytes: &[u8]) -> StateResult<()> {
+    let temp_path = path.with_extension(format!("{}.tmp", Uuid::new_v4().simple()));
+    let result = async {
         let mut file = fs::File::create(&temp_path).await?;
-        file.write_all(json.as_bytes()).await?;
+        file.write_all(bytes).await?;
         file.sync_all().await?;
         drop(file);
 
         // Rename temp file to final name (atomic on most filesystems)
-        fs::rename(&temp_path, &path).await?;
+        fs::rename(&temp_path, path).await
+    }
+    .await;
 
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path).await;
+    }
+    Ok(result?)
+}
+
+#[async_trait]
+impl StateStore for FileStore {
+    async fn save(&self, state: &ConversationState) -> StateResult<()> {
+        let path = self.get_file_path(&state.id);
+        trace!("Saving conversation {} to file: {:?}", state.id, path);
+        self.write(state, None).await?;
         debug!("Saved conversation {} to file: {:?}", state.id, path);
         Ok(())
     }
 
+    async fn save_if_version(&self, state: &ConversationState, expected: u64) -> StateResult<u64> {
+        trace!(
+            "Saving conversation {} to file if at version {}",
+            state.id,
+            expected
+        );
+        self.write(state, Some(expected)).await
+    }
+
     async fn load(&self, id: &Uuid) -> StateResult<ConversationState> {
         let path = self.get_file_path(id);
         trace!("Loading conversation {} from file: {:?}", id, path);
@@ -100,17 +308,17 @@ impl StateStore for FileStore {
     }
 
     async fn delete(&self, id: &Uuid) -> StateResult<()> {
-        let path = self.get_file_path(id);
-        trace!("Deleting conversation {} from file: {:?}", id, path);
+        trace!("Deleting conversation {} from file store", id);
+        self.remove(id, None).await
+    }
 
-        match fs::remove_file(&path).await {
-            Ok(()) => {
-                debug!("Deleted conversation {} from file: {:?}", id, path);
-                Ok(())
-            }
-            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StateError::NotFound(*id)),
-            Err(e) => Err(e.into()),
-        }
+    async fn delete_if_version(&self, id: &Uuid, expected: u64) -> StateResult<()> {
+        trace!(
+            "Deleting conversation {} from file store if at version {}",
+            id,
+            expected
+        );
+        self.remove(id, Some(expected)).await
     }
 
     async fn list(&self) -> StateResult<Vec<ConversationState>> {
@@ -140,12 +348,52 @@ impl StateStore for FileStore {
         }
 
         // Sort by updated_at descending (most recent first)
-        states.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        states.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
 
         debug!("Listed {} conversations from file store", states.len());
         Ok(states)
     }
 
+    async fn query(&self, query: &StateQuery) -> StateResult<QueryPage> {
+        trace!("Querying conversations in file store");
+        let mut matching = Vec::new();
+        for summary in self.list_summaries().await? {
+            if !query.matches_summary(&summary) {
+                continue;
+            }
+            // Message text isn't indexed, so only candidates are loaded
+            if query.text.is_some() {
+                match self.load(&summary.id).await {
+                    Ok(state) if query.matches_text(&state) => {}
+                    Ok(_) | Err(StateError::NotFound(_)) => continue,
+                    Err(e) => return Err(e),
+                }
+            }
+            matching.push(summary);
+        }
+        query.paginate(matching)
+    }
+
+    async fn list_summaries(&self) -> StateResult<Vec<ConversationSummary>> {
+        let _lock = self.lock().await?;
+        let mut index = self.read_index().await;
+        if self.reconcile_index(&mut index).await? {
+            self.write_index(&index).await?;
+        }
+
+        let mut summaries: Vec<_> = index.into_values().collect();
+        summaries.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        Ok(summaries)
+    }
+
+    async fn size_of(&self, id: &Uuid) -> StateResult<u64> {
+        match fs::metadata(self.get_file_path(id)).await {
+            Ok(metadata) => Ok(metadata.len()),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StateError::NotFound(*id)),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     async fn exists(&self, id: &Uuid) -> StateResult<bool> {
         let path = self.get_file_path(id);
         Ok(path.exists())
@@ -185,6 +433,7 @@ mod tests {
         state.add_message(Message {
             role: Role::User,
             content: Content::Text("Hello".to_string()),
+            tool_calls: Vec::new(),
             metadata: Metadata::default(),
         });
 
@@ -198,7 +447,7 @@ mod tests {
         // Load
         let loaded = store.load(&state.id).await.unwrap();
         assert_eq!(loaded.metadata.title, Some("Test".to_string()));
-        assert_eq!(loaded.messages.len(), 1);
+        assert_eq!(loaded.messages().len(), 1);
 
         // Update
         state.add_tag("updated");
@@ -220,6 +469,70 @@ mod tests {
         ));
     }
 
+    #[tokio::test]
+    async fn test_file_store_branches_and_legacy_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileStore::new(temp_dir.path()).unwrap();
+
+        let mut state = ConversationState::new();
+        state.add_messages([Message::user("Hi"), Message::assistant("Hello!")]);
+        state.rewind_last_turn().unwrap();
+        state.add_message(Message::assistant("Hey!"));
+        store.save(&state).await.unwrap();
+
+        let loaded = store.load(&state.id).await.unwrap();
+        assert_eq!(loaded.nodes(), state.nodes());
+        assert_eq!(loaded.siblings(1).unwrap().len(), 2);
+        assert_eq!(loaded.messages()[1].content.as_text(), Some("Hey!"));
+
+        // Files written before conversations were trees hold a flat list
+        let id = Uuid::new_v4();
+        let legacy = serde_json::json!({
+            "id": id,
+            "messages": [Message::user("Hi"), Message::assistant("Hello!")],
+            "metadata": { "title": "Old", "tags": [], "agent_config": null, "token_count": null, "custom": {} },
+            "created_at": "2025-01-01T00:00:00Z",
+            "updated_at": "2025-01-01T00:00:00Z",
+        });
+        std::fs::write(store.get_file_path(&id), legacy.to_string()).unwrap();
+
+        let migrated = store.load(&id).await.unwrap();
+        assert_eq!(migrated.messages().len(), 2);
+        assert_eq!(migrated.branch().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_file_store_query_uses_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = FileStore::new(temp_dir.path()).unwrap();
+
+        let mut billing = ConversationState::new();
+        billing.add_tag("support");
+        billing.add_message(Message::user("Where is my refund?"));
+        store.save(&billing).await.unwrap();
+
+        let mut login = ConversationState::new();
+        login.add_tag("support");
+        login.set_title("Login");
+        store.save(&login).await.unwrap();
+        store.save(&ConversationState::new()).await.unwrap();
+
+        let support = StateQuery::new().with_any_tags(["support"]);
+        let page = store.query(&support).await.unwrap();
+        assert_eq!(page.conversations.len(), 2);
+
+        let page = store.query(&support.with_text("refund")).await.unwrap();
+        assert_eq!(page.conversations[0].id, billing.id);
+        assert_eq!(page.conversations[0].message_count, 1);
+
+        // A file added behind the store's back is picked up, and a deleted
+        // one dropped, the next time the index is read
+        std::fs::remove_file(store.index_path()).unwrap();
+        store.delete(&login.id).await.unwrap();
+        assert_eq!(store.list_summaries().await.unwrap().len(), 2);
+        assert!(store.index_path().exists());
+    }
+
     #[tokio::test]
     async fn test_file_store_concurrent_access() {
         let temp_dir = TempDir::new().unwrap();
@@ -254,6 +567,51 @@ mod tests {
         }
     }
 
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_file_store_save_if_version_under_contention() {
+        let temp_dir = TempDir::new().unwrap();
+        let state = ConversationState::new();
+        let id = state.id;
+        FileStore::new(temp_dir.path())
+            .unwrap()
+            .save_if_version(&state, 0)
+            .await
+            .unwrap();
+
+        // Separate stores share only the directory, like separate processes
+        let mut handles = vec![];
+        for i in 0..8 {
+            let store = FileStore::new(temp_dir.path()).unwrap();
+            handles.push(tokio::spawn(async move {
+                loop {
+                    let mut state = store.load(&id).await.unwrap();
+                    let expected = state.version;
+                    state.add_message(Message::user(format!("writer {}", i)));
+                    match store.save_if_version(&state, expected).await {
+                        Ok(_) => break,
+                        Err(StateError::Conflict { .. }) => continue,
+                        Err(e) => panic!("unexpected error: {}", e),
+                    }
+                }
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let store = FileStore::new(temp_dir.path()).unwrap();
+        let state = store.load(&id).await.unwrap();
+        assert_eq!(state.messages().len(), 8);
+        assert_eq!(state.version, 9);
+
+        // Every temp file was renamed into place
+        let leftovers = std::fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter(|e| e.as_ref().unwrap().path().extension() == Some("tmp".as_ref()))
+            .count();
+        assert_eq!(leftovers, 0);
+    }
+
     #[tokio::test]
     async fn test_file_store_invalid_files() {
         let temp_dir = TempDir::new().unwrap();
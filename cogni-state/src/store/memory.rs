@@ -1,6 +1,10 @@
 //! In-memory state storage implementation
 
-use crate::{ConversationState, StateError, StateResult, StateStore};
+use super::next_version;
+use crate::{
+    ConversationState, ConversationSummary, QueryPage, StateError, StateQuery, StateResult,
+    StateStore,
+};
 use async_trait::async_trait;
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -44,6 +48,16 @@ impl MemoryStore {
         self.states.read().await.is_empty()
     }
 
+    async fn write(&self, state: &ConversationState, expected: Option<u64>) -> StateResult<u64> {
+        let mut states = self.states.write().await;
+        let current = states.get(&state.id).map_or(0, |s| s.version);
+        let version = next_version(state.id, current, expected)?;
+        let mut state = state.clone();
+        state.version = version;
+        states.insert(state.id, state);
+        Ok(version)
+    }
+
     /// Clear all stored conversations
     pub async fn clear(&self) {
         self.states.write().await.clear();
@@ -61,12 +75,20 @@ impl Default for MemoryStore {
 impl StateStore for MemoryStore {
     async fn save(&self, state: &ConversationState) -> StateResult<()> {
         trace!("Saving conversation {} to memory store", state.id);
-        let mut states = self.states.write().await;
-        states.insert(state.id, state.clone());
+        self.write(state, None).await?;
         debug!("Saved conversation {} to memory store", state.id);
         Ok(())
     }
 
+    async fn save_if_version(&self, state: &ConversationState, expected: u64) -> StateResult<u64> {
+        trace!(
+            "Saving conversation {} to memory store if at version {}",
+            state.id,
+            expected
+        );
+        self.write(state, Some(expected)).await
+    }
+
     async fn load(&self, id: &Uuid) -> StateResult<ConversationState> {
         trace!("Loading conversation {} from memory store", id);
         let states = self.states.read().await;
@@ -84,12 +106,26 @@ impl StateStore for MemoryStore {
         }
     }
 
+    async fn delete_if_version(&self, id: &Uuid, expected: u64) -> StateResult<()> {
+        trace!(
+            "Deleting conversation {} from memory store if at version {}",
+            id,
+            expected
+        );
+        let mut states = self.states.write().await;
+        let current = states.get(id).ok_or(StateError::NotFound(*id))?.version;
+        next_version(*id, current, Some(expected))?;
+        states.remove(id);
+        debug!("Deleted conversation {} from memory store", id);
+        Ok(())
+    }
+
     async fn list(&self) -> StateResult<Vec<ConversationState>> {
         trace!("Listing all conversations from memory store");
         let states = self.states.read().await;
         let mut conversations: Vec<_> = states.values().cloned().collect();
         // Sort by updated_at descending (most recent first)
-        conversations.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+        conversations.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
         debug!(
             "Listed {} conversations from memory store",
             conversations.len()
@@ -97,6 +133,17 @@ impl StateStore for MemoryStore {
         Ok(conversations)
     }
 
+    async fn query(&self, query: &StateQuery) -> StateResult<QueryPage> {
+        trace!("Querying conversations in memory store");
+        let states = self.states.read().await;
+        query.execute(states.values())
+    }
+
+    async fn list_summaries(&self) -> StateResult<Vec<ConversationSummary>> {
+        let states = self.states.read().await;
+        Ok(states.values().map(ConversationSummary::from).collect())
+    }
+
     async fn exists(&self, id: &Uuid) -> StateResult<bool> {
         trace!("Checking if conversation {} exists in memory store", id);
         Ok(self.states.read().await.contains_key(id))
@@ -125,6 +172,7 @@ mod tests {
         state.add_message(Message {
             role: Role::User,
             content: Content::Text("Hello".to_string()),
+            tool_calls: Vec::new(),
             metadata: Metadata::default(),
         });
 
@@ -182,6 +230,40 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_memory_store_save_if_version() {
+        let store = MemoryStore::new();
+        let mut state = ConversationState::new();
+
+        assert_eq!(store.save_if_version(&state, 0).await.unwrap(), 1);
+        assert!(matches!(
+            store.save_if_version(&state, 0).await,
+            Err(StateError::Conflict {
+                expected: 0,
+                actual: 1,
+                ..
+            })
+        ));
+
+        // A plain save still bumps the version, invalidating older copies
+        state.set_title("Renamed");
+        store.save(&state).await.unwrap();
+        assert_eq!(store.load(&state.id).await.unwrap().version, 2);
+        assert!(store.save_if_version(&state, 1).await.is_err());
+        assert_eq!(store.save_if_version(&state, 2).await.unwrap(), 3);
+
+        assert!(matches!(
+            store.delete_if_version(&state.id, 2).await,
+            Err(StateError::Conflict { actual: 3, .. })
+        ));
+        assert!(store.exists(&state.id).await.unwrap());
+        store.delete_if_version(&state.id, 3).await.unwrap();
+        assert!(matches!(
+            store.delete_if_version(&state.id, 3).await,
+            Err(StateError::NotFound(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_memory_store_list_ordering() {
         let store = MemoryStore::new();
@@ -0,0 +1,634 @@
+//! Redis-backed state storage implementation
+
+use super::next_version;
+use crate::{ConversationState, StateError, StateResult, StateStore};
+use ::redis::aio::MultiplexedConnection;
+use ::redis::{Client, RedisError};
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tracing::{debug, trace, warn};
+use uuid::Uuid;
+
+#[cfg(test)]
+mod test_server;
+
+/// Default prefix for every key the store writes
+const DEFAULT_PREFIX: &str = "cogni:";
+
+/// Default number of times a write is retried after a concurrent change
+const DEFAULT_MAX_RETRIES: usize = 16;
+
+impl From<RedisError> for StateError {
+    fn from(e: RedisError) -> Self {
+        StateError::storage(format!("Redis error: {}", e))
+    }
+}
+
+/// Redis-backed state store
+///
+/// Lets several processes share conversations through one Redis server.
+/// Keys are laid out under a prefix (`cogni:` by default):
+///
+/// - `conversation:<uuid>` holds the conversation as a JSON string
+/// - `conversations` is a set of every conversation ID
+/// - `tag:<tag>` is a set of the IDs of conversations with that tag, so
+///   [`find_by_tags`](StateStore::find_by_tags) doesn't scan every conversation
+///
+/// A conversation with [`expires_at`](crate::StateMetadata::expires_at) set
+/// gets a matching key expiry, so Redis deletes it on time even if no
+/// [`RetentionSweeper`](crate::RetentionSweeper) runs. Index entries left
+/// behind by expired keys are pruned as they're found.
+///
+/// Writes use `WATCH`/`MULTI`/`EXEC`, so a conversation and its index entries
+/// change together and [`save_if_version`](StateStore::save_if_version) is a
+/// true compare-and-swap across processes.
+#[derive(Clone)]
+pub struct RedisStore {
+    reader: MultiplexedConnection,
+    writer: Arc<Mutex<MultiplexedConnection>>,
+    prefix: String,
+    max_retries: usize,
+}
+
+impl fmt::Debug for RedisStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RedisStore")
+            .field("prefix", &self.prefix)
+            .field("max_retries", &self.max_retries)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The parts of a stored conversation needed to update it
+#[derive(Deserialize)]
+struct Stored {
+    #[serde(default)]
+    version: u64,
+    metadata: StoredMetadata,
+}
+
+#[derive(Deserialize)]
+struct StoredMetadata {
+    #[serde(default)]
+    tags: Vec<String>,
+}
+
+impl RedisStore {
+    /// Connect to the Redis server at `url`, such as `redis://127.0.0.1/`
+    pub async fn connect(url: &str) -> StateResult<Self> {
+        let client = Client::open(url)
+            .map_err(|e| StateError::configuration(format!("Invalid Redis URL: {}", e)))?;
+        Self::from_client(client).await
+    }
+
+    /// Connect using an existing client
+    ///
+    /// Two connections are opened: one shared by reads, and one for
+    /// transactions, which can't be interleaved with other commands.
+    pub async fn from_client(client: Client) -> StateResult<Self> {
+        let reader = client.get_multiplexed_async_connection().await?;
+        let writer = client.get_multiplexed_async_connection().await?;
+        debug!(
+            "Connected Redis store to {:?}",
+            client.get_connection_info().addr
+        );
+        Ok(Self {
+            reader,
+            writer: Arc::new(Mutex::new(writer)),
+            prefix: DEFAULT_PREFIX.to_string(),
+            max_retries: DEFAULT_MAX_RETRIES,
+        })
+    }
+
+    /// Set the prefix for every key, to share a database with other data
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Set how often a write is retried when another writer changes the
+    /// conversation mid-transaction
+    pub fn with_max_retries(mut self, max_retries: usize) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    fn conversation_key(&self, id: &Uuid) -> String {
+        format!("{}conversation:{}", self.prefix, id)
+    }
+
+    fn ids_key(&self) -> String {
+        format!("{}conversations", self.prefix)
+    }
+
+    fn tag_key(&self, tag: &str) -> String {
+        format!("{}tag:{}", self.prefix, tag)
+    }
+
+    /// Run a transaction on the conversation `id`, retrying if it changes
+    ///
+    /// `build` sees the stored JSON and either adds the commands to run to the
+    /// pipeline, returning the result, or fails without writing anything.
+    async fn transact<T>(
+        &self,
+        id: &Uuid,
+        mut build: impl FnMut(Option<&str>, &mut ::redis::Pipeline) -> StateResult<T>,
+    ) -> StateResult<T> {
+        let key = self.conversation_key(id);
+        let mut con = self.writer.lock().await;
+
+        for attempt in 0..=self.max_retries {
+            ::redis::cmd("WATCH")
+                .arg(&key)
+                .query_async::<()>(&mut *con)
+                .await?;
+            let current: Option<String> =
+                ::redis::cmd("GET").arg(&key).query_async(&mut *con).await?;
+
+            let mut pipe = ::redis::pipe();
+            pipe.atomic();
+            let result = match build(current.as_deref(), &mut pipe) {
+                Ok(result) => result,
+                Err(e) => {
+                    ::redis::cmd("UNWATCH").query_async::<()>(&mut *con).await?;
+                    return Err(e);
+                }
+            };
+
+            let committed: Option<()> = pipe.query_async(&mut *con).await?;
+            if committed.is_some() {
+                return Ok(result);
+            }
+            trace!(
+                "Conversation {} changed during write, retrying (attempt {})",
+                id,
+                attempt + 1
+            );
+        }
+
+        Err(StateError::lock_contention(format!(
+            "conversation {} kept changing during {} write attempts",
+            id,
+            self.max_retries + 1
+        )))
+    }
+
+    async fn write(&self, state: &ConversationState, expected: Option<u64>) -> StateResult<u64> {
+        let key = self.conversation_key(&state.id);
+        let id = state.id.to_string();
+
+        self.transact(&state.id, |current, pipe| {
+            let stored = current.map(serde_json::from_str::<Stored>).transpose()?;
+            let current_version = stored.as_ref().map_or(0, |s| s.version);
+            let version = next_version(state.id, current_version, expected)?;
+
+            let mut state = state.clone();
+            state.version = version;
+            pipe.cmd("SET")
+                .arg(&key)
+                .arg(serde_json::to_string(&state)?)
+                .ignore();
+            if let Some(expires_at) = state.metadata.expires_at {
+                pipe.cmd("PEXPIREAT")
+                    .arg(&key)
+                    .arg(expires_at.timestamp_millis())
+                    .ignore();
+            }
+            pipe.cmd("SADD").arg(self.ids_key()).arg(&id).ignore();
+
+            let tags: HashSet<&String> = state.metadata.tags.iter().collect();
+            for old in stored.iter().flat_map(|s| &s.metadata.tags) {
+                if !tags.contains(old) {
+                    pipe.cmd("SREM").arg(self.tag_key(old)).arg(&id).ignore();
+                }
+            }
+            for tag in tags {
+                pipe.cmd("SADD").arg(self.tag_key(tag)).arg(&id).ignore();
+            }
+            Ok(version)
+        })
+        .await
+    }
+
+    /// Delete a conversation and its index entries, checking its version if
+    /// given
+    async fn remove(&self, id: &Uuid, expected: Option<u64>) -> StateResult<()> {
+        let key = self.conversation_key(id);
+        let member = id.to_string();
+
+        self.transact(id, |current, pipe| {
+            let stored: Stored = serde_json::from_str(current.ok_or(StateError::NotFound(*id))?)?;
+            next_version(*id, stored.version, expected)?;
+            pipe.cmd("DEL").arg(&key).ignore();
+            pipe.cmd("SREM").arg(self.ids_key()).arg(&member).ignore();
+            for tag in &stored.metadata.tags {
+                pipe.cmd("SREM")
+                    .arg(self.tag_key(tag))
+                    .arg(&member)
+                    .ignore();
+            }
+            Ok(())
+        })
+        .await?;
+        debug!("Deleted conversation {} from Redis", id);
+        Ok(())
+    }
+
+    /// Load conversations by ID, dropping IDs whose key has expired from the
+    /// index sets in `indexes`
+    ///
+    /// Members that aren't UUIDs are skipped.
+    async fn load_many(
+        &self,
+        ids: Vec<String>,
+        indexes: &[String],
+    ) -> StateResult<Vec<ConversationState>> {
+        let ids: Vec<Uuid> = ids
+            .iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .collect();
+        if ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let keys: Vec<String> = ids.iter().map(|id| self.conversation_key(id)).collect();
+        let values: Vec<Option<String>> = ::redis::cmd("MGET")
+            .arg(&keys)
+            .query_async(&mut self.reader.clone())
+            .await?;
+
+        let mut states = Vec::new();
+        let mut stale = Vec::new();
+        for (id, value) in ids.into_iter().zip(values) {
+            match value.map(|json| serde_json::from_str::<ConversationState>(&json)) {
+                Some(Ok(state)) => states.push(state),
+                Some(Err(e)) => warn!("Failed to parse conversation {}: {}", id, e),
+                None => stale.push(id),
+            }
+        }
+
+        if !stale.is_empty() {
+            self.prune(&stale, indexes).await?;
+        }
+        Ok(states)
+    }
+
+    /// Remove the IDs of expired conversations from the index sets in
+    /// `indexes`
+    ///
+    /// Each ID is checked again under `WATCH`, so a conversation saved by
+    /// another process since it was found missing keeps its index entries.
+    /// Pruning is best-effort: if the conversations keep changing, the
+    /// entries are left for a later read.
+    async fn prune(&self, stale: &[Uuid], indexes: &[String]) -> StateResult<()> {
+        let keys: Vec<String> = stale.iter().map(|id| self.conversation_key(id)).collect();
+        let mut con = self.writer.lock().await;
+
+        for attempt in 0..=self.max_retries {
+            ::redis::cmd("WATCH")
+                .arg(&keys)
+                .query_async::<()>(&mut *con)
+                .await?;
+            let mut check = ::redis::pipe();
+            for key in &keys {
+                check.cmd("EXISTS").arg(key);
+            }
+            let live: Vec<bool> = check.query_async(&mut *con).await?;
+            let gone: Vec<String> = stale
+                .iter()
+                .zip(live)
+                .filter(|(_, live)| !live)
+                .map(|(id, _)| id.to_string())
+                .collect();
+            if gone.is_empty() {
+                ::redis::cmd("UNWATCH").query_async::<()>(&mut *con).await?;
+                return Ok(());
+            }
+
+            let mut pipe = ::redis::pipe();
+            pipe.atomic();
+            for index in indexes {
+                pipe.cmd("SREM").arg(index).arg(&gone).ignore();
+            }
+            let committed: Option<()> = pipe.query_async(&mut *con).await?;
+            if committed.is_some() {
+                debug!("Pruned {} expired conversations from index", gone.len());
+                return Ok(());
+            }
+            trace!(
+                "Conversations changed while pruning, retrying (attempt {})",
+                attempt + 1
+            );
+        }
+        debug!("Conversations kept changing, leaving index entries to prune later");
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl StateStore for RedisStore {
+    async fn save(&self, state: &ConversationState) -> StateResult<()> {
+        trace!("Saving conversation {} to Redis", state.id);
+        self.write(state, None).await?;
+        debug!("Saved conversation {} to Redis", state.id);
+        Ok(())
+    }
+
+    async fn save_if_version(&self, state: &ConversationState, expected: u64) -> StateResult<u64> {
+        trace!(
+            "Saving conversation {} to Redis if at version {}",
+            state.id,
+            expected
+        );
+        self.write(state, Some(expected)).await
+    }
+
+    async fn load(&self, id: &Uuid) -> StateResult<ConversationState> {
+        trace!("Loading conversation {} from Redis", id);
+        let json: Option<String> = ::redis::cmd("GET")
+            .arg(self.conversation_key(id))
+            .query_async(&mut self.reader.clone())
+            .await?;
+        let state: ConversationState =
+            serde_json::from_str(&json.ok_or(StateError::NotFound(*id))?)?;
+        if state.id != *id {
+            return Err(StateError::InvalidState(
+                "ID mismatch: key contains different conversation".to_string(),
+            ));
+        }
+        Ok(state)
+    }
+
+    async fn delete(&self, id: &Uuid) -> StateResult<()> {
+        trace!("Deleting conversation {} from Redis", id);
+        self.remove(id, None).await
+    }
+
+    async fn delete_if_version(&self, id: &Uuid, expected: u64) -> StateResult<()> {
+        trace!(
+            "Deleting conversation {} from Redis if at version {}",
+            id,
+            expected
+        );
+        self.remove(id, Some(expected)).await
+    }
+
+    async fn list(&self) -> StateResult<Vec<ConversationState>> {
+        trace!("Listing all conversations from Redis");
+        let ids: Vec<String> = ::redis::cmd("SMEMBERS")
+            .arg(self.ids_key())
+            .query_async(&mut self.reader.clone())
+            .await?;
+        let mut states = self.load_many(ids, &[self.ids_key()]).await?;
+
+        // Sort by updated_at descending (most recent first)
+        states.sort_by_key(|s| std::cmp::Reverse(s.updated_at));
+        Ok(states)
+    }
+
+    async fn find_by_tags(&self, tags: &[String]) -> StateResult<Vec<ConversationState>> {
+        if tags.is_empty() {
+            return Ok(Vec::new());
+        }
+        let tag_keys: Vec<String> = tags.iter().map(|t| self.tag_key(t)).collect();
+        let ids: Vec<String> = ::redis::cmd("SUNION")
+            .arg(&tag_keys)
+            .query_async(&mut self.reader.clone())
+            .await?;
+        self.load_many(ids, &tag_keys).await
+    }
+
+    async fn size_of(&self, id: &Uuid) -> StateResult<u64> {
+        let len: u64 = ::redis::cmd("STRLEN")
+            .arg(self.conversation_key(id))
+            .query_async(&mut self.reader.clone())
+            .await?;
+        match len {
+            0 => Err(StateError::NotFound(*id)),
+            len => Ok(len),
+        }
+    }
+
+    async fn exists(&self, id: &Uuid) -> StateResult<bool> {
+        Ok(::redis::cmd("EXISTS")
+            .arg(self.conversation_key(id))
+            .query_async(&mut self.reader.clone())
+            .await?)
+    }
+
+    async fn list_ids(&self) -> StateResult<Vec<Uuid>> {
+        let mut con = self.reader.clone();
+        let members: Vec<String> = ::redis::cmd("SMEMBERS")
+            .arg(self.ids_key())
+            .query_async(&mut con)
+            .await?;
+        let ids: Vec<Uuid> = members
+            .iter()
+            .filter_map(|id| Uuid::parse_str(id).ok())
+            .collect();
+        if ids.is_empty() {
+            return Ok(ids);
+        }
+
+        // Skip IDs whose conversation has expired
+        let mut pipe = ::redis::pipe();
+        for id in &ids {
+            pipe.cmd("EXISTS").arg(self.conversation_key(id));
+        }
+        let live: Vec<bool> = pipe.query_async(&mut con).await?;
+        Ok(ids
+            .into_iter()
+            .zip(live)
+            .filter_map(|(id, live)| live.then_some(id))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use cogni_core::Message;
+
+    async fn store() -> (RedisStore, String) {
+        let url = test_server::spawn().await;
+        (RedisStore::connect(&url).await.unwrap(), url)
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_operations() {
+        let (store, _) = store().await;
+        let mut state = ConversationState::new();
+        state.set_title("Test");
+        state.add_tag("support");
+        state.add_message(Message::user("Hello"));
+
+        store.save(&state).await.unwrap();
+        let loaded = store.load(&state.id).await.unwrap();
+        assert_eq!(loaded.messages(), state.messages());
+        assert_eq!(loaded.version, 1);
+        assert!(store.exists(&state.id).await.unwrap());
+        assert!(store.size_of(&state.id).await.unwrap() > 0);
+        assert_eq!(store.list_ids().await.unwrap(), [state.id]);
+        assert_eq!(store.list().await.unwrap().len(), 1);
+
+        store.delete(&state.id).await.unwrap();
+        assert!(matches!(
+            store.load(&state.id).await,
+            Err(StateError::NotFound(_))
+        ));
+        assert!(matches!(
+            store.delete(&state.id).await,
+            Err(StateError::NotFound(_))
+        ));
+        assert!(store.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_tag_index() {
+        let (store, _) = store().await;
+        let mut billing = ConversationState::new();
+        billing.add_tag("billing");
+        let mut support = ConversationState::new();
+        support.add_tag("support");
+        store.save(&billing).await.unwrap();
+        store.save(&support).await.unwrap();
+
+        let found = store.find_by_tags(&["billing".to_string()]).await.unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].id, billing.id);
+
+        // Retagging moves the conversation between index sets
+        billing.metadata.tags = vec!["support".to_string()];
+        store.save(&billing).await.unwrap();
+        assert!(store
+            .find_by_tags(&["billing".to_string()])
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            store
+                .find_by_tags(&["support".to_string()])
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_expiry() {
+        let (store, _) = store().await;
+        let mut state = ConversationState::new();
+        state.add_tag("temp");
+        state.set_expiry(Utc::now() + chrono::Duration::milliseconds(50));
+        store.save(&state).await.unwrap();
+        assert!(store.exists(&state.id).await.unwrap());
+
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(!store.exists(&state.id).await.unwrap());
+        assert!(store.list_ids().await.unwrap().is_empty());
+        assert!(store
+            .find_by_tags(&["temp".to_string()])
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(store.list().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_prune_keeps_live_conversations() {
+        let (store, _) = store().await;
+        let mut state = ConversationState::new();
+        state.add_tag("live");
+        store.save(&state).await.unwrap();
+        let indexes = [store.ids_key(), store.tag_key("live")];
+
+        // Saved again after a reader found it missing: still indexed
+        store.prune(&[state.id], &indexes).await.unwrap();
+        assert_eq!(store.list().await.unwrap().len(), 1);
+        assert_eq!(
+            store
+                .find_by_tags(&["live".to_string()])
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        ::redis::cmd("DEL")
+            .arg(store.conversation_key(&state.id))
+            .query_async::<()>(&mut store.reader.clone())
+            .await
+            .unwrap();
+        store.prune(&[state.id], &indexes).await.unwrap();
+        let members: Vec<String> = ::redis::cmd("SMEMBERS")
+            .arg(store.ids_key())
+            .query_async(&mut store.reader.clone())
+            .await
+            .unwrap();
+        assert!(members.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_redis_store_skips_foreign_index_members() {
+        let (store, _) = store().await;
+        let first = ConversationState::new();
+        let second = ConversationState::new();
+        store.save(&first).await.unwrap();
+        store.save(&second).await.unwrap();
+        ::redis::cmd("SADD")
+            .arg(store.ids_key())
+            .arg("not-a-uuid")
+            .query_async::<()>(&mut store.reader.clone())
+            .await
+            .unwrap();
+
+        let mut ids: Vec<Uuid> = store.list().await.unwrap().iter().map(|s| s.id).collect();
+        ids.sort();
+        let mut expected = vec![first.id, second.id];
+        expected.sort();
+        assert_eq!(ids, expected);
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_redis_store_save_if_version_across_replicas() {
+        let (store, url) = store().await;
+        let state = ConversationState::new();
+        let id = state.id;
+        assert_eq!(store.save_if_version(&state, 0).await.unwrap(), 1);
+        assert!(matches!(
+            store.save_if_version(&state, 0).await,
+            Err(StateError::Conflict { actual: 1, .. })
+        ));
+
+        // Each replica has its own connections, like a separate process
+        let mut handles = vec![];
+        for i in 0..6 {
+            let replica = RedisStore::connect(&url).await.unwrap();
+            handles.push(tokio::spawn(async move {
+                loop {
+                    let mut state = replica.load(&id).await.unwrap();
+                    let expected = state.version;
+                    state.add_message(Message::user(format!("replica {}", i)));
+                    match replica.save_if_version(&state, expected).await {
+                        Ok(_) => break,
+                        Err(StateError::Conflict { .. }) => continue,
+                        Err(e) => panic!("unexpected error: {}", e),
+                    }
+                }
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap();
+        }
+
+        let state = store.load(&id).await.unwrap();
+        assert_eq!(state.messages().len(), 6);
+        assert_eq!(state.version, 7);
+    }
+}
@@ -0,0 +1,301 @@
+//! Minimal in-process RESP server for testing `RedisStore` without Redis
+//!
+//! Implements just the commands the store uses, including key expiry and
+//! `WATCH`/`MULTI`/`EXEC` semantics.
+
+use std::collections::{BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+
+/// Start a server on a free local port and return its URL
+pub(super) async fn spawn() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("redis://{}/", listener.local_addr().unwrap());
+    let db = Arc::new(Mutex::new(Db::default()));
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            tokio::spawn(serve(stream, db.clone()));
+        }
+    });
+    url
+}
+
+enum Entry {
+    Str(Vec<u8>),
+    Set(BTreeSet<Vec<u8>>),
+}
+
+enum Reply {
+    Status(&'static str),
+    Int(i64),
+    Bulk(Option<Vec<u8>>),
+    Array(Option<Vec<Reply>>),
+    Error(String),
+}
+
+impl Reply {
+    fn encode(&self, out: &mut Vec<u8>) {
+        match self {
+            Reply::Status(s) => out.extend(format!("+{}\r\n", s).into_bytes()),
+            Reply::Int(n) => out.extend(format!(":{}\r\n", n).into_bytes()),
+            Reply::Bulk(None) => out.extend(b"$-1\r\n"),
+            Reply::Array(None) => out.extend(b"*-1\r\n"),
+            Reply::Bulk(Some(b)) => {
+                out.extend(format!("${}\r\n", b.len()).into_bytes());
+                out.extend(b);
+                out.extend(b"\r\n");
+            }
+            Reply::Array(Some(items)) => {
+                out.extend(format!("*{}\r\n", items.len()).into_bytes());
+                for item in items {
+                    item.encode(out);
+                }
+            }
+            Reply::Error(e) => out.extend(format!("-ERR {}\r\n", e).into_bytes()),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Db {
+    entries: HashMap<Vec<u8>, Entry>,
+    /// Expiry of a key in Unix milliseconds
+    expiry: HashMap<Vec<u8>, i64>,
+    /// Last modification of each key, for `WATCH`
+    revisions: HashMap<Vec<u8>, u64>,
+    clock: u64,
+}
+
+impl Db {
+    fn touch(&mut self, key: &[u8]) {
+        self.clock += 1;
+        self.revisions.insert(key.to_vec(), self.clock);
+    }
+
+    fn remove(&mut self, key: &[u8]) -> bool {
+        self.expiry.remove(key);
+        let removed = self.entries.remove(key).is_some();
+        if removed {
+            self.touch(key);
+        }
+        removed
+    }
+
+    /// Drop the key if it has expired
+    fn purge(&mut self, key: &[u8]) {
+        let now = chrono::Utc::now().timestamp_millis();
+        if self.expiry.get(key).is_some_and(|&at| at <= now) {
+            self.remove(key);
+        }
+    }
+
+    fn revision(&mut self, key: &[u8]) -> u64 {
+        self.purge(key);
+        self.revisions.get(key).copied().unwrap_or(0)
+    }
+
+    fn set(&mut self, key: &[u8]) -> Result<&mut BTreeSet<Vec<u8>>, Reply> {
+        self.purge(key);
+        match self
+            .entries
+            .entry(key.to_vec())
+            .or_insert_with(|| Entry::Set(BTreeSet::new()))
+        {
+            Entry::Set(set) => Ok(set),
+            Entry::Str(_) => Err(wrong_type()),
+        }
+    }
+
+    fn members(&mut self, key: &[u8]) -> Result<Vec<Vec<u8>>, Reply> {
+        self.purge(key);
+        match self.entries.get(key) {
+            None => Ok(Vec::new()),
+            Some(Entry::Set(set)) => Ok(set.iter().cloned().collect()),
+            Some(Entry::Str(_)) => Err(wrong_type()),
+        }
+    }
+
+    fn execute(&mut self, args: &[Vec<u8>]) -> Reply {
+        match self.try_execute(args) {
+            Ok(reply) | Err(reply) => reply,
+        }
+    }
+
+    fn try_execute(&mut self, args: &[Vec<u8>]) -> Result<Reply, Reply> {
+        let name = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+        let args = &args[1..];
+        let reply = match name.as_str() {
+            "PING" => Reply::Status("PONG"),
+            "CLIENT" => Reply::Status("OK"),
+            "GET" => {
+                self.purge(&args[0]);
+                match self.entries.get(&args[0]) {
+                    None => Reply::Bulk(None),
+                    Some(Entry::Str(value)) => Reply::Bulk(Some(value.clone())),
+                    Some(Entry::Set(_)) => return Err(wrong_type()),
+                }
+            }
+            "MGET" => Reply::Array(Some(
+                args.iter()
+                    .map(|key| {
+                        self.purge(key);
+                        match self.entries.get(key) {
+                            Some(Entry::Str(value)) => Reply::Bulk(Some(value.clone())),
+                            _ => Reply::Bulk(None),
+                        }
+                    })
+                    .collect(),
+            )),
+            "SET" => {
+                self.expiry.remove(&args[0]);
+                self.entries
+                    .insert(args[0].clone(), Entry::Str(args[1].clone()));
+                self.touch(&args[0]);
+                Reply::Status("OK")
+            }
+            "STRLEN" => {
+                self.purge(&args[0]);
+                match self.entries.get(&args[0]) {
+                    None => Reply::Int(0),
+                    Some(Entry::Str(value)) => Reply::Int(value.len() as i64),
+                    Some(Entry::Set(_)) => return Err(wrong_type()),
+                }
+            }
+            "DEL" => Reply::Int(args.iter().filter(|key| self.remove(key)).count() as i64),
+            "EXISTS" => Reply::Int(
+                args.iter()
+                    .filter(|key| {
+                        self.purge(key);
+                        self.entries.contains_key(*key)
+                    })
+                    .count() as i64,
+            ),
+            "PEXPIREAT" => {
+                self.purge(&args[0]);
+                if !self.entries.contains_key(&args[0]) {
+                    return Ok(Reply::Int(0));
+                }
+                let at: i64 = String::from_utf8_lossy(&args[1])
+                    .parse()
+                    .map_err(|_| Reply::Error("value is not an integer".into()))?;
+                self.expiry.insert(args[0].clone(), at);
+                self.touch(&args[0]);
+                self.purge(&args[0]);
+                Reply::Int(1)
+            }
+            "SADD" => {
+                let set = self.set(&args[0])?;
+                let added = args[1..].iter().filter(|m| set.insert(m.to_vec())).count();
+                self.touch(&args[0]);
+                Reply::Int(added as i64)
+            }
+            "SREM" => {
+                let set = self.set(&args[0])?;
+                let removed = args[1..].iter().filter(|m| set.remove(*m)).count();
+                if set.is_empty() {
+                    self.entries.remove(&args[0]);
+                }
+                self.touch(&args[0]);
+                Reply::Int(removed as i64)
+            }
+            "SMEMBERS" => bulk_array(self.members(&args[0])?),
+            "SUNION" => {
+                let mut union = BTreeSet::new();
+                for key in args {
+                    union.extend(self.members(key)?);
+                }
+                bulk_array(union.into_iter().collect())
+            }
+            other => return Err(Reply::Error(format!("unknown command '{}'", other))),
+        };
+        Ok(reply)
+    }
+}
+
+fn wrong_type() -> Reply {
+    Reply::Error("WRONGTYPE Operation against a key holding the wrong kind of value".into())
+}
+
+fn bulk_array(items: Vec<Vec<u8>>) -> Reply {
+    Reply::Array(Some(
+        items.into_iter().map(|i| Reply::Bulk(Some(i))).collect(),
+    ))
+}
+
+async fn read_command(reader: &mut BufReader<TcpStream>) -> Option<Vec<Vec<u8>>> {
+    let mut line = String::new();
+    reader.read_line(&mut line).await.ok()?;
+    let count: usize = line.trim().strip_prefix('*')?.parse().ok()?;
+
+    let mut args = Vec::with_capacity(count);
+    for _ in 0..count {
+        line.clear();
+        reader.read_line(&mut line).await.ok()?;
+        let len: usize = line.trim().strip_prefix('$')?.parse().ok()?;
+        let mut arg = vec![0; len + 2];
+        reader.read_exact(&mut arg).await.ok()?;
+        arg.truncate(len);
+        args.push(arg);
+    }
+    Some(args)
+}
+
+async fn serve(stream: TcpStream, db: Arc<Mutex<Db>>) {
+    let mut reader = BufReader::new(stream);
+    let mut watched: Vec<(Vec<u8>, u64)> = Vec::new();
+    let mut queued: Option<Vec<Vec<Vec<u8>>>> = None;
+
+    while let Some(args) = read_command(&mut reader).await {
+        if args.is_empty() {
+            continue;
+        }
+        let name = String::from_utf8_lossy(&args[0]).to_ascii_uppercase();
+        let reply = match (name.as_str(), queued.as_mut()) {
+            ("MULTI", None) => {
+                queued = Some(Vec::new());
+                Reply::Status("OK")
+            }
+            ("EXEC", Some(_)) => {
+                let commands = queued.take().unwrap_or_default();
+                let mut db = db.lock().unwrap();
+                let changed = watched
+                    .drain(..)
+                    .any(|(key, revision)| db.revision(&key) != revision);
+                if changed {
+                    Reply::Array(None)
+                } else {
+                    Reply::Array(Some(commands.iter().map(|c| db.execute(c)).collect()))
+                }
+            }
+            ("DISCARD", Some(_)) => {
+                queued = None;
+                watched.clear();
+                Reply::Status("OK")
+            }
+            (_, Some(commands)) => {
+                commands.push(args);
+                Reply::Status("QUEUED")
+            }
+            ("WATCH", None) => {
+                let mut db = db.lock().unwrap();
+                for key in &args[1..] {
+                    let revision = db.revision(key);
+                    watched.push((key.clone(), revision));
+                }
+                Reply::Status("OK")
+            }
+            ("UNWATCH", None) => {
+                watched.clear();
+                Reply::Status("OK")
+            }
+            (_, None) => db.lock().unwrap().execute(&args),
+        };
+
+        let mut out = Vec::new();
+        reply.encode(&mut out);
+        if reader.get_mut().write_all(&out).await.is_err() {
+            break;
+        }
+    }
+}
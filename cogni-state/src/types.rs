@@ -1,28 +1,125 @@
 //! Core types for conversation state management
 
+use crate::branch::MessageNode;
 use chrono::{DateTime, Utc};
-use cogni_core::Message;
+use cogni_core::{Message, Role};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
 /// Represents the complete state of a conversation
+///
+/// Messages form a tree: editing or regenerating a message adds a sibling
+/// instead of overwriting it. The current branch runs from a root to the
+/// `head` node and is what [`messages`](Self::messages) returns. See the
+/// branching methods for forking, switching and listing alternatives.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "StateRepr")]
 pub struct ConversationState {
     /// Unique identifier for this conversation
     pub id: Uuid,
-    /// All messages in the conversation
-    pub messages: Vec<Message>,
+    /// Every message node, parents before their children
+    pub(crate) nodes: Vec<MessageNode>,
+    /// Last node of the current branch
+    pub(crate) head: Option<Uuid>,
+    /// Node indices along the current branch, root first
+    #[serde(skip)]
+    pub(crate) branch: Vec<usize>,
+    /// Messages along the current branch, root first
+    #[serde(skip)]
+    pub(crate) messages: Vec<Message>,
     /// Metadata about the conversation
     pub metadata: StateMetadata,
     /// When this conversation was created
     pub created_at: DateTime<Utc>,
     /// When this conversation was last updated
     pub updated_at: DateTime<Utc>,
+    /// Number of times this conversation has been written to its store
+    ///
+    /// Stores bump the version on every save, so it can be passed to
+    /// [`StateStore::save_if_version`](crate::StateStore::save_if_version)
+    /// to detect concurrent updates. Zero means never saved.
+    #[serde(default)]
+    pub version: u64,
+}
+
+/// Serialized form of a conversation, including the flat message list
+/// written before conversations were trees
+#[derive(Deserialize)]
+struct StateRepr {
+    id: Uuid,
+    #[serde(default)]
+    nodes: Vec<MessageNode>,
+    #[serde(default)]
+    head: Option<Uuid>,
+    #[serde(default)]
+    messages: Option<Vec<Message>>,
+    metadata: StateMetadata,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    #[serde(default)]
+    version: u64,
+}
+
+impl TryFrom<StateRepr> for ConversationState {
+    type Error = String;
+
+    fn try_from(repr: StateRepr) -> Result<Self, Self::Error> {
+        let mut state = Self {
+            id: repr.id,
+            nodes: repr.nodes,
+            head: repr.head,
+            branch: Vec::new(),
+            messages: Vec::new(),
+            metadata: repr.metadata,
+            created_at: repr.created_at,
+            updated_at: repr.updated_at,
+            version: repr.version,
+        };
+
+        // Legacy states stored the conversation as a single flat branch
+        if let Some(messages) = repr.messages.filter(|_| state.nodes.is_empty()) {
+            for message in messages {
+                state.push_node(message, repr.created_at);
+            }
+            return Ok(state);
+        }
+
+        check_tree(&state.nodes, state.head)?;
+        state.rebuild_branch();
+        Ok(state)
+    }
+}
+
+/// Check that every parent comes before its children and the head is a node
+fn check_tree(nodes: &[MessageNode], head: Option<Uuid>) -> Result<(), String> {
+    let mut seen = HashSet::new();
+    for node in nodes {
+        if let Some(parent) = node.parent {
+            if !seen.contains(&parent) {
+                return Err(format!(
+                    "message {} appears before its parent {}",
+                    node.id, parent
+                ));
+            }
+        }
+        if !seen.insert(node.id) {
+            return Err(format!("duplicate message id {}", node.id));
+        }
+    }
+    if let Some(head) = head {
+        if !seen.contains(&head) {
+            return Err(format!(
+                "head {} is not a message in the conversation",
+                head
+            ));
+        }
+    }
+    Ok(())
 }
 
 /// Metadata associated with a conversation state
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct StateMetadata {
     /// Optional title for the conversation
     pub title: Option<String>,
@@ -34,6 +131,9 @@ pub struct StateMetadata {
     pub token_count: Option<u32>,
     /// Custom key-value pairs
     pub custom: HashMap<String, String>,
+    /// When the conversation should be deleted by a retention sweep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<DateTime<Utc>>,
 }
 
 impl StateMetadata {
@@ -60,19 +160,18 @@ impl StateMetadata {
         self.custom.insert(key.into(), value.into());
         self
     }
+
+    /// Set when the conversation expires
+    pub fn with_expiry(mut self, expires_at: DateTime<Utc>) -> Self {
+        self.expires_at = Some(expires_at);
+        self
+    }
 }
 
 impl ConversationState {
     /// Create a new conversation state with a random ID
     pub fn new() -> Self {
-        let now = Utc::now();
-        Self {
-            id: Uuid::new_v4(),
-            messages: Vec::new(),
-            metadata: StateMetadata::default(),
-            created_at: now,
-            updated_at: now,
-        }
+        Self::with_id(Uuid::new_v4())
     }
 
     /// Create a conversation state with a specific ID
@@ -80,22 +179,101 @@ impl ConversationState {
         let now = Utc::now();
         Self {
             id,
+            nodes: Vec::new(),
+            head: None,
+            branch: Vec::new(),
             messages: Vec::new(),
             metadata: StateMetadata::default(),
             created_at: now,
             updated_at: now,
+            version: 0,
         }
     }
 
-    /// Add a message to the conversation
-    pub fn add_message(&mut self, message: Message) {
-        self.messages.push(message);
+    /// Messages on the current branch, oldest first
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    /// Add a message to the end of the current branch
+    ///
+    /// Returns the ID of the new message node.
+    pub fn add_message(&mut self, message: Message) -> Uuid {
+        let id = self.push_node(message, Utc::now());
         self.updated_at = Utc::now();
+        id
     }
 
     /// Add multiple messages to the conversation
     pub fn add_messages(&mut self, messages: impl IntoIterator<Item = Message>) {
-        self.messages.extend(messages);
+        let now = Utc::now();
+        for message in messages {
+            self.push_node(message, now);
+        }
+        self.updated_at = now;
+    }
+
+    /// Remove the last message of the current branch
+    ///
+    /// The message is deleted unless other branches continue from it, in
+    /// which case the branch only steps back past it.
+    pub fn pop_message(&mut self) -> Option<Message> {
+        let index = *self.branch.last()?;
+        let node = &self.nodes[index];
+        let (id, parent) = (node.id, node.parent);
+        let message = if self.nodes.iter().any(|n| n.parent == Some(id)) {
+            node.message.clone()
+        } else {
+            self.nodes.remove(index).message
+        };
+        self.head = parent;
+        self.rebuild_branch();
+        self.updated_at = Utc::now();
+        Some(message)
+    }
+
+    /// Set or remove the leading system message on every branch
+    ///
+    /// Existing system messages at the root are replaced in place. Without
+    /// one, a new system message becomes the parent of every root.
+    pub fn set_system_message(&mut self, system: Option<&str>) {
+        let is_system_root = |n: &MessageNode| n.parent.is_none() && n.message.role == Role::System;
+
+        match system {
+            Some(text) if self.nodes.iter().any(is_system_root) => {
+                for node in self.nodes.iter_mut().filter(|n| is_system_root(n)) {
+                    node.message = Message::system(text);
+                }
+            }
+            Some(text) => {
+                let node = MessageNode::new(None, Message::system(text), Utc::now());
+                for root in self.nodes.iter_mut().filter(|n| n.parent.is_none()) {
+                    root.parent = Some(node.id);
+                }
+                if self.head.is_none() {
+                    self.head = Some(node.id);
+                }
+                self.nodes.insert(0, node);
+            }
+            None => {
+                let removed: Vec<Uuid> = self
+                    .nodes
+                    .iter()
+                    .filter(|n| is_system_root(n))
+                    .map(|n| n.id)
+                    .collect();
+                for id in &removed {
+                    for child in self.nodes.iter_mut().filter(|n| n.parent == Some(*id)) {
+                        child.parent = None;
+                    }
+                    if self.head == Some(*id) {
+                        self.head = None;
+                    }
+                }
+                self.nodes.retain(|n| !removed.contains(&n.id));
+            }
+        }
+        self.rebuild_branch();
         self.updated_at = Utc::now();
     }
 
@@ -143,12 +321,38 @@ impl ConversationState {
         self.metadata.custom.get(key).map(|s| s.as_str())
     }
 
-    /// Clear all messages while preserving metadata
+    /// Clear all messages, including other branches, while preserving metadata
     pub fn clear_messages(&mut self) {
-        self.messages.clear();
+        self.nodes.clear();
+        self.head = None;
+        self.rebuild_branch();
+        self.updated_at = Utc::now();
+    }
+
+    /// Set when the conversation expires
+    pub fn set_expiry(&mut self, expires_at: DateTime<Utc>) {
+        self.metadata.expires_at = Some(expires_at);
+        self.updated_at = Utc::now();
+    }
+
+    /// Expire the conversation `ttl` from now
+    pub fn expire_in(&mut self, ttl: chrono::Duration) {
+        self.set_expiry(Utc::now() + ttl);
+    }
+
+    /// Remove the conversation's expiry
+    pub fn clear_expiry(&mut self) {
+        self.metadata.expires_at = None;
         self.updated_at = Utc::now();
     }
 
+    /// Check if the conversation's expiry has passed
+    pub fn is_expired(&self) -> bool {
+        self.metadata
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= Utc::now())
+    }
+
     /// Get the age of the conversation
     pub fn age(&self) -> chrono::Duration {
         Utc::now() - self.created_at
@@ -158,6 +362,54 @@ impl ConversationState {
     pub fn modified_since(&self, timestamp: DateTime<Utc>) -> bool {
         self.updated_at > timestamp
     }
+
+    /// Append a node to the current branch and move the head to it
+    fn push_node(&mut self, message: Message, created_at: DateTime<Utc>) -> Uuid {
+        let node = MessageNode::new(self.head, message, created_at);
+        let id = node.id;
+        self.branch.push(self.nodes.len());
+        self.messages.push(node.message.clone());
+        self.nodes.push(node);
+        self.head = Some(id);
+        id
+    }
+
+    /// Replace the whole message tree, checking that it is well formed
+    pub(crate) fn set_nodes(
+        &mut self,
+        nodes: Vec<MessageNode>,
+        head: Option<Uuid>,
+    ) -> Result<(), String> {
+        check_tree(&nodes, head)?;
+        self.nodes = nodes;
+        self.head = head;
+        self.rebuild_branch();
+        Ok(())
+    }
+
+    /// Recompute the cached current branch from `head`
+    pub(crate) fn rebuild_branch(&mut self) {
+        let index: HashMap<Uuid, usize> = self
+            .nodes
+            .iter()
+            .enumerate()
+            .map(|(i, n)| (n.id, i))
+            .collect();
+
+        let mut branch = Vec::new();
+        let mut current = self.head.and_then(|id| index.get(&id).copied());
+        while let Some(i) = current {
+            branch.push(i);
+            current = self.nodes[i].parent.and_then(|id| index.get(&id).copied());
+        }
+        branch.reverse();
+
+        self.messages = branch
+            .iter()
+            .map(|&i| self.nodes[i].message.clone())
+            .collect();
+        self.branch = branch;
+    }
 }
 
 impl Default for ConversationState {
@@ -174,7 +426,7 @@ mod tests {
     #[test]
     fn test_conversation_state_creation() {
         let state = ConversationState::new();
-        assert_eq!(state.messages.len(), 0);
+        assert_eq!(state.messages().len(), 0);
         assert!(state.metadata.title.is_none());
         assert_eq!(state.metadata.tags.len(), 0);
     }
@@ -190,10 +442,11 @@ mod tests {
         state.add_message(Message {
             role: Role::User,
             content: Content::Text("Hello".to_string()),
+            tool_calls: Vec::new(),
             metadata: Metadata::default(),
         });
 
-        assert_eq!(state.messages.len(), 1);
+        assert_eq!(state.messages().len(), 1);
         assert!(state.updated_at > original_updated);
     }
 
@@ -214,6 +467,22 @@ mod tests {
         assert_eq!(state.metadata.tags.len(), 1);
     }
 
+    #[test]
+    fn test_expiry() {
+        let mut state = ConversationState::new();
+        assert!(!state.is_expired());
+
+        state.expire_in(chrono::Duration::seconds(-1));
+        assert!(state.is_expired());
+
+        let json = serde_json::to_string(&state).unwrap();
+        let restored: ConversationState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.metadata.expires_at, state.metadata.expires_at);
+
+        state.clear_expiry();
+        assert!(!state.is_expired());
+    }
+
     #[test]
     fn test_custom_metadata() {
         let mut state = ConversationState::new();
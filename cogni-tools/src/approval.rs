@@ -0,0 +1,474 @@
+//! Human-in-the-loop approval of tool calls
+
+use async_trait::async_trait;
+use cogni_core::ToolCall;
+use serde_json::Value;
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, Write};
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+use tokio::sync::{mpsc, oneshot};
+
+/// How much oversight a tool needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolClass {
+    /// Runs without asking
+    Safe,
+    /// Runs only once the approval handler agrees
+    NeedsApproval,
+    /// Never runs
+    Forbidden,
+}
+
+/// An approval handler's answer to a tool call
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalDecision {
+    /// Run the call as requested
+    Approve,
+    /// Don't run the call; the reason is returned to the model
+    Deny {
+        /// Why the call was denied
+        reason: String,
+    },
+    /// Run the call with different arguments
+    Edit {
+        /// Replacement arguments
+        arguments: Value,
+    },
+}
+
+impl ApprovalDecision {
+    /// Deny with a reason
+    pub fn deny(reason: impl Into<String>) -> Self {
+        Self::Deny {
+            reason: reason.into(),
+        }
+    }
+}
+
+/// Decides whether tool calls that need approval may run
+#[async_trait]
+pub trait ApprovalHandler: Send + Sync {
+    /// Review a call before it is executed
+    async fn review(&self, call: &ToolCall) -> ApprovalDecision;
+}
+
+/// Decisions an [`ApprovalPolicy`] keeps by default
+pub const DEFAULT_MAX_RECORDS: usize = 1000;
+
+/// A decision made about a tool call
+#[derive(Debug, Clone, PartialEq)]
+pub struct ApprovalRecord {
+    /// The call as the model made it
+    pub call: ToolCall,
+    /// The tool's class
+    pub class: ToolClass,
+    /// What was decided; forbidden tools are recorded as denied
+    pub decision: ApprovalDecision,
+    /// When the decision was made
+    pub timestamp: SystemTime,
+}
+
+impl ApprovalRecord {
+    /// The decision as message metadata, for the tool message answering the
+    /// call
+    ///
+    /// `approval` is `approved`, `denied` or `edited`. Denials add
+    /// `approval_reason`; edits add `approval_arguments` with the arguments
+    /// the tool actually ran with.
+    pub fn metadata(&self) -> HashMap<String, String> {
+        let mut metadata = HashMap::new();
+        let outcome = match &self.decision {
+            ApprovalDecision::Approve => "approved",
+            ApprovalDecision::Deny { reason } => {
+                metadata.insert("approval_reason".to_string(), reason.clone());
+                "denied"
+            }
+            ApprovalDecision::Edit { arguments } => {
+                metadata.insert("approval_arguments".to_string(), arguments.to_string());
+                "edited"
+            }
+        };
+        metadata.insert("approval".to_string(), outcome.to_string());
+        metadata
+    }
+}
+
+/// Which tools need approval and who gives it
+///
+/// Tools not classified explicitly get the default class, which is
+/// [`ToolClass::NeedsApproval`] unless changed. The most recent decisions, up
+/// to [`max_records`](Self::max_records), are kept and can be read back with
+/// [`records`](Self::records) or removed with
+/// [`take_records`](Self::take_records) and [`take_record`](Self::take_record).
+///
+/// # Example
+///
+/// ```
+/// use cogni_tools::{ApprovalPolicy, CliApprovalHandler, ToolClass, ToolRegistry};
+///
+/// let registry = ToolRegistry::new().with_approval(
+///     ApprovalPolicy::new(CliApprovalHandler::new())
+///         .default_class(ToolClass::Safe)
+///         .needs_approval("deploy")
+///         .forbid("drop_database"),
+/// );
+/// ```
+#[derive(Clone)]
+pub struct ApprovalPolicy {
+    handler: Arc<dyn ApprovalHandler>,
+    classes: HashMap<String, ToolClass>,
+    default_class: ToolClass,
+    max_records: usize,
+    records: Arc<Mutex<VecDeque<ApprovalRecord>>>,
+}
+
+impl ApprovalPolicy {
+    /// Create a policy that asks `handler` about every tool
+    pub fn new(handler: impl ApprovalHandler + 'static) -> Self {
+        Self {
+            handler: Arc::new(handler),
+            classes: HashMap::new(),
+            default_class: ToolClass::NeedsApproval,
+            max_records: DEFAULT_MAX_RECORDS,
+            records: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Keep at most this many decisions, dropping the oldest first
+    pub fn max_records(mut self, max: usize) -> Self {
+        self.max_records = max;
+        self
+    }
+
+    /// Set the class of tools that aren't classified explicitly
+    pub fn default_class(mut self, class: ToolClass) -> Self {
+        self.default_class = class;
+        self
+    }
+
+    /// Set the class of a tool
+    pub fn classify(mut self, name: impl Into<String>, class: ToolClass) -> Self {
+        self.classes.insert(name.into(), class);
+        self
+    }
+
+    /// Let a tool run without asking
+    pub fn safe(self, name: impl Into<String>) -> Self {
+        self.classify(name, ToolClass::Safe)
+    }
+
+    /// Require approval for a tool
+    pub fn needs_approval(self, name: impl Into<String>) -> Self {
+        self.classify(name, ToolClass::NeedsApproval)
+    }
+
+    /// Never run a tool
+    pub fn forbid(self, name: impl Into<String>) -> Self {
+        self.classify(name, ToolClass::Forbidden)
+    }
+
+    /// The class of a tool
+    pub fn class_of(&self, name: &str) -> ToolClass {
+        self.classes
+            .get(name)
+            .copied()
+            .unwrap_or(self.default_class)
+    }
+
+    /// Decisions kept so far, oldest first
+    pub fn records(&self) -> Vec<ApprovalRecord> {
+        self.records.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Remove and return the decisions kept so far, oldest first
+    pub fn take_records(&self) -> Vec<ApprovalRecord> {
+        self.records.lock().unwrap().drain(..).collect()
+    }
+
+    /// Remove and return the latest decision about the call with this ID
+    pub fn take_record(&self, call_id: &str) -> Option<ApprovalRecord> {
+        let mut records = self.records.lock().unwrap();
+        let index = records
+            .iter()
+            .rposition(|record| record.call.id == call_id)?;
+        records.remove(index)
+    }
+
+    /// Decide on a call, recording the outcome
+    ///
+    /// Returns `None` for safe tools, which aren't recorded.
+    pub(crate) async fn review(&self, call: &ToolCall) -> Option<ApprovalDecision> {
+        let class = self.class_of(&call.name);
+        let decision = match class {
+            ToolClass::Safe => return None,
+            ToolClass::Forbidden => {
+                ApprovalDecision::deny(format!("tool '{}' is not allowed", call.name))
+            }
+            ToolClass::NeedsApproval => self.handler.review(call).await,
+        };
+        let mut records = self.records.lock().unwrap();
+        records.push_back(ApprovalRecord {
+            call: call.clone(),
+            class,
+            decision: decision.clone(),
+            timestamp: SystemTime::now(),
+        });
+        while records.len() > self.max_records {
+            records.pop_front();
+        }
+        drop(records);
+        Some(decision)
+    }
+}
+
+/// Asks on the terminal whether a call may run
+///
+/// The call is shown on stderr and the answer read from stdin: `y` approves,
+/// `n` denies (optionally with a reason) and `e` asks for replacement JSON
+/// arguments.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CliApprovalHandler;
+
+impl CliApprovalHandler {
+    /// Create a handler using stdin and stderr
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[async_trait]
+impl ApprovalHandler for CliApprovalHandler {
+    async fn review(&self, call: &ToolCall) -> ApprovalDecision {
+        let call = call.clone();
+        tokio::task::spawn_blocking(move || {
+            prompt(&call, &mut std::io::stdin().lock(), &mut std::io::stderr())
+        })
+        .await
+        .unwrap_or_else(|e| ApprovalDecision::deny(format!("approval prompt failed: {}", e)))
+    }
+}
+
+/// Ask about `call` on `output`, reading answers from `input`
+fn prompt(call: &ToolCall, input: &mut impl BufRead, output: &mut impl Write) -> ApprovalDecision {
+    let question = format!(
+        "Allow tool call {}({})? [y]es / [n]o / [e]dit: ",
+        call.name, call.arguments
+    );
+    loop {
+        let Some(answer) = ask(input, output, &question) else {
+            return ApprovalDecision::deny("no answer from user");
+        };
+        match answer.to_lowercase().as_str() {
+            "y" | "yes" => return ApprovalDecision::Approve,
+            "n" | "no" => {
+                let reason = ask(input, output, "Reason (optional): ").unwrap_or_default();
+                return ApprovalDecision::deny(if reason.is_empty() {
+                    "denied by user".to_string()
+                } else {
+                    reason
+                });
+            }
+            "e" | "edit" => loop {
+                let Some(arguments) = ask(input, output, "New arguments (JSON): ") else {
+                    return ApprovalDecision::deny("no answer from user");
+                };
+                match serde_json::from_str(&arguments) {
+                    Ok(arguments) => return ApprovalDecision::Edit { arguments },
+                    Err(e) => {
+                        let _ = writeln!(output, "Invalid JSON: {}", e);
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+/// Write `question` and read the trimmed answer, or `None` at end of input
+fn ask(input: &mut impl BufRead, output: &mut impl Write, question: &str) -> Option<String> {
+    let _ = write!(output, "{}", question);
+    let _ = output.flush();
+    let mut line = String::new();
+    match input.read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim().to_string()),
+    }
+}
+
+/// A tool call waiting for a decision from a [`ChannelApprovalHandler`]
+#[derive(Debug)]
+pub struct ApprovalRequest {
+    /// The call to review
+    pub call: ToolCall,
+    respond: oneshot::Sender<ApprovalDecision>,
+}
+
+impl ApprovalRequest {
+    /// Send the decision back to the waiting tool call
+    pub fn respond(self, decision: ApprovalDecision) {
+        let _ = self.respond.send(decision);
+    }
+}
+
+/// Sends calls to another task, such as a UI, and waits for its decision
+///
+/// Calls are denied if the receiver is dropped or a request is dropped
+/// without a response.
+///
+/// # Example
+///
+/// ```
+/// use cogni_tools::{ApprovalDecision, ChannelApprovalHandler};
+///
+/// # async fn example() {
+/// let (handler, mut requests) = ChannelApprovalHandler::new(16);
+/// tokio::spawn(async move {
+///     while let Some(request) = requests.recv().await {
+///         // Show request.call to the user...
+///         request.respond(ApprovalDecision::Approve);
+///     }
+/// });
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ChannelApprovalHandler {
+    sender: mpsc::Sender<ApprovalRequest>,
+}
+
+impl ChannelApprovalHandler {
+    /// Create a handler and the receiver its requests arrive on
+    pub fn new(buffer: usize) -> (Self, mpsc::Receiver<ApprovalRequest>) {
+        let (sender, receiver) = mpsc::channel(buffer);
+        (Self { sender }, receiver)
+    }
+}
+
+#[async_trait]
+impl ApprovalHandler for ChannelApprovalHandler {
+    async fn review(&self, call: &ToolCall) -> ApprovalDecision {
+        let (respond, response) = oneshot::channel();
+        let request = ApprovalRequest {
+            call: call.clone(),
+            respond,
+        };
+        if self.sender.send(request).await.is_err() {
+            return ApprovalDecision::deny("approval channel closed");
+        }
+        response
+            .await
+            .unwrap_or_else(|_| ApprovalDecision::deny("no decision was made"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::io::Cursor;
+
+    fn call(name: &str) -> ToolCall {
+        ToolCall {
+            id: "1".to_string(),
+            name: name.to_string(),
+            arguments: r#"{"env":"prod"}"#.to_string(),
+        }
+    }
+
+    fn answer(input: &str) -> (ApprovalDecision, String) {
+        let mut output = Vec::new();
+        let decision = prompt(&call("deploy"), &mut Cursor::new(input), &mut output);
+        (decision, String::from_utf8(output).unwrap())
+    }
+
+    #[test]
+    fn test_prompt() {
+        let (decision, output) = answer("y\n");
+        assert_eq!(decision, ApprovalDecision::Approve);
+        assert_eq!(
+            output,
+            r#"Allow tool call deploy({"env":"prod"})? [y]es / [n]o / [e]dit: "#
+        );
+
+        assert_eq!(answer("n\n\n").0, ApprovalDecision::deny("denied by user"));
+        assert_eq!(
+            answer("maybe\nno\nnot on a Friday\n").0,
+            ApprovalDecision::deny("not on a Friday")
+        );
+        assert_eq!(answer("").0, ApprovalDecision::deny("no answer from user"));
+
+        let (decision, output) = answer("e\n{env: staging}\n{\"env\":\"staging\"}\n");
+        assert_eq!(
+            decision,
+            ApprovalDecision::Edit {
+                arguments: json!({ "env": "staging" })
+            }
+        );
+        assert!(output.contains("Invalid JSON"));
+    }
+
+    #[tokio::test]
+    async fn test_policy_classes_and_records() {
+        let (handler, mut requests) = ChannelApprovalHandler::new(1);
+        let policy = ApprovalPolicy::new(handler).safe("read").forbid("drop");
+
+        tokio::spawn(async move {
+            while let Some(request) = requests.recv().await {
+                request.respond(ApprovalDecision::deny("not now"));
+            }
+        });
+
+        assert_eq!(policy.review(&call("read")).await, None);
+        assert_eq!(
+            policy.review(&call("drop")).await,
+            Some(ApprovalDecision::deny("tool 'drop' is not allowed"))
+        );
+        assert_eq!(
+            policy.review(&call("deploy")).await,
+            Some(ApprovalDecision::deny("not now"))
+        );
+
+        let records = policy.records();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].class, ToolClass::Forbidden);
+        assert_eq!(records[1].call.name, "deploy");
+        assert_eq!(records[1].class, ToolClass::NeedsApproval);
+        assert_eq!(
+            records[1].metadata(),
+            HashMap::from([
+                ("approval".to_string(), "denied".to_string()),
+                ("approval_reason".to_string(), "not now".to_string()),
+            ])
+        );
+
+        assert_eq!(policy.take_record("1").unwrap().call.name, "deploy");
+        assert_eq!(policy.take_records().len(), 1);
+        assert!(policy.records().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_records_are_capped() {
+        let policy = ApprovalPolicy::new(ChannelApprovalHandler::new(1).0)
+            .forbid("drop")
+            .max_records(2);
+        for id in ["1", "2", "3"] {
+            let call = ToolCall {
+                id: id.to_string(),
+                ..call("drop")
+            };
+            policy.review(&call).await;
+        }
+        let ids: Vec<_> = policy.records().into_iter().map(|r| r.call.id).collect();
+        assert_eq!(ids, vec!["2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn test_channel_closed() {
+        let (handler, requests) = ChannelApprovalHandler::new(1);
+        drop(requests);
+        assert_eq!(
+            handler.review(&call("deploy")).await,
+            ApprovalDecision::deny("approval channel closed")
+        );
+    }
+}
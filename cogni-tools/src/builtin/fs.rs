@@ -0,0 +1,914 @@
+//! Filesystem tools confined to a root directory
+//!
+//! [`filesystem_tools`] creates `read_file`, `write_file`, `edit_file`,
+//! `list_dir`, `glob` and `grep`. Every path is resolved against the sandbox
+//! root: `..` can't climb out of it, symlinks are followed only when their
+//! target is also inside it, and directory walks don't descend into symlinks.
+
+use crate::error::{Result, ToolError};
+use crate::executor::{FunctionExecutor, FunctionExecutorBuilder};
+use crate::registry::ToolRegistry;
+use regex::{Regex, RegexBuilder};
+use serde_json::{json, Value};
+use std::fs::{self, OpenOptions};
+use std::io::{ErrorKind, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+
+/// Directories skipped by `glob` and `grep`
+const SKIPPED_DIRS: &[&str] = &[".git"];
+
+/// Where the filesystem tools may operate and what they may do
+///
+/// # Example
+///
+/// ```no_run
+/// use cogni_tools::builtin::fs::{create_filesystem_registry, Sandbox};
+///
+/// # async fn example() -> cogni_tools::error::Result<()> {
+/// let sandbox = Sandbox::new("./workspace")?
+///     .read_only(true)
+///     .max_file_size(256 * 1024);
+/// let registry = create_filesystem_registry(sandbox).await?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sandbox {
+    root: PathBuf,
+    read_only: bool,
+    max_file_size: u64,
+    max_results: usize,
+}
+
+impl Sandbox {
+    /// Confine the tools to `root`, which must be an existing directory
+    pub fn new(root: impl AsRef<Path>) -> Result<Self> {
+        let root = root.as_ref();
+        let canonical = root
+            .canonicalize()
+            .ok()
+            .filter(|path| path.is_dir())
+            .ok_or_else(|| ToolError::InvalidArguments {
+                tool: "filesystem".to_string(),
+                message: format!("sandbox root {} is not a directory", root.display()),
+                source: None,
+            })?;
+        Ok(Self {
+            root: canonical,
+            read_only: false,
+            max_file_size: 1024 * 1024,
+            max_results: 200,
+        })
+    }
+
+    /// Disable `write_file` and `edit_file`
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    /// Largest file, in bytes, that may be read or written (default 1 MiB)
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = bytes;
+        self
+    }
+
+    /// Most entries returned by `list_dir`, `glob` and `grep` (default 200)
+    pub fn max_results(mut self, max: usize) -> Self {
+        self.max_results = max;
+        self
+    }
+
+    /// The canonical root directory
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Resolve a path given to a tool to a location inside the root
+    ///
+    /// Relative paths are taken from the root. The path doesn't need to exist,
+    /// but the part of it that does must resolve inside the root.
+    pub fn resolve(&self, path: &str) -> std::result::Result<PathBuf, String> {
+        let requested = Path::new(path);
+        let joined = if requested.is_absolute() {
+            requested.to_path_buf()
+        } else {
+            self.root.join(requested)
+        };
+
+        // Remove `.` and `..` without touching the filesystem
+        let mut normalized = PathBuf::new();
+        for component in joined.components() {
+            match component {
+                Component::ParentDir => {
+                    normalized.pop();
+                }
+                Component::CurDir => {}
+                other => normalized.push(other),
+            }
+        }
+        if !normalized.starts_with(&self.root) {
+            return Err(format!("{} is outside the sandbox", path));
+        }
+
+        // Resolve symlinks in the longest existing prefix
+        let mut existing = normalized.as_path();
+        let mut rest = Vec::new();
+        let resolved = loop {
+            match existing.canonicalize() {
+                Ok(resolved) => break resolved,
+                // A dangling symlink can't be checked, and writing through it
+                // would create its target wherever that is
+                Err(_) if existing.is_symlink() => {
+                    return Err(format!("{} is a dangling symlink", path))
+                }
+                Err(_) => match (existing.parent(), existing.file_name()) {
+                    (Some(parent), Some(name)) => {
+                        rest.push(name.to_owned());
+                        existing = parent;
+                    }
+                    _ => return Err(format!("cannot resolve {}", path)),
+                },
+            }
+        };
+        if !resolved.starts_with(&self.root) {
+            return Err(format!("{} resolves outside the sandbox", path));
+        }
+        Ok(rest
+            .into_iter()
+            .rev()
+            .fold(resolved, |path, name| path.join(name)))
+    }
+
+    /// A path relative to the root, with `/` separators
+    fn display(&self, path: &Path) -> String {
+        let relative = path.strip_prefix(&self.root).unwrap_or(path);
+        let parts: Vec<_> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        if parts.is_empty() {
+            ".".to_string()
+        } else {
+            parts.join("/")
+        }
+    }
+
+    fn check_writable(&self) -> std::result::Result<(), String> {
+        if self.read_only {
+            Err("the filesystem is read-only".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Read a UTF-8 file within the size limit
+    fn read_text(&self, path: &Path, name: &str) -> std::result::Result<String, String> {
+        let metadata = fs::metadata(path).map_err(|e| io_message(name, e))?;
+        if !metadata.is_file() {
+            return Err(format!("{} is not a file", name));
+        }
+        if metadata.len() > self.max_file_size {
+            return Err(format!(
+                "{} is {} bytes, over the limit of {}",
+                name,
+                metadata.len(),
+                self.max_file_size
+            ));
+        }
+        let bytes = fs::read(path).map_err(|e| io_message(name, e))?;
+        String::from_utf8(bytes).map_err(|_| format!("{} is not a UTF-8 text file", name))
+    }
+
+    fn write_text(
+        &self,
+        path: &Path,
+        name: &str,
+        content: &str,
+    ) -> std::result::Result<(), String> {
+        if content.len() as u64 > self.max_file_size {
+            return Err(format!(
+                "content is {} bytes, over the limit of {}",
+                content.len(),
+                self.max_file_size
+            ));
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| io_message(name, e))?;
+        }
+        // Create new files exclusively, which fails rather than following a
+        // symlink planted after `resolve`
+        let file = match OpenOptions::new().write(true).create_new(true).open(path) {
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => {
+                if path.is_symlink() {
+                    return Err(format!("{} is a symlink", name));
+                }
+                OpenOptions::new().write(true).truncate(true).open(path)
+            }
+            file => file,
+        };
+        file.and_then(|mut file| file.write_all(content.as_bytes()))
+            .map_err(|e| io_message(name, e))
+    }
+
+    /// Files under `dir`, in path order, without following symlinks
+    fn walk(&self, dir: &Path, files: &mut Vec<PathBuf>) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        let mut entries: Vec<_> = entries.filter_map(|entry| entry.ok()).collect();
+        entries.sort_by_key(|entry| entry.file_name());
+        for entry in entries {
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            if file_type.is_dir() {
+                let name = entry.file_name();
+                if !SKIPPED_DIRS.iter().any(|skipped| name == *skipped) {
+                    self.walk(&entry.path(), files);
+                }
+            } else if file_type.is_file() {
+                files.push(entry.path());
+            }
+        }
+    }
+}
+
+fn io_message(name: &str, error: std::io::Error) -> String {
+    match error.kind() {
+        ErrorKind::NotFound => format!("{} does not exist", name),
+        ErrorKind::PermissionDenied => format!("permission denied for {}", name),
+        _ => format!("{}: {}", name, error),
+    }
+}
+
+/// Convert a glob to an anchored regex
+///
+/// Supports `*`, `**`, `?`, `[...]` and `{a,b}`.
+fn glob_regex(pattern: &str) -> std::result::Result<Regex, String> {
+    let mut regex = String::from("^");
+    let mut chars = pattern.chars().peekable();
+    let mut braces = 0;
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            '[' => {
+                regex.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    regex.push('^');
+                }
+                for c in chars.by_ref() {
+                    if c == ']' {
+                        break;
+                    }
+                    if c == '\\' || c == '[' {
+                        regex.push('\\');
+                    }
+                    regex.push(c);
+                }
+                regex.push(']');
+            }
+            '{' => {
+                braces += 1;
+                regex.push_str("(?:");
+            }
+            '}' if braces > 0 => {
+                braces -= 1;
+                regex.push(')');
+            }
+            ',' if braces > 0 => regex.push('|'),
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    regex.push('$');
+    Regex::new(&regex).map_err(|e| format!("invalid glob '{}': {}", pattern, e))
+}
+
+/// Build a tool whose blocking body runs off the async runtime
+fn tool(
+    sandbox: &Arc<Sandbox>,
+    name: &'static str,
+    description: &str,
+    parameters: Value,
+    body: fn(&Sandbox, &Value) -> std::result::Result<Value, String>,
+) -> FunctionExecutor {
+    let sandbox = sandbox.clone();
+    FunctionExecutorBuilder::new(name)
+        .description(description)
+        .parameters(parameters)
+        .build_async(move |args| {
+            let sandbox = sandbox.clone();
+            async move {
+                let result = tokio::task::spawn_blocking(move || body(&sandbox, &args))
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|result| result);
+                result.map_err(|message| ToolError::ExecutionFailed {
+                    tool: name.to_string(),
+                    message,
+                    source: None,
+                })
+            }
+        })
+}
+
+fn string_arg<'a>(args: &'a Value, key: &str) -> std::result::Result<&'a str, String> {
+    args[key]
+        .as_str()
+        .ok_or_else(|| format!("missing '{}'", key))
+}
+
+fn read_file(sandbox: &Sandbox, args: &Value) -> std::result::Result<Value, String> {
+    let name = string_arg(args, "path")?;
+    let path = sandbox.resolve(name)?;
+    let content = sandbox.read_text(&path, name)?;
+
+    let lines: Vec<&str> = content.lines().collect();
+    let total = lines.len();
+    let start = args["start_line"].as_u64().unwrap_or(1).max(1) as usize;
+    let end = args["end_line"]
+        .as_u64()
+        .map_or(total, |end| (end as usize).min(total));
+    let selected = if start > end {
+        String::new()
+    } else {
+        lines[start - 1..end].join("\n")
+    };
+
+    Ok(json!({
+        "path": sandbox.display(&path),
+        "content": selected,
+        "start_line": start,
+        "end_line": end.max(start - 1),
+        "total_lines": total,
+    }))
+}
+
+fn write_file(sandbox: &Sandbox, args: &Value) -> std::result::Result<Value, String> {
+    sandbox.check_writable()?;
+    let name = string_arg(args, "path")?;
+    let content = string_arg(args, "content")?;
+    let path = sandbox.resolve(name)?;
+    if path.is_dir() {
+        return Err(format!("{} is a directory", name));
+    }
+    sandbox.write_text(&path, name, content)?;
+    Ok(json!({
+        "path": sandbox.display(&path),
+        "bytes_written": content.len(),
+    }))
+}
+
+fn edit_file(sandbox: &Sandbox, args: &Value) -> std::result::Result<Value, String> {
+    sandbox.check_writable()?;
+    let name = string_arg(args, "path")?;
+    let path = sandbox.resolve(name)?;
+    let mut content = sandbox.read_text(&path, name)?;
+    let edits = args["edits"].as_array().ok_or("missing 'edits'")?;
+
+    // Apply every edit or none
+    let mut replacements = 0;
+    for (i, edit) in edits.iter().enumerate() {
+        let old = string_arg(edit, "old_text")?;
+        let new = string_arg(edit, "new_text")?;
+        if old.is_empty() {
+            return Err(format!("edit {}: old_text is empty", i));
+        }
+        let count = content.matches(old).count();
+        let replace_all = edit["replace_all"].as_bool().unwrap_or(false);
+        match count {
+            0 => return Err(format!("edit {}: old_text not found in {}", i, name)),
+            1 => {}
+            _ if replace_all => {}
+            _ => {
+                return Err(format!(
+                    "edit {}: old_text occurs {} times in {}; add context or set replace_all",
+                    i, count, name
+                ))
+            }
+        }
+        content = content.replace(old, new);
+        replacements += count;
+    }
+
+    sandbox.write_text(&path, name, &content)?;
+    Ok(json!({
+        "path": sandbox.display(&path),
+        "replacements": replacements,
+    }))
+}
+
+fn list_dir(sandbox: &Sandbox, args: &Value) -> std::result::Result<Value, String> {
+    let name = args["path"].as_str().unwrap_or(".");
+    let path = sandbox.resolve(name)?;
+    let entries = fs::read_dir(&path).map_err(|e| io_message(name, e))?;
+
+    let mut listed = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let Ok(metadata) = entry.path().symlink_metadata() else {
+            continue;
+        };
+        let kind = if metadata.is_symlink() {
+            "symlink"
+        } else if metadata.is_dir() {
+            "dir"
+        } else {
+            "file"
+        };
+        listed.push(json!({
+            "name": entry.file_name().to_string_lossy(),
+            "type": kind,
+            "size": metadata.len(),
+        }));
+    }
+    listed.sort_by(|a, b| a["name"].as_str().cmp(&b["name"].as_str()));
+    let truncated = listed.len() > sandbox.max_results;
+    listed.truncate(sandbox.max_results);
+
+    Ok(json!({
+        "path": sandbox.display(&path),
+        "entries": listed,
+        "truncated": truncated,
+    }))
+}
+
+fn glob(sandbox: &Sandbox, args: &Value) -> std::result::Result<Value, String> {
+    let pattern = glob_regex(string_arg(args, "pattern")?)?;
+    let base = sandbox.resolve(args["path"].as_str().unwrap_or("."))?;
+
+    let mut files = Vec::new();
+    sandbox.walk(&base, &mut files);
+    let mut matches: Vec<String> = files
+        .iter()
+        .filter(|file| {
+            let relative = file.strip_prefix(&base).unwrap_or(file);
+            pattern.is_match(&relative.to_string_lossy().replace('\\', "/"))
+        })
+        .map(|file| sandbox.display(file))
+        .collect();
+    let truncated = matches.len() > sandbox.max_results;
+    matches.truncate(sandbox.max_results);
+
+    Ok(json!({ "matches": matches, "truncated": truncated }))
+}
+
+fn grep(sandbox: &Sandbox, args: &Value) -> std::result::Result<Value, String> {
+    let pattern = string_arg(args, "pattern")?;
+    let regex = RegexBuilder::new(pattern)
+        .case_insensitive(args["case_insensitive"].as_bool().unwrap_or(false))
+        .build()
+        .map_err(|e| format!("invalid pattern '{}': {}", pattern, e))?;
+    let include = args["glob"].as_str().map(glob_regex).transpose()?;
+    let base = sandbox.resolve(args["path"].as_str().unwrap_or("."))?;
+
+    let mut files = Vec::new();
+    if base.is_file() {
+        files.push(base.clone());
+    } else {
+        sandbox.walk(&base, &mut files);
+    }
+
+    let mut matches = Vec::new();
+    let mut truncated = false;
+    'files: for file in files {
+        if let Some(include) = &include {
+            let relative = file.strip_prefix(&base).unwrap_or(&file);
+            if !include.is_match(&relative.to_string_lossy().replace('\\', "/")) {
+                continue;
+            }
+        }
+        // Large and binary files are skipped rather than failing the search
+        let name = sandbox.display(&file);
+        let Ok(content) = sandbox.read_text(&file, &name) else {
+            continue;
+        };
+        for (i, line) in content.lines().enumerate() {
+            if regex.is_match(line) {
+                if matches.len() == sandbox.max_results {
+                    truncated = true;
+                    break 'files;
+                }
+                matches.push(json!({ "path": name, "line": i + 1, "text": line }));
+            }
+        }
+    }
+
+    Ok(json!({ "matches": matches, "truncated": truncated }))
+}
+
+/// Create the filesystem tools for a sandbox
+///
+/// In read-only mode `write_file` and `edit_file` are left out.
+pub fn filesystem_tools(sandbox: Sandbox) -> Vec<FunctionExecutor> {
+    let sandbox = Arc::new(sandbox);
+    let mut tools = vec![
+        tool(
+            &sandbox,
+            "read_file",
+            "Read a UTF-8 text file, optionally only a range of lines",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File path relative to the workspace root" },
+                    "start_line": { "type": "integer", "minimum": 1, "description": "First line to return (1-based)" },
+                    "end_line": { "type": "integer", "minimum": 1, "description": "Last line to return (inclusive)" }
+                },
+                "required": ["path"],
+                "additionalProperties": false
+            }),
+            read_file,
+        ),
+        tool(
+            &sandbox,
+            "list_dir",
+            "List the entries of a directory",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "Directory relative to the workspace root (default: the root)" }
+                },
+                "additionalProperties": false
+            }),
+            list_dir,
+        ),
+        tool(
+            &sandbox,
+            "glob",
+            "Find files whose paths match a glob such as src/**/*.rs",
+            json!({
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string", "description": "Glob supporting *, **, ?, [abc] and {a,b}" },
+                    "path": { "type": "string", "description": "Directory to search from (default: the root)" }
+                },
+                "required": ["pattern"],
+                "additionalProperties": false
+            }),
+            glob,
+        ),
+        tool(
+            &sandbox,
+            "grep",
+            "Search file contents for lines matching a regular expression",
+            json!({
+                "type": "object",
+                "properties": {
+                    "pattern": { "type": "string", "description": "Regular expression" },
+                    "path": { "type": "string", "description": "File or directory to search (default: the root)" },
+                    "glob": { "type": "string", "description": "Only search files matching this glob" },
+                    "case_insensitive": { "type": "boolean" }
+                },
+                "required": ["pattern"],
+                "additionalProperties": false
+            }),
+            grep,
+        ),
+    ];
+
+    if !sandbox.read_only {
+        tools.push(tool(
+            &sandbox,
+            "write_file",
+            "Create or overwrite a file, creating parent directories as needed",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File path relative to the workspace root" },
+                    "content": { "type": "string", "description": "The complete new content" }
+                },
+                "required": ["path", "content"],
+                "additionalProperties": false
+            }),
+            write_file,
+        ));
+        tools.push(tool(
+            &sandbox,
+            "edit_file",
+            "Replace exact text in a file. Each old_text must occur once unless replace_all is set; \
+             either every edit applies or none do",
+            json!({
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File path relative to the workspace root" },
+                    "edits": {
+                        "type": "array",
+                        "minItems": 1,
+                        "items": {
+                            "type": "object",
+                            "properties": {
+                                "old_text": { "type": "string", "minLength": 1 },
+                                "new_text": { "type": "string" },
+                                "replace_all": { "type": "boolean" }
+                            },
+                            "required": ["old_text", "new_text"],
+                            "additionalProperties": false
+                        }
+                    }
+                },
+                "required": ["path", "edits"],
+                "additionalProperties": false
+            }),
+            edit_file,
+        ));
+    }
+
+    tools
+}
+
+/// Create a registry with the filesystem tools for a sandbox
+pub async fn create_filesystem_registry(sandbox: Sandbox) -> Result<ToolRegistry> {
+    let registry = ToolRegistry::new();
+    registry.register(filesystem_tools(sandbox)).await?;
+    Ok(registry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::executor::ToolExecutor;
+    use cogni_core::{ToolCall, ToolResult};
+
+    async fn run(registry: &ToolRegistry, name: &str, args: Value) -> ToolResult {
+        let call = ToolCall {
+            id: "1".to_string(),
+            name: name.to_string(),
+            arguments: args.to_string(),
+        };
+        registry.execute(&call).await.unwrap()
+    }
+
+    fn output(result: &ToolResult) -> Value {
+        assert!(result.success, "{}", result.content);
+        serde_json::from_str(&result.content).unwrap()
+    }
+
+    fn workspace() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("src/nested")).unwrap();
+        fs::create_dir_all(dir.path().join(".git")).unwrap();
+        fs::write(dir.path().join("README.md"), "# Demo\nhello world\n").unwrap();
+        fs::write(dir.path().join("src/lib.rs"), "fn a() {}\nfn b() {}\n").unwrap();
+        fs::write(dir.path().join("src/nested/mod.rs"), "// TODO: b\n").unwrap();
+        fs::write(dir.path().join(".git/config"), "fn hidden\n").unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_resolve_confines_paths() {
+        let dir = workspace();
+        let sandbox = Sandbox::new(dir.path()).unwrap();
+        let root = sandbox.root().to_path_buf();
+
+        assert_eq!(
+            sandbox.resolve("src/lib.rs").unwrap(),
+            root.join("src/lib.rs")
+        );
+        assert_eq!(
+            sandbox.resolve("src/../new/file.txt").unwrap(),
+            root.join("new/file.txt")
+        );
+        assert_eq!(
+            sandbox
+                .resolve(root.join("README.md").to_str().unwrap())
+                .unwrap(),
+            root.join("README.md")
+        );
+        assert!(sandbox.resolve("../outside").is_err());
+        assert!(sandbox.resolve("src/../../outside").is_err());
+        assert!(sandbox.resolve("/etc/passwd").is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_symlink_escapes_are_rejected() {
+        let dir = workspace();
+        let outside = tempfile::tempdir().unwrap();
+        fs::write(outside.path().join("secret"), "secret").unwrap();
+        std::os::unix::fs::symlink(outside.path(), dir.path().join("link")).unwrap();
+        std::os::unix::fs::symlink(dir.path().join("README.md"), dir.path().join("readme"))
+            .unwrap();
+
+        let sandbox = Sandbox::new(dir.path()).unwrap();
+        assert!(sandbox.resolve("link/secret").is_err());
+        assert!(sandbox.resolve("link/new.txt").is_err());
+        assert!(sandbox.resolve("readme").is_ok());
+
+        let registry = create_filesystem_registry(sandbox).await.unwrap();
+        let result = run(&registry, "read_file", json!({ "path": "link/secret" })).await;
+        assert!(!result.success);
+        assert!(result.content.contains("resolves outside the sandbox"));
+
+        // Dangling links can't be used to create files outside the root
+        let escaped = outside.path().join("escaped.txt");
+        std::os::unix::fs::symlink(&escaped, dir.path().join("dangling")).unwrap();
+        let result = run(
+            &registry,
+            "write_file",
+            json!({ "path": "dangling", "content": "escaped" }),
+        )
+        .await;
+        assert!(!result.success);
+        assert!(result.content.contains("dangling symlink"));
+        assert!(!escaped.exists());
+
+        // Walks don't follow the link
+        let found = output(&run(&registry, "grep", json!({ "pattern": "secret" })).await);
+        assert_eq!(found["matches"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_read_and_list() {
+        let dir = workspace();
+        let registry = create_filesystem_registry(Sandbox::new(dir.path()).unwrap())
+            .await
+            .unwrap();
+
+        let read = output(&run(&registry, "read_file", json!({ "path": "README.md" })).await);
+        assert_eq!(read["content"], "# Demo\nhello world");
+        assert_eq!(read["total_lines"], 2);
+
+        let read = output(
+            &run(
+                &registry,
+                "read_file",
+                json!({ "path": "src/lib.rs", "start_line": 2, "end_line": 5 }),
+            )
+            .await,
+        );
+        assert_eq!(read["content"], "fn b() {}");
+        assert_eq!(read["start_line"], 2);
+        assert_eq!(read["end_line"], 2);
+
+        let listed = output(&run(&registry, "list_dir", json!({})).await);
+        let names: Vec<_> = listed["entries"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| {
+                format!(
+                    "{} {}",
+                    e["name"].as_str().unwrap(),
+                    e["type"].as_str().unwrap()
+                )
+            })
+            .collect();
+        assert_eq!(names, vec![".git dir", "README.md file", "src dir"]);
+
+        let missing = run(&registry, "read_file", json!({ "path": "nope.txt" })).await;
+        assert!(missing.content.contains("nope.txt does not exist"));
+    }
+
+    #[tokio::test]
+    async fn test_glob_and_grep() {
+        let dir = workspace();
+        let registry = create_filesystem_registry(Sandbox::new(dir.path()).unwrap())
+            .await
+            .unwrap();
+
+        let found = output(&run(&registry, "glob", json!({ "pattern": "**/*.rs" })).await);
+        assert_eq!(found["matches"], json!(["src/lib.rs", "src/nested/mod.rs"]));
+        let found = output(
+            &run(
+                &registry,
+                "glob",
+                json!({ "pattern": "*.{md,toml}", "path": "." }),
+            )
+            .await,
+        );
+        assert_eq!(found["matches"], json!(["README.md"]));
+
+        let found = output(&run(&registry, "grep", json!({ "pattern": "\\bb\\b" })).await);
+        assert_eq!(
+            found["matches"],
+            json!([
+                { "path": "src/lib.rs", "line": 2, "text": "fn b() {}" },
+                { "path": "src/nested/mod.rs", "line": 1, "text": "// TODO: b" }
+            ])
+        );
+        let found = output(
+            &run(
+                &registry,
+                "grep",
+                json!({ "pattern": "HELLO", "case_insensitive": true, "glob": "*.md" }),
+            )
+            .await,
+        );
+        assert_eq!(found["matches"][0]["path"], "README.md");
+    }
+
+    #[tokio::test]
+    async fn test_write_and_edit() {
+        let dir = workspace();
+        let registry = create_filesystem_registry(Sandbox::new(dir.path()).unwrap())
+            .await
+            .unwrap();
+
+        let written = output(
+            &run(
+                &registry,
+                "write_file",
+                json!({ "path": "docs/notes.txt", "content": "one\ntwo\ntwo\n" }),
+            )
+            .await,
+        );
+        assert_eq!(written["bytes_written"], 12);
+
+        // Ambiguous edits fail and leave the file untouched
+        let result = run(
+            &registry,
+            "edit_file",
+            json!({ "path": "docs/notes.txt", "edits": [
+                { "old_text": "one", "new_text": "1" },
+                { "old_text": "two", "new_text": "2" }
+            ] }),
+        )
+        .await;
+        assert!(result.content.contains("occurs 2 times"));
+        assert_eq!(
+            fs::read_to_string(dir.path().join("docs/notes.txt")).unwrap(),
+            "one\ntwo\ntwo\n"
+        );
+
+        let edited = output(
+            &run(
+                &registry,
+                "edit_file",
+                json!({ "path": "docs/notes.txt", "edits": [
+                    { "old_text": "one", "new_text": "1" },
+                    { "old_text": "two", "new_text": "2", "replace_all": true }
+                ] }),
+            )
+            .await,
+        );
+        assert_eq!(edited["replacements"], 3);
+        assert_eq!(
+            fs::read_to_string(dir.path().join("docs/notes.txt")).unwrap(),
+            "1\n2\n2\n"
+        );
+
+        let escaped = run(
+            &registry,
+            "write_file",
+            json!({ "path": "../escape.txt", "content": "x" }),
+        )
+        .await;
+        assert!(escaped.content.contains("outside the sandbox"));
+    }
+
+    #[tokio::test]
+    async fn test_limits_and_read_only() {
+        let dir = workspace();
+        let sandbox = Sandbox::new(dir.path())
+            .unwrap()
+            .max_file_size(10)
+            .max_results(1);
+        let registry = create_filesystem_registry(sandbox.clone()).await.unwrap();
+
+        let result = run(&registry, "read_file", json!({ "path": "README.md" })).await;
+        assert!(result.content.contains("over the limit of 10"));
+        let result = run(
+            &registry,
+            "write_file",
+            json!({ "path": "big.txt", "content": "01234567890" }),
+        )
+        .await;
+        assert!(result.content.contains("over the limit of 10"));
+        let found = output(&run(&registry, "glob", json!({ "pattern": "**" })).await);
+        assert_eq!(found["truncated"], true);
+
+        let tools = filesystem_tools(sandbox.read_only(true));
+        let names: Vec<_> = tools.iter().map(|t| t.tool().name.as_str()).collect();
+        assert_eq!(names, vec!["read_file", "list_dir", "glob", "grep"]);
+    }
+
+    #[test]
+    fn test_glob_regex() {
+        let matches = |glob: &str, path: &str| glob_regex(glob).unwrap().is_match(path);
+        assert!(matches("*.rs", "lib.rs"));
+        assert!(!matches("*.rs", "src/lib.rs"));
+        assert!(matches("**/*.rs", "lib.rs"));
+        assert!(matches("**/*.rs", "a/b/lib.rs"));
+        assert!(matches("src/**", "src/a/b"));
+        assert!(matches("file?.[ch]", "file1.c"));
+        assert!(!matches("file[!0-9].c", "file1.c"));
+        assert!(matches("*.{md,txt}", "notes.txt"));
+        assert!(matches("a+b.txt", "a+b.txt"));
+    }
+
+    #[test]
+    fn test_sandbox_root_must_exist() {
+        assert!(Sandbox::new("/definitely/not/here").is_err());
+    }
+}
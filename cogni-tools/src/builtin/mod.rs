@@ -1,5 +1,8 @@
 //! Built-in tools for common operations
 
+pub mod fs;
+pub mod shell;
+
 use crate::error::Result;
 use crate::executor::{FunctionExecutor, FunctionExecutorBuilder};
 use crate::validation::param_schema;
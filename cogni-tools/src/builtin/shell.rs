@@ -0,0 +1,610 @@
+//! A tool that runs programs with resource limits
+//!
+//! The `shell` tool takes a program and an argument list; nothing is
+//! interpreted by a shell, so the allow and deny lists can't be sidestepped
+//! with `;` or `$(...)`. Commands run in a fixed working directory with a
+//! scrubbed environment, a wall-clock timeout and capped output.
+
+use crate::error::{Result, ToolError};
+use crate::executor::ToolExecutor;
+use crate::validation::check_args;
+use async_trait::async_trait;
+use cogni_core::{CompiledSchema, Function, Tool, ToolCall, ToolResult};
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt};
+use tokio::process::Command;
+use tokio_util::sync::CancellationToken;
+
+/// Variables passed through from the parent environment by default
+const DEFAULT_PASS_ENV: &[&str] = &["PATH", "HOME", "LANG", "TERM"];
+
+/// How long to wait for the pipes to close once the process group is killed
+const KILL_GRACE: Duration = Duration::from_secs(1);
+
+/// Operating system limits applied to the spawned process
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ResourceLimits {
+    /// CPU time in seconds (`ulimit -t`)
+    pub cpu_seconds: Option<u64>,
+    /// Virtual memory in bytes (`ulimit -v`)
+    pub memory_bytes: Option<u64>,
+    /// Run in a new network namespace with `unshare`, so the command has no
+    /// network access; commands fail to start where `unshare` isn't usable
+    pub no_network: bool,
+}
+
+/// Runs allowed programs for a model
+///
+/// # Example
+///
+/// ```
+/// use cogni_tools::builtin::shell::{ResourceLimits, ShellExecutor};
+/// use std::time::Duration;
+///
+/// let shell = ShellExecutor::new("/tmp")
+///     .allow(["ls", "cat", "grep", "cargo"])
+///     .timeout(Duration::from_secs(60))
+///     .limits(ResourceLimits {
+///         cpu_seconds: Some(30),
+///         memory_bytes: Some(1 << 30),
+///         no_network: true,
+///     });
+/// ```
+pub struct ShellExecutor {
+    tool: Tool,
+    schema: CompiledSchema,
+    working_dir: PathBuf,
+    allow: Option<HashSet<String>>,
+    deny: HashSet<String>,
+    timeout: Duration,
+    max_output_bytes: usize,
+    pass_env: Vec<String>,
+    env: BTreeMap<String, String>,
+    limits: ResourceLimits,
+}
+
+impl ShellExecutor {
+    /// Create a tool that runs commands in `working_dir`
+    ///
+    /// Any program may run until [`allow`](Self::allow) or
+    /// [`deny`](Self::deny) restricts it.
+    pub fn new(working_dir: impl Into<PathBuf>) -> Self {
+        let parameters = json!({
+            "type": "object",
+            "properties": {
+                "program": {
+                    "type": "string",
+                    "minLength": 1,
+                    "description": "Program to run, e.g. \"ls\"; no shell syntax is interpreted"
+                },
+                "args": {
+                    "type": "array",
+                    "items": { "type": "string" },
+                    "description": "Arguments passed to the program"
+                },
+                "stdin": { "type": "string", "description": "Text written to standard input" }
+            },
+            "required": ["program"],
+            "additionalProperties": false
+        });
+        Self {
+            schema: CompiledSchema::compile(&parameters).expect("shell schema is valid"),
+            tool: Tool {
+                name: "shell".to_string(),
+                description:
+                    "Run a program with arguments and return its exit code, stdout and stderr"
+                        .to_string(),
+                function: Function {
+                    parameters,
+                    returns: Some("JSON with exit_code, stdout, stderr and timed_out".to_string()),
+                },
+            },
+            working_dir: working_dir.into(),
+            allow: None,
+            deny: HashSet::new(),
+            timeout: Duration::from_secs(30),
+            max_output_bytes: 64 * 1024,
+            pass_env: DEFAULT_PASS_ENV.iter().map(|s| s.to_string()).collect(),
+            env: BTreeMap::new(),
+            limits: ResourceLimits::default(),
+        }
+    }
+
+    /// Only run these programs, given by bare name and looked up on `PATH`
+    pub fn allow(mut self, programs: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.allow
+            .get_or_insert_with(HashSet::new)
+            .extend(programs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Never run these programs, matched by file name
+    pub fn deny(mut self, programs: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.deny.extend(programs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Kill commands that run longer than this (default 30 seconds)
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Keep at most this many bytes each of stdout and stderr (default 64 KiB)
+    pub fn max_output_bytes(mut self, bytes: usize) -> Self {
+        self.max_output_bytes = bytes;
+        self
+    }
+
+    /// Replace the variables passed through from the parent environment
+    ///
+    /// Defaults to `PATH`, `HOME`, `LANG` and `TERM`; everything else is
+    /// removed.
+    pub fn pass_env(mut self, names: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.pass_env = names.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set an environment variable for every command
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Apply operating system resource limits
+    pub fn limits(mut self, limits: ResourceLimits) -> Self {
+        self.limits = limits;
+        self
+    }
+
+    /// The reason a program may not run, if any
+    fn check_program(&self, program: &str) -> Option<String> {
+        let name = Path::new(program)
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if self.deny.contains(program) || self.deny.contains(&name) {
+            return Some(format!("program '{}' is not allowed", program));
+        }
+        match &self.allow {
+            Some(_) if program.contains('/') => Some(format!(
+                "program '{}' must be given by name, not path",
+                program
+            )),
+            Some(allow) if !allow.contains(program) => {
+                Some(format!("program '{}' is not allowed", program))
+            }
+            _ => None,
+        }
+    }
+
+    /// The command to spawn, wrapped to apply resource limits
+    fn command(&self, program: &str, args: &[String]) -> Command {
+        let mut argv: Vec<String> = Vec::new();
+        if self.limits.no_network {
+            argv.extend(["unshare", "--user", "--map-root-user", "--net", "--"].map(String::from));
+        }
+        let mut ulimits = Vec::new();
+        if let Some(seconds) = self.limits.cpu_seconds {
+            ulimits.push(format!("ulimit -t {}", seconds));
+        }
+        if let Some(bytes) = self.limits.memory_bytes {
+            ulimits.push(format!("ulimit -v {}", bytes.div_ceil(1024)));
+        }
+        if !ulimits.is_empty() {
+            argv.extend([
+                "/bin/sh".to_string(),
+                "-c".to_string(),
+                format!("{} && exec \"$0\" \"$@\"", ulimits.join(" && ")),
+            ]);
+        }
+        argv.push(program.to_string());
+        argv.extend(args.iter().cloned());
+
+        let mut command = Command::new(&argv[0]);
+        command
+            .args(&argv[1..])
+            .current_dir(&self.working_dir)
+            .env_clear()
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        #[cfg(unix)]
+        command.process_group(0);
+        for name in &self.pass_env {
+            if let Ok(value) = std::env::var(name) {
+                command.env(name, value);
+            }
+        }
+        command.envs(&self.env);
+        command
+    }
+
+    fn failed(&self, message: String) -> ToolError {
+        ToolError::ExecutionFailed {
+            tool: self.tool.name.clone(),
+            message,
+            source: None,
+        }
+    }
+}
+
+/// The process group a command runs in, led by the command itself
+struct ProcessGroup(Option<u32>);
+
+impl ProcessGroup {
+    /// Kill every process in the group, including background processes the
+    /// command started
+    fn kill(&self) {
+        #[cfg(unix)]
+        if let Some(pid) = self
+            .0
+            .and_then(|id| i32::try_from(id).ok())
+            .and_then(rustix::process::Pid::from_raw)
+        {
+            let _ = rustix::process::kill_process_group(pid, rustix::process::Signal::KILL);
+        }
+    }
+}
+
+/// Read a stream to the end, keeping at most `limit` bytes
+async fn read_capped(
+    mut stream: impl AsyncRead + Unpin,
+    limit: usize,
+) -> std::io::Result<(String, bool)> {
+    let mut kept = Vec::new();
+    let mut truncated = false;
+    let mut buffer = [0u8; 8192];
+    loop {
+        let n = stream.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        let room = limit.saturating_sub(kept.len());
+        kept.extend_from_slice(&buffer[..n.min(room)]);
+        truncated |= n > room;
+    }
+    Ok((String::from_utf8_lossy(&kept).into_owned(), truncated))
+}
+
+#[async_trait]
+impl ToolExecutor for ShellExecutor {
+    async fn execute(&self, call: &ToolCall) -> Result<ToolResult> {
+        self.execute_cancellable(call, &CancellationToken::new())
+            .await
+    }
+
+    async fn execute_cancellable(
+        &self,
+        call: &ToolCall,
+        cancel: &CancellationToken,
+    ) -> Result<ToolResult> {
+        let args: Value =
+            serde_json::from_str(&call.arguments).map_err(|e| ToolError::InvalidArguments {
+                tool: call.name.clone(),
+                message: format!("Failed to parse arguments: {}", e),
+                source: Some(Box::new(e)),
+            })?;
+        self.validate(&args).await?;
+
+        let program = args["program"].as_str().unwrap_or_default();
+        let argv: Vec<String> = args["args"]
+            .as_array()
+            .map(|items| {
+                items
+                    .iter()
+                    .filter_map(|item| item.as_str().map(String::from))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let deadline = tokio::time::Instant::now() + self.timeout;
+        let mut child = self
+            .command(program, &argv)
+            .spawn()
+            .map_err(|e| self.failed(format!("failed to start '{}': {}", program, e)))?;
+        let group = ProcessGroup(child.id());
+
+        // Feed stdin and drain both pipes concurrently so none can block
+        let stdin = args["stdin"].as_str().unwrap_or_default().to_string();
+        let mut input = child.stdin.take().expect("stdin is piped");
+        let writer = tokio::spawn(async move {
+            use tokio::io::AsyncWriteExt;
+            let _ = input.write_all(stdin.as_bytes()).await;
+        });
+        let stdout = tokio::spawn(read_capped(
+            child.stdout.take().expect("stdout is piped"),
+            self.max_output_bytes,
+        ));
+        let stderr = tokio::spawn(read_capped(
+            child.stderr.take().expect("stderr is piped"),
+            self.max_output_bytes,
+        ));
+        let readers = [stdout.abort_handle(), stderr.abort_handle()];
+
+        let status = tokio::select! {
+            status = tokio::time::timeout_at(deadline, child.wait()) => match status {
+                Ok(status) => Some(status.map_err(|e| self.failed(e.to_string()))?),
+                Err(_) => {
+                    group.kill();
+                    let _ = child.kill().await;
+                    None
+                }
+            },
+            _ = cancel.cancelled() => {
+                group.kill();
+                let _ = child.kill().await;
+                return Err(ToolError::Cancelled { tool: call.name.clone() });
+            }
+        };
+        writer.abort();
+
+        // Background processes the command started can hold the pipes open
+        // after it exits, so the reads share its deadline
+        let mut timed_out = status.is_none();
+        let reads = async { (stdout.await, stderr.await) };
+        tokio::pin!(reads);
+        let (stdout, stderr) = tokio::select! {
+            output = tokio::time::timeout_at(deadline, &mut reads) => match output {
+                Ok(output) => output,
+                Err(_) => {
+                    timed_out = true;
+                    group.kill();
+                    match tokio::time::timeout(KILL_GRACE, &mut reads).await {
+                        Ok(output) => output,
+                        Err(_) => {
+                            readers.iter().for_each(|reader| reader.abort());
+                            reads.await
+                        }
+                    }
+                }
+            },
+            _ = cancel.cancelled() => {
+                group.kill();
+                readers.iter().for_each(|reader| reader.abort());
+                return Err(ToolError::Cancelled { tool: call.name.clone() });
+            }
+        };
+
+        let collect = |joined: std::result::Result<std::io::Result<(String, bool)>, _>| {
+            joined
+                .map_err(|e: tokio::task::JoinError| e.to_string())
+                .and_then(|read| read.map_err(|e| e.to_string()))
+                .unwrap_or_default()
+        };
+        let (stdout, stdout_truncated) = collect(stdout);
+        let (stderr, stderr_truncated) = collect(stderr);
+
+        let exit_code = status.and_then(|status| status.code());
+        let output = json!({
+            "exit_code": exit_code,
+            "stdout": stdout,
+            "stderr": stderr,
+            "stdout_truncated": stdout_truncated,
+            "stderr_truncated": stderr_truncated,
+            "timed_out": timed_out,
+        });
+        Ok(ToolResult {
+            call_id: call.id.clone(),
+            content: output.to_string(),
+            success: exit_code == Some(0) && !timed_out,
+        })
+    }
+
+    fn tool(&self) -> &Tool {
+        &self.tool
+    }
+
+    async fn validate(&self, args: &Value) -> Result<()> {
+        check_args(&self.tool.name, &self.schema, args)?;
+        let program = args["program"].as_str().unwrap_or_default();
+        match self.check_program(program) {
+            Some(reason) => Err(ToolError::ValidationFailed {
+                tool: self.tool.name.clone(),
+                errors: vec![reason],
+            }),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn call(args: Value) -> ToolCall {
+        ToolCall {
+            id: "1".to_string(),
+            name: "shell".to_string(),
+            arguments: args.to_string(),
+        }
+    }
+
+    async fn run(shell: &ShellExecutor, args: Value) -> (ToolResult, Value) {
+        let result = shell.execute(&call(args)).await.unwrap();
+        let output = serde_json::from_str(&result.content).unwrap();
+        (result, output)
+    }
+
+    #[tokio::test]
+    async fn test_runs_in_working_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("hello.txt"), "hi").unwrap();
+        let shell = ShellExecutor::new(dir.path());
+
+        let (result, output) = run(&shell, json!({ "program": "ls" })).await;
+        assert!(result.success);
+        assert_eq!(output["exit_code"], 0);
+        assert_eq!(output["stdout"], "hello.txt\n");
+
+        let (result, output) = run(&shell, json!({ "program": "cat", "stdin": "piped" })).await;
+        assert!(result.success);
+        assert_eq!(output["stdout"], "piped");
+
+        let (result, output) = run(
+            &shell,
+            json!({ "program": "sh", "args": ["-c", "echo oops >&2; exit 3"] }),
+        )
+        .await;
+        assert!(!result.success);
+        assert_eq!(output["exit_code"], 3);
+        assert_eq!(output["stderr"], "oops\n");
+    }
+
+    #[tokio::test]
+    async fn test_allow_and_deny_lists() {
+        let shell = ShellExecutor::new(".").allow(["echo", "rm"]).deny(["rm"]);
+
+        assert!(shell.validate(&json!({ "program": "echo" })).await.is_ok());
+        for (args, reason) in [
+            (
+                json!({ "program": "rm", "args": ["-rf", "/"] }),
+                "program 'rm' is not allowed",
+            ),
+            (
+                json!({ "program": "curl" }),
+                "program 'curl' is not allowed",
+            ),
+            (
+                json!({ "program": "/tmp/echo" }),
+                "program '/tmp/echo' must be given by name, not path",
+            ),
+        ] {
+            let err = shell.execute(&call(args)).await.unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!("Validation failed for tool 'shell': {}", reason)
+            );
+        }
+
+        let err = shell
+            .validate(&json!({ "program": "echo", "args": "not a list" }))
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("/args: expected array"));
+
+        // Paths are matched by file name against the deny list
+        let shell = ShellExecutor::new(".").deny(["rm"]);
+        assert!(shell
+            .validate(&json!({ "program": "/bin/rm" }))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_truncation() {
+        let shell = ShellExecutor::new(".")
+            .timeout(Duration::from_millis(100))
+            .max_output_bytes(10);
+
+        let (result, output) = run(&shell, json!({ "program": "sleep", "args": ["5"] })).await;
+        assert!(!result.success);
+        assert_eq!(output["timed_out"], true);
+        assert_eq!(output["exit_code"], Value::Null);
+
+        // Background processes are killed with the command
+        let started = std::time::Instant::now();
+        let (result, output) = run(
+            &shell,
+            json!({ "program": "sh", "args": ["-c", "sleep 8 & sleep 30"] }),
+        )
+        .await;
+        assert!(!result.success);
+        assert_eq!(output["timed_out"], true);
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        // ...also when the command itself has already exited
+        let started = std::time::Instant::now();
+        let (result, output) = run(
+            &shell,
+            json!({ "program": "sh", "args": ["-c", "sleep 8 & echo started"] }),
+        )
+        .await;
+        assert!(!result.success);
+        assert_eq!(output["timed_out"], true);
+        assert_eq!(output["exit_code"], 0);
+        assert!(started.elapsed() < Duration::from_secs(3));
+
+        let (_, output) = run(&shell, json!({ "program": "seq", "args": ["1", "1000"] })).await;
+        assert_eq!(output["stdout"], "1\n2\n3\n4\n5\n");
+        assert_eq!(output["stdout_truncated"], true);
+        assert_eq!(output["stderr_truncated"], false);
+    }
+
+    #[tokio::test]
+    async fn test_environment_is_scrubbed() {
+        std::env::set_var("COGNI_SHELL_TEST_SECRET", "hunter2");
+        let shell = ShellExecutor::new(".")
+            .pass_env(["PATH"])
+            .env("GREETING", "hello");
+
+        let (_, output) = run(&shell, json!({ "program": "env" })).await;
+        let stdout = output["stdout"].as_str().unwrap();
+        assert!(stdout.contains("GREETING=hello"));
+        assert!(stdout.contains("PATH="));
+        assert!(!stdout.contains("hunter2"));
+        assert!(!stdout.contains("HOME="));
+    }
+
+    #[tokio::test]
+    async fn test_resource_limits() {
+        let shell = ShellExecutor::new(".").limits(ResourceLimits {
+            cpu_seconds: Some(5),
+            memory_bytes: Some(512 * 1024 * 1024),
+            no_network: false,
+        });
+        let (result, output) = run(
+            &shell,
+            json!({ "program": "sh", "args": ["-c", "ulimit -t; ulimit -v"] }),
+        )
+        .await;
+        assert!(result.success, "{}", result.content);
+        assert_eq!(output["stdout"], "5\n524288\n");
+
+        let shell = ShellExecutor::new(".").limits(ResourceLimits {
+            no_network: true,
+            ..ResourceLimits::default()
+        });
+        let command = shell.command("ls", &["-l".to_string()]);
+        let argv: Vec<_> = std::iter::once(command.as_std().get_program())
+            .chain(command.as_std().get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(
+            argv,
+            vec![
+                "unshare",
+                "--user",
+                "--map-root-user",
+                "--net",
+                "--",
+                "ls",
+                "-l"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_kills_the_command() {
+        let shell = ShellExecutor::new(".");
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            trigger.cancel();
+        });
+
+        let started = std::time::Instant::now();
+        let err = shell
+            .execute_cancellable(&call(json!({ "program": "sleep", "args": ["5"] })), &cancel)
+            .await
+            .unwrap_err();
+        assert!(matches!(err, ToolError::Cancelled { .. }));
+        assert!(started.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -63,6 +63,18 @@ pub enum ToolError {
         /// Timeout duration
         duration: std::time::Duration,
     },
+
+    /// Execution was cancelled
+    Cancelled {
+        /// Tool name
+        tool: String,
+    },
+
+    /// Execution policy can't be applied
+    InvalidPolicy {
+        /// Error message
+        message: String,
+    },
 }
 
 /// Error kind for categorizing errors
@@ -82,6 +94,10 @@ pub enum ToolErrorKind {
     Network,
     /// Timeout
     Timeout,
+    /// Cancelled
+    Cancelled,
+    /// Invalid execution policy
+    InvalidPolicy,
 }
 
 impl ToolError {
@@ -95,6 +111,8 @@ impl ToolError {
             ToolError::ValidationFailed { .. } => ToolErrorKind::ValidationFailed,
             ToolError::Network { .. } => ToolErrorKind::Network,
             ToolError::Timeout { .. } => ToolErrorKind::Timeout,
+            ToolError::Cancelled { .. } => ToolErrorKind::Cancelled,
+            ToolError::InvalidPolicy { .. } => ToolErrorKind::InvalidPolicy,
         }
     }
 }
@@ -128,6 +146,12 @@ impl fmt::Display for ToolError {
             ToolError::Timeout { tool, duration } => {
                 write!(f, "Tool '{}' timed out after {:?}", tool, duration)
             }
+            ToolError::Cancelled { tool } => {
+                write!(f, "Tool '{}' was cancelled", tool)
+            }
+            ToolError::InvalidPolicy { message } => {
+                write!(f, "Invalid execution policy: {}", message)
+            }
         }
     }
 }
@@ -219,6 +243,19 @@ mod tests {
             duration: std::time::Duration::from_secs(30),
         };
         assert_eq!(err.to_string(), "Tool 'slow_api' timed out after 30s");
+
+        let err = ToolError::Cancelled {
+            tool: "slow_api".to_string(),
+        };
+        assert_eq!(err.to_string(), "Tool 'slow_api' was cancelled");
+
+        let err = ToolError::InvalidPolicy {
+            message: "max_concurrency must be at least 1".to_string(),
+        };
+        assert_eq!(
+            err.to_string(),
+            "Invalid execution policy: max_concurrency must be at least 1"
+        );
     }
 
     #[test]
@@ -274,6 +311,12 @@ mod tests {
                 },
                 ToolErrorKind::Timeout,
             ),
+            (
+                ToolError::Cancelled {
+                    tool: "test".to_string(),
+                },
+                ToolErrorKind::Cancelled,
+            ),
         ];
 
         for (error, expected_kind) in test_cases {
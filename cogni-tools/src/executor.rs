@@ -1,12 +1,14 @@
 //! Tool executor trait and implementations
 
 use crate::error::{Result, ToolError};
+use crate::validation::check_args;
 use async_trait::async_trait;
-use cogni_core::{Tool, ToolCall, ToolResult};
+use cogni_core::{CompiledSchema, Tool, ToolCall, ToolResult};
 use serde_json::Value;
 use std::future::Future;
 use std::pin::Pin;
-use std::sync::Arc;
+use std::sync::{Arc, OnceLock};
+use tokio_util::sync::CancellationToken;
 
 /// Type alias for async tool functions
 pub type AsyncToolFunction =
@@ -21,6 +23,20 @@ pub trait ToolExecutor: Send + Sync {
     /// Execute a tool call
     async fn execute(&self, call: &ToolCall) -> Result<ToolResult>;
 
+    /// Execute a tool call that can be cancelled
+    ///
+    /// Long-running executors should override this and stop early once
+    /// `cancel` fires. The default runs [`execute`](Self::execute); the
+    /// registry stops waiting for it when the token is cancelled.
+    async fn execute_cancellable(
+        &self,
+        call: &ToolCall,
+        cancel: &CancellationToken,
+    ) -> Result<ToolResult> {
+        let _ = cancel;
+        self.execute(call).await
+    }
+
     /// Get the tool definition
     fn tool(&self) -> &Tool;
 
@@ -31,15 +47,23 @@ pub trait ToolExecutor: Send + Sync {
 }
 
 /// A function-based tool executor
+///
+/// Arguments are validated against the tool's parameter schema before the
+/// function runs. The schema is compiled on first use and kept.
 pub struct FunctionExecutor {
     tool: Tool,
     pub(crate) func: AsyncToolFunction,
+    schema: OnceLock<std::result::Result<CompiledSchema, Vec<String>>>,
 }
 
 impl FunctionExecutor {
     /// Create a new function executor
     pub fn new(tool: Tool, func: AsyncToolFunction) -> Self {
-        Self { tool, func }
+        Self {
+            tool,
+            func,
+            schema: OnceLock::new(),
+        }
     }
 
     /// Create from a synchronous function
@@ -53,10 +77,20 @@ impl FunctionExecutor {
             Box::pin(async move { func(args) })
         });
 
-        Self {
-            tool,
-            func: async_func,
-        }
+        Self::new(tool, async_func)
+    }
+
+    /// The compiled parameter schema
+    fn schema(&self) -> Result<&CompiledSchema> {
+        let compiled = self.schema.get_or_init(|| {
+            CompiledSchema::compile(&self.tool.function.parameters).map_err(|e| vec![e.to_string()])
+        });
+        compiled
+            .as_ref()
+            .map_err(|errors| ToolError::ValidationFailed {
+                tool: self.tool.name.clone(),
+                errors: errors.clone(),
+            })
     }
 }
 
@@ -67,6 +101,14 @@ impl ToolExecutor for Box<dyn ToolExecutor> {
         self.as_ref().execute(call).await
     }
 
+    async fn execute_cancellable(
+        &self,
+        call: &ToolCall,
+        cancel: &CancellationToken,
+    ) -> Result<ToolResult> {
+        self.as_ref().execute_cancellable(call, cancel).await
+    }
+
     fn tool(&self) -> &Tool {
         self.as_ref().tool()
     }
@@ -116,6 +158,10 @@ impl ToolExecutor for FunctionExecutor {
     fn tool(&self) -> &Tool {
         &self.tool
     }
+
+    async fn validate(&self, args: &Value) -> Result<()> {
+        check_args(&self.tool.name, self.schema()?, args)
+    }
 }
 
 /// Builder for creating function executors
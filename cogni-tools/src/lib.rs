@@ -8,9 +8,11 @@
 #![warn(missing_docs)]
 #![deny(unsafe_code)]
 
+pub mod approval;
 pub mod builtin;
 pub mod error;
 pub mod executor;
+pub mod policy;
 pub mod registry;
 pub mod validation;
 
@@ -21,11 +23,17 @@ pub mod mcp;
 pub use cogni_core::{Function, Tool, ToolCall, ToolChoice, ToolResult};
 
 // Re-export main types
+pub use approval::{
+    ApprovalDecision, ApprovalHandler, ApprovalPolicy, ApprovalRecord, ApprovalRequest,
+    ChannelApprovalHandler, CliApprovalHandler, ToolClass,
+};
 pub use error::{ToolError, ToolErrorKind};
 pub use executor::{
     AsyncToolFunction, FunctionExecutor, FunctionExecutorBuilder, SyncToolFunction, ToolExecutor,
 };
+pub use policy::{ExecutionPolicy, RetryPolicy};
 pub use registry::ToolRegistry;
+pub use tokio_util::sync::CancellationToken;
 pub use validation::ToolValidator;
 
 /// Macro for creating a vector of boxed tool executors
@@ -0,0 +1,225 @@
+//! Execution policies for tools run through a registry
+
+use crate::error::ToolError;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+/// Limits applied when a [`ToolRegistry`](crate::ToolRegistry) runs a tool
+///
+/// A policy can be set for the whole registry and for individual tools. Fields
+/// left unset on a tool's policy fall back to the registry's.
+///
+/// # Example
+///
+/// ```
+/// use cogni_tools::{ExecutionPolicy, RetryPolicy};
+/// use std::time::Duration;
+///
+/// let policy = ExecutionPolicy::new()
+///     .with_timeout(Duration::from_secs(10))
+///     .with_max_concurrency(4)
+///     .with_retry(RetryPolicy::default())
+///     .idempotent(true);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExecutionPolicy {
+    /// Time allowed for each attempt
+    pub timeout: Option<Duration>,
+    /// Maximum number of executions running at once
+    ///
+    /// On a registry this caps all tools together; on a tool it caps that tool.
+    pub max_concurrency: Option<usize>,
+    /// How failed executions are retried
+    pub retry: Option<RetryPolicy>,
+    /// Whether running the tool twice is safe; only idempotent tools are retried
+    pub idempotent: Option<bool>,
+}
+
+impl ExecutionPolicy {
+    /// Create a policy with no limits
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the time allowed for each attempt
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the maximum number of concurrent executions
+    ///
+    /// Zero is rejected by [`validate`](Self::validate), since no execution
+    /// could ever start.
+    pub fn with_max_concurrency(mut self, max: usize) -> Self {
+        self.max_concurrency = Some(max);
+        self
+    }
+
+    /// Retry idempotent tools that time out or fail
+    pub fn with_retry(mut self, retry: RetryPolicy) -> Self {
+        self.retry = Some(retry);
+        self
+    }
+
+    /// Mark the tool as safe to retry
+    pub fn idempotent(mut self, idempotent: bool) -> Self {
+        self.idempotent = Some(idempotent);
+        self
+    }
+
+    /// Check that the policy can be applied
+    ///
+    /// [`ToolRegistry`](crate::ToolRegistry) checks policies when they are
+    /// set, so this only needs calling to report errors early.
+    pub fn validate(&self) -> Result<(), ToolError> {
+        if self.max_concurrency == Some(0) {
+            return Err(ToolError::InvalidPolicy {
+                message: "max_concurrency must be at least 1".to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// This policy with unset fields taken from `defaults`
+    ///
+    /// The concurrency limit isn't inherited, since the registry's limit is
+    /// shared by all tools rather than applied to each.
+    pub(crate) fn or(&self, defaults: &Self) -> Self {
+        Self {
+            timeout: self.timeout.or(defaults.timeout),
+            max_concurrency: self.max_concurrency,
+            retry: self.retry.clone().or_else(|| defaults.retry.clone()),
+            idempotent: self.idempotent.or(defaults.idempotent),
+        }
+    }
+
+    /// Semaphore enforcing `max_concurrency`, if set, after validating
+    pub(crate) fn concurrency_limit(&self) -> Result<Option<Semaphore>, ToolError> {
+        self.validate()?;
+        Ok(self.max_concurrency.map(Semaphore::new))
+    }
+
+    /// Backoff before retrying after a failed (one-based) attempt, or `None`
+    /// if the failure shouldn't be retried
+    pub(crate) fn should_retry(&self, error: &ToolError, attempt: u32) -> Option<Duration> {
+        let retry = self.retry.as_ref()?;
+        let retryable = matches!(
+            error,
+            ToolError::Timeout { .. }
+                | ToolError::Network { .. }
+                | ToolError::ExecutionFailed { .. }
+        );
+        (self.idempotent == Some(true) && retryable && attempt < retry.max_attempts)
+            .then(|| retry.backoff(attempt - 1))
+    }
+}
+
+/// Retry behavior for idempotent tools
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first
+    pub max_attempts: u32,
+    /// Initial backoff duration
+    pub initial_backoff: Duration,
+    /// Maximum backoff duration
+    pub max_backoff: Duration,
+    /// Backoff multiplier
+    pub backoff_multiplier: f64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            backoff_multiplier: 2.0,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Retry up to `max_attempts` attempts in total with the default backoff
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Backoff before the retry following the given (zero-based) attempt
+    fn backoff(&self, attempt: u32) -> Duration {
+        let base = self.initial_backoff.as_millis() as f64;
+        let backoff_ms = base * self.backoff_multiplier.powi(attempt as i32);
+        let backoff = Duration::from_millis(backoff_ms as u64);
+
+        std::cmp::min(backoff, self.max_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_fallback() {
+        let defaults = ExecutionPolicy::new()
+            .with_timeout(Duration::from_secs(30))
+            .with_max_concurrency(8)
+            .with_retry(RetryPolicy::new(2));
+        let policy = ExecutionPolicy::new()
+            .with_timeout(Duration::from_secs(1))
+            .idempotent(true)
+            .or(&defaults);
+
+        assert_eq!(policy.timeout, Some(Duration::from_secs(1)));
+        assert_eq!(policy.max_concurrency, None);
+        assert_eq!(policy.retry, Some(RetryPolicy::new(2)));
+        assert_eq!(policy.idempotent, Some(true));
+    }
+
+    #[test]
+    fn test_zero_concurrency_is_rejected() {
+        assert!(ExecutionPolicy::new()
+            .with_max_concurrency(1)
+            .validate()
+            .is_ok());
+        assert!(matches!(
+            ExecutionPolicy::new().with_max_concurrency(0).validate(),
+            Err(ToolError::InvalidPolicy { .. })
+        ));
+    }
+
+    #[test]
+    fn test_should_retry() {
+        let timeout = ToolError::Timeout {
+            tool: "t".to_string(),
+            duration: Duration::from_secs(1),
+        };
+        let not_found = ToolError::NotFound {
+            name: "t".to_string(),
+        };
+        let policy = ExecutionPolicy::new().with_retry(RetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_millis(150),
+            backoff_multiplier: 2.0,
+        });
+
+        // Tools aren't retried unless they are marked idempotent
+        assert_eq!(policy.should_retry(&timeout, 1), None);
+
+        let policy = policy.idempotent(true);
+        assert_eq!(
+            policy.should_retry(&timeout, 1),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.should_retry(&timeout, 2),
+            Some(Duration::from_millis(150))
+        );
+        assert_eq!(policy.should_retry(&timeout, 3), None);
+        assert_eq!(policy.should_retry(&not_found, 1), None);
+    }
+}
@@ -1,16 +1,35 @@
 //! Tool registry for managing available tools
 
+use crate::approval::{ApprovalDecision, ApprovalPolicy, ApprovalRecord};
 use crate::error::{Result, ToolError};
 use crate::executor::ToolExecutor;
+use crate::policy::ExecutionPolicy;
 use cogni_core::{Tool, ToolCall, ToolResult};
 use std::collections::HashMap;
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use tokio::sync::{RwLock, Semaphore};
+use tokio_util::sync::CancellationToken;
+use tracing::warn;
 
 /// Registry for managing tools
+///
+/// Calls run under an [`ExecutionPolicy`]: one for the whole registry, set
+/// with [`with_policy`](Self::with_policy), and optionally one per tool, set
+/// with [`set_policy`](Self::set_policy). Calls can also be gated on human
+/// approval with [`with_approval`](Self::with_approval).
 #[derive(Clone)]
 pub struct ToolRegistry {
     tools: Arc<RwLock<HashMap<String, Arc<dyn ToolExecutor>>>>,
+    policies: Arc<RwLock<HashMap<String, Arc<ToolPolicy>>>>,
+    policy: ExecutionPolicy,
+    limit: Option<Arc<Semaphore>>,
+    approval: Option<ApprovalPolicy>,
+}
+
+/// A tool's policy with its concurrency limit
+struct ToolPolicy {
+    policy: ExecutionPolicy,
+    limit: Option<Semaphore>,
 }
 
 impl ToolRegistry {
@@ -18,9 +37,57 @@ impl ToolRegistry {
     pub fn new() -> Self {
         Self {
             tools: Arc::new(RwLock::new(HashMap::new())),
+            policies: Arc::new(RwLock::new(HashMap::new())),
+            policy: ExecutionPolicy::default(),
+            limit: None,
+            approval: None,
         }
     }
 
+    /// Set the policy applied to every tool
+    ///
+    /// Its `max_concurrency` caps executions across all tools together.
+    /// Fails with [`ToolError::InvalidPolicy`] if the policy doesn't
+    /// [`validate`](ExecutionPolicy::validate).
+    pub fn with_policy(mut self, policy: ExecutionPolicy) -> Result<Self> {
+        self.limit = policy.concurrency_limit()?.map(Arc::new);
+        self.policy = policy;
+        Ok(self)
+    }
+
+    /// Set the policy for one tool, overriding the registry's where set
+    ///
+    /// Fails with [`ToolError::InvalidPolicy`] if the policy doesn't
+    /// [`validate`](ExecutionPolicy::validate).
+    pub async fn set_policy(&self, name: impl Into<String>, policy: ExecutionPolicy) -> Result<()> {
+        let limit = policy.concurrency_limit()?;
+        let mut policies = self.policies.write().await;
+        policies.insert(name.into(), Arc::new(ToolPolicy { policy, limit }));
+        Ok(())
+    }
+
+    /// Ask for approval before running tools, as classified by `approval`
+    ///
+    /// Denied and forbidden calls aren't run; they return a failed
+    /// [`ToolResult`] with the reason, for the model to read.
+    pub fn with_approval(mut self, approval: ApprovalPolicy) -> Self {
+        self.approval = Some(approval);
+        self
+    }
+
+    /// Approval decisions kept so far, oldest first
+    pub fn approvals(&self) -> Vec<ApprovalRecord> {
+        self.approval
+            .as_ref()
+            .map(ApprovalPolicy::records)
+            .unwrap_or_default()
+    }
+
+    /// Remove and return the latest approval decision about a call
+    pub fn take_approval(&self, call_id: &str) -> Option<ApprovalRecord> {
+        self.approval.as_ref()?.take_record(call_id)
+    }
+
     /// Create a registry from a collection of executors
     pub async fn from_executors(
         executors: impl IntoIterator<Item = impl ToolExecutor + 'static>,
@@ -63,7 +130,21 @@ impl ToolRegistry {
     }
 
     /// Execute a tool call
+    ///
+    /// A call that runs out of time returns a failed [`ToolResult`] saying so,
+    /// after any retries the policy allows.
     pub async fn execute(&self, call: &ToolCall) -> Result<ToolResult> {
+        self.execute_cancellable(call, &CancellationToken::new())
+            .await
+    }
+
+    /// Execute a tool call, stopping with [`ToolError::Cancelled`] when
+    /// `cancel` fires
+    pub async fn execute_cancellable(
+        &self,
+        call: &ToolCall,
+        cancel: &CancellationToken,
+    ) -> Result<ToolResult> {
         let tools = self.tools.read().await;
 
         let executor = tools.get(&call.name).ok_or_else(|| ToolError::NotFound {
@@ -76,14 +157,139 @@ impl ToolRegistry {
         // Drop the read lock before executing
         drop(tools);
 
-        executor.execute(call).await
+        let tool_policy = self.policies.read().await.get(&call.name).cloned();
+        let policy = match &tool_policy {
+            Some(tool_policy) => tool_policy.policy.or(&self.policy),
+            None => self.policy.clone(),
+        };
+
+        let cancelled = || ToolError::Cancelled {
+            tool: call.name.clone(),
+        };
+
+        // Wait for approval before taking permits, so a slow reviewer doesn't
+        // hold up other calls
+        let edited;
+        let call = match &self.approval {
+            Some(approval) => {
+                let decision = tokio::select! {
+                    decision = approval.review(call) => decision,
+                    _ = cancel.cancelled() => return Err(cancelled()),
+                };
+                match decision {
+                    None | Some(ApprovalDecision::Approve) => call,
+                    Some(ApprovalDecision::Deny { reason }) => {
+                        return Ok(ToolResult::error(
+                            call.id.clone(),
+                            format!("Tool call denied: {}", reason),
+                        ))
+                    }
+                    Some(ApprovalDecision::Edit { arguments }) => {
+                        edited = ToolCall {
+                            arguments: arguments.to_string(),
+                            ..call.clone()
+                        };
+                        &edited
+                    }
+                }
+            }
+            None => call,
+        };
+
+        // Hold a permit from the registry and the tool for the whole call
+        let _permit = match &self.limit {
+            Some(limit) => tokio::select! {
+                permit = limit.acquire() => Some(permit.expect("semaphore is never closed")),
+                _ = cancel.cancelled() => return Err(cancelled()),
+            },
+            None => None,
+        };
+        let _tool_permit = match tool_policy.as_ref().and_then(|p| p.limit.as_ref()) {
+            Some(limit) => tokio::select! {
+                permit = limit.acquire() => Some(permit.expect("semaphore is never closed")),
+                _ = cancel.cancelled() => return Err(cancelled()),
+            },
+            None => None,
+        };
+
+        let mut attempt = 0;
+        loop {
+            attempt += 1;
+            let result = tokio::select! {
+                result = Self::attempt(executor.as_ref(), call, &policy, cancel) => result,
+                _ = cancel.cancelled() => return Err(cancelled()),
+            };
+
+            let error = match result {
+                Ok(result) => return Ok(result),
+                Err(error) => error,
+            };
+            match policy.should_retry(&error, attempt) {
+                Some(backoff) => {
+                    warn!(
+                        tool = %call.name,
+                        attempt = attempt,
+                        backoff_ms = backoff.as_millis(),
+                        error = %error,
+                        "Tool call failed, retrying"
+                    );
+                    tokio::select! {
+                        _ = tokio::time::sleep(backoff) => {}
+                        _ = cancel.cancelled() => return Err(cancelled()),
+                    }
+                }
+                // Timeouts are reported to the model rather than the caller
+                None if matches!(error, ToolError::Timeout { .. }) => {
+                    return Ok(ToolResult::error(call.id.clone(), error.to_string()))
+                }
+                None => return Err(error),
+            }
+        }
+    }
+
+    /// Run one attempt, applying the policy's timeout
+    async fn attempt(
+        executor: &dyn ToolExecutor,
+        call: &ToolCall,
+        policy: &ExecutionPolicy,
+        cancel: &CancellationToken,
+    ) -> Result<ToolResult> {
+        let execution = executor.execute_cancellable(call, cancel);
+        match policy.timeout {
+            Some(duration) => tokio::time::timeout(duration, execution)
+                .await
+                .unwrap_or_else(|_| {
+                    Err(ToolError::Timeout {
+                        tool: call.name.clone(),
+                        duration,
+                    })
+                }),
+            None => execution.await,
+        }
     }
 
     /// Execute multiple tool calls in parallel
+    ///
+    /// Results are in the same order as `calls`.
     pub async fn execute_many(&self, calls: &[ToolCall]) -> Vec<Result<ToolResult>> {
+        self.execute_many_cancellable(calls, &CancellationToken::new())
+            .await
+    }
+
+    /// Execute multiple tool calls in parallel until `cancel` fires
+    ///
+    /// Results are in the same order as `calls`; calls still running when the
+    /// token is cancelled end with [`ToolError::Cancelled`].
+    pub async fn execute_many_cancellable(
+        &self,
+        calls: &[ToolCall],
+        cancel: &CancellationToken,
+    ) -> Vec<Result<ToolResult>> {
         use futures::future;
 
-        let futures = calls.iter().map(|call| self.execute(call));
+        let futures = calls
+            .iter()
+            .map(|call| self.execute_cancellable(call, cancel));
         future::join_all(futures).await
     }
 
@@ -207,4 +413,265 @@ mod tests {
         assert!(result.success);
         assert!(result.content.contains("tool2"));
     }
+
+    /// A tool that sleeps for `ms` milliseconds, tracking how many run at once
+    fn sleeper(
+        name: &str,
+        running: Arc<std::sync::atomic::AtomicUsize>,
+        peak: Arc<std::sync::atomic::AtomicUsize>,
+    ) -> crate::FunctionExecutor {
+        use std::sync::atomic::Ordering;
+
+        FunctionExecutorBuilder::new(name).build_async(move |args| {
+            let running = running.clone();
+            let peak = peak.clone();
+            async move {
+                let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+                peak.fetch_max(now, Ordering::SeqCst);
+                let ms = args["ms"].as_u64().unwrap_or(0);
+                tokio::time::sleep(std::time::Duration::from_millis(ms)).await;
+                running.fetch_sub(1, Ordering::SeqCst);
+                Ok(json!({ "slept": ms }))
+            }
+        })
+    }
+
+    fn sleep_call(id: &str, name: &str, ms: u64) -> ToolCall {
+        ToolCall {
+            id: id.to_string(),
+            name: name.to_string(),
+            arguments: json!({ "ms": ms }).to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_order() {
+        use std::time::Duration;
+
+        let counter = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let registry = ToolRegistry::new()
+            .with_policy(ExecutionPolicy::new().with_timeout(Duration::from_millis(50)))
+            .unwrap();
+        registry
+            .register([sleeper("sleep", counter.clone(), counter.clone())])
+            .await
+            .unwrap();
+
+        let results = registry
+            .execute_many(&[
+                sleep_call("1", "sleep", 30),
+                sleep_call("2", "sleep", 500),
+                sleep_call("3", "sleep", 0),
+            ])
+            .await;
+        let results: Vec<ToolResult> = results.into_iter().map(|r| r.unwrap()).collect();
+
+        assert_eq!(results[0], ToolResult::success("1", r#"{"slept":30}"#));
+        assert_eq!(
+            results[1],
+            ToolResult::error("2", "Tool 'sleep' timed out after 50ms")
+        );
+        assert_eq!(results[2], ToolResult::success("3", r#"{"slept":0}"#));
+    }
+
+    #[tokio::test]
+    async fn test_concurrency_limits() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let running = Arc::new(AtomicUsize::new(0));
+        let peak = Arc::new(AtomicUsize::new(0));
+        let registry = ToolRegistry::new()
+            .with_policy(ExecutionPolicy::new().with_max_concurrency(3))
+            .unwrap();
+        registry
+            .register([
+                sleeper("a", running.clone(), peak.clone()),
+                sleeper("b", running.clone(), peak.clone()),
+            ])
+            .await
+            .unwrap();
+        registry
+            .set_policy("b", ExecutionPolicy::new().with_max_concurrency(1))
+            .await
+            .unwrap();
+
+        // The registry-wide limit caps all tools
+        let calls: Vec<_> = (0..6)
+            .map(|i| sleep_call(&i.to_string(), "a", 20))
+            .collect();
+        let results = registry.execute_many(&calls).await;
+        assert!(results.iter().all(|r| r.as_ref().unwrap().success));
+        assert_eq!(peak.load(Ordering::SeqCst), 3);
+
+        // A tool's own limit applies on top
+        peak.store(0, Ordering::SeqCst);
+        let calls: Vec<_> = (0..3)
+            .map(|i| sleep_call(&i.to_string(), "b", 20))
+            .collect();
+        registry.execute_many(&calls).await;
+        assert_eq!(peak.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_zero_concurrency_policy_is_rejected() {
+        let policy = ExecutionPolicy {
+            max_concurrency: Some(0),
+            ..ExecutionPolicy::default()
+        };
+        assert!(matches!(
+            ToolRegistry::new().with_policy(policy.clone()),
+            Err(ToolError::InvalidPolicy { .. })
+        ));
+        assert!(matches!(
+            ToolRegistry::new().set_policy("a", policy).await,
+            Err(ToolError::InvalidPolicy { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_retries_idempotent_tools() {
+        use crate::RetryPolicy;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::time::Duration;
+
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let counted = attempts.clone();
+        // Hangs on the first attempt, then answers immediately
+        let flaky = FunctionExecutorBuilder::new("flaky").build_async(move |_| {
+            let attempt = counted.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt == 0 {
+                    tokio::time::sleep(Duration::from_secs(5)).await;
+                }
+                Ok(json!({ "attempt": attempt + 1 }))
+            }
+        });
+
+        let retry = RetryPolicy {
+            initial_backoff: Duration::from_millis(1),
+            ..RetryPolicy::new(3)
+        };
+        let registry = ToolRegistry::new()
+            .with_policy(
+                ExecutionPolicy::new()
+                    .with_timeout(Duration::from_millis(20))
+                    .with_retry(retry),
+            )
+            .unwrap();
+        registry.register([flaky]).await.unwrap();
+        let call = ToolCall {
+            id: "1".to_string(),
+            name: "flaky".to_string(),
+            arguments: "{}".to_string(),
+        };
+
+        // Not retried until marked idempotent
+        let result = registry.execute(&call).await.unwrap();
+        assert!(!result.success);
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+
+        attempts.store(0, Ordering::SeqCst);
+        registry
+            .set_policy("flaky", ExecutionPolicy::new().idempotent(true))
+            .await
+            .unwrap();
+        let result = registry.execute(&call).await.unwrap();
+        assert_eq!(result, ToolResult::success("1", r#"{"attempt":2}"#));
+    }
+
+    #[tokio::test]
+    async fn test_cancellation() {
+        use std::time::Duration;
+
+        let counter = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let registry = ToolRegistry::new();
+        registry
+            .register([sleeper("sleep", counter.clone(), counter.clone())])
+            .await
+            .unwrap();
+
+        let cancel = CancellationToken::new();
+        let trigger = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(30)).await;
+            trigger.cancel();
+        });
+
+        let results = registry
+            .execute_many_cancellable(
+                &[sleep_call("1", "sleep", 0), sleep_call("2", "sleep", 5_000)],
+                &cancel,
+            )
+            .await;
+        assert!(results[0].as_ref().unwrap().success);
+        assert!(matches!(
+            results[1],
+            Err(ToolError::Cancelled { ref tool }) if tool == "sleep"
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_approval() {
+        use crate::approval::{ApprovalPolicy, ChannelApprovalHandler, ToolClass};
+
+        let (handler, mut requests) = ChannelApprovalHandler::new(1);
+        tokio::spawn(async move {
+            while let Some(request) = requests.recv().await {
+                let decision = match request.call.arguments.as_str() {
+                    r#"{"text":"rm -rf /"}"# => ApprovalDecision::deny("too dangerous"),
+                    r#"{"text":"hi"}"# => ApprovalDecision::Edit {
+                        arguments: json!({ "text": "hello" }),
+                    },
+                    _ => ApprovalDecision::Approve,
+                };
+                request.respond(decision);
+            }
+        });
+
+        let echo = |name: &str| {
+            FunctionExecutorBuilder::new(name).build_sync(|args| Ok(args["text"].clone()))
+        };
+        let registry = ToolRegistry::new()
+            .with_approval(ApprovalPolicy::new(handler).safe("read").forbid("drop"));
+        registry
+            .register([echo("read"), echo("drop"), echo("shell")])
+            .await
+            .unwrap();
+
+        let call = |name: &str, text: &str| ToolCall {
+            id: "1".to_string(),
+            name: name.to_string(),
+            arguments: json!({ "text": text }).to_string(),
+        };
+
+        let results = registry
+            .execute_many(&[
+                call("read", "x"),
+                call("drop", "x"),
+                call("shell", "rm -rf /"),
+                call("shell", "hi"),
+                call("shell", "ls"),
+            ])
+            .await;
+        let results: Vec<ToolResult> = results.into_iter().map(|r| r.unwrap()).collect();
+        assert_eq!(
+            results,
+            vec![
+                ToolResult::success("1", r#""x""#),
+                ToolResult::error("1", "Tool call denied: tool 'drop' is not allowed"),
+                ToolResult::error("1", "Tool call denied: too dangerous"),
+                ToolResult::success("1", r#""hello""#),
+                ToolResult::success("1", r#""ls""#),
+            ]
+        );
+
+        // Everything but the safe tool is recorded, with the original arguments
+        let records = registry.approvals();
+        assert_eq!(records.len(), 4);
+        assert_eq!(records[0].class, ToolClass::Forbidden);
+        assert!(records
+            .iter()
+            .any(|r| r.call.arguments == r#"{"text":"hi"}"#
+                && matches!(r.decision, ApprovalDecision::Edit { .. })));
+    }
 }
@@ -1,6 +1,7 @@
 //! Tool validation utilities
 
 use crate::error::{Result, ToolError};
+use cogni_core::CompiledSchema;
 use serde_json::{Map, Value};
 
 /// Trait for validating tool arguments
@@ -9,7 +10,13 @@ pub trait ToolValidator {
     fn validate(&self, args: &Value, schema: &Value) -> Result<()>;
 }
 
-/// Default JSON Schema validator
+/// JSON Schema validator backed by [`CompiledSchema`]
+///
+/// Each call compiles the schema. Executors that validate repeatedly should
+/// keep a [`CompiledSchema`] and use [`check_args`], as [`FunctionExecutor`]
+/// does.
+///
+/// [`FunctionExecutor`]: crate::FunctionExecutor
 pub struct JsonSchemaValidator;
 
 impl JsonSchemaValidator {
@@ -17,143 +24,6 @@ impl JsonSchemaValidator {
     pub fn new() -> Self {
         Self
     }
-
-    /// Validate a value against a JSON schema (simplified implementation)
-    #[allow(clippy::only_used_in_recursion)]
-    fn validate_value(&self, value: &Value, schema: &Value, path: &str) -> Result<Vec<String>> {
-        let mut errors = Vec::new();
-
-        if let Some(schema_obj) = schema.as_object() {
-            // Check type
-            if let Some(expected_type) = schema_obj.get("type").and_then(|t| t.as_str()) {
-                match (expected_type, value) {
-                    ("string", Value::String(_)) => {}
-                    ("number", Value::Number(_)) => {}
-                    ("integer", Value::Number(n)) if n.is_i64() || n.is_u64() => {}
-                    ("boolean", Value::Bool(_)) => {}
-                    ("array", Value::Array(_)) => {}
-                    ("object", Value::Object(_)) => {}
-                    ("null", Value::Null) => {}
-                    _ => {
-                        errors.push(format!(
-                            "{}: Expected type '{}', got '{}'",
-                            path,
-                            expected_type,
-                            value_type_name(value)
-                        ));
-                    }
-                }
-            }
-
-            // Check required properties for objects
-            if value.is_object() {
-                let obj = value.as_object().unwrap();
-
-                // Check required fields
-                if let Some(required) = schema_obj.get("required").and_then(|r| r.as_array()) {
-                    for req in required {
-                        if let Some(field_name) = req.as_str() {
-                            if !obj.contains_key(field_name) {
-                                errors.push(format!(
-                                    "{}: Missing required field '{}'",
-                                    path, field_name
-                                ));
-                            }
-                        }
-                    }
-                }
-
-                // Validate properties
-                if let Some(properties) = schema_obj.get("properties").and_then(|p| p.as_object()) {
-                    for (key, value) in obj {
-                        if let Some(prop_schema) = properties.get(key) {
-                            let prop_path = if path.is_empty() {
-                                key.clone()
-                            } else {
-                                format!("{}.{}", path, key)
-                            };
-                            errors.extend(self.validate_value(value, prop_schema, &prop_path)?);
-                        } else if schema_obj.get("additionalProperties")
-                            == Some(&Value::Bool(false))
-                        {
-                            errors.push(format!("{}: Unexpected property '{}'", path, key));
-                        }
-                    }
-                }
-            }
-
-            // Check array items
-            if let (Some(items_schema), Some(array)) = (schema_obj.get("items"), value.as_array()) {
-                for (i, item) in array.iter().enumerate() {
-                    let item_path = format!("{}[{}]", path, i);
-                    errors.extend(self.validate_value(item, items_schema, &item_path)?);
-                }
-            }
-
-            // Check minimum/maximum for numbers
-            if let Some(n) = value.as_f64() {
-                if let Some(min) = schema_obj.get("minimum").and_then(|v| v.as_f64()) {
-                    if n < min {
-                        errors.push(format!(
-                            "{}: Value {} is less than minimum {}",
-                            path, n, min
-                        ));
-                    }
-                }
-                if let Some(max) = schema_obj.get("maximum").and_then(|v| v.as_f64()) {
-                    if n > max {
-                        errors.push(format!(
-                            "{}: Value {} is greater than maximum {}",
-                            path, n, max
-                        ));
-                    }
-                }
-            }
-
-            // Check string constraints
-            if let Some(s) = value.as_str() {
-                if let Some(min_length) = schema_obj.get("minLength").and_then(|v| v.as_u64()) {
-                    if s.len() < min_length as usize {
-                        errors.push(format!(
-                            "{}: String length {} is less than minLength {}",
-                            path,
-                            s.len(),
-                            min_length
-                        ));
-                    }
-                }
-                if let Some(max_length) = schema_obj.get("maxLength").and_then(|v| v.as_u64()) {
-                    if s.len() > max_length as usize {
-                        errors.push(format!(
-                            "{}: String length {} is greater than maxLength {}",
-                            path,
-                            s.len(),
-                            max_length
-                        ));
-                    }
-                }
-                if let Some(pattern) = schema_obj.get("pattern").and_then(|v| v.as_str()) {
-                    if let Ok(re) = regex::Regex::new(pattern) {
-                        if !re.is_match(s) {
-                            errors.push(format!(
-                                "{}: String '{}' does not match pattern '{}'",
-                                path, s, pattern
-                            ));
-                        }
-                    }
-                }
-            }
-
-            // Check enum values
-            if let Some(enum_values) = schema_obj.get("enum").and_then(|e| e.as_array()) {
-                if !enum_values.contains(value) {
-                    errors.push(format!("{}: Value must be one of {:?}", path, enum_values));
-                }
-            }
-        }
-
-        Ok(errors)
-    }
 }
 
 impl Default for JsonSchemaValidator {
@@ -164,29 +34,30 @@ impl Default for JsonSchemaValidator {
 
 impl ToolValidator for JsonSchemaValidator {
     fn validate(&self, args: &Value, schema: &Value) -> Result<()> {
-        let errors = self.validate_value(args, schema, "")?;
-
-        if !errors.is_empty() {
-            return Err(ToolError::ValidationFailed {
-                tool: "unknown".to_string(),
-                errors,
-            });
-        }
-
-        Ok(())
+        let schema = compile_schema("unknown", schema)?;
+        check_args("unknown", &schema, args)
     }
 }
 
-/// Get the type name of a JSON value
-fn value_type_name(value: &Value) -> &'static str {
-    match value {
-        Value::Null => "null",
-        Value::Bool(_) => "boolean",
-        Value::Number(_) => "number",
-        Value::String(_) => "string",
-        Value::Array(_) => "array",
-        Value::Object(_) => "object",
-    }
+/// Compile a tool's parameter schema
+pub fn compile_schema(tool: &str, schema: &Value) -> Result<CompiledSchema> {
+    CompiledSchema::compile(schema).map_err(|e| ToolError::ValidationFailed {
+        tool: tool.to_string(),
+        errors: vec![e.to_string()],
+    })
+}
+
+/// Check arguments against a compiled schema
+///
+/// Each error is reported as `<JSON pointer>: <message>`, e.g.
+/// `/items/2/price: -1 is less than the minimum of 0`.
+pub fn check_args(tool: &str, schema: &CompiledSchema, args: &Value) -> Result<()> {
+    schema
+        .validate(args)
+        .map_err(|errors| ToolError::ValidationFailed {
+            tool: tool.to_string(),
+            errors: errors.iter().map(ToString::to_string).collect(),
+        })
 }
 
 /// Validate tool arguments using a schema
@@ -347,6 +218,90 @@ mod tests {
         assert!(validator.validate(&invalid, &schema).is_err());
     }
 
+    #[test]
+    fn test_error_paths() {
+        let schema = compile_schema(
+            "order",
+            &json!({
+                "type": "object",
+                "properties": {
+                    "items": {
+                        "type": "array",
+                        "items": { "$ref": "#/$defs/Item" }
+                    }
+                },
+                "$defs": {
+                    "Item": {
+                        "type": "object",
+                        "properties": { "price": { "type": "number", "minimum": 0 } },
+                        "required": ["price"]
+                    }
+                }
+            }),
+        )
+        .unwrap();
+
+        let err = check_args(
+            "order",
+            &schema,
+            &json!({ "items": [{ "price": 1 }, {}, { "price": -1 }] }),
+        )
+        .unwrap_err();
+        match err {
+            ToolError::ValidationFailed { tool, errors } => {
+                assert_eq!(tool, "order");
+                assert_eq!(
+                    errors,
+                    vec![
+                        "/items/1: missing required property 'price'",
+                        "/items/2/price: -1 is less than the minimum of 0",
+                    ]
+                );
+            }
+            other => panic!("unexpected error: {}", other),
+        }
+
+        assert!(compile_schema("broken", &json!({ "pattern": "(" })).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_executor_validates_arguments() {
+        use crate::{FunctionExecutorBuilder, ToolExecutor};
+        use cogni_core::ToolCall;
+
+        let executor = FunctionExecutorBuilder::new("echo")
+            .parameters(
+                param_schema()
+                    .string_required("text", "Text to echo")
+                    .additional_properties(false)
+                    .build(),
+            )
+            .build_sync(Ok);
+
+        let call = |arguments: &str| ToolCall {
+            id: "1".to_string(),
+            name: "echo".to_string(),
+            arguments: arguments.to_string(),
+        };
+        assert!(
+            executor
+                .execute(&call(r#"{"text":"hi"}"#))
+                .await
+                .unwrap()
+                .success
+        );
+
+        let err = executor
+            .execute(&call(r#"{"text":1,"loud":true}"#))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Validation failed for tool 'echo': (root): unexpected property 'loud', \
+             /text: expected string, got integer"
+        );
+    }
+
     #[test]
     fn test_schema_builder() {
         let schema = param_schema()
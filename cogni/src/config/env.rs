@@ -0,0 +1,123 @@
+//! Environment variable interpolation for configuration values
+
+use cogni_core::Error;
+use serde_json::Value;
+
+/// Replace `${VAR}` and `${VAR:-default}` references in every string of a
+/// configuration tree
+///
+/// `$$` produces a literal `$`. Errors name the key whose value referenced
+/// the missing variable.
+pub(crate) fn interpolate(
+    value: &mut Value,
+    path: &mut Vec<String>,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> Result<(), Error> {
+    match value {
+        Value::String(s) if s.contains('$') => {
+            *s = expand(s, lookup).map_err(|msg| super::key_error(&path.join("."), msg))?;
+        }
+        Value::Array(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                let last = path.pop().unwrap_or_default();
+                path.push(format!("{}[{}]", last, i));
+                let result = interpolate(item, path, lookup);
+                path.pop();
+                path.push(last);
+                result?;
+            }
+        }
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                path.push(key.clone());
+                let result = interpolate(item, path, lookup);
+                path.pop();
+                result?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn expand(input: &str, lookup: &dyn Fn(&str) -> Option<String>) -> Result<String, String> {
+    let mut output = String::with_capacity(input.len());
+    let mut rest = input;
+
+    while let Some(pos) = rest.find('$') {
+        output.push_str(&rest[..pos]);
+        rest = &rest[pos + 1..];
+
+        if let Some(stripped) = rest.strip_prefix('$') {
+            output.push('$');
+            rest = stripped;
+        } else if let Some(stripped) = rest.strip_prefix('{') {
+            let end = stripped
+                .find('}')
+                .ok_or_else(|| format!("unterminated variable reference in \"{}\"", input))?;
+            let reference = &stripped[..end];
+            let (name, default) = match reference.split_once(":-") {
+                Some((name, default)) => (name, Some(default)),
+                None => (reference, None),
+            };
+            if name.is_empty() {
+                return Err(format!("empty variable reference in \"{}\"", input));
+            }
+            match (lookup(name), default) {
+                (Some(value), _) => output.push_str(&value),
+                (None, Some(default)) => output.push_str(default),
+                (None, None) => {
+                    return Err(format!("environment variable {} is not set", name));
+                }
+            }
+            rest = &stripped[end + 1..];
+        } else {
+            output.push('$');
+        }
+    }
+
+    output.push_str(rest);
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn env(name: &str) -> Option<String> {
+        match name {
+            "API_KEY" => Some("secret".to_string()),
+            "HOST" => Some("localhost".to_string()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_expand() {
+        assert_eq!(expand("${API_KEY}", &env).unwrap(), "secret");
+        assert_eq!(
+            expand("http://${HOST}:${PORT:-8000}/v1", &env).unwrap(),
+            "http://localhost:8000/v1"
+        );
+        assert_eq!(expand("cost: $$5, $x", &env).unwrap(), "cost: $5, $x");
+        assert!(expand("${MISSING}", &env)
+            .unwrap_err()
+            .contains("MISSING is not set"));
+        assert!(expand("${API_KEY", &env).is_err());
+    }
+
+    #[test]
+    fn test_error_names_key() {
+        let mut value = json!({ "providers": { "openai": { "api_key": "${API_KEY}" } } });
+        interpolate(&mut value, &mut Vec::new(), &env).unwrap();
+        assert_eq!(value["providers"]["openai"]["api_key"], "secret");
+
+        let mut value = json!({ "middleware": [{ "type": "logging" }, { "header": "${NOPE}" }] });
+        let err = interpolate(&mut value, &mut Vec::new(), &env).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Configuration error: middleware[1].header: environment variable NOPE is not set"
+        );
+    }
+}
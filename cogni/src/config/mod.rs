@@ -0,0 +1,1272 @@
+//! Declarative stack configuration
+//!
+//! A [`Config`] describes a complete stack — named providers, default models
+//! and parameters, a middleware chain, and state and context settings — in a
+//! TOML or YAML file, and builds a ready [`Client`] from it.
+//!
+//! String values may reference environment variables as `${VAR}` or
+//! `${VAR:-default}`, so secrets stay out of the file. Every error names the
+//! key it came from, e.g. `providers.openai.api_key`.
+//!
+//! # Example
+//!
+//! ```toml
+//! default_provider = "openai"
+//!
+//! [providers.openai]
+//! type = "openai"
+//! api_key = "${OPENAI_API_KEY}"
+//! model = "gpt-4o"
+//! timeout_secs = 60
+//!
+//! [providers.local]
+//! type = "ollama"
+//! model = "llama3.2"
+//!
+//! [parameters]
+//! temperature = 0.7
+//!
+//! [[middleware]]
+//! type = "retry"
+//! max_attempts = 3
+//!
+//! [[middleware]]
+//! type = "logging"
+//! level = "info"
+//!
+//! [state]
+//! backend = "file"
+//! path = "./conversations"
+//!
+//! [context]
+//! max_tokens = 8000
+//! keep_recent = 20
+//! ```
+//!
+//! ```no_run
+//! use cogni::config::Config;
+//!
+//! # async fn example() -> Result<(), cogni::Error> {
+//! let config = Config::from_file("cogni.toml")?;
+//! let client = config.client()?;
+//! let answer = client.chat("Hello!").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod env;
+mod provider;
+
+pub use provider::ConfiguredProvider;
+
+use cogni_client::{Client, StatefulClient};
+use cogni_context::{ContextManager, CounterSelector, SlidingWindowStrategy};
+use cogni_core::{Error, Parameters};
+use cogni_middleware::{
+    CacheKeyOptions, CacheLayer, DiskCache, LogLevel, LoggingLayer, RateLimitLayer, RetryConfig,
+    RetryLayer,
+};
+use cogni_providers::builder::{
+    AnthropicBuilder, AzureOpenAIBuilder, OllamaBuilder, OpenAIBuilder, OpenAICompatibleBuilder,
+    ProviderBuilder,
+};
+use cogni_providers::compatible::OpenAICompatibleConfig;
+use cogni_providers::constants::{LLAMA_CPP_DEFAULT_BASE_URL, VLLM_DEFAULT_BASE_URL};
+use cogni_providers::http::{HttpClient, ReqwestClientBuilder};
+use cogni_state::{FileStore, MemoryStore, StateStore};
+use provider::{Backend, LayeredService};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A complete stack configuration
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Provider used by [`client`](Self::client); optional with a single provider
+    pub default_provider: Option<String>,
+    /// Named providers
+    #[serde(default)]
+    pub providers: BTreeMap<String, ProviderConfig>,
+    /// Default request parameters
+    #[serde(default)]
+    pub parameters: ParametersConfig,
+    /// Middleware chain; each entry wraps the ones before it
+    #[serde(default)]
+    pub middleware: Vec<MiddlewareConfig>,
+    /// Conversation state storage
+    pub state: Option<StateConfig>,
+    /// Context window management
+    pub context: Option<ContextConfig>,
+}
+
+/// Configuration for one named provider
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ProviderConfig {
+    /// Provider type
+    #[serde(rename = "type")]
+    pub kind: ProviderKind,
+    /// API key
+    pub api_key: Option<String>,
+    /// Base URL, or the resource endpoint for Azure
+    pub base_url: Option<String>,
+    /// Organization ID (OpenAI and OpenAI-compatible)
+    pub organization: Option<String>,
+    /// Default model for requests
+    pub model: Option<String>,
+    /// Azure REST API version
+    pub api_version: Option<String>,
+    /// Azure model-to-deployment mapping
+    #[serde(default)]
+    pub deployments: BTreeMap<String, String>,
+    /// Headers added to every request
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    /// Total request timeout in seconds
+    pub timeout_secs: Option<u64>,
+    /// Connection timeout in seconds
+    pub connect_timeout_secs: Option<u64>,
+    /// Proxy URL for all traffic
+    pub proxy: Option<String>,
+}
+
+/// Provider types that can be configured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ProviderKind {
+    /// OpenAI
+    #[serde(rename = "openai")]
+    OpenAI,
+    /// Anthropic
+    #[serde(rename = "anthropic")]
+    Anthropic,
+    /// Ollama
+    #[serde(rename = "ollama")]
+    Ollama,
+    /// Azure OpenAI
+    #[serde(rename = "azure")]
+    Azure,
+    /// Any OpenAI-compatible server at `base_url`
+    #[serde(rename = "openai_compatible")]
+    OpenAICompatible,
+    /// vLLM preset
+    #[serde(rename = "vllm")]
+    Vllm,
+    /// llama.cpp preset
+    #[serde(rename = "llama_cpp")]
+    LlamaCpp,
+    /// Groq preset
+    #[serde(rename = "groq")]
+    Groq,
+    /// Together AI preset
+    #[serde(rename = "together")]
+    Together,
+    /// Mistral preset
+    #[serde(rename = "mistral")]
+    Mistral,
+    /// OpenRouter preset
+    #[serde(rename = "openrouter")]
+    OpenRouter,
+}
+
+impl ProviderKind {
+    const ALL: [ProviderKind; 11] = [
+        ProviderKind::OpenAI,
+        ProviderKind::Anthropic,
+        ProviderKind::Ollama,
+        ProviderKind::Azure,
+        ProviderKind::OpenAICompatible,
+        ProviderKind::Vllm,
+        ProviderKind::LlamaCpp,
+        ProviderKind::Groq,
+        ProviderKind::Together,
+        ProviderKind::Mistral,
+        ProviderKind::OpenRouter,
+    ];
+
+    /// The name used for this type in configuration files
+    pub fn name(self) -> &'static str {
+        match self {
+            ProviderKind::OpenAI => "openai",
+            ProviderKind::Anthropic => "anthropic",
+            ProviderKind::Ollama => "ollama",
+            ProviderKind::Azure => "azure",
+            ProviderKind::OpenAICompatible => "openai_compatible",
+            ProviderKind::Vllm => "vllm",
+            ProviderKind::LlamaCpp => "llama_cpp",
+            ProviderKind::Groq => "groq",
+            ProviderKind::Together => "together",
+            ProviderKind::Mistral => "mistral",
+            ProviderKind::OpenRouter => "openrouter",
+        }
+    }
+
+    /// Whether providers of this type need an API key
+    pub fn requires_api_key(self) -> bool {
+        !matches!(
+            self,
+            ProviderKind::Ollama
+                | ProviderKind::OpenAICompatible
+                | ProviderKind::Vllm
+                | ProviderKind::LlamaCpp
+        )
+    }
+
+    /// Whether providers of this type need a base URL
+    pub fn requires_base_url(self) -> bool {
+        matches!(self, ProviderKind::Azure | ProviderKind::OpenAICompatible)
+    }
+}
+
+impl std::str::FromStr for ProviderKind {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == s)
+            .ok_or_else(|| {
+                let names: Vec<_> = Self::ALL.iter().map(|kind| kind.name()).collect();
+                Error::Configuration(format!(
+                    "Unknown provider type `{}`, expected one of: {}",
+                    s,
+                    names.join(", ")
+                ))
+            })
+    }
+}
+
+impl ProviderConfig {
+    /// Create a provider configuration of the given type with no other settings
+    pub fn new(kind: ProviderKind) -> Self {
+        Self {
+            kind,
+            api_key: None,
+            base_url: None,
+            organization: None,
+            model: None,
+            api_version: None,
+            deployments: BTreeMap::new(),
+            headers: BTreeMap::new(),
+            timeout_secs: None,
+            connect_timeout_secs: None,
+            proxy: None,
+        }
+    }
+}
+
+/// Default request parameters
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ParametersConfig {
+    /// Maximum tokens to generate
+    pub max_tokens: Option<u32>,
+    /// Sampling temperature
+    pub temperature: Option<f32>,
+    /// Nucleus sampling
+    pub top_p: Option<f32>,
+    /// Stop sequences
+    pub stop: Option<Vec<String>>,
+    /// Presence penalty
+    pub presence_penalty: Option<f32>,
+    /// Frequency penalty
+    pub frequency_penalty: Option<f32>,
+    /// Random seed
+    pub seed: Option<u64>,
+}
+
+impl From<&ParametersConfig> for Parameters {
+    fn from(config: &ParametersConfig) -> Self {
+        Parameters {
+            max_tokens: config.max_tokens,
+            temperature: config.temperature,
+            top_p: config.top_p,
+            stop: config.stop.clone(),
+            presence_penalty: config.presence_penalty,
+            frequency_penalty: config.frequency_penalty,
+            seed: config.seed,
+            ..Default::default()
+        }
+    }
+}
+
+/// One layer of the middleware chain
+///
+/// Entries name their layer with a `type` key, e.g. `type = "retry"`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(remote = "Self", rename_all = "snake_case", deny_unknown_fields)]
+pub enum MiddlewareConfig {
+    /// Retry transient failures with exponential backoff
+    Retry {
+        /// Maximum number of attempts
+        #[serde(default = "default_max_attempts")]
+        max_attempts: u32,
+        /// Initial backoff in milliseconds
+        #[serde(default = "default_initial_backoff_ms")]
+        initial_backoff_ms: u64,
+        /// Maximum backoff in milliseconds
+        #[serde(default = "default_max_backoff_ms")]
+        max_backoff_ms: u64,
+        /// Backoff multiplier
+        #[serde(default = "default_backoff_multiplier")]
+        backoff_multiplier: f64,
+    },
+    /// Limit the request rate
+    RateLimit {
+        /// Requests per second
+        requests_per_second: f64,
+    },
+    /// Cache responses in memory, or on disk when `path` is set
+    Cache {
+        /// Maximum number of cached responses in memory
+        #[serde(default = "default_cache_size")]
+        max_size: usize,
+        /// Time to live in seconds
+        #[serde(default = "default_cache_ttl_secs")]
+        ttl_secs: u64,
+        /// Directory for a persistent on-disk cache
+        #[serde(default)]
+        path: Option<PathBuf>,
+        /// Maximum total size of the on-disk cache in bytes
+        #[serde(default)]
+        max_bytes: Option<u64>,
+        /// Whether streaming requests are cached too
+        #[serde(default)]
+        stream: bool,
+        /// Request fields left out of cache keys, e.g. `parameters.temperature`
+        #[serde(default)]
+        ignore: Vec<String>,
+        /// Namespace keeping these cache keys apart from others
+        #[serde(default)]
+        namespace: Option<String>,
+        /// Don't cache requests without a seed unless their temperature is zero
+        #[serde(default)]
+        deterministic_only: bool,
+    },
+    /// Log requests and responses through `tracing`
+    Logging {
+        /// Log level
+        #[serde(default)]
+        level: LogLevelConfig,
+        /// Whether to log message content
+        #[serde(default)]
+        content: bool,
+    },
+}
+
+impl<'de> Deserialize<'de> for MiddlewareConfig {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        parse_middleware(Value::deserialize(deserializer)?).map_err(|(key, message)| {
+            serde::de::Error::custom(match key.is_empty() {
+                true => message,
+                false => format!("{}: {}", key, message),
+            })
+        })
+    }
+}
+
+/// Deserialize a middleware entry in two steps, reading its `type` and then
+/// the fields of that variant, so errors name the offending key
+///
+/// On failure, returns the key relative to the entry and the message.
+fn parse_middleware(mut entry: Value) -> Result<MiddlewareConfig, (String, String)> {
+    let kind = match entry.as_object_mut().map(|fields| fields.remove("type")) {
+        Some(Some(Value::String(kind))) => kind,
+        Some(Some(_)) => return Err(("type".to_string(), "expected a string".to_string())),
+        Some(None) => return Err((String::new(), "missing field `type`".to_string())),
+        None => return Err((String::new(), "expected a table".to_string())),
+    };
+
+    let tagged = Value::Object([(kind, entry)].into_iter().collect());
+    let mut track = serde_path_to_error::Track::new();
+    MiddlewareConfig::deserialize(serde_path_to_error::Deserializer::new(tagged, &mut track))
+        .map_err(|e| {
+            let path = track.path();
+            let mut segments = path.iter();
+            // The first segment is the variant, so without one the `type`
+            // itself was rejected
+            let key = match segments.next() {
+                None => "type".to_string(),
+                Some(_) => segments.fold(String::new(), |key, segment| match segment {
+                    serde_path_to_error::Segment::Seq { .. } => format!("{}{}", key, segment),
+                    _ if key.is_empty() => segment.to_string(),
+                    _ => format!("{}.{}", key, segment),
+                }),
+            };
+            (key, e.to_string())
+        })
+}
+
+fn default_max_attempts() -> u32 {
+    RetryConfig::default().max_attempts
+}
+
+fn default_initial_backoff_ms() -> u64 {
+    RetryConfig::default().initial_backoff.as_millis() as u64
+}
+
+fn default_max_backoff_ms() -> u64 {
+    RetryConfig::default().max_backoff.as_millis() as u64
+}
+
+fn default_backoff_multiplier() -> f64 {
+    RetryConfig::default().backoff_multiplier
+}
+
+fn default_cache_size() -> usize {
+    1000
+}
+
+fn default_cache_ttl_secs() -> u64 {
+    300
+}
+
+/// Log level for the logging middleware
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevelConfig {
+    /// Trace level
+    Trace,
+    /// Debug level
+    #[default]
+    Debug,
+    /// Info level
+    Info,
+}
+
+impl From<LogLevelConfig> for LogLevel {
+    fn from(level: LogLevelConfig) -> Self {
+        match level {
+            LogLevelConfig::Trace => LogLevel::Trace,
+            LogLevelConfig::Debug => LogLevel::Debug,
+            LogLevelConfig::Info => LogLevel::Info,
+        }
+    }
+}
+
+/// Conversation state storage
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "backend", rename_all = "snake_case", deny_unknown_fields)]
+pub enum StateConfig {
+    /// In-memory storage
+    Memory,
+    /// One JSON file per conversation in a directory
+    File {
+        /// Directory for conversation files
+        path: PathBuf,
+    },
+}
+
+/// Context window management
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ContextConfig {
+    /// Model used for token counting; defaults to the provider's model
+    pub model: Option<String>,
+    /// Maximum context size in tokens; defaults to the model's limit
+    pub max_tokens: Option<usize>,
+    /// Tokens reserved for the response
+    pub reserve_output_tokens: Option<usize>,
+    /// Keep only this many recent messages when pruning
+    pub keep_recent: Option<usize>,
+    /// Always keep system messages when pruning
+    #[serde(default = "default_keep_system")]
+    pub keep_system: bool,
+}
+
+fn default_keep_system() -> bool {
+    true
+}
+
+/// Build a configuration error that names the offending key
+pub(crate) fn key_error(key: &str, message: impl std::fmt::Display) -> Error {
+    if key.is_empty() {
+        Error::Configuration(message.to_string())
+    } else {
+        Error::Configuration(format!("{}: {}", key, message))
+    }
+}
+
+impl Config {
+    /// Load a configuration file, choosing the format from its extension
+    ///
+    /// `.toml` files are parsed as TOML, `.yaml` and `.yml` files as YAML.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let text = std::fs::read_to_string(path).map_err(|e| {
+            Error::Configuration(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("toml") => Self::from_toml(&text),
+            Some("yaml") | Some("yml") => Self::from_yaml(&text),
+            _ => Err(Error::Configuration(format!(
+                "Unsupported config format for {}: expected .toml, .yaml or .yml",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Parse a TOML configuration
+    pub fn from_toml(text: &str) -> Result<Self, Error> {
+        let value: Value = toml::from_str(text)
+            .map_err(|e| Error::Configuration(format!("Invalid TOML: {}", e)))?;
+        Self::from_value(value, &|name| std::env::var(name).ok())
+    }
+
+    /// Parse a YAML configuration
+    pub fn from_yaml(text: &str) -> Result<Self, Error> {
+        let value: Value = serde_yaml::from_str(text)
+            .map_err(|e| Error::Configuration(format!("Invalid YAML: {}", e)))?;
+        Self::from_value(value, &|name| std::env::var(name).ok())
+    }
+
+    fn from_value(
+        mut value: Value,
+        lookup: &dyn Fn(&str) -> Option<String>,
+    ) -> Result<Self, Error> {
+        env::interpolate(&mut value, &mut Vec::new(), lookup)?;
+
+        // Middleware entries are read separately so errors can name the
+        // field inside the entry, which serde can't for internally tagged
+        // enums
+        let middleware = value
+            .as_object_mut()
+            .and_then(|config| config.remove("middleware"));
+        let mut config: Config = serde_path_to_error::deserialize(value).map_err(|e| {
+            let path = e.path().to_string();
+            let key = if path == "." { String::new() } else { path };
+            key_error(&key, e.into_inner())
+        })?;
+        if let Some(middleware) = middleware {
+            let entries: Vec<Value> =
+                serde_json::from_value(middleware).map_err(|e| key_error("middleware", e))?;
+            config.middleware = entries
+                .into_iter()
+                .enumerate()
+                .map(|(i, entry)| {
+                    parse_middleware(entry).map_err(|(key, message)| {
+                        let entry = format!("middleware[{}]", i);
+                        match key.is_empty() {
+                            true => key_error(&entry, message),
+                            false => key_error(&format!("{}.{}", entry, key), message),
+                        }
+                    })
+                })
+                .collect::<Result<_, _>>()?;
+        }
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Check the configuration for missing or inconsistent settings
+    ///
+    /// This runs automatically when a configuration is parsed; call it after
+    /// building or modifying a `Config` in code.
+    pub fn validate(&self) -> Result<(), Error> {
+        if let Some(name) = &self.default_provider {
+            if !self.providers.contains_key(name) {
+                return Err(key_error(
+                    "default_provider",
+                    format!("no provider named `{}`", name),
+                ));
+            }
+        }
+
+        for (name, provider) in &self.providers {
+            let key = |field: &str| format!("providers.{}.{}", name, field);
+            let kind = provider.kind.name();
+
+            if provider.kind.requires_api_key() && provider.api_key.is_none() {
+                return Err(key_error(
+                    &key("api_key"),
+                    format!("required for `{}` providers", kind),
+                ));
+            }
+            if provider.kind.requires_base_url() && provider.base_url.is_none() {
+                return Err(key_error(
+                    &key("base_url"),
+                    format!("required for `{}` providers", kind),
+                ));
+            }
+            if let Some(proxy) = &provider.proxy {
+                reqwest::Proxy::all(proxy).map_err(|e| key_error(&key("proxy"), e))?;
+            }
+            for (header, value) in &provider.headers {
+                let header_key = key(&format!("headers.{}", header));
+                reqwest::header::HeaderName::from_bytes(header.as_bytes())
+                    .map_err(|e| key_error(&header_key, e))?;
+                reqwest::header::HeaderValue::from_str(value)
+                    .map_err(|e| key_error(&header_key, e))?;
+            }
+            if provider.kind != ProviderKind::Azure {
+                if provider.api_version.is_some() {
+                    return Err(key_error(
+                        &key("api_version"),
+                        "only supported by `azure` providers",
+                    ));
+                }
+                if !provider.deployments.is_empty() {
+                    return Err(key_error(
+                        &key("deployments"),
+                        "only supported by `azure` providers",
+                    ));
+                }
+            }
+        }
+
+        for (i, middleware) in self.middleware.iter().enumerate() {
+            let key = |field: &str| format!("middleware[{}].{}", i, field);
+            match middleware {
+                MiddlewareConfig::Retry { max_attempts, .. } if *max_attempts == 0 => {
+                    return Err(key_error(&key("max_attempts"), "must be at least 1"));
+                }
+                MiddlewareConfig::RateLimit {
+                    requests_per_second,
+                } if *requests_per_second <= 0.0 || requests_per_second.is_nan() => {
+                    return Err(key_error(
+                        &key("requests_per_second"),
+                        "must be greater than 0",
+                    ));
+                }
+                MiddlewareConfig::Cache { max_size, .. } if *max_size == 0 => {
+                    return Err(key_error(&key("max_size"), "must be at least 1"));
+                }
+                MiddlewareConfig::Cache {
+                    path: None,
+                    max_bytes: Some(_),
+                    ..
+                } => {
+                    return Err(key_error(&key("max_bytes"), "requires `path`"));
+                }
+                _ => {}
+            }
+        }
+
+        if let Some(context) = &self.context {
+            if let (Some(max), Some(reserve)) = (context.max_tokens, context.reserve_output_tokens)
+            {
+                if reserve >= max {
+                    return Err(key_error(
+                        "context.reserve_output_tokens",
+                        "must be less than context.max_tokens",
+                    ));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Name of the provider used by [`client`](Self::client)
+    ///
+    /// This is `default_provider` if set, or the only configured provider.
+    pub fn default_provider_name(&self) -> Result<&str, Error> {
+        if let Some(name) = &self.default_provider {
+            return Ok(name);
+        }
+        let mut names = self.providers.keys();
+        match (names.next(), names.next()) {
+            (Some(name), None) => Ok(name),
+            (None, _) => Err(key_error("providers", "no providers configured")),
+            (Some(_), Some(_)) => Err(key_error(
+                "default_provider",
+                "required when more than one provider is configured",
+            )),
+        }
+    }
+
+    /// Build a client for the default provider
+    pub fn client(&self) -> Result<Client<ConfiguredProvider>, Error> {
+        self.client_for(self.default_provider_name()?)
+    }
+
+    /// Build a client for a named provider
+    pub fn client_for(&self, name: &str) -> Result<Client<ConfiguredProvider>, Error> {
+        self.build_client(name, None)
+    }
+
+    /// Build a stateful client for the default provider using the configured store
+    pub fn stateful_client(&self) -> Result<StatefulClient<ConfiguredProvider>, Error> {
+        let store = self
+            .state_store()?
+            .ok_or_else(|| key_error("state", "not configured"))?;
+        Ok(self.client()?.with_state(store))
+    }
+
+    /// Build the configured state store, if any
+    pub fn state_store(&self) -> Result<Option<Arc<dyn StateStore>>, Error> {
+        let store: Arc<dyn StateStore> = match &self.state {
+            None => return Ok(None),
+            Some(StateConfig::Memory) => Arc::new(MemoryStore::new()),
+            Some(StateConfig::File { path }) => {
+                Arc::new(FileStore::new(path).map_err(|e| key_error("state.path", e))?)
+            }
+        };
+        Ok(Some(store))
+    }
+
+    /// Build the configured context manager, if any
+    ///
+    /// Tokens are counted for `context.model`, falling back to the default
+    /// provider's model, with the most accurate counter available for it (see
+    /// [`CounterSelector`]). An Ollama default provider is asked at its
+    /// configured URL.
+    pub async fn context_manager(&self) -> Result<Option<Arc<ContextManager>>, Error> {
+        let Some(context) = &self.context else {
+            return Ok(None);
+        };
+
+        let (model, key) = match &context.model {
+            Some(model) => (model.clone(), "context.model".to_string()),
+            None => {
+                let name = self.default_provider_name()?;
+                let model = self.providers[name].model.clone().ok_or_else(|| {
+                    key_error(
+                        "context.model",
+                        format!("required when providers.{}.model is not set", name),
+                    )
+                })?;
+                (model, format!("providers.{}.model", name))
+            }
+        };
+
+        let selector = CounterSelector::new();
+        #[cfg(feature = "ollama-counter")]
+        let selector = match self
+            .default_provider_name()
+            .ok()
+            .and_then(|name| self.providers.get(name))
+        {
+            Some(provider) if provider.kind == ProviderKind::Ollama => {
+                selector.with_ollama_url(Some(
+                    provider
+                        .base_url
+                        .clone()
+                        .unwrap_or_else(|| cogni_context::OLLAMA_DEFAULT_URL.to_string()),
+                ))
+            }
+            _ => selector,
+        };
+        let counter = selector
+            .select(&model)
+            .await
+            .map_err(|e| key_error(&key, e))?;
+        let mut manager = ContextManager::new(counter);
+        if let Some(max_tokens) = context.max_tokens {
+            manager = manager.with_max_tokens(max_tokens);
+        }
+        if let Some(reserve) = context.reserve_output_tokens {
+            manager = manager.with_reserve_output_tokens(reserve);
+        }
+        if let Some(keep_recent) = context.keep_recent {
+            manager = manager.with_strategy(Arc::new(SlidingWindowStrategy::new(
+                context.keep_system,
+                keep_recent,
+            )));
+        }
+        Ok(Some(Arc::new(manager)))
+    }
+
+    pub(crate) fn build_client(
+        &self,
+        name: &str,
+        http: Option<Arc<dyn HttpClient>>,
+    ) -> Result<Client<ConfiguredProvider>, Error> {
+        let provider_config = self
+            .providers
+            .get(name)
+            .ok_or_else(|| key_error("providers", format!("no provider named `{}`", name)))?;
+
+        let backend = build_backend(name, provider_config, http)?;
+        let (service, stream_cache) = self.build_middleware(backend.clone())?;
+        let provider = ConfiguredProvider::new(name.to_string(), backend, service)
+            .with_stream_cache(stream_cache);
+
+        let mut client = Client::new(provider).with_parameters(Parameters::from(&self.parameters));
+        if let Some(model) = &provider_config.model {
+            client = client.with_model(model.as_str());
+        }
+        Ok(client)
+    }
+
+    /// Build the middleware chain, and the cache streaming requests go
+    /// through if one is configured with `stream = true`
+    fn build_middleware(
+        &self,
+        backend: Backend,
+    ) -> Result<(Option<LayeredService>, Option<CacheLayer>), Error> {
+        if self.middleware.is_empty() {
+            return Ok((None, None));
+        }
+
+        let mut service = LayeredService::new(backend);
+        let mut stream_cache = None;
+        for (i, middleware) in self.middleware.iter().enumerate() {
+            service = match middleware {
+                MiddlewareConfig::Retry {
+                    max_attempts,
+                    initial_backoff_ms,
+                    max_backoff_ms,
+                    backoff_multiplier,
+                } => service.layer(&RetryLayer::with_config(RetryConfig {
+                    max_attempts: *max_attempts,
+                    initial_backoff: Duration::from_millis(*initial_backoff_ms),
+                    max_backoff: Duration::from_millis(*max_backoff_ms),
+                    backoff_multiplier: *backoff_multiplier,
+                })),
+                MiddlewareConfig::RateLimit {
+                    requests_per_second,
+                } => service.layer(&RateLimitLayer::new(*requests_per_second)),
+                MiddlewareConfig::Cache {
+                    max_size,
+                    ttl_secs,
+                    path,
+                    max_bytes,
+                    stream,
+                    ignore,
+                    namespace,
+                    deterministic_only,
+                } => {
+                    let ttl = Duration::from_secs(*ttl_secs);
+                    let mut layer = match path {
+                        Some(path) => {
+                            let mut cache = DiskCache::new(path)
+                                .map_err(|e| key_error(&format!("middleware[{}].path", i), e))?
+                                .ttl(ttl);
+                            if let Some(max_bytes) = max_bytes {
+                                cache = cache.max_bytes(*max_bytes);
+                            }
+                            CacheLayer::with_backend(cache)
+                        }
+                        None => CacheLayer::new(*max_size, ttl),
+                    };
+                    let mut keys = ignore
+                        .iter()
+                        .fold(CacheKeyOptions::new(), |keys, field| keys.ignore(field));
+                    if let Some(namespace) = namespace {
+                        keys = keys.namespace(namespace);
+                    }
+                    layer = layer.with_key_options(keys);
+                    if *deterministic_only {
+                        layer = layer.deterministic_only();
+                    }
+                    if *stream {
+                        layer = layer.with_streaming();
+                        stream_cache = Some(layer.clone());
+                    }
+                    service.layer(&layer)
+                }
+                MiddlewareConfig::Logging { level, content } => {
+                    let mut layer = LoggingLayer::with_level((*level).into());
+                    if *content {
+                        layer = layer.with_content();
+                    }
+                    service.layer(&layer)
+                }
+            };
+        }
+        Ok((Some(service), stream_cache))
+    }
+}
+
+fn build_transport(config: &ProviderConfig) -> Option<ReqwestClientBuilder> {
+    if config.timeout_secs.is_none()
+        && config.connect_timeout_secs.is_none()
+        && config.proxy.is_none()
+        && config.headers.is_empty()
+    {
+        return None;
+    }
+
+    let mut transport = ReqwestClientBuilder::new();
+    if let Some(secs) = config.timeout_secs {
+        transport = transport.timeout(Duration::from_secs(secs));
+    }
+    if let Some(secs) = config.connect_timeout_secs {
+        transport = transport.connect_timeout(Duration::from_secs(secs));
+    }
+    if let Some(proxy) = &config.proxy {
+        transport = transport.proxy(proxy);
+    }
+    for (name, value) in &config.headers {
+        transport = transport.header(name, value);
+    }
+    Some(transport)
+}
+
+fn finish<B: ProviderBuilder>(
+    builder: B,
+    http: Option<Arc<dyn HttpClient>>,
+) -> Result<B::Provider, Error> {
+    match http {
+        Some(http) => builder.with_client(http),
+        None => builder,
+    }
+    .build()
+}
+
+fn build_backend(
+    name: &str,
+    config: &ProviderConfig,
+    http: Option<Arc<dyn HttpClient>>,
+) -> Result<Backend, Error> {
+    // Build the transport here so its errors name the provider
+    let http = match (http, build_transport(config)) {
+        (Some(http), _) => Some(http),
+        (None, Some(transport)) => {
+            let client = transport
+                .build()
+                .map_err(|e| key_error(&format!("providers.{}", name), e))?;
+            Some(Arc::new(client) as Arc<dyn HttpClient>)
+        }
+        (None, None) => None,
+    };
+    let api_key = config.api_key.clone().unwrap_or_default();
+
+    let backend = match config.kind {
+        ProviderKind::OpenAI => {
+            let mut builder = OpenAIBuilder::new(api_key);
+            if let Some(url) = &config.base_url {
+                builder = builder.base_url(url);
+            }
+            if let Some(org) = &config.organization {
+                builder = builder.organization(org);
+            }
+            Backend::OpenAI(finish(builder, http)?)
+        }
+        ProviderKind::Anthropic => {
+            let mut builder = AnthropicBuilder::new(api_key);
+            if let Some(url) = &config.base_url {
+                builder = builder.base_url(url);
+            }
+            if let Some(model) = &config.model {
+                builder = builder.default_model(model);
+            }
+            Backend::Anthropic(finish(builder, http)?)
+        }
+        ProviderKind::Ollama => {
+            let mut builder = OllamaBuilder::new();
+            if let Some(url) = &config.base_url {
+                builder = builder.base_url(url);
+            }
+            if let Some(model) = &config.model {
+                builder = builder.default_model(model);
+            }
+            Backend::Ollama(finish(builder, http)?)
+        }
+        ProviderKind::Azure => {
+            let endpoint = config.base_url.clone().unwrap_or_default();
+            let mut builder = AzureOpenAIBuilder::new(endpoint).api_key(api_key);
+            if let Some(version) = &config.api_version {
+                builder = builder.api_version(version);
+            }
+            for (model, deployment) in &config.deployments {
+                builder = builder.deployment(model, deployment);
+            }
+            Backend::Azure(finish(builder, http)?)
+        }
+        kind => {
+            let base_url = config.base_url.clone();
+            let mut compatible = match kind {
+                ProviderKind::Vllm => OpenAICompatibleConfig::vllm(
+                    base_url.unwrap_or_else(|| VLLM_DEFAULT_BASE_URL.to_string()),
+                ),
+                ProviderKind::LlamaCpp => OpenAICompatibleConfig::llama_cpp(
+                    base_url.unwrap_or_else(|| LLAMA_CPP_DEFAULT_BASE_URL.to_string()),
+                ),
+                ProviderKind::Groq => OpenAICompatibleConfig::groq(&api_key),
+                ProviderKind::Together => OpenAICompatibleConfig::together(&api_key),
+                ProviderKind::Mistral => OpenAICompatibleConfig::mistral(&api_key),
+                ProviderKind::OpenRouter => OpenAICompatibleConfig::openrouter(&api_key),
+                _ => OpenAICompatibleConfig::new(name, base_url.clone().unwrap_or_default()),
+            };
+            if let (Some(url), false) = (&config.base_url, kind == ProviderKind::OpenAICompatible) {
+                compatible.base_url = url.trim_end_matches('/').to_string();
+            }
+            if let Some(key) = &config.api_key {
+                compatible = compatible.with_api_key(key);
+            }
+            if let Some(org) = &config.organization {
+                compatible = compatible.with_organization(org);
+            }
+            Backend::Compatible(finish(
+                OpenAICompatibleBuilder::from_config(compatible),
+                http,
+            )?)
+        }
+    };
+
+    Ok(backend)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cogni_core::Provider;
+    use cogni_providers::testing::MockHttpClient;
+    use serde_json::json;
+
+    fn env(name: &str) -> Option<String> {
+        (name == "TEST_KEY").then(|| "sk-test".to_string())
+    }
+
+    fn toml_config(text: &str) -> Result<Config, Error> {
+        Config::from_value(toml::from_str(text).unwrap(), &env)
+    }
+
+    fn message(result: Result<Config, Error>) -> String {
+        match result {
+            Err(Error::Configuration(msg)) => msg,
+            other => panic!("expected configuration error, got {:?}", other.map(|_| ())),
+        }
+    }
+
+    fn connection_reset() -> Error {
+        Error::Network {
+            message: "connection reset".into(),
+            source: None,
+        }
+    }
+
+    const STACK: &str = r#"
+        default_provider = "main"
+
+        [providers.main]
+        type = "openai"
+        api_key = "${TEST_KEY}"
+        model = "gpt-4o"
+        timeout_secs = 30
+
+        [providers.local]
+        type = "vllm"
+        base_url = "http://gpu-box:8000/v1"
+
+        [parameters]
+        temperature = 0.2
+
+        [[middleware]]
+        type = "retry"
+        max_attempts = 3
+        initial_backoff_ms = 1
+
+        [[middleware]]
+        type = "logging"
+        level = "info"
+
+        [state]
+        backend = "memory"
+
+        [context]
+        max_tokens = 4000
+        reserve_output_tokens = 500
+        keep_recent = 10
+    "#;
+
+    #[tokio::test]
+    async fn test_parse_toml_stack() {
+        let config = toml_config(STACK).unwrap();
+        assert_eq!(config.default_provider_name().unwrap(), "main");
+        assert_eq!(config.providers["main"].api_key.as_deref(), Some("sk-test"));
+        assert_eq!(config.providers["local"].kind, ProviderKind::Vllm);
+        assert_eq!(config.middleware.len(), 2);
+        assert!(config.state_store().unwrap().is_some());
+
+        let manager = config.context_manager().await.unwrap().unwrap();
+        assert_eq!(manager.available_tokens(), 3500);
+        assert_eq!(
+            config.client_for("local").unwrap().provider().name(),
+            "local"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_context_manager_for_claude() {
+        let config = toml_config(
+            r#"
+            [providers.claude]
+            type = "anthropic"
+            api_key = "sk-test"
+            model = "claude-3-5-sonnet-20241022"
+
+            [context]
+            reserve_output_tokens = 1000
+            "#,
+        )
+        .unwrap();
+        let manager = config.context_manager().await.unwrap().unwrap();
+        assert_eq!(manager.available_tokens(), 199000);
+    }
+
+    #[test]
+    fn test_parse_yaml_matches_toml() {
+        let yaml = r#"
+providers:
+  local:
+    type: ollama
+    model: llama3.2
+middleware:
+  - type: cache
+    ttl_secs: 60
+state:
+  backend: file
+  path: /tmp/cogni-config-test
+"#;
+        let config = Config::from_yaml(yaml).unwrap();
+        assert_eq!(config.default_provider_name().unwrap(), "local");
+        assert!(matches!(
+            config.middleware[0],
+            MiddlewareConfig::Cache {
+                max_size: 1000,
+                ttl_secs: 60,
+                path: None,
+                stream: false,
+                ..
+            }
+        ));
+        assert!(matches!(config.state, Some(StateConfig::File { .. })));
+    }
+
+    #[test]
+    fn test_errors_name_offending_key() {
+        assert_eq!(
+            message(toml_config(
+                "[providers.main]\ntype = \"openai\"\napi_key = \"${MISSING}\""
+            )),
+            "providers.main.api_key: environment variable MISSING is not set"
+        );
+        assert_eq!(
+            message(toml_config("[providers.main]\ntype = \"anthropic\"")),
+            "providers.main.api_key: required for `anthropic` providers"
+        );
+        assert!(message(toml_config(
+            "[providers.main]\ntype = \"ollama\"\ntimeout_secs = \"soon\""
+        ))
+        .starts_with("providers.main.timeout_secs: invalid type"));
+        assert!(message(toml_config("[providers.main]\ntype = \"bard\""))
+            .starts_with("providers.main.type: unknown variant `bard`"));
+        assert_eq!(
+            message(toml_config(
+                "default_provider = \"x\"\n[providers.main]\ntype = \"ollama\""
+            )),
+            "default_provider: no provider named `x`"
+        );
+        assert_eq!(
+            message(toml_config(
+                "[[middleware]]\ntype = \"logging\"\n[[middleware]]\ntype = \"rate_limit\"\nrequests_per_second = 0"
+            )),
+            "middleware[1].requests_per_second: must be greater than 0"
+        );
+        assert_eq!(
+            message(toml_config("[state]\nbackend = \"s3\"")),
+            "state.backend: unknown variant `s3`, expected `memory` or `file`"
+        );
+        assert_eq!(
+            message(toml_config(
+                "[[middleware]]\ntype = \"cache\"\nmax_bytes = 1024"
+            )),
+            "middleware[0].max_bytes: requires `path`"
+        );
+        assert!(message(toml_config(
+            "[[middleware]]\ntype = \"retry\"\nmax_attempts = \"x\""
+        ))
+        .starts_with("middleware[0].max_attempts: invalid type"));
+        assert!(message(toml_config(
+            "[[middleware]]\ntype = \"cache\"\nignore = [1]"
+        ))
+        .starts_with("middleware[0].ignore[0]: invalid type"));
+        assert!(message(toml_config("[[middleware]]\ntype = \"gzip\""))
+            .starts_with("middleware[0].type: unknown variant `gzip`"));
+        assert_eq!(
+            message(toml_config("[[middleware]]\nmax_attempts = 1")),
+            "middleware[0]: missing field `type`"
+        );
+        assert!(message(toml_config(
+            "[providers.main]\ntype = \"ollama\"\nproxy = \"::not a url\""
+        ))
+        .starts_with("providers.main.proxy: "));
+        assert!(message(toml_config(
+            "[providers.main]\ntype = \"ollama\"\n[providers.main.headers]\n\"bad header\" = \"x\""
+        ))
+        .starts_with("providers.main.headers.bad header: "));
+        assert!(message(toml_config("providerz = 1")).contains("unknown field `providerz`"));
+    }
+
+    #[test]
+    fn test_provider_kind_from_str() {
+        assert_eq!(
+            "llama_cpp".parse::<ProviderKind>().unwrap(),
+            ProviderKind::LlamaCpp
+        );
+        assert!("bard".parse::<ProviderKind>().is_err());
+    }
+
+    #[test]
+    fn test_default_provider_ambiguous() {
+        let config =
+            toml_config("[providers.a]\ntype = \"ollama\"\n[providers.b]\ntype = \"ollama\"")
+                .unwrap();
+        assert!(matches!(
+            config.client(),
+            Err(Error::Configuration(msg)) if msg.starts_with("default_provider: required")
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_client_applies_model_parameters_and_middleware() {
+        let config = toml_config(STACK).unwrap();
+        let http = Arc::new(
+            MockHttpClient::new()
+                .with_error(connection_reset())
+                .with_error(connection_reset()),
+        );
+        let client = config.build_client("main", Some(http.clone())).unwrap();
+        assert!(client.provider().has_middleware());
+
+        let answer = client.chat("hi").await.unwrap();
+        assert_eq!(answer, "ok");
+        let sent = http.requests();
+        assert_eq!(sent.len(), 3);
+        assert_eq!(sent[0].body["model"], "gpt-4o");
+        assert_eq!(sent[0].body["temperature"], json!(0.2f32));
+    }
+
+    #[tokio::test]
+    async fn test_client_without_middleware() {
+        let config =
+            toml_config("[providers.main]\ntype = \"groq\"\napi_key = \"k\"\nmodel = \"llama\"")
+                .unwrap();
+        let http = Arc::new(MockHttpClient::new());
+        let client = config.build_client("main", Some(http.clone())).unwrap();
+        assert!(!client.provider().has_middleware());
+
+        let response = client
+            .provider()
+            .request(client.request().user("hi").build())
+            .await;
+        assert_eq!(response.unwrap().content, "ok");
+        assert_eq!(http.requests()[0].body["model"], "llama");
+    }
+
+    #[tokio::test]
+    async fn test_disk_cache_survives_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = format!(
+            "[providers.main]\ntype = \"groq\"\napi_key = \"k\"\nmodel = \"llama\"\n\
+             [[middleware]]\ntype = \"cache\"\npath = {:?}\nstream = true",
+            dir.path()
+        );
+        let http = Arc::new(MockHttpClient::new());
+        for _ in 0..2 {
+            let client = toml_config(&text)
+                .unwrap()
+                .build_client("main", Some(http.clone()))
+                .unwrap();
+            assert_eq!(client.chat("hi").await.unwrap(), "ok");
+        }
+        assert_eq!(http.requests().len(), 1);
+    }
+}
@@ -0,0 +1,159 @@
+//! Provider type produced from a configuration file
+
+use async_trait::async_trait;
+use cogni_core::{Error, Provider, Request, Response, StreamEvent};
+use cogni_middleware::{BoxFuture, BoxStream, CacheLayer, Layer, ProviderService, Service};
+use cogni_providers::{Anthropic, AzureOpenAI, Ollama, OpenAI, OpenAICompatible};
+use futures::StreamExt;
+
+/// A configured backend, independent of its concrete provider type
+#[derive(Clone)]
+pub(crate) enum Backend {
+    OpenAI(OpenAI),
+    Anthropic(Anthropic),
+    Ollama(Ollama),
+    Azure(AzureOpenAI),
+    Compatible(OpenAICompatible),
+}
+
+macro_rules! dispatch {
+    ($backend:expr, $p:ident => $body:expr) => {
+        match $backend {
+            Backend::OpenAI($p) => $body,
+            Backend::Anthropic($p) => $body,
+            Backend::Ollama($p) => $body,
+            Backend::Azure($p) => $body,
+            Backend::Compatible($p) => $body,
+        }
+    };
+}
+
+#[async_trait]
+impl Provider for Backend {
+    type Stream = BoxStream<Result<StreamEvent, Error>>;
+
+    async fn request(&self, request: Request) -> Result<Response, Error> {
+        dispatch!(self, p => p.request(request).await)
+    }
+
+    async fn stream(&self, request: Request) -> Result<Self::Stream, Error> {
+        dispatch!(self, p => Ok(p.stream(request).await?.boxed()))
+    }
+}
+
+/// Object-safe, cloneable view of a middleware service
+trait DynService: Send + Sync {
+    fn call(&mut self, request: Request) -> BoxFuture<Result<Response, Error>>;
+    fn clone_box(&self) -> Box<dyn DynService>;
+}
+
+impl<S> DynService for S
+where
+    S: Service<Request, Response = Response, Error = Error> + Clone + Send + Sync + 'static,
+    S::Future: Send + 'static,
+{
+    fn call(&mut self, request: Request) -> BoxFuture<Result<Response, Error>> {
+        Box::pin(Service::call(self, request))
+    }
+
+    fn clone_box(&self) -> Box<dyn DynService> {
+        Box::new(self.clone())
+    }
+}
+
+/// A type-erased middleware chain
+///
+/// Unlike [`BoxService`](cogni_middleware::BoxService) this is `Clone`, so
+/// layers that require a cloneable inner service can be stacked on top of it.
+pub(crate) struct LayeredService(Box<dyn DynService>);
+
+impl LayeredService {
+    pub(crate) fn new(backend: Backend) -> Self {
+        Self(Box::new(ProviderService::new(backend)))
+    }
+
+    pub(crate) fn layer<L>(self, layer: &L) -> Self
+    where
+        L: Layer<Self>,
+        L::Service:
+            Service<Request, Response = Response, Error = Error> + Clone + Send + Sync + 'static,
+        <L::Service as Service<Request>>::Future: Send + 'static,
+    {
+        Self(Box::new(layer.layer(self)))
+    }
+}
+
+impl Clone for LayeredService {
+    fn clone(&self) -> Self {
+        Self(self.0.clone_box())
+    }
+}
+
+impl Service<Request> for LayeredService {
+    type Response = Response;
+    type Error = Error;
+    type Future = BoxFuture<Result<Response, Error>>;
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        DynService::call(self.0.as_mut(), request)
+    }
+}
+
+/// Provider built from a [`Config`](super::Config)
+///
+/// Non-streaming requests pass through the configured middleware chain.
+/// Streaming requests go straight to the backend, since the middleware
+/// services only handle complete responses, except that a cache configured
+/// with `stream = true` answers and records them too.
+#[derive(Clone)]
+pub struct ConfiguredProvider {
+    name: String,
+    backend: Backend,
+    service: Option<LayeredService>,
+    stream_cache: Option<CacheLayer>,
+}
+
+impl ConfiguredProvider {
+    pub(crate) fn new(name: String, backend: Backend, service: Option<LayeredService>) -> Self {
+        Self {
+            name,
+            backend,
+            service,
+            stream_cache: None,
+        }
+    }
+
+    pub(crate) fn with_stream_cache(mut self, cache: Option<CacheLayer>) -> Self {
+        self.stream_cache = cache;
+        self
+    }
+
+    /// Name of the provider entry in the configuration
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether requests pass through a middleware chain
+    pub fn has_middleware(&self) -> bool {
+        self.service.is_some()
+    }
+}
+
+#[async_trait]
+impl Provider for ConfiguredProvider {
+    type Stream = BoxStream<Result<StreamEvent, Error>>;
+
+    async fn request(&self, request: Request) -> Result<Response, Error> {
+        match &self.service {
+            Some(service) => Service::call(&mut service.clone(), request).await,
+            None => self.backend.request(request).await,
+        }
+    }
+
+    async fn stream(&self, request: Request) -> Result<Self::Stream, Error> {
+        match &self.stream_cache {
+            Some(cache) => cache.stream(&self.backend, request).await,
+            None => self.backend.stream(request).await,
+        }
+    }
+}
@@ -49,6 +49,10 @@ pub use cogni_core::*;
 #[cfg(feature = "derive")]
 pub use cogni_core::DeriveStructuredOutput as StructuredOutput;
 
+// Re-export the tool attribute macro
+#[cfg(all(feature = "derive", feature = "tools"))]
+pub use cogni_derive::tool;
+
 // Re-export feature-gated modules
 #[cfg(feature = "providers")]
 #[cfg_attr(docsrs, doc(cfg(feature = "providers")))]
@@ -92,6 +96,19 @@ pub mod context {
     pub use cogni_context::*;
 }
 
+#[cfg(feature = "config")]
+#[cfg_attr(docsrs, doc(cfg(feature = "config")))]
+pub mod config;
+
+// Dependencies referenced by code generated from the derive macros
+#[cfg(feature = "derive")]
+#[doc(hidden)]
+pub mod __private {
+    pub use async_trait;
+    pub use serde;
+    pub use serde_json;
+}
+
 /// Prelude module for convenient imports
 pub mod prelude {
 
@@ -236,10 +236,8 @@ async fn main() -> Result<(), Error> {
         println!(
             "- {} | {} | {} messages | {}",
             conv.id,
-            conv.metadata
-                .title
-                .unwrap_or_else(|| "Untitled".to_string()),
-            conv.messages.len(),
+            conv.metadata.title.as_deref().unwrap_or("Untitled"),
+            conv.messages().len(),
             conv.updated_at.format("%Y-%m-%d %H:%M")
         );
     }
@@ -248,7 +246,7 @@ async fn main() -> Result<(), Error> {
 }
 
 // Helper function to request structured output with context management
-async fn chat_structured_with_context<P: Provider, T: StructuredOutput>(
+async fn chat_structured_with_context<P: Provider, T: StructuredOutput + 'static>(
     agent: &mut StatefulClient<P>,
     provider: &P,
     message: &str,
@@ -264,7 +262,7 @@ async fn chat_structured_with_context<P: Provider, T: StructuredOutput>(
     // Get all messages and apply context management
     let all_messages = agent
         .current_state()
-        .map(|s| s.messages.clone())
+        .map(|s| s.messages().to_vec())
         .unwrap_or_default();
     let pruned_messages = context_manager.fit_messages(all_messages).await?;
 
@@ -166,7 +166,7 @@ async fn test_stateful_conversation_persistence() -> Result<(), Box<dyn std::err
 
     // Verify conversation was loaded correctly
     let state = agent2.current_state().unwrap();
-    assert_eq!(state.messages.len(), 5); // system + 2 user + 2 assistant
+    assert_eq!(state.messages().len(), 5); // system + 2 user + 2 assistant
     assert_eq!(state.metadata.title, Some("Test Conversation".to_string()));
     assert!(state.metadata.tags.contains(&"test".to_string()));
 
@@ -231,7 +231,7 @@ async fn test_context_manager_with_conversation() -> Result<(), Box<dyn std::err
     // Apply context management
     let all_messages = agent
         .current_state()
-        .map(|s| s.messages.clone())
+        .map(|s| s.messages().to_vec())
         .unwrap_or_default();
     assert_eq!(all_messages.len(), 21); // 1 system + 10 user + 10 assistant
 
@@ -280,7 +280,7 @@ async fn test_structured_output_with_state() -> Result<(), Box<dyn std::error::E
         .messages(
             agent
                 .current_state()
-                .map(|s| s.messages.clone())
+                .map(|s| s.messages().to_vec())
                 .unwrap_or_default(),
         )
         .response_format(ResponseFormat::JsonSchema {
@@ -360,7 +360,7 @@ async fn test_all_features_combined() -> Result<(), Box<dyn std::error::Error>>
     // Request structured analysis with context management
     let messages = agent2
         .current_state()
-        .map(|s| s.messages.clone())
+        .map(|s| s.messages().to_vec())
         .unwrap_or_default();
     let pruned_messages = context_manager.fit_messages(messages).await.unwrap();
 
@@ -439,7 +439,7 @@ async fn test_context_pruning_strategies() -> Result<(), Box<dyn std::error::Err
 
     let all_messages = agent
         .current_state()
-        .map(|s| s.messages.clone())
+        .map(|s| s.messages().to_vec())
         .unwrap_or_default();
     let pruned = context_manager.fit_messages(all_messages).await.unwrap();
 
@@ -499,7 +499,7 @@ async fn test_real_provider_integration() -> Result<(), Box<dyn std::error::Erro
     // Test with context management
     let messages = agent
         .current_state()
-        .map(|s| s.messages.clone())
+        .map(|s| s.messages().to_vec())
         .unwrap_or_default();
     let pruned = context_manager.fit_messages(messages).await.unwrap();
     assert!(!pruned.is_empty());
@@ -423,11 +423,30 @@ async fn test_tool_validation() -> Result<(), Box<dyn std::error::Error>> {
         .to_string(),
     };
 
-    let result = registry.execute(&call).await.unwrap();
+    // Arguments that break the schema are rejected before the tool runs
+    let err = registry.execute(&call).await.unwrap_err();
+    match err {
+        cogni::tools::ToolError::ValidationFailed { tool, errors } => {
+            assert_eq!(tool, "calculator");
+            assert_eq!(
+                errors,
+                vec![
+                    "/a: expected number, got string",
+                    "/b: expected number, got boolean",
+                ]
+            );
+        }
+        other => panic!("unexpected error: {}", other),
+    }
 
-    // Should handle invalid operation gracefully
+    // Arguments that match the schema reach the tool, which handles the operation
+    let call = ToolCall {
+        arguments: json!({ "operation": "invalid_op", "a": 1, "b": 2 }).to_string(),
+        ..call
+    };
+    let result = registry.execute(&call).await.unwrap();
     let result_json: serde_json::Value = serde_json::from_str(&result.content).unwrap();
-    assert!(result_json.get("error").is_some() || result_json.get("result").is_some());
+    assert!(result_json.get("error").is_some());
 
     Ok(())
 }
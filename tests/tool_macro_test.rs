@@ -0,0 +1,249 @@
+//! Tests for the `#[cogni::tool]` attribute macro
+
+use cogni::tools::{ToolCall, ToolExecutor, ToolRegistry};
+use serde::Serialize;
+use serde_json::json;
+use std::fmt;
+
+/// Add two numbers
+#[cogni::tool]
+fn add(
+    /// First operand
+    a: f64,
+    /// Second operand
+    b: f64,
+) -> f64 {
+    a + b
+}
+
+#[derive(Serialize)]
+struct Weather {
+    city: String,
+    temperature: f64,
+    unit: String,
+}
+
+#[derive(Debug)]
+struct UnknownCity(String);
+
+impl fmt::Display for UnknownCity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown city: {}", self.0)
+    }
+}
+
+/// Get the current weather
+///
+/// Returns the temperature for a city.
+#[cogni::tool(name = "weather")]
+async fn get_weather(
+    /// City name
+    city: String,
+    /// "celsius" or "fahrenheit"
+    unit: Option<String>,
+) -> Result<Weather, UnknownCity> {
+    if city != "Paris" {
+        return Err(UnknownCity(city));
+    }
+    let unit = unit.unwrap_or_else(|| "celsius".to_string());
+    let temperature = if unit == "fahrenheit" { 68.0 } else { 20.0 };
+    Ok(Weather {
+        city,
+        temperature,
+        unit,
+    })
+}
+
+/// Repeat a word
+#[cogni::tool]
+fn repeat(
+    #[schema(min_length = 1)] word: String,
+    /// How many times
+    #[schema(minimum = 1, maximum = 10)]
+    times: u32,
+) -> String {
+    word.repeat(times as usize)
+}
+
+#[cogni::tool(description = "Say hello")]
+fn hello() -> String {
+    "hello".to_string()
+}
+
+fn call(name: &str, arguments: serde_json::Value) -> ToolCall {
+    ToolCall {
+        id: "call_1".to_string(),
+        name: name.to_string(),
+        arguments: arguments.to_string(),
+    }
+}
+
+#[test]
+fn test_function_is_kept() {
+    assert_eq!(add(1.0, 2.0), 3.0);
+}
+
+#[test]
+fn test_tool_definition() {
+    let tool = AddTool::new();
+    let definition = tool.tool();
+    assert_eq!(definition.name, "add");
+    assert_eq!(definition.description, "Add two numbers");
+    assert_eq!(
+        definition.function.parameters,
+        json!({
+            "type": "object",
+            "properties": {
+                "a": { "type": "number", "description": "First operand" },
+                "b": { "type": "number", "description": "Second operand" }
+            },
+            "required": ["a", "b"]
+        })
+    );
+
+    let weather = GetWeatherTool::default();
+    let definition = weather.tool();
+    assert_eq!(definition.name, "weather");
+    assert_eq!(
+        definition.description,
+        "Get the current weather\n\nReturns the temperature for a city."
+    );
+    assert_eq!(definition.function.parameters["required"], json!(["city"]));
+    assert_eq!(
+        definition.function.parameters["properties"]["unit"]["description"],
+        "\"celsius\" or \"fahrenheit\""
+    );
+
+    let repeat = RepeatTool::new();
+    assert_eq!(
+        repeat.tool().function.parameters["properties"],
+        json!({
+            "word": { "type": "string", "minLength": 1 },
+            "times": {
+                "type": "integer",
+                "minimum": 1,
+                "maximum": 10,
+                "description": "How many times"
+            }
+        })
+    );
+
+    let hello = HelloTool::new();
+    assert_eq!(hello.tool().description, "Say hello");
+    assert_eq!(hello.tool().function.parameters["properties"], json!({}));
+}
+
+#[tokio::test]
+async fn test_sync_execution() {
+    let result = AddTool::new()
+        .execute(&call("add", json!({ "a": 2, "b": 3.5 })))
+        .await
+        .unwrap();
+    assert!(result.success);
+    assert_eq!(result.call_id, "call_1");
+    assert_eq!(result.content, "5.5");
+
+    let result = HelloTool::new()
+        .execute(&call("hello", json!({})))
+        .await
+        .unwrap();
+    assert_eq!(result.content, "\"hello\"");
+}
+
+#[tokio::test]
+async fn test_async_execution() {
+    let tool = GetWeatherTool::new();
+
+    let result = tool
+        .execute(&call("weather", json!({ "city": "Paris" })))
+        .await
+        .unwrap();
+    assert!(result.success);
+    let output: serde_json::Value = serde_json::from_str(&result.content).unwrap();
+    assert_eq!(
+        output,
+        json!({ "city": "Paris", "temperature": 20.0, "unit": "celsius" })
+    );
+
+    let result = tool
+        .execute(&call(
+            "weather",
+            json!({ "city": "Paris", "unit": "fahrenheit" }),
+        ))
+        .await
+        .unwrap();
+    assert!(result.content.contains("68.0"));
+
+    // Strict-mode models send null for omitted optional parameters
+    let result = tool
+        .execute(&call("weather", json!({ "city": "Paris", "unit": null })))
+        .await
+        .unwrap();
+    assert!(result.success, "{}", result.content);
+    assert!(result.content.contains("celsius"));
+}
+
+#[tokio::test]
+async fn test_errors_become_failed_results() {
+    let tool = GetWeatherTool::new();
+
+    let result = tool
+        .execute(&call("weather", json!({ "city": "Atlantis" })))
+        .await
+        .unwrap();
+    assert!(!result.success);
+    assert!(result.content.contains("unknown city: Atlantis"));
+}
+
+#[tokio::test]
+async fn test_arguments_are_validated() {
+    let err = GetWeatherTool::new()
+        .execute(&call("weather", json!({ "unit": "celsius" })))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Validation failed for tool 'weather': (root): missing required property 'city'"
+    );
+
+    let err = AddTool::new()
+        .execute(&call("add", json!({ "a": "two", "b": 1 })))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("/a: expected number, got string"));
+
+    let err = RepeatTool::new()
+        .execute(&call("repeat", json!({ "word": "", "times": 20 })))
+        .await
+        .unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("/word: must be at least 1 characters long"));
+    assert!(message.contains("/times: 20 is greater than the maximum of 10"));
+}
+
+#[tokio::test]
+async fn test_registry() {
+    let registry = ToolRegistry::new();
+    registry
+        .register([
+            Box::new(AddTool::new()) as Box<dyn ToolExecutor>,
+            Box::new(GetWeatherTool::new()),
+        ])
+        .await
+        .unwrap();
+
+    let mut names: Vec<_> = registry
+        .list_tools()
+        .await
+        .into_iter()
+        .map(|tool| tool.name)
+        .collect();
+    names.sort();
+    assert_eq!(names, vec!["add", "weather"]);
+
+    let result = registry
+        .execute(&call("add", json!({ "a": 1, "b": 1 })))
+        .await
+        .unwrap();
+    assert_eq!(result.content, "2.0");
+}